@@ -0,0 +1,51 @@
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from './server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+test('a thrown HttpError is sent with its status and exposed message', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/http-error/tobi`, {
+    headers: { Accept: 'text/html' },
+    validateStatus: () => true,
+  })
+  t.is(res.status, 404)
+  t.is(res.headers['content-type'], 'text/html; charset=utf-8')
+  t.true(res.data.includes('NotFoundError: No such user: tobi'))
+})
+
+test('a rejected HttpError is sent with its headers', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/http-error-busy`, { validateStatus: () => true })
+  t.is(res.status, 503)
+  t.is(res.headers['retry-after'], '5')
+  t.is(res.data.message, 'Busy')
+})
+
+test('requests no middleware handled are not found', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/no-such-route`, {
+    headers: { Accept: 'application/problem+json' },
+    validateStatus: () => true,
+  })
+  t.is(res.status, 404)
+  t.is(res.headers['content-type'], 'application/problem+json')
+  t.deepEqual(res.data, {
+    type: 'about:blank',
+    title: 'Not Found',
+    status: 404,
+    detail: 'Cannot GET /no-such-route',
+    requestId: res.headers['x-request-id'],
+  })
+})
@@ -0,0 +1,50 @@
+// __test__/access_log.spec.ts
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+async function accessLog(): Promise<any[]> {
+  // the lines are written once the responses are sent
+  await new Promise((resolve) => setTimeout(resolve, 100))
+  const res = await axios.get(`http://localhost:${port}/access-log`)
+  return res.data
+}
+
+test.serial('logs requests as JSON lines', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/logged`, {
+    headers: { 'User-Agent': 'ava', 'X-Tenant': 'acme' },
+  })
+  const line = (await accessLog()).find((line) => line.id === res.headers['x-request-id'])
+
+  t.truthy(line)
+  t.is(line.method, 'GET')
+  t.is(line.url, '/logged')
+  t.is(line.status, 200)
+  t.is(line.bytes, 6)
+  t.is(line.userAgent, 'ava')
+  t.is(line.tenant, 'acme')
+  t.is(typeof line.responseTime, 'number')
+  t.truthy(Date.parse(line.time))
+})
+
+test.serial('skips the requests matching the predicate', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/logged-quiet`)
+  t.is(res.status, 204)
+  const lines = await accessLog()
+  t.false(lines.some((line) => line.id === res.headers['x-request-id']))
+})
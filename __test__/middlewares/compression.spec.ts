@@ -0,0 +1,79 @@
+// __test__/compression.spec.ts
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import { brotliDecompressSync, gunzipSync, inflateSync } from 'node:zlib'
+import axios from 'axios'
+
+import { CompressionMiddleware } from '../../index'
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+const expected = { items: Array.from({ length: 100 }, (_, i) => ({ id: i, name: `item ${i}` })) }
+
+function get(path: string, acceptEncoding: string) {
+  return axios.get(`http://localhost:${port}${path}`, {
+    headers: { 'Accept-Encoding': acceptEncoding },
+    decompress: false,
+    responseType: 'arraybuffer',
+  })
+}
+
+for (const [encoding, decode] of [
+  ['gzip', gunzipSync],
+  ['br', brotliDecompressSync],
+  ['deflate', inflateSync],
+] as const) {
+  test(`compresses with ${encoding}`, async (t) => {
+    const res = await get('/compressed', encoding)
+    t.is(res.headers['content-encoding'], encoding)
+    t.is(res.headers['vary'], 'Accept-Encoding')
+    t.is(res.headers['etag'], 'W/"compressible"')
+    t.deepEqual(JSON.parse(decode(Buffer.from(res.data)).toString()), expected)
+  })
+}
+
+test('prefers the encoding with the highest quality', async (t) => {
+  const res = await get('/compressed', 'br;q=0.5, gzip;q=0.8')
+  t.is(res.headers['content-encoding'], 'gzip')
+})
+
+test('does not compress without an accepted encoding', async (t) => {
+  const res = await get('/compressed', 'identity')
+  t.is(res.headers['content-encoding'], undefined)
+  t.is(res.headers['vary'], 'Accept-Encoding')
+  t.is(res.headers['etag'], '"compressible"')
+  t.deepEqual(JSON.parse(Buffer.from(res.data).toString()), expected)
+})
+
+test('does not compress bodies below the threshold', async (t) => {
+  const res = await get('/compressed-small', 'gzip')
+  t.is(res.headers['content-encoding'], undefined)
+  t.deepEqual(JSON.parse(Buffer.from(res.data).toString()), { id: 0 })
+})
+
+test('does not compress no-transform responses', async (t) => {
+  const res = await get('/compressed-no-transform', 'gzip')
+  t.is(res.headers['content-encoding'], undefined)
+  t.is(res.headers['vary'], undefined)
+})
+
+test('sends an internal error when the Vary header cannot be extended', async (t) => {
+  const res = await get('/compressed-invalid-vary', 'gzip').catch((err) => err.response)
+  t.is(res.status, 500)
+})
+
+test('rejects a negative threshold', (t) => {
+  t.throws(() => new CompressionMiddleware({ threshold: -1 }), { message: /^Invalid threshold value: -1/ })
+})
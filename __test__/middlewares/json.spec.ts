@@ -1,7 +1,8 @@
 // __test__/echo.spec.ts
 import test from 'ava'
 import { ChildProcess } from 'node:child_process'
-import axios from 'axios'
+import axios, { isAxiosError } from 'axios'
+import { brotliCompressSync, gzipSync } from 'node:zlib'
 
 import * as server from '../server.js'
 
@@ -35,3 +36,153 @@ test('/json-echo', async (t) => {
   const data = res.data
   t.deepEqual(data, { greeting: 'Hello, world!' })
 })
+
+test('/json-echo - gzip', async (t) => {
+  const res = await axios.post(`http://localhost:${port}/json-echo`, gzipSync(JSON.stringify({ greeting: 'Hello!' })), {
+    headers: {
+      'content-type': 'application/json',
+      'content-encoding': 'gzip',
+    },
+  })
+  t.deepEqual(res.data, { greeting: 'Hello!' })
+})
+
+test('/json-echo - brotli', async (t) => {
+  const res = await axios.post(
+    `http://localhost:${port}/json-echo`,
+    brotliCompressSync(JSON.stringify({ greeting: 'Hello!' })),
+    {
+      headers: {
+        'content-type': 'application/json',
+        'content-encoding': 'br',
+      },
+    },
+  )
+  t.deepEqual(res.data, { greeting: 'Hello!' })
+})
+
+test('/json-echo - unsupported encoding', async (t) => {
+  try {
+    await axios.post(`http://localhost:${port}/json-echo`, JSON.stringify({ greeting: 'Hello!' }), {
+      headers: {
+        'content-type': 'application/json',
+        'content-encoding': 'compress',
+      },
+    })
+    t.fail('Expected request to fail.')
+  } catch (e) {
+    if (isAxiosError(e)) {
+      t.is(e.response?.status, 415)
+    } else {
+      t.fail('Expected an AxiosError.')
+    }
+  }
+})
+
+test('/json-echo - malformed body', async (t) => {
+  try {
+    await axios.post(`http://localhost:${port}/json-echo`, '{"greeting":', {
+      headers: {
+        'content-type': 'application/json',
+      },
+    })
+    t.fail('Expected request to fail.')
+  } catch (e) {
+    if (isAxiosError(e)) {
+      t.is(e.response?.status, 400)
+    } else {
+      t.fail('Expected an AxiosError.')
+    }
+  }
+})
+
+test('/json-small - too large', async (t) => {
+  const res = await axios.post(`http://localhost:${port}/json-small`, JSON.stringify({ greeting: 'Hello, world!' }), {
+    headers: {
+      'content-type': 'application/json',
+    },
+    validateStatus: () => true,
+  })
+  t.is(res.status, 413)
+  t.deepEqual(res.data, { status: 413, type: 'entity.too.large', expose: true, limit: 16, length: 28 })
+})
+
+test('/json-small - parse failed', async (t) => {
+  const res = await axios.post(`http://localhost:${port}/json-small`, '"hello"', {
+    headers: {
+      'content-type': 'application/json',
+    },
+    validateStatus: () => true,
+  })
+  t.is(res.status, 400)
+  t.deepEqual(res.data, { status: 400, type: 'entity.parse.failed', expose: true, body: '"hello"' })
+})
+
+test('/json-small - unsupported encoding', async (t) => {
+  const res = await axios.post(`http://localhost:${port}/json-small`, '{}', {
+    headers: {
+      'content-type': 'application/json',
+      'content-encoding': 'compress',
+    },
+    validateStatus: () => true,
+  })
+  t.is(res.status, 415)
+  t.is(res.data.type, 'encoding.unsupported')
+})
+
+test('/json-revive', async (t) => {
+  const res = await axios.post(
+    `http://localhost:${port}/json-revive`,
+    JSON.stringify({ a: 1, b: [2, { secret: 'x', c: 3 }] }),
+    {
+      headers: {
+        'content-type': 'application/json',
+      },
+    },
+  )
+  t.deepEqual(res.data, { a: 2, b: [4, { c: 6 }] })
+})
+
+test('/json-revive-date', async (t) => {
+  const res = await axios.post(
+    `http://localhost:${port}/json-revive-date`,
+    JSON.stringify({ at: '2026-10-19T00:00:00.000Z' }),
+    {
+      headers: {
+        'content-type': 'application/json',
+      },
+    },
+  )
+  t.deepEqual(res.data, { isDate: true, year: 2026 })
+})
+
+test('/json-guarded - within limits', async (t) => {
+  const res = await axios.post(
+    `http://localhost:${port}/json-guarded`,
+    '{"a":[1,2],"constructor":{"prototype":{}}}',
+    {
+      headers: {
+        'content-type': 'application/json',
+      },
+    },
+  )
+  t.deepEqual(res.data, { a: [1, 2] })
+})
+
+for (const [name, body] of [
+  ['__proto__ key', '{"__proto__":{"isAdmin":true}}'],
+  ['too deep', '{"a":{"b":{"c":{}}}}'],
+  ['array too long', '[1,2,3,4,5]'],
+  ['string too long', '["a string that is too long"]'],
+  ['duplicate keys', '{"a":1,"a":2}'],
+]) {
+  test(`/json-guarded - ${name}`, async (t) => {
+    const res = await axios.post(`http://localhost:${port}/json-guarded`, body, {
+      headers: {
+        'content-type': 'application/json',
+      },
+      validateStatus: () => true,
+    })
+    t.is(res.status, 400)
+  })
+}
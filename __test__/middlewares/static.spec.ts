@@ -41,3 +41,78 @@ test('fetch alternative_index.html in the public directory', async (t) => {
   })
   t.is(data, index_file_contents)
 })
+
+test('list the public directory as HTML', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/public/`)
+  t.is(res.headers['content-type'], 'text/html; charset=utf-8')
+  t.regex(res.data, /<a href="index.html">index.html<\/a>/)
+  t.regex(res.data, /<a href="alternative_index.html">alternative_index.html<\/a>/)
+  // dotfiles are ignored
+  t.notRegex(res.data, /\.dotfile\.html/)
+})
+
+test('list the public directory as JSON', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/public/`, { headers: { Accept: 'application/json' } })
+  t.deepEqual(
+    res.data.map((entry: { name: string; type: string }) => [entry.name, entry.type]),
+    [
+      ['alternative_index.html', 'file'],
+      ['index.html', 'file'],
+    ],
+  )
+})
+
+test('list the public directory as plain text', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/public/`, { headers: { Accept: 'text/plain' } })
+  t.is(res.data, 'alternative_index.html\nindex.html\n')
+})
+
+test('serve cached files like files read from disk', async (t) => {
+  const first = await axios.get(`http://localhost:${port}/public/alternative_index.html`)
+  // the cache is filled in the background
+  await new Promise((resolve) => setTimeout(resolve, 100))
+  const cached = await axios.get(`http://localhost:${port}/public/alternative_index.html`)
+
+  t.is(cached.data, first.data)
+  t.is(cached.headers['content-type'], first.headers['content-type'])
+  t.is(cached.headers['last-modified'], first.headers['last-modified'])
+  t.truthy(cached.headers['x-timestamp'])
+})
+
+test('do not serve the headers of other requests with cached files', async (t) => {
+  const first = await axios.get(`http://localhost:${port}/public/index.html?login`)
+  // the cache is filled in the background
+  await new Promise((resolve) => setTimeout(resolve, 100))
+  const cached = await axios.get(`http://localhost:${port}/public/index.html`)
+
+  t.is(cached.data, first.data)
+  t.is(cached.headers['set-cookie'], undefined)
+})
+
+test('serve the single-page application for client-side routes', async (t) => {
+  const index = await axios.get(`http://localhost:${port}/public/index.html`)
+  const res = await axios.get(`http://localhost:${port}/spa/dashboard/settings`, {
+    headers: { Accept: 'text/html' },
+  })
+
+  t.is(res.status, 200)
+  t.is(res.data, index.data)
+  t.is(res.headers['cache-control'], 'no-cache')
+})
+
+test('keep 404s for missing assets, API routes and non-HTML requests', async (t) => {
+  const options = { headers: { Accept: 'text/html' }, validateStatus: () => true }
+
+  t.is((await axios.get(`http://localhost:${port}/spa/main.js`, options)).status, 404)
+  t.is((await axios.get(`http://localhost:${port}/spa/api/users`, options)).status, 404)
+  t.is((await axios.get(`http://localhost:${port}/outside/spa`, options)).status, 404)
+  t.is(
+    (
+      await axios.get(`http://localhost:${port}/spa/dashboard`, {
+        headers: { Accept: 'application/json' },
+        validateStatus: () => true,
+      })
+    ).status,
+    404,
+  )
+})
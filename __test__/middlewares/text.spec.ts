@@ -1,7 +1,7 @@
 // __test__/echo.spec.ts
 import test from 'ava'
 import { ChildProcess } from 'node:child_process'
-import axios from 'axios'
+import axios, { isAxiosError } from 'axios'
 
 import * as server from '../server.js'
 
@@ -34,3 +34,38 @@ test('/echo - text/plain header', async (t) => {
   const data = res.data
   t.is(data, 'ping')
 })
+
+test('/echo - latin1 charset', async (t) => {
+  const res = await axios.post(`http://localhost:${port}/echo`, Buffer.from('café', 'latin1'), {
+    headers: {
+      'content-type': 'text/plain; charset=iso-8859-1',
+    },
+  })
+  t.is(res.data, 'café')
+})
+
+test('/echo - shift_jis charset', async (t) => {
+  const res = await axios.post(`http://localhost:${port}/echo`, Buffer.from([0x82, 0xb1, 0x82, 0xf1]), {
+    headers: {
+      'content-type': 'text/plain; charset=Shift_JIS',
+    },
+  })
+  t.is(res.data, 'こん')
+})
+
+test('/echo - unknown charset', async (t) => {
+  try {
+    await axios.post(`http://localhost:${port}/echo`, 'ping', {
+      headers: {
+        'content-type': 'text/plain; charset=klingon',
+      },
+    })
+    t.fail('Expected request to fail.')
+  } catch (e) {
+    if (isAxiosError(e)) {
+      t.is(e.response?.status, 415)
+    } else {
+      t.fail('Expected an AxiosError.')
+    }
+  }
+})
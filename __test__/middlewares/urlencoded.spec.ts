@@ -47,3 +47,12 @@ test('/urlencoded', async (t) => {
   const data = res.data
   t.deepEqual(data, { greeting: 'Hello, world!' })
 })
+
+test('/urlencoded - latin1 charset', async (t) => {
+  const res = await axios.post(`http://localhost:${port}/urlencoded`, 'name=Ren%E9e', {
+    headers: {
+      'content-type': 'application/x-www-form-urlencoded; charset=iso-8859-1',
+    },
+  })
+  t.deepEqual(res.data, { name: 'Renée' })
+})
@@ -0,0 +1,51 @@
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from './server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+test('next() runs the next handler of the route', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/next/tobi`)
+  t.is(res.data, 'first route tobi')
+})
+
+test("next('route') skips the remaining handlers of the route", async (t) => {
+  const res = await axios.get(`http://localhost:${port}/next/other`)
+  t.is(res.data, 'second route')
+})
+
+test('next() and the end of the response are awaited after the handler returned', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/next-later`)
+  t.is(res.data, 'sent later')
+})
+
+test('next(err) sends the error status and headers from the final handler', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/next-error`, { validateStatus: () => true })
+  t.is(res.status, 410)
+  t.is(res.headers['retry-after'], '120')
+  t.is(res.headers['content-type'], 'text/html; charset=utf-8')
+  t.true(res.data.includes('<h1>410 Gone</h1>'))
+  t.true(res.data.includes('<p>Gone for good</p>'))
+})
+
+test('errors are passed to the error handlers', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/error`, {
+    headers: { 'X-Error-Format': 'json' },
+    validateStatus: () => true,
+  })
+  t.is(res.status, 500)
+  t.deepEqual(res.data, { error: 'Intentional error for testing' })
+})
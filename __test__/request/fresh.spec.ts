@@ -0,0 +1,52 @@
+// __test__/fresh.spec.ts
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+function get(path: string, headers: Record<string, string> = {}) {
+  return axios.get(`http://localhost:${port}${path}`, { headers, validateStatus: () => true })
+}
+
+test('send() generates a weak ETag', async (t) => {
+  const res = await get('/etag')
+  t.is(res.status, 200)
+  t.regex(res.headers['etag'], /^W\/"14-\d+"$/)
+  t.is(res.data, 'cacheable body')
+})
+
+test('send() answers a fresh request with 304', async (t) => {
+  const { headers } = await get('/etag')
+  const res = await get('/etag', { 'If-None-Match': headers['etag'] })
+  t.is(res.status, 304)
+  t.is(res.data, '')
+  t.is(res.headers['content-type'], undefined)
+})
+
+test('send() ignores freshness for end-to-end reloads', async (t) => {
+  const { headers } = await get('/etag')
+  const res = await get('/etag', { 'If-None-Match': headers['etag'], 'Cache-Control': 'no-cache' })
+  t.is(res.status, 200)
+})
+
+test('req.fresh and req.stale', async (t) => {
+  const stale = await get('/fresh', { 'If-None-Match': '"fresh-v0"' })
+  t.deepEqual(stale.data, { fresh: false, stale: true })
+
+  const fresh = await get('/fresh', { 'If-None-Match': '"fresh-v1"' })
+  t.is(fresh.status, 304)
+})
@@ -0,0 +1,58 @@
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+test('reads the forwarded headers of trusted proxies', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/request-info/Tobi`, {
+    headers: {
+      'X-Forwarded-For': 'client, 10.0.0.1, 127.0.0.2',
+      'X-Forwarded-Proto': 'https',
+      'X-Forwarded-Host': 'tobi.ferrets.example.com',
+    },
+  })
+  t.is(res.headers['x-powered-by'], 'hyperjs')
+  t.is(res.data.ip, '10.0.0.1')
+  t.deepEqual(res.data.ips, ['10.0.0.1', '127.0.0.2'])
+  t.is(res.data.protocol, 'https')
+  t.true(res.data.secure)
+  t.is(res.data.hostname, 'tobi.ferrets.example.com')
+  t.deepEqual(res.data.subdomains, ['ferrets', 'tobi'])
+  t.deepEqual(res.data.trustProxy, ['loopback'])
+})
+
+test('routes ignore case and trailing slashes, and parse the query', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/Request-Info/Tobi/?user[name]=tobi&tags[]=a&tags[]=b`)
+  t.is(res.data.name, 'Tobi')
+  t.is(res.data.protocol, 'http')
+  t.false(res.data.secure)
+  t.deepEqual(res.data.query, { user: { name: 'tobi' }, tags: ['a', 'b'] })
+})
+
+test('echoes the request ID of trusted proxies', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/request-info/Tobi`, {
+    headers: { 'X-Request-Id': 'f058ebd6-02f7-4d3f-942e-904344e8cde5' },
+  })
+  t.is(res.data.id, 'f058ebd6-02f7-4d3f-942e-904344e8cde5')
+  t.is(res.headers['x-request-id'], 'f058ebd6-02f7-4d3f-942e-904344e8cde5')
+})
+
+test('generates request IDs', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/request-info/Tobi`)
+  t.truthy(res.data.id)
+  t.is(res.headers['x-request-id'], res.data.id)
+})
@@ -0,0 +1,23 @@
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+test('/locals-identity - returns the values set by a middleware themselves', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/locals-identity`)
+  t.deepEqual(res.data, { same: true, rows: 'rows of SELECT 1', admin: true })
+})
@@ -23,6 +23,12 @@ test('/method - get', async (t) => {
   t.is(data, 'GET')
 })
 
+test('/method - get with a query string', async (t) => {
+  let res = await axios.get(`http://localhost:${port}/method?foo=bar`)
+  let data = res.data
+  t.is(data, 'GET')
+})
+
 test('/method - post', async (t) => {
   let res = await axios.post(`http://localhost:${port}/method`)
   let data = res.data
@@ -0,0 +1,90 @@
+// __test__/byteranges.spec.ts
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+function get(path: string, headers: Record<string, string> = {}) {
+  return axios.get(`http://localhost:${port}${path}`, {
+    headers,
+    responseType: 'text',
+    validateStatus: () => true,
+  })
+}
+
+test('send() answers a single range with 206', async (t) => {
+  const res = await get('/range-buffer', { Range: 'bytes=0-2' })
+  t.is(res.status, 206)
+  t.is(res.headers['content-range'], 'bytes 0-2/10')
+  t.is(res.headers['content-type'], 'text/plain')
+  t.is(res.data, 'abc')
+})
+
+test('send() combines overlapping ranges', async (t) => {
+  const res = await get('/range-buffer', { Range: 'bytes=0-3,2-5' })
+  t.is(res.status, 206)
+  t.is(res.headers['content-range'], 'bytes 0-5/10')
+  t.is(res.data, 'abcdef')
+})
+
+test('send() answers multiple ranges with multipart/byteranges', async (t) => {
+  const res = await get('/range-buffer', { Range: 'bytes=0-1,5-6' })
+  t.is(res.status, 206)
+  const boundary = res.headers['content-type'].match(/^multipart\/byteranges; boundary=(\w+)$/)?.[1]
+  t.truthy(boundary)
+  t.is(
+    res.data,
+    `--${boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\nab\r\n` +
+      `--${boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 5-6/10\r\n\r\nfg\r\n` +
+      `--${boundary}--\r\n`,
+  )
+})
+
+test('send() answers unsatisfiable ranges with 416', async (t) => {
+  const res = await get('/range-buffer', { Range: 'bytes=500-' })
+  t.is(res.status, 416)
+  t.is(res.headers['content-range'], 'bytes */10')
+})
+
+test('send() ignores ranges when If-Range does not match', async (t) => {
+  const res = await get('/range-buffer', { Range: 'bytes=0-2', 'If-Range': '"stale"' })
+  t.is(res.status, 200)
+  t.is(res.headers['accept-ranges'], 'bytes')
+  t.is(res.data, 'abcdefghij')
+})
+
+test('sendFile() answers ranges with 206', async (t) => {
+  const full = await get('/send-file/allow/index.html')
+  const res = await get('/send-file/allow/index.html', { Range: 'bytes=0-9' })
+  t.is(res.status, 206)
+  t.is(res.headers['content-type'], 'text/html')
+  t.is(res.headers['content-range'], `bytes 0-9/${full.data.length}`)
+  t.is(res.data, full.data.slice(0, 10))
+})
+
+test('sendFile() answers multiple ranges with multipart/byteranges', async (t) => {
+  const res = await get('/send-file/allow/index.html', { Range: 'bytes=0-1,5-6' })
+  t.is(res.status, 206)
+  t.regex(res.headers['content-type'], /^multipart\/byteranges; boundary=/)
+})
+
+test('sendFile() answers unsatisfiable ranges with 416', async (t) => {
+  const full = await get('/send-file/allow/index.html')
+  const res = await get('/send-file/allow/index.html', { Range: 'bytes=100000-' })
+  t.is(res.status, 416)
+  t.is(res.headers['content-range'], `bytes */${full.data.length}`)
+})
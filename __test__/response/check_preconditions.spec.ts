@@ -0,0 +1,63 @@
+// __test__/check_preconditions.spec.ts
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios, { Method } from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+function request(method: Method, headers: Record<string, string> = {}) {
+  return axios.request({
+    method,
+    url: `http://localhost:${port}/preconditions`,
+    headers,
+    validateStatus: () => true,
+  })
+}
+
+test('checkPreconditions() passes unconditional requests', async (t) => {
+  t.is((await request('GET')).status, 200)
+  t.is((await request('PUT')).status, 204)
+})
+
+test('checkPreconditions() answers If-Match mismatches with 412', async (t) => {
+  t.is((await request('PUT', { 'If-Match': '"article-v1"' })).status, 204)
+
+  const res = await request('PUT', { 'If-Match': '"article-v0"' })
+  t.is(res.status, 412)
+  t.is(res.data, 'Precondition Failed')
+})
+
+test('checkPreconditions() answers If-Unmodified-Since mismatches with 412', async (t) => {
+  const res = await request('PUT', { 'If-Unmodified-Since': 'Fri, 31 Dec 1999 00:00:00 GMT' })
+  t.is(res.status, 412)
+})
+
+test('checkPreconditions() answers unmodified GET requests with 304', async (t) => {
+  const res = await request('GET', { 'If-None-Match': '"article-v1"' })
+  t.is(res.status, 304)
+  t.is(res.headers['etag'], '"article-v1"')
+  t.is(res.headers['last-modified'], 'Sat, 01 Jan 2000 00:00:00 GMT')
+
+  t.is((await request('PUT', { 'If-None-Match': '*' })).status, 412)
+})
+
+test('checkPreconditions() drops the Range header when If-Range does not match', async (t) => {
+  const matching = await request('GET', { Range: 'bytes=0-1', 'If-Range': '"article-v1"' })
+  t.deepEqual(matching.data, { range: 'bytes=0-1' })
+
+  const mismatching = await request('GET', { Range: 'bytes=0-1', 'If-Range': '"article-v0"' })
+  t.deepEqual(mismatching.data, { range: '' })
+})
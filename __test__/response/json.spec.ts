@@ -0,0 +1,60 @@
+// __test__/json.spec.ts
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+test('serializes like JSON.stringify, using the json settings', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/json-values`, { responseType: 'text' })
+  t.is(res.headers['content-type'], 'application/json; charset=utf-8')
+  t.is(
+    res.data,
+    '{"float":1.5,"floats":[0.1,2.5],"date":"1970-01-01T00:00:00.000Z",' +
+      '"html":"\\u003cb\\u003e\\u0026\\u003c/b\\u003e","nested":"nested"}',
+  )
+})
+
+test('throws on BigInt values', async (t) => {
+  const error = await t.throwsAsync(axios.get(`http://localhost:${port}/json-bigint`))
+  t.is((error as any).response.status, 500)
+  t.is((error as any).response.data, 'Do not know how to serialize a BigInt')
+})
+
+test('send() serializes numbers as JSON', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/send-float`, { responseType: 'text' })
+  t.is(res.headers['content-type'], 'application/json; charset=utf-8')
+  t.is(res.data, '1.5')
+})
+
+test('jsonp() wraps the JSON in a call to the sanitized callback', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/jsonp?callback=window.cbs%5B0%5D()%3Balert`, {
+    responseType: 'text',
+  })
+  t.is(res.headers['content-type'], 'text/javascript; charset=utf-8')
+  t.is(res.headers['x-content-type-options'], 'nosniff')
+  t.is(
+    res.data,
+    `/**/ typeof window.cbs[0]alert === 'function' && window.cbs[0]alert({"user":"tobi","separators":"\\u2028\\u2029"});`,
+  )
+})
+
+test('jsonp() sends JSON without a callback', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/jsonp`, { responseType: 'text' })
+  t.is(res.headers['content-type'], 'application/json; charset=utf-8')
+  t.is(res.headers['x-content-type-options'], 'nosniff')
+  t.is(res.data, '{"user":"tobi","separators":"\u2028\u2029"}')
+})
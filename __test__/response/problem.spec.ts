@@ -0,0 +1,32 @@
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+test('/problem - sends a problem details document', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/problem`, { validateStatus: () => true })
+  t.is(res.status, 403)
+  t.is(res.headers['content-type'], 'application/problem+json; charset=utf-8')
+  t.deepEqual(res.data, {
+    type: 'https://example.com/probs/out-of-credit',
+    title: 'Forbidden',
+    status: 403,
+    detail: 'Your current balance is 30, but that costs 50.',
+    instance: '/account/12345/msgs/abc',
+    balance: 30,
+  })
+})
@@ -0,0 +1,46 @@
+import test from 'ava'
+import { ChildProcess } from 'node:child_process'
+import axios from 'axios'
+
+import * as server from '../server.js'
+
+let serverApp: ChildProcess
+let port: number
+
+test.before(async () => {
+  const result = await server.start()
+  serverApp = result.process
+  port = result.port
+})
+
+test.after.always(() => {
+  server.stop(serverApp)
+})
+
+test('/render - renders a Jinja view with server, response and render locals', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/render`)
+  t.is(res.headers['content-type'], 'text/html; charset=utf-8')
+  t.truthy(res.headers['etag'])
+  t.true(res.data.includes('<title>Items - hyperjs</title>'))
+  t.true(res.data.includes('<li>1. &#60;b&#62;first&#60;/b&#62;</li>'))
+  t.true(res.data.includes('<li>2. second</li>'))
+  t.true(res.data.includes('<footer>hyperjs - tobi</footer>'))
+})
+
+test('/render-locals - renders the request locals set by a middleware', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/render-locals`)
+  t.true(res.data.includes('<title>Locals - hyperjs</title>'))
+  t.true(res.data.includes('<footer>hyperjs - loki</footer>'))
+})
+
+test('/render-engine - renders a view with an engine registered from JS', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/render-engine`)
+  t.is(res.headers['content-type'], 'text/html; charset=utf-8')
+  t.is(res.data, 'Hello Tobi!\n')
+})
+
+test('/render-callback - passes lookup errors to the callback', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/render-callback`, { validateStatus: () => true })
+  t.is(res.status, 500)
+  t.regex(res.data, /^Failed to lookup view "missing" in views directory ".*views"$/)
+})
@@ -63,3 +63,35 @@ test('/folder - dir default index', async (t) => {
   let index_file_contents = readFileSync(path.join(__dirname, '../../public/index.html'), { encoding: 'utf-8' })
   t.is(data, index_file_contents)
 })
+
+test('/bundle - send files from a virtual file system', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/bundle/bundle.js`, {
+    headers: { 'Accept-Encoding': 'identity' },
+  })
+  t.is(res.data, "console.log('bundled')")
+  t.truthy(res.headers['etag'])
+  t.truthy(res.headers['last-modified'])
+  t.is(res.headers['cache-control'], 'public, max-age=86400')
+
+  const range = await axios.get(`http://localhost:${port}/bundle/bundle.js`, {
+    headers: { 'Accept-Encoding': 'identity', Range: 'bytes=0-6' },
+  })
+  t.is(range.status, 206)
+  t.is(range.data, 'console')
+
+  // files missing from the bundle are sent from the `public` directory
+  const index = await axios.get(`http://localhost:${port}/bundle/index.html`)
+  const index_file_contents = readFileSync(path.join(__dirname, '../../public/index.html'), { encoding: 'utf-8' })
+  t.is(index.data, index_file_contents)
+})
+
+test('/bundle - send precompressed variants and ignore dotfiles', async (t) => {
+  const res = await axios.get(`http://localhost:${port}/bundle/bundle.js`, {
+    headers: { 'Accept-Encoding': 'br' },
+  })
+  t.is(res.headers['content-encoding'], 'br')
+  t.is(res.data, "console.log('bundled')")
+
+  const dotfile = await axios.get(`http://localhost:${port}/bundle/.env`, { validateStatus: () => true })
+  t.is(dotfile.status, 404)
+})
@@ -6,3 +6,10 @@ test('serializeNapiObject', (t) => {
   t.is(serializeNapiObject({}), '{}')
   t.is(serializeNapiObject({ a: 'a', b: 'b' }), '{"a":"a","b":"b"}')
 })
+
+test('serializeNapiObject matches JSON.stringify', (t) => {
+  t.is(serializeNapiObject([1.5, undefined, new Date(0)]), '[1.5,null,"1970-01-01T00:00:00.000Z"]')
+  t.is(serializeNapiObject({ a: undefined, b: { toJSON: () => 'b' } }), '{"b":"b"}')
+  t.is(serializeNapiObject(undefined), undefined)
+  t.throws(() => serializeNapiObject({ a: 1n }), { instanceOf: TypeError })
+})
@@ -0,0 +1,106 @@
+// Throughput of static file responses served by `StaticMiddleware`, with and
+// without its in-memory cache, and by `res.sendFile`. Run from the repository
+// root, after a release build:
+//
+//   node --import @oxc-node/core/register benchmark/static.ts
+//
+// Each scenario runs in its own process, as a process listens only once.
+// `CONNECTIONS` and `DURATION` (in seconds) tune the load.
+//
+// Release builds on one CPU core, with the default load, before and after
+// files were served as futures on the shared napi runtime (requests/s):
+//
+//   scenario        before  after
+//   static            2301   3166
+//   static (cache)    6655   6928
+//   sendFile          2518   3406
+
+import { fork } from 'node:child_process'
+import http from 'node:http'
+import path from 'node:path'
+import process from 'node:process'
+
+import { Server, Request, Response, StaticMiddleware } from '../index.js'
+
+const PORT = 3456
+const CONNECTIONS = Number(process.env.CONNECTIONS ?? 64)
+const DURATION = Number(process.env.DURATION ?? 5) * 1000
+const SCENARIOS = ['static', 'static (cache)', 'sendFile']
+
+const agent = new http.Agent({ keepAlive: true, maxSockets: CONNECTIONS })
+
+function request(urlPath: string): Promise<number> {
+  return new Promise((resolve, reject) => {
+    http
+      .get({ host: '127.0.0.1', port: PORT, path: urlPath, agent }, (res) => {
+        res.resume()
+        res.on('end', () => resolve(res.statusCode ?? 0))
+      })
+      .on('error', reject)
+  })
+}
+
+async function load(urlPath: string) {
+  const deadline = Date.now() + DURATION
+  let completed = 0
+  let failed = 0
+  const worker = async () => {
+    while (Date.now() < deadline) {
+      const status = await request(urlPath)
+      if (status === 200) completed++
+      else failed++
+    }
+  }
+  const start = process.hrtime.bigint()
+  await Promise.all(Array.from({ length: CONNECTIONS }, worker))
+  const seconds = Number(process.hrtime.bigint() - start) / 1e9
+  return { 'requests/s': Math.round(completed / seconds), failed }
+}
+
+async function serve(scenario: string) {
+  const app = new Server()
+  switch (scenario) {
+    case 'sendFile': {
+      const root = path.join(process.cwd(), 'public')
+      app.get('/public/index.html', async (_req: Request, res: Response) => {
+        await res.sendFile('index.html', { root })
+      })
+      break
+    }
+    default: {
+      const staticMiddleware = new StaticMiddleware('public', { cache: scenario === 'static (cache)' })
+      app.use(null, (req: Request, res: Response) => staticMiddleware.run(req, res))
+    }
+  }
+  app.listen(`127.0.0.1:${PORT}`)
+
+  // wait for the server to listen
+  for (;;) {
+    try {
+      await request('/public/index.html')
+      break
+    } catch {
+      await new Promise((resolve) => setTimeout(resolve, 50))
+    }
+  }
+
+  await load('/public/index.html') // warm up
+  process.send?.(await load('/public/index.html'))
+  process.exit(0)
+}
+
+if (process.argv[2]) {
+  await serve(process.argv[2])
+} else {
+  const results = []
+  for (const scenario of SCENARIOS) {
+    // the server logs every request to stderr
+    const child = fork(process.argv[1], [scenario], {
+      execArgv: process.execArgv,
+      stdio: ['inherit', 'inherit', 'ignore', 'ipc'],
+    })
+    const result = await new Promise((resolve) => child.once('message', resolve))
+    results.push({ scenario, ...(result as object) })
+  }
+  console.table(results)
+}
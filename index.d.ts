@@ -1,5 +1,61 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/**
+ * Returns the access log middleware using the given `options`, logging the
+ * requests that traverse through the middleware once their response is
+ * complete, like [morgan](https://www.npmjs.com/package/morgan) does.
+ *
+ * The response time is measured from the middleware call until the body of
+ * the response is sent, and the bytes sent are counted as the body streams.
+ * Requests whose middleware chain fails are logged too, with
+ * the response sent to the client.
+ *
+ * ```javascript
+ * const accessLog = new AccessLogMiddleware({
+ *   format: 'json',
+ *   stream: { path: 'access.log', size: '10mb', maxFiles: 5 },
+ *   skip: (req, res) => res.statusCode < 400,
+ * })
+ * app.use(null, (req, res) => accessLog.run(req, res))
+ * ```
+ */
+export declare class AccessLogMiddleware {
+  constructor(options?: JsAccessLogOptions | undefined | null)
+  /**
+   * Starts timing the request; the request is logged once its response is
+   * complete. Always returns `true`.
+   */
+  run(request: Request, response: Response): boolean
+}
+
+/**
+ * The settings and locals of the server handling a request, as
+ * `req.app` and `res.app`. They are those of the server when it started
+ * listening.
+ *
+ * ```javascript
+ * app.use((req, res) => {
+ *   if (req.app.enabled('trust proxy')) {
+ *     // ...
+ *   }
+ *   return true
+ * })
+ * ```
+ */
+export declare class Application {
+  /** The value of setting `name`, `undefined` when it is not set */
+  get(name: string): unknown
+  /** Whether setting `name` is truthy */
+  enabled(name: string): boolean
+  /** Whether setting `name` is falsy */
+  disabled(name: string): boolean
+  /**
+   * Variables available to all the views rendered by `res.render()`, set
+   * with `app.locals.set(name, value)`
+   */
+  get locals(): Locals
+}
+
 export declare class Bytes {
   constructor()
   static fromOwner(owner: Buffer): Bytes
@@ -15,6 +71,37 @@ export declare class Bytes {
   clear(): void
 }
 
+/**
+ * Returns the compression middleware using the given `options`. The
+ * middleware will attempt to compress response bodies for all requests that
+ * traverse through the middleware, based on the given `options`.
+ *
+ * The encoding is negotiated from the request's `Accept-Encoding` header
+ * among `br`, `zstd`, `gzip` and `deflate`. The response body, including
+ * bodies sent with `res.send()`, `res.json()` or `res.sendFile()`, is
+ * compressed once the response is complete and `Vary: Accept-Encoding` is
+ * added to it.
+ *
+ * Responses are not compressed when their `Content-Type` is not matched, their
+ * body is smaller than the threshold, they already have a `Content-Encoding`
+ * (e.g. a precompressed static file), they are partial content, their
+ * `Cache-Control` header has a `no-transform` directive, or the request is a
+ * `HEAD` request. The `ETag` of a compressed response is weakened.
+ *
+ * ```javascript
+ * const compression = new CompressionMiddleware({ threshold: '2kb' })
+ * app.use(null, (req, res) => compression.run(req, res))
+ * ```
+ */
+export declare class CompressionMiddleware {
+  constructor(options?: JsCompressionOptions | undefined | null)
+  /**
+   * Negotiates the response's encoding; the response is compressed once it
+   * is complete. Always returns `true`.
+   */
+  run(request: Request, response: Response): boolean
+}
+
 /**
  * Create a new cookie parser middleware function using the given secret and
  * options.
@@ -43,14 +130,64 @@ export declare class FileStat {
   get birthtimeMs(): bigint
 }
 
+/**
+ * An error with an HTTP status, like the ones of the `http-errors` package.
+ * When a handler throws one, or passes one to `next()`, the final handler
+ * responds with its status and headers, and shows its message only when it
+ * is exposed.
+ *
+ * ```javascript
+ * server.get('/users/{id}', (req, res) => {
+ *   const user = users.get(req.params.id)
+ *   if (!user) {
+ *     throw new HttpError(404, 'No such user')
+ *   }
+ *   res.json(user)
+ * })
+ * ```
+ */
+export declare class HttpError {
+  /**
+   * Create an error of `status` (`500` by default), a 4xx or 5xx status.
+   * `message` defaults to the reason phrase of the status.
+   *
+   * `properties` are extra properties of the error. Among them, `expose`
+   * tells whether the message may be shown to the client, which by default
+   * it may for 4xx errors only, and `headers` are set on the response.
+   */
+  constructor(status?: number | StatusCode, message?: string, properties?: Record<string, unknown>)
+  /** The status of the error */
+  get status(): number
+  /** The status of the error, an alias of `status` */
+  get statusCode(): number
+  get message(): string
+  /**
+   * The name of the error, from the reason phrase of its status, e.g.
+   * `NotFoundError`
+   */
+  get name(): string
+  /** Whether the message may be shown to the client */
+  get expose(): boolean
+  /** Headers to set on the response */
+  get headers(): Record<string, string> | null
+  /** The extra properties the error was created with */
+  get properties(): Record<string, unknown>
+  /**
+   * The stack trace of the code creating the error, like the one of an
+   * `Error`
+   */
+  get stack(): string | null
+  toString(): string
+}
+
 /**
  * This is a built-in middleware function in Express. It parses incoming
  * requests with JSON payloads.
  *
  * Returns middleware that only parses JSON and only looks at requests where
  * the `Content-Type` header matches the `type` option. This parser accepts
- * any Unicode encoding of the body and supports automatic inflation of `gzip`
- * and `deflate` encodings.
+ * any Unicode encoding of the body and supports automatic inflation of `gzip`,
+ * `deflate`, `br` and `zstd` encodings.
  *
  * A new `body` object containing the parsed data is populated on the
  * `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -66,17 +203,63 @@ export declare class FileStat {
  */
 export declare class JsonMiddleware {
   constructor(options?: JsJsonOptions | undefined | null)
+  /**
+   * Parses the request's body, rejecting with a body-parser style error
+   * (`status`, `type`, ...) when it is too large, malformed or in an
+   * unsupported encoding.
+   */
   run(request: Request, response: Response): Promise<boolean>
 }
 
+/**
+ * Variables available to the views rendered by `res.render()`, e.g. the
+ * application-wide `server.locals` and the per-request `res.locals`. The JS
+ * values themselves are stored: `get()` returns the very value given to
+ * `set()`, be it a function, a class instance or a connection handle.
+ *
+ * `req.locals` and `res.locals` are the same map, shared by all the
+ * middlewares handling a request, whether written in JS or native.
+ *
+ * Unlike Express' `res.locals`, this is not a plain object: the variables
+ * are read and written with `get()`, `set()` and `assign()`, and assigning a
+ * property, e.g. `res.locals.user = user`, sets no variable.
+ *
+ * ```javascript
+ * server.locals.set('title', 'My App')
+ *
+ * app.use((req, res) => {
+ *   req.locals.set('db', db)
+ *   return true
+ * })
+ *
+ * app.get('/', async (req, res) => {
+ *   res.send(await res.locals.get('db').query('SELECT 1'))
+ * })
+ * ```
+ */
+export declare class Locals {
+  constructor()
+  /** The value of variable `name`, `undefined` when it is not set */
+  get(name: string): unknown
+  set(name: string, value: unknown): void
+  has(name: string): boolean
+  /** Remove variable `name`, returns whether it was set */
+  delete(name: string): boolean
+  clear(): void
+  /** Set the properties of `values` as variables */
+  assign(values: Record<string, unknown>): void
+  /** The variables as an object */
+  toJSON(): Record<string, unknown>
+}
+
 /**
  * This is a built-in middleware function in Express. It parses incoming
  * requests with JSON payloads.
  *
  * Returns middleware that only parses JSON and only looks at requests where
  * the `Content-Type` header matches the `type` option. This parser accepts
- * any Unicode encoding of the body and supports automatic inflation of `gzip`
- * and `deflate` encodings.
+ * any Unicode encoding of the body and supports automatic inflation of `gzip`,
+ * `deflate`, `br` and `zstd` encodings.
  *
  * A new `body` object containing the parsed data is populated on the
  * `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -92,6 +275,11 @@ export declare class JsonMiddleware {
  */
 export declare class RawMiddleware {
   constructor(options?: JsRawOptions | undefined | null)
+  /**
+   * Reads the request's body, rejecting with a body-parser style error
+   * (`status`, `type`, ...) when it is too large or in an unsupported
+   * encoding.
+   */
   run(request: Request, response: Response): Promise<boolean>
 }
 
@@ -136,6 +324,72 @@ export declare class Request {
    * [accepts](https://github.com/expressjs/accepts).
    */
   accepts(types: string | Array<string>): string | Array<string> | null
+  /**
+   * Contains the remote IP address of the request.
+   *
+   * When the `trust proxy` setting does not evaluate to `false`, the value
+   * of this property is derived from the left-most entry in the
+   * `X-Forwarded-For` header that is not a trusted proxy.
+   *
+   * ```javascript
+   * console.dir(req.ip)
+   * // => "127.0.0.1"
+   * ```
+   */
+  get ip(): string | null
+  /**
+   * When the `trust proxy` setting does not evaluate to `false`, this
+   * property contains an array of IP addresses specified in the
+   * `X-Forwarded-For` request header, from the client to the closest
+   * trusted proxy. Otherwise, it contains an empty array.
+   *
+   * For example, if `X-Forwarded-For` is `client, proxy1, proxy2`,
+   * `req.ips` would be `["client", "proxy1", "proxy2"]`, where `proxy2` is
+   * the furthest downstream.
+   */
+  get ips(): Array<string>
+  /**
+   * Contains the request protocol string: either `http` or (for TLS
+   * requests) `https`.
+   *
+   * When the `trust proxy` setting trusts the socket address, the value of
+   * the `X-Forwarded-Proto` header field, if present, is used instead.
+   */
+  get protocol(): string
+  /**
+   * A Boolean property that is true if a TLS connection is established.
+   * Equivalent to `req.protocol === 'https'`
+   */
+  get secure(): boolean
+  /**
+   * When the response is still "fresh" in the client's cache `true` is
+   * returned, otherwise `false` is returned to indicate that the client cache
+   * is now stale and the full response should be sent.
+   *
+   * When a client sends the `Cache-Control: no-cache` request header to
+   * indicate an end-to-end reload request, this property returns `false`.
+   *
+   * The request's `If-None-Match` and `If-Modified-Since` headers are
+   * checked against the response's `ETag` and `Last-Modified` headers; only
+   * `GET` and `HEAD` requests answered with a `2xx` or `304` status can be
+   * fresh.
+   *
+   * ```javascript
+   * console.dir(req.fresh)
+   * // => true
+   * ```
+   */
+  get fresh(): boolean
+  /**
+   * Indicates whether the request is "stale", and is the opposite of
+   * `req.fresh`.
+   *
+   * ```javascript
+   * console.dir(req.stale)
+   * // => true
+   * ```
+   */
+  get stale(): boolean
   /**
    * Returns the specified HTTP request header field (case-insensitive match).
    * The `Referrer` and `Referer` fields are interchangeable.
@@ -155,12 +409,87 @@ export declare class Request {
    */
   get(field: string): string | Buffer
   header(field: string): string | Buffer
+  /**
+   * Contains the host derived from the `Host` HTTP header, including the
+   * port.
+   *
+   * When the `trust proxy` setting trusts the socket address, the value of
+   * the `X-Forwarded-Host` header field, if present, is used instead.
+   *
+   * ```javascript
+   * // Host: "example.com:3000"
+   * console.dir(req.host)
+   * // => 'example.com:3000'
+   * ```
+   */
+  get host(): string | null
+  /**
+   * Contains the hostname derived from `req.host`, without the port.
+   *
+   * ```javascript
+   * // Host: "example.com:3000"
+   * console.dir(req.hostname)
+   * // => 'example.com'
+   * ```
+   */
+  get hostname(): string | null
+  /**
+   * An array of subdomains in the domain name of the request.
+   *
+   * ```javascript
+   * // Host: "tobi.ferrets.example.com"
+   * console.dir(req.subdomains)
+   * // => ["ferrets", "tobi"]
+   * ```
+   *
+   * The `subdomain offset` setting, which defaults to 2, is used for
+   * determining the beginning of the subdomain segments.
+   */
+  get subdomains(): Array<string>
+  /**
+   * The ID of the request, also sent in the `X-Request-Id` header of the
+   * response and written in the logs and error pages of the server.
+   *
+   * The ID is taken from the `X-Request-Id` header, or the header of the
+   * `request id header` setting, when the request comes from a trusted
+   * proxy (see the `trust proxy` setting). It is generated otherwise, as set
+   * by the `request id` setting.
+   *
+   * ```javascript
+   * app.use((req, res) => {
+   *   console.log(`[${req.id}] ${req.method} ${req.url}`)
+   *   return true
+   * })
+   * ```
+   */
+  get id(): string
   /**
    * Contains a string corresponding to the HTTP method of the request: `GET`,
    * `POST`, `PUT`, and so on.
    */
   get method(): string
   get params(): object
+  /**
+   * This property is an object containing a property for each query string
+   * parameter in the route. It is parsed per the `query parser` setting:
+   * flat with `"simple"` (the default), with nested objects and arrays with
+   * `"extended"`, or by the function the setting is assigned to.
+   *
+   * ```javascript
+   * // GET /search?q=tobi+ferret
+   * console.dir(req.query.q)
+   * // => 'tobi ferret'
+   *
+   * // GET /shoes?color[]=blue&color[]=black (extended)
+   * console.dir(req.query.color)
+   * // => ['blue', 'black']
+   * ```
+   *
+   * As `req.query`'s shape is based on user-controlled input, all
+   * properties and values in this object are untrusted and should be
+   * validated before trusting.
+   */
+  get query(): Record<string, unknown>
   /**
    * Range header parser.
    *
@@ -207,6 +536,14 @@ export declare class Request {
    * other user input.
    */
   get body(): string | unknown | Buffer | undefined
+  /** The settings and locals of the server handling the request */
+  get app(): Application
+  /**
+   * Variables of the request, readable and writable by all the
+   * middlewares handling it, JS or native, set with
+   * `req.locals.set(name, value)`. The same map as `res.locals`.
+   */
+  get locals(): Locals
   get cookies(): unknown | undefined
 }
 
@@ -239,6 +576,28 @@ export declare class Response {
    * ```
    */
   attachment(filePath?: string | undefined | null): void
+  /**
+   * Evaluates the conditional headers of the request (`If-Match`,
+   * `If-Unmodified-Since`, `If-None-Match`, `If-Modified-Since` and
+   * `If-Range`) against the validators of the current representation of the
+   * resource, in the order of RFC 9110.
+   *
+   * Returns `true` when the request can be performed. When a precondition
+   * fails, the response is sent and `false` is returned: `304 Not Modified`
+   * for `GET` and `HEAD` requests whose representation did not change, `412
+   * Precondition Failed` otherwise. When `If-Range` does not match, the
+   * request's `Range` header is dropped, so that the full representation is
+   * sent.
+   *
+   * ```javascript
+   * app.put('/articles/{id}', (req, res) => {
+   *   const article = articles.get(req.params.id)
+   *   if (!res.checkPreconditions({ etag: article.version })) return
+   *   // update the article
+   * })
+   * ```
+   */
+  checkPreconditions(options: PreconditionOptions): boolean
   /**
    * Clears the cookie with the specified name by sending a `Set-Cookie` header that sets its expiration date in the past.
    * This instructs the client that the cookie has expired and is no longer valid. For more information about available
@@ -288,7 +647,7 @@ export declare class Response {
    * | `encode`      | Function | A synchronous function used for cookie value encoding. Defaults to `encodeURIComponent`. |
    * | `expires`     | Date     | Expiry date of the cookie in GMT. If not specified or set to 0, creates a session cookie. |
    * | `httpOnly`    | Boolean  | Flags the cookie to be accessible only by the web server. |
-   * | `maxAge`      | Number   | Convenient option for setting the expiry time relative to the current time in milliseconds. |
+   * | `maxAge`      | Number or String | Convenient option for setting the expiry time relative to the current time in milliseconds, or as a string in [ms format](https://www.npmjs.org/package/ms), e.g. `'7 days'`. |
    * | `path`        | String   | Path for the cookie. Defaults to "/". |
    * | `partitioned` | Boolean  | Indicates that the cookie should be stored using partitioned storage. See [Cookies Having Independent Partitioned State (CHIPS)](https://developer.mozilla.org/en-US/docs/Web/Privacy/Partitioned_cookies) for more details. |
    * | `priority`    | String   | Value of the "Priority" Set-Cookie attribute. |
@@ -377,7 +736,7 @@ export declare class Response {
    * | Property | Description | Default |
    * | --- | --- | --- |
    * | `maxAge` | Sets the max-age property of the `Cache-Control` header in milliseconds or a string in [ms format](https://www.npmjs.org/package/ms) | 0 |
-   * | `root` | Root directory for relative filenames. | |
+   * | `root` | Root directory for relative filenames, or a `VirtualFileSystem` to serve them from. | |
    * | `lastModified` | Sets the `Last-Modified` header to the last modified date of the file on the OS. Set `false` to disable it. | Enabled |
    * | `headers` | Object containing HTTP headers to serve with the file. | |
    * | `dotfiles` | Option for serving dotfiles. Possible values are "allow", "deny", "ignore". | "ignore" |
@@ -409,7 +768,7 @@ export declare class Response {
    *
    * ```
    */
-  download(path: string, options?: DownloadOptions | undefined | null): Promise<unknown>
+  download(path: string, options?: DownloadOptions | undefined | null): Promise<undefined>
   /**
    *  Performs content-negotiation on the Accept HTTP header on the request
    *  object, when present. It uses `req.accepts()` to select a handler for the
@@ -490,8 +849,50 @@ export declare class Response {
    * res.json({ user: 'tobi' })
    * res.status(500).json({ error: 'message' })
    * ```
+   *
+   * The body is serialized exactly like `JSON.stringify` does it (`toJSON`
+   * methods are called, `undefined` properties are omitted and `BigInt`
+   * values throw), using the `json replacer`, `json spaces` and `json escape`
+   * settings of the server.
    */
-  json(body: string | number | boolean | object | null): void
+  json(body: unknown): void
+  /**
+   * Sends a JSON response with JSONP support. This method is identical to
+   * `res.json()`, except that it opts-in to JSONP callback support.
+   *
+   * ```javascript
+   * res.jsonp(null)
+   * // => callback(null)
+   *
+   * res.jsonp({ user: 'tobi' })
+   * // => callback({ "user": "tobi" })
+   *
+   * res.status(500).jsonp({ error: 'message' })
+   * // => callback({ "error": "message" })
+   * ```
+   *
+   * By default, the JSONP callback name is simply `callback`. Override this
+   * with the `jsonp callback name` setting.
+   *
+   * The following are some examples of JSONP responses using the same code:
+   *
+   * ```javascript
+   * // ?callback=foo
+   * res.jsonp({ user: 'tobi' })
+   * // => foo({ "user": "tobi" })
+   *
+   * app.set('jsonp callback name', 'cb')
+   *
+   * // ?cb=foo
+   * res.status(500).jsonp({ error: 'message' })
+   * // => foo({ "error": "message" })
+   * ```
+   *
+   * The callback name is restricted to letters, digits, `_`, `$`, `.`, `[`
+   * and `]`, and the script is sent as `text/javascript` with
+   * `X-Content-Type-Options: nosniff`.
+   */
+  jsonp(body: unknown): void
   /**
    * Joins the links provided as properties of the parameter to populate the response’s Link HTTP header field.
    *
@@ -531,6 +932,27 @@ export declare class Response {
    * _Referer_ headers or "/".
    */
   location(url: string): Response
+  /**
+   * Sends an RFC 9457 problem details document, with the
+   * `application/problem+json` content type. The `status` of the problem,
+   * if any, is set as the status of the response, and is the status of the
+   * response otherwise. `title` defaults to the reason phrase of the status.
+   *
+   * Members other than `type`, `status`, `title`, `detail` and `instance`
+   * are sent as extension members. The document is serialized like
+   * `res.json()` does it.
+   *
+   * ```javascript
+   * res.problem({
+   *   status: 403,
+   *   type: 'https://example.com/probs/out-of-credit',
+   *   detail: 'Your current balance is 30, but that costs 50.',
+   *   instance: '/account/12345/msgs/abc',
+   *   balance: 30,
+   * })
+   * ```
+   */
+  problem(problem: { status?: number | StatusCode, type?: string, title?: string, detail?: string, instance?: string, [extension: string]: unknown }): void
   /**
    * Redirects to the URL derived from the specified `path`, with specified
    * `status`, a positive integer that corresponds to an
@@ -585,6 +1007,51 @@ export declare class Response {
    * See also [Security best practices: Prevent open redirect vulnerabilities](http://expressjs.com/en/advanced/best-practice-security.html#prevent-open-redirects).
    */
   redirect(status: number | string, address?: string | undefined | null): void
+  /**
+   * Variables available to the views rendered for this request, over those
+   * of `server.locals`, set with `res.locals.set(name, value)`
+   */
+  get locals(): Locals
+  /**
+   * Renders a `view` and sends the rendered HTML string to the client.
+   * Optional parameters:
+   *
+   * - `locals`, an object whose properties define local variables for the
+   *   view, over those of `server.locals` and `res.locals`.
+   * - `callback`, a callback function. If provided, the method returns both
+   *   the possible error and rendered string, but does not perform an
+   *   automated response. When an error occurs, the method invokes
+   *   `callback(err)` internally.
+   *
+   * The `view` argument is a string that is the file path of the view file
+   * to render. This can be an absolute path, or a path relative to the
+   * `views` setting. If the path does not contain a file extension, then
+   * the `view engine` setting determines the file extension. The view is
+   * rendered by the engine registered with `server.engine()` for its
+   * extension; `.jinja` views are rendered by the built-in Jinja-style
+   * engine, with the locals converted like `JSON.stringify` does it.
+   *
+   * > The `view` argument performs file system operations like reading a
+   * > file from disk and evaluating Node.js modules, and as so for security
+   * > reasons should not contain input from the end-user.
+   *
+   * ```javascript
+   * // send the rendered view to the client
+   * await res.render('index')
+   *
+   * // if a callback is specified, the rendered HTML string has to be sent
+   * // explicitly
+   * await res.render('index', (err, html) => {
+   *   res.send(html)
+   * })
+   *
+   * // pass a local variable to the view
+   * await res.render('user', { name: 'Tobi' }, (err, html) => {
+   *   // ...
+   * })
+   * ```
+   */
+  render(view: string, locals?: Record<string, unknown> | ((err: Error | null, html?: string) => void), callback?: (err: Error | null, html?: string) => void): Promise<undefined>
   /**
    * Sends the HTTP response.
    *
@@ -626,8 +1093,22 @@ export declare class Response {
    * res.send({ user: 'tobi' })
    * res.send([1, 2, 3])
    * ```
+   *
+   * When the parameter is `null` or `undefined`, an empty body is sent.
+   *
+   * An `ETag` is generated for the body according to the `etag` setting of
+   * the server, unless the response already has one. When the request is
+   * fresh (see `req.fresh`), the response is turned into a
+   * `304 Not Modified` without a body.
+   *
+   * Buffer bodies are sent with `Accept-Ranges: bytes`, unless the header is
+   * already set to `none`, and `GET` requests for byte ranges are answered
+   * with `206 Partial Content`, as `multipart/byteranges` when several ranges
+   * are requested, or with `416 Range Not Satisfiable`. The `If-Range`
+   * header is checked against the `ETag` and `Last-Modified` headers of the
+   * response.
    */
-  send(body: string | number | boolean | null | Buffer | object): void
+  send(body?: unknown): void
   /**
    * Transfers the file at the given `path`. Sets the `Content-Type` response
    * HTTP header field based on the filename’s extension. Unless the `root`
@@ -650,7 +1131,7 @@ export declare class Response {
    * | Property | Description | Default |
    * | --- | --- | --- |
    * | `maxAge` | Sets the max-age property of the `Cache-Control` header in milliseconds or a string in [ms format](https://www.npmjs.org/package/ms) | 0 |
-   * | `root` | Root directory for relative filenames. | |
+   * | `root` | Root directory for relative filenames, or a `VirtualFileSystem` to serve them from. | |
    * | `lastModified` | Sets the `Last-Modified` header to the last modified date of the file on the OS. Set `false` to disable it. | Enabled |
    * | `headers` | Object containing HTTP headers to serve with the file. | |
    * | `dotfiles` | Option for serving dotfiles. Possible values are "allow", "deny", "ignore". | "ignore" |
@@ -709,7 +1190,7 @@ export declare class Response {
    * For more information, or if you have issues or concerns, see
    * [send](https://github.com/pillarjs/send).
    */
-  sendFile(path: string, options?: SendFileOptions | undefined | null): Promise<unknown>
+  sendFile(path: string, options?: SendFileOptions | undefined | null): Promise<undefined>
   /**
    * Sets the response HTTP status code to statusCode and sends the registered
    * status message as the text response body. If an unknown status code is
@@ -748,6 +1229,16 @@ export declare class Response {
    * ```
    */
   status(body: number | StatusCode): Response
+  /**
+   * The HTTP status of the response, `200` until it is set.
+   *
+   * ```javascript
+   * if (res.statusCode >= 400) {
+   *   // ...
+   * }
+   * ```
+   */
+  get statusCode(): number
   /**
    * Adds the field to the `Vary` response header, if it is not there already.
    *
@@ -757,6 +1248,8 @@ export declare class Response {
    */
   vary(field: string): void
   constructor()
+  /** The settings and locals of the server handling the request */
+  get app(): Application
   end(data?: string | Buffer | Uint8Array | undefined | null): void
   get req(): Request
 }
@@ -765,12 +1258,182 @@ export declare class Response {
 export declare class Server {
   /** Create a new server with a router */
   constructor()
-  delete(route: string, handler: JsHandlerFn): void
-  get(route: string, handler: JsHandlerFn): void
-  post(route: string, handler: JsHandlerFn): void
-  put(route: string, handler: JsHandlerFn): void
+  delete(route: string, handler: JsHandlerFn | JsHandlerFn[]): void
+  /**
+   * Route GET requests to `handler`, or to an array of handlers run in
+   * order. Handlers taking `next`, `(req, res, next)`, run the next one by
+   * calling `next()`, skip the remaining handlers of the route with
+   * `next('route')`, and pass an error to the error handlers with
+   * `next(err)`. Other handlers run the next one by returning `true`.
+   *
+   * Without `handler`, returns the value of setting `route` instead,
+   * `undefined` when it is not set.
+   *
+   * ```javascript
+   * server.get('/', (req, res) => res.send('hello'))
+   * server.get('/users/{id}', [
+   *   (req, res, next) => next(req.params.id === '0' ? 'route' : undefined),
+   *   (req, res) => res.send(`user ${req.params.id}`),
+   * ])
+   * server.get('json spaces')
+   * // => 2
+   * ```
+   */
+  get(route: string, handler?: JsHandlerFn | JsHandlerFn[]): unknown
+  post(route: string, handler: JsHandlerFn | JsHandlerFn[]): void
+  put(route: string, handler: JsHandlerFn | JsHandlerFn[]): void
   use(route: string | undefined | null, middleware: JsHandlerFn): void
+  /**
+   * Register an error handler, called as `handler(err, req, res, next)`
+   * when a previous middleware threw `err`, its promise rejected with it, or
+   * it called `next(err)`.
+   *
+   * The handler ends the chain, unless it calls `next()` to resume with the
+   * next middleware or `next(err)` to pass an error to the next error
+   * handler. Handlers that do not take `next` pass the error on by returning
+   * `true`. Errors left unhandled are sent by the final handler, with the
+   * `status` (or `statusCode`) and the `headers` of the error, see
+   * `server.errorPage()`.
+   *
+   * ```javascript
+   * server.useError((err, req, res) => {
+   *   res.status(err.status ?? 500).json({ error: err.message })
+   * })
+   * ```
+   */
+  useError(handler: (err: any, req: Request, res: Response, next: (err?: unknown) => void) => unknown): void
+  /**
+   * Register the error page of `status`, used by the final handler to
+   * respond to the errors of this status that no error handler handled, and
+   * for `404`, to the requests that no middleware handled.
+   *
+   * The page is either a view, rendered by `res.render()`, or a handler
+   * called like an error handler; when the handler does not end the chain,
+   * the default response is sent. The response already has the status and
+   * the headers of the error, which is described by the `error` local:
+   *
+   * - `status`: the status of the error
+   * - `title`: the reason phrase of the status
+   * - `message`: the message of the error, when it is exposed or outside of
+   *   production, or the reason phrase of the status
+   * - `requestId`: the ID of the request, `req.id`
+   * - `stack`: the stack trace of the error, outside of production only
+   *
+   * Without an error page, the default response is an HTML page, JSON, or a
+   * problem details document (RFC 9457), negotiated from the `Accept`
+   * header of the request.
+   *
+   * ```javascript
+   * server.errorPage(404, 'errors/not-found')
+   * server.errorPage(503, (err, req, res) => {
+   *   res.json({ retry: true })
+   * })
+   * ```
+   */
+  errorPage(status: number, page: string | ((err: any, req: Request, res: Response, next: (err?: unknown) => void) => unknown)): void
   acmeConfigMeta(config: AcmeConfigMeta): void
+  /**
+   * Assign setting `name` to `value`. Other names store any JSON value,
+   * read with `server.get(name)`. Settings apply to the whole server: there
+   * are no mounted sub-apps, so they cannot be overridden per sub-app.
+   * Supported settings:
+   *
+   * - `env`: the environment mode. Defaults to `NODE_ENV`, or `development`.
+   *   In `production`, the final handler hides the messages of errors that
+   *   are not exposed; otherwise it shows their stack traces
+   * - `trust proxy`: the proxies trusted to set the `X-Forwarded-For`,
+   *   `X-Forwarded-Host` and `X-Forwarded-Proto` headers read by `req.ip`,
+   *   `req.ips`, `req.host`, `req.hostname` and `req.protocol`: `true` for
+   *   all, a number of hops from the server, or addresses and subnets as a
+   *   comma-separated string or an array, including the names `loopback`,
+   *   `linklocal` and `uniquelocal`. Disabled by default
+   * - `x-powered-by`: send the `X-Powered-By: hyperjs` header. Disabled by
+   *   default
+   * - `problem details`: send the HTTP errors left unhandled, such as the
+   *   `HttpError`s and the errors of the body parsers, as problem details
+   *   documents (RFC 9457), whatever the `Accept` header of the request.
+   *   Disabled by default
+   * - `request id`: how the IDs of requests, `req.id`, are generated:
+   *   `"counter"` (the default) for a counter of the requests of the
+   *   process, `"uuid"` for random UUIDs or `"ulid"` for ULIDs
+   * - `request id header`: the header holding the ID of a request, used
+   *   instead of a generated one when it comes from a trusted proxy, and
+   *   set on the response. Defaults to `X-Request-Id`; `false` disables it
+   * - `log level`: the level of the diagnostic logs of the server, written
+   *   to stderr: `"off"`, `"error"`, `"warn"` (the default), `"info"`,
+   *   `"debug"` or `"trace"`. The level is global to the process and set by
+   *   `server.listen()`; the `RUST_LOG` environment variable overrides it.
+   *   Requests are logged by the `AccessLogMiddleware`
+   * - `case sensitive routing`: `/Foo` and `/foo` are different routes.
+   *   Disabled by default
+   * - `strict routing`: `/foo` and `/foo/` are different routes. Disabled by
+   *   default
+   * - `query parser`: how `req.query` is parsed: `"simple"` (the default),
+   *   `"extended"` for nested objects and arrays, `false` to disable it, or
+   *   a function called with the query string that returns `req.query`
+   * - `subdomain offset`: the number of dot-separated parts of the host
+   *   removed to access `req.subdomains`. Defaults to 2
+   * - `json replacer`: the `replacer` argument of `JSON.stringify` used by
+   *   `res.json()` and `res.jsonp()`, a function or an array of keys
+   * - `json spaces`: the `space` argument of `JSON.stringify` used by
+   *   `res.json()` and `res.jsonp()`
+   * - `json escape`: escape `<`, `>` and `&` as unicode sequences in
+   *   `res.json()` and `res.jsonp()` output
+   * - `jsonp callback name`: the query parameter holding the callback name
+   *   of `res.jsonp()`. Defaults to `callback`
+   * - `etag`: `ETag` generation of `res.send()`: `true` or `"weak"` (the
+   *   default) for weak ETags, `"strong"` for strong ones, `false` to disable
+   *   it, or a function called as `etag(body, encoding)` that returns the
+   *   `ETag`
+   * - `views`: the directory, or an array of directories, searched in order
+   *   for the views of `res.render()`. Defaults to `views`
+   * - `view engine`: the extension of view names given without one
+   * - `view cache`: cache the views found and the templates parsed, enabled
+   *   by default when `NODE_ENV` is `production`
+   *
+   * ```javascript
+   * server.set('json spaces', 2)
+   * server.set('json escape', true)
+   * server.set('views', ['views', 'shared/views'])
+   * server.set('trust proxy', 'loopback, 10.0.0.0/8')
+   * ```
+   *
+   * The routing settings apply to the routes registered before and after
+   * them; settings assigned after `server.listen()` are ignored.
+   */
+  set(name: string, value: unknown): void
+  /** Set boolean setting `name` to `true` */
+  enable(name: string): void
+  /** Set boolean setting `name` to `false` */
+  disable(name: string): void
+  /**
+   * Whether setting `name` is truthy
+   *
+   * ```javascript
+   * server.enabled('trust proxy')
+   * // => false
+   * ```
+   */
+  enabled(name: string): boolean
+  /** Whether setting `name` is falsy */
+  disabled(name: string): boolean
+  /**
+   * Register the template engine rendering the views with extension `ext`:
+   * `'jinja'` for the built-in Jinja-style engine, or a function called as
+   * `engine(path, locals, callback)` that renders the file at `path` and
+   * calls `callback(err, html)`, or returns the HTML or a promise of it.
+   *
+   * ```javascript
+   * server.engine('html', 'jinja')
+   * server.engine('md', async (path, locals) => marked(await readFile(path, 'utf8')))
+   * ```
+   */
+  engine(ext: string, engine: 'jinja' | ((path: string, locals: Record<string, unknown>, callback: (err: Error | null, html?: string) => void) => string | Promise<string> | void)): void
+  /**
+   * Variables available to all the views rendered by `res.render()`, set
+   * with `server.locals.set(name, value)`
+   */
+  get locals(): Locals
   listen(addr: string): void
 }
 
@@ -783,10 +1446,11 @@ export declare class Server {
  * > cache to improve performance of serving static assets.
  *
  * The `root` argument specifies the root directory from which to serve static
- * assets. The function determines the file to serve by combining `req.url`
- * with the provided `root` directory. When a file is not found, instead of
- * sending a 404 response, it instead calls `next()` to move on to the next
- * middleware, allowing for stacking and fall-backs.
+ * assets, or a `VirtualFileSystem` of files held in memory. The function
+ * determines the file to serve by combining `req.url` with the provided
+ * `root`. When a file is not found, instead of sending a 404 response, it
+ * instead calls `next()` to move on to the next middleware, allowing for
+ * stacking and fall-backs.
  *
  * Example of using the StaticMiddleware
  * Here is an example of using the Static middleware with an elaborate options object:
@@ -808,8 +1472,8 @@ export declare class Server {
  * ```
  */
 export declare class StaticMiddleware {
-  constructor(root: string, options?: JsStaticOptions | undefined | null)
-  run(request: Request, response: Response): Promise<unknown>
+  constructor(root: string | VirtualFileSystem, options?: JsStaticOptions | undefined | null)
+  run(request: Request, response: Response): Promise<boolean>
 }
 
 export declare class StatusCode {
@@ -890,8 +1554,10 @@ export declare class StatusCode {
  *
  * Returns middleware that parses all bodies as a string and only looks at
  * requests where the `Content-Type` header matches the `type` option. This
- * parser accepts any Unicode encoding of the body and supports automatic
- * inflation of `gzip` and `deflate` encodings.
+ * parser decodes the body using the `charset` of the `Content-Type` header
+ * (or `defaultCharset`), accepting any encoding from the WHATWG Encoding
+ * Standard, and supports automatic inflation of `gzip`, `deflate`, `br` and
+ * `zstd` encodings. Bodies in an unknown charset are rejected with `415`.
  *
  * A new `body` string containing the parsed data is populated on the
  * `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -907,6 +1573,11 @@ export declare class StatusCode {
  */
 export declare class TextMiddleware {
   constructor(options?: JsTextOptions | undefined | null)
+  /**
+   * Parses the request's body, rejecting with a body-parser style error
+   * (`status`, `type`, ...) when it is too large or in an unsupported
+   * encoding or charset.
+   */
   run(request: Request, response: Response): Promise<boolean>
 }
 
@@ -916,8 +1587,8 @@ export declare class TextMiddleware {
  *
  * Returns middleware that only parses urlencoded bodies and only looks at
  * requests where the `Content-Type` header matches the `type` option. This
- * parser accepts only UTF-8 encoding of the body and supports automatic
- * inflation of `gzip` and `deflate` encodings.
+ * parser accepts UTF-8 and ISO-8859-1 encodings of the body and supports
+ * automatic inflation of `gzip`, `deflate`, `br` and `zstd` encodings.
  *
  * A new `body` object containing the parsed data is populated on the
  * `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -935,6 +1606,11 @@ export declare class TextMiddleware {
  */
 export declare class UrlencodedMiddleware {
   constructor(options?: JsUrlencodedOptions | undefined | null)
+  /**
+   * Parses the request's body, rejecting with a body-parser style error
+   * (`status`, `type`, ...) when it is too large, malformed or in an
+   * unsupported encoding or charset.
+   */
   run(request: Request, response: Response): Promise<boolean>
 }
 
@@ -946,6 +1622,40 @@ export declare class Version {
   static http3(): Version
 }
 
+/**
+ * Files served by `StaticMiddleware` and `res.sendFile` in place of a root
+ * directory, e.g. the assets of an application bundled into its binary.
+ * `ETag`, `Last-Modified`, ranges, precompressed `.br` and `.gz` variants and
+ * dotfiles are handled as for files on disk.
+ *
+ * ```javascript
+ * const assets = VirtualFileSystem.overlay([
+ *   'public',
+ *   VirtualFileSystem.fromArchive('ui.tar'),
+ * ])
+ *
+ * app.use(new StaticMiddleware(assets))
+ * ```
+ */
+export declare class VirtualFileSystem {
+  /**
+   * Files held in memory, given as an object of paths to contents, e.g.
+   * `{ 'index.html': '<!DOCTYPE html>…', 'logo.png': buffer }`. The contents
+   * are copied, and last modified now.
+   */
+  static fromFiles(files: Record<string, Buffer | string>): VirtualFileSystem
+  /**
+   * The files of a `.tar` or `.zip` archive, read into memory once, when the
+   * file system is created.
+   */
+  static fromArchive(path: string): VirtualFileSystem
+  /**
+   * Layers of root directories and file systems. A path is served by the
+   * first layer holding it.
+   */
+  static overlay(layers: Array<string | VirtualFileSystem>): VirtualFileSystem
+}
+
 export interface AcmeConfigMeta {
   domains: Array<string>
   contactEmail: string
@@ -961,7 +1671,7 @@ export interface CookieOptions {
   encode?: (arg: string) => string
   expires?: Date | ClearCookie
   httpOnly?: boolean
-  maxAge?: number
+  maxAge?: number | string
   path?: string
   partitioned?: boolean
   priority?: string
@@ -971,8 +1681,8 @@ export interface CookieOptions {
 }
 
 export interface DownloadOptions {
-  maxAge?: number
-  root?: string
+  maxAge?: number | string
+  root?: string | VirtualFileSystem
   lastModified?: boolean
   headers?: object
   dotfiles?: string
@@ -981,6 +1691,111 @@ export interface DownloadOptions {
   immutable?: boolean
 }
 
+export interface JsAccessLogFile {
+  /**
+   * The path of the log file, created if it does not exist. Lines are
+   * appended to it.
+   */
+  path: string
+  /**
+   * The size of the file, once reached, at which the file is rotated: it is
+   * renamed after the time of the rotation, e.g.
+   * `access.log.20261019-134501`, and a new file is created. If this is a
+   * number, then the value specifies the number of bytes; if it is a
+   * string, the value is passed to the
+   * [bytes](https://docs.rs/byte-unit/latest/byte_unit/) library for
+   * parsing.
+   *
+   * Default = no rotation by size
+   */
+  size?: number | string
+  /**
+   * The time after which the file is rotated. If this is a number, then the
+   * value specifies the number of milliseconds; if it is a string, the value
+   * is passed to the [ms](https://www.npmjs.org/package/ms) library for
+   * parsing, e.g. `"1d"`.
+   *
+   * Default = no rotation by time
+   */
+  interval?: number | string
+  /**
+   * The number of rotated files to keep, the oldest ones are removed.
+   *
+   * Default = all of them
+   */
+  maxFiles?: number
+}
+
+export interface JsAccessLogOptions {
+  /**
+   * The format of the lines: `combined`, `common`, `short`, `tiny`, `dev`
+   * (colored by status), `json` (one JSON object per line), or a format
+   * string of tokens, e.g. `:method :url :status - :response-time ms`.
+   *
+   * The tokens are the ones of
+   * [morgan](https://www.npmjs.com/package/morgan): `:date[format]`,
+   * `:http-version`, `:method`, `:referrer`, `:remote-addr`,
+   * `:remote-user`, `:req[header]`, `:res[header]`,
+   * `:response-time[digits]`, `:status`, `:url` and `:user-agent`, plus
+   * `:id`, the ID of the request, and the custom `tokens`. Tokens without a
+   * value are logged as `-`. `:res[content-length]` is the size of the body
+   * when the response has no `Content-Length`.
+   *
+   * Default = "combined"
+   */
+  format?: string
+  /**
+   * Custom tokens, each called as `token(req, res, arg)` once the response
+   * is complete, for `:name[arg]`, and returning the value of the token.
+   * The `json` format has a member for each of them.
+   */
+  tokens?: Record<string, JsTokenFn>
+  /**
+   * This option, if supplied, is called as `skip(req, res)` once the
+   * response is complete; the request is not logged when it returns `true`.
+   */
+  skip?: JsSkipFn
+  /**
+   * Where the lines are written: `"stdout"`, `"stderr"`, a file, or a
+   * function called as `write(line)` for every line, without line break.
+   *
+   * Default = "stdout"
+   */
+  stream?: string | JsWriteFn | JsAccessLogFile
+}
+
+export interface JsCompressionOptions {
+  /**
+   * The byte threshold for the response body size before compression is
+   * considered for the response. If this is a number, then the value
+   * specifies the number of bytes; if it is a string, the value is passed to
+   * the [bytes](https://docs.rs/byte-unit/latest/byte_unit/) library for
+   * parsing. Bodies of an unknown size (e.g. streamed files without a
+   * `Content-Length`) are always compressed.
+   *
+   * Default = "1kb"
+   */
+  threshold?: number | string
+  /**
+   * The level of compression to apply, passed to the negotiated encoder: `0`
+   * to `9` for `gzip` and `deflate`, `0` to `11` for `br` and `1` to `22` for
+   * `zstd`. Out of range levels are clamped.
+   *
+   * Default = the default level of each encoder
+   */
+  level?: number
+  /**
+   * The media types to compress. This option can be a string or an array of
+   * strings, each passed to the
+   * [mime_guess](https://docs.rs/mime_guess/latest/mime_guess/) library: an
+   * extension name (like `json`), a mime type (like `text/plain`), or a mime
+   * type with a wildcard (like `*\/*` or `text/*`).
+   *
+   * Default = the compressible types (`text/*`, JSON, JavaScript, XML, ...)
+   */
+  typ?: string | Array<string>
+}
+
 export interface JsCookieParserOptions {
   /**
    * If `true`, the cookie value will be percent-decoded
@@ -991,10 +1806,27 @@ export interface JsCookieParserOptions {
   percentDecode?: boolean
 }
 
+export interface JsDirectoryListingOptions {
+  /**
+   * Show an icon in front of each entry of HTML listings.
+   *
+   * Default: true
+   */
+  icons?: boolean
+  /**
+   * The order of the entries: "name", "size" or "modified" (most recent
+   * first). Directories are always listed first.
+   *
+   * Default: "name"
+   */
+  sort?: string
+}
+
 export interface JsJsonOptions {
   /**
    * Enables or disables handling deflated (compressed) bodies; when disabled,
-   * deflated bodies are rejected.
+   * deflated bodies are rejected with `415 Unsupported Media Type`. The
+   * `limit` option applies to the inflated body.
    *
    * Default = true
    */
@@ -1009,11 +1841,60 @@ export interface JsJsonOptions {
    */
   limit?: number | string
   /**
-   * TODO: Add support for reviver option
+   * The `reviver` option behaves like the second argument of `JSON.parse`:
+   * it is called as `reviver(key, value)` with the holder object as `this`
+   * for every value of the parsed body, bottom-up, and its return value
+   * replaces the value (`undefined` removes it), so `req.body` may hold
+   * values without a JSON form, e.g. a `Date`. An error thrown by the
+   * reviver fails the parse with a `400` error.
+   *
+   * Default = none
+   */
+  reviver?: JsReviverFn
+  /**
+   * What to do with `__proto__` keys, which can poison the prototype of an
+   * object built from the body: `"error"` fails the parse with a `400`
+   * error, `"remove"` drops the key and `"ignore"` keeps it.
+   *
+   * Default = "ignore"
+   */
+  protoAction?: string
+  /**
+   * What to do with `constructor` keys whose value is an object with a
+   * `prototype` key: `"error"`, `"remove"` or `"ignore"`, like
+   * `protoAction`.
+   *
+   * Default = "ignore"
+   */
+  constructorAction?: string
+  /**
+   * Maximum nesting depth of arrays and objects, a top level array or object
+   * having a depth of 1. Deeper bodies fail with a `400` error.
    *
    * Default = none
    */
-  reviver?: any
+  maxDepth?: number
+  /**
+   * Maximum number of elements in an array. Longer arrays fail the parse
+   * with a `400` error.
+   *
+   * Default = none
+   */
+  maxArrayLength?: number
+  /**
+   * Maximum number of characters in a string, whether a key or a value.
+   * Longer strings fail the parse with a `400` error.
+   *
+   * Default = none
+   */
+  maxStringLength?: number
+  /**
+   * Enables or disables failing the parse with a `400` error when an object
+   * has the same key more than once; when disabled the last value wins.
+   *
+   * Default = false
+   */
+  rejectDuplicateKeys?: boolean
   /**
    * Enables or disables only accepting arrays and objects; when disabled will
    * accept anything `serde_json::from_slice` accepts.
@@ -1049,7 +1930,8 @@ export interface JsJsonOptions {
 export interface JsRawOptions {
   /**
    * Enables or disables handling deflated (compressed) bodies; when disabled,
-   * deflated bodies are rejected.
+   * deflated bodies are rejected with `415 Unsupported Media Type`. The
+   * `limit` option applies to the inflated body.
    *
    * Default = true
    */
@@ -1088,6 +1970,58 @@ export interface JsRawOptions {
   verify?: JsVerifyFn
 }
 
+export interface JsSpaFallbackOptions {
+  /**
+   * The file sent in place of missing files, resolved like a request path.
+   *
+   * Default: "index.html"
+   */
+  file?: string
+  /**
+   * Patterns of the request paths eligible for the fallback, where `*`
+   * matches within a path segment and `**` across segments. Every path is
+   * eligible when empty.
+   *
+   * Default: []
+   */
+  include?: Array<string>
+  /**
+   * Patterns of the request paths that never get the fallback, e.g.
+   * `['/api/**']`.
+   *
+   * Default: []
+   */
+  exclude?: Array<string>
+}
+
+export interface JsStaticCacheOptions {
+  /**
+   * The total size of the cached files, including their compressed variants.
+   * If this is a number, then the value specifies the number of bytes; if it
+   * is a string, the value is passed to the
+   * [bytes](https://docs.rs/byte-unit/latest/byte_unit/) library for
+   * parsing.
+   *
+   * Default: "32mb"
+   */
+  maxSize?: number | string
+  /**
+   * The size of the largest file to cache, as a number of bytes or a string
+   * like `maxSize`.
+   *
+   * Default: "256kb"
+   */
+  maxFileSize?: number | string
+  /**
+   * How long, in milliseconds, a cached file is served before its
+   * modification time is checked again. Set to `0` to check the file on
+   * every request.
+   *
+   * Default: 1000
+   */
+  revalidate?: number
+}
+
 export interface JsStaticOptions {
   /**
    * Determines how dotfiles (files or directories that begin with a dot “.”)
@@ -1164,11 +2098,11 @@ export interface JsStaticOptions {
   lastModified?: boolean
   /**
    * Set the max-age property of the Cache-Control header in milliseconds or a
-   * string in [jackdauer](https://docs.rs/jackdauer/0.1.2/jackdauer/) format.
+   * string in [ms](https://www.npmjs.org/package/ms) format, e.g. `'1d'`.
    *
    * Default: 0
    */
-  maxAge?: number
+  maxAge?: number | string
   /**
    * Redirect to trailing “/” when the pathname is a directory.
    *
@@ -1211,19 +2145,60 @@ export interface JsStaticOptions {
    * Default: true
    */
   cacheControl?: boolean
+  /**
+   * List the content of directories without an index file, e.g. for
+   * internal file shares. The listing is rendered as HTML, JSON or plain
+   * text, negotiated from the request's `Accept` header. Dotfiles are only
+   * listed when `dotfiles` is "allow".
+   *
+   * Set to `true` or to an object of options to enable listings.
+   *
+   * Default: false
+   */
+  directoryListing?: boolean | JsDirectoryListingOptions
+  /**
+   * Keep small files in memory, along with their `Content-Type`, `ETag`,
+   * `Last-Modified`, `Cache-Control` and `Accept-Ranges` headers and their
+   * `br` and `gzip` compressed variants, so that hot assets are served
+   * without touching the disk. The headers set by other middlewares, e.g.
+   * cookies, are not cached. The least recently used files are
+   * evicted once `maxSize` is reached, and changed files are reloaded once
+   * their modification time is checked again.
+   *
+   * Set to `true` or to an object of options to enable the cache.
+   *
+   * Default: false
+   */
+  cache?: boolean | JsStaticCacheOptions
+  /**
+   * Serve a single-page application: missing files are answered with its
+   * `index.html`, sent with `Cache-Control: no-cache`, so that client-side
+   * routes can be loaded directly. Only `GET` and `HEAD` requests accepting
+   * `text/html`, for paths without a file extension, get the fallback;
+   * missing assets like `/main.js` are still `404`s. Exclude API routes with
+   * the `exclude` option.
+   *
+   * Set to `true` or to an object of options to enable the fallback.
+   *
+   * Default: false
+   */
+  spaFallback?: boolean | JsSpaFallbackOptions
 }
 
 export interface JsTextOptions {
   /**
    * Specify the default character set for the text content if the charset is
-   * not specified in the `Content-Type` header of the request.
+   * not specified in the `Content-Type` header of the request. Any
+   * [WHATWG encoding label](https://encoding.spec.whatwg.org/#names-and-labels)
+   * is accepted, e.g. `latin1` or `shift_jis`.
    *
    * Default = "utf-8"
    */
   defaultCharset?: string
   /**
    * Enables or disables handling deflated (compressed) bodies; when disabled,
-   * deflated bodies are rejected.
+   * deflated bodies are rejected with `415 Unsupported Media Type`. The
+   * `limit` option applies to the inflated body.
    *
    * Default = true
    */
@@ -1272,7 +2247,8 @@ export interface JsUrlencodedOptions {
   extended?: boolean
   /**
    * Enables or disables handling deflated (compressed) bodies; when disabled,
-   * deflated bodies are rejected.
+   * deflated bodies are rejected with `415 Unsupported Media Type`. The
+   * `limit` option applies to the inflated body.
    *
    * Default = true
    */
@@ -1323,6 +2299,36 @@ export interface JsUrlencodedOptions {
    * Default = 32
    */
   depth?: number
+  /**
+   * The default charset to parse as, if not specified in the `Content-Type`
+   * of the request. Supported values are `utf-8` and `iso-8859-1`; other
+   * charsets are rejected with `415 Unsupported Media Type`.
+   *
+   * Default = "utf-8"
+   */
+  defaultCharset?: string
+  /**
+   * Whether to let the value of the `utf8` parameter take precedence as the
+   * charset selector. Forms can include a hidden `utf8=✓` field to signal
+   * the charset they were submitted in; the field is removed from
+   * `req.body`.
+   *
+   * Default = false
+   */
+  charsetSentinel?: boolean
+}
+
+export interface PreconditionOptions {
+  /**
+   * The entity tag of the current representation of the resource, e.g.
+   * `"v1"` or `W/"v1"`. A value without quotes is taken as a strong tag.
+   */
+  etag?: string
+  /**
+   * The last modification date of the current representation of the
+   * resource, as a `Date` or an HTTP date string.
+   */
+  lastModified?: Date | string
 }
 
 /** Represents a single byte range with start and end positions */
@@ -1343,8 +2349,8 @@ export interface Ranges {
 }
 
 export interface SendFileOptions {
-  maxAge?: number
-  root?: string
+  maxAge?: number | string
+  root?: string | VirtualFileSystem
   lastModified?: boolean
   headers?: object
   dotfiles?: string
@@ -1353,4 +2359,8 @@ export interface SendFileOptions {
   immutable?: boolean
 }
 
-export declare function serializeNapiObject(obj: object): string
+/**
+ * Serialize `value` with `JSON.stringify`. Returns `undefined` when the value
+ * has no JSON representation (e.g. `undefined` or a function).
+ */
+export declare function serializeNapiObject(value: unknown): string | undefined
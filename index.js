@@ -576,10 +576,15 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.AccessLogMiddleware = nativeBinding.AccessLogMiddleware
+module.exports.Application = nativeBinding.Application
 module.exports.Bytes = nativeBinding.Bytes
+module.exports.CompressionMiddleware = nativeBinding.CompressionMiddleware
 module.exports.CookieParserMiddleware = nativeBinding.CookieParserMiddleware
 module.exports.FileStat = nativeBinding.FileStat
+module.exports.HttpError = nativeBinding.HttpError
 module.exports.JsonMiddleware = nativeBinding.JsonMiddleware
+module.exports.Locals = nativeBinding.Locals
 module.exports.RawMiddleware = nativeBinding.RawMiddleware
 module.exports.Request = nativeBinding.Request
 module.exports.Response = nativeBinding.Response
@@ -589,4 +594,5 @@ module.exports.StatusCode = nativeBinding.StatusCode
 module.exports.TextMiddleware = nativeBinding.TextMiddleware
 module.exports.UrlencodedMiddleware = nativeBinding.UrlencodedMiddleware
 module.exports.Version = nativeBinding.Version
+module.exports.VirtualFileSystem = nativeBinding.VirtualFileSystem
 module.exports.serializeNapiObject = nativeBinding.serializeNapiObject
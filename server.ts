@@ -3,6 +3,7 @@ import {
   Request,
   Response,
   StatusCode,
+  HttpError,
   TextMiddleware,
   JsonMiddleware,
   RawMiddleware,
@@ -10,9 +11,14 @@ import {
   FileStat,
   UrlencodedMiddleware,
   CookieParserMiddleware,
+  CompressionMiddleware,
+  AccessLogMiddleware,
+  VirtualFileSystem,
 } from './index.js'
 import path from 'path'
 import process from 'process'
+import { brotliCompressSync } from 'zlib'
+import { readFile } from 'fs/promises'
 
 const __dirname = process.cwd()
 
@@ -23,6 +29,17 @@ const __dirname = process.cwd()
 // Create app with router
 const app = new Server()
 
+// JSON responses: drop `password` keys, escape HTML characters
+app.set('json replacer', (key: string, value: unknown) => (key === 'password' ? undefined : value))
+app.set('json escape', true)
+
+// Trust the `X-Forwarded-*` headers set by a proxy on this machine
+app.set('trust proxy', 'loopback')
+app.set('query parser', 'extended')
+
+// Send the `X-Powered-By` header, disabled by default
+app.enable('x-powered-by')
+
 // ============================================================================
 // LETSENCRYPT: How to configure
 // ============================================================================
@@ -65,6 +82,22 @@ app.get('/users/{user_id}', async (req: Request, res: Response) => {
   res.status(200).json(params)
 })
 
+// GET | Request properties derived from the settings
+app.get('/request-info/{name}', (req: Request, res: Response) => {
+  res.json({
+    name: (req.params as any).name,
+    ip: req.ip,
+    ips: req.ips,
+    protocol: req.protocol,
+    secure: req.secure,
+    hostname: req.hostname,
+    subdomains: req.subdomains,
+    query: req.query,
+    trustProxy: req.app.get('trust proxy'),
+    id: req.id,
+  })
+})
+
 // Text middleware
 const textMiddleware = new TextMiddleware({
   limit: '100mb',
@@ -93,6 +126,64 @@ app.post('/json-echo', async (req: Request, res: Response) => {
   else res.status(500).send(`Expected object, found '${typeof req.body}'`)
 })
 
+// JSON middleware with a small limit, reporting its errors' properties
+const smallJsonMiddleware = new JsonMiddleware({
+  limit: 16,
+})
+app.use('/json-small', (req: Request, res: Response) =>
+  smallJsonMiddleware.run(req, res).catch((err) => {
+    const { status, type, expose, limit, length, body } = err
+    res.status(status).json({ status, type, expose, limit, length, body })
+    return false
+  }),
+)
+
+app.post('/json-small', async (req: Request, res: Response) => {
+  res.status(200).send(req.body)
+})
+
+// JSON middleware with a reviver
+const revivingJsonMiddleware = new JsonMiddleware({
+  reviver(key: string, value: unknown) {
+    if (key === 'secret') return undefined
+    if (typeof value === 'number') return value * 2
+    return value
+  },
+})
+app.use('/json-revive', (req: Request, res: Response) => revivingJsonMiddleware.run(req, res))
+
+app.post('/json-revive', async (req: Request, res: Response) => {
+  res.status(200).send(req.body)
+})
+
+// JSON middleware with a reviver building values without a JSON form
+const dateJsonMiddleware = new JsonMiddleware({
+  reviver(key: string, value: unknown) {
+    return key === 'at' ? new Date(value as string) : value
+  },
+})
+app.use('/json-revive-date', (req: Request, res: Response) => dateJsonMiddleware.run(req, res))
+
+app.post('/json-revive-date', async (req: Request, res: Response) => {
+  const { at } = req.body
+  res.status(200).json({ isDate: at instanceof Date, year: at.getUTCFullYear() })
+})
+
+// JSON middleware with structural limits
+const guardedJsonMiddleware = new JsonMiddleware({
+  protoAction: 'error',
+  constructorAction: 'remove',
+  maxDepth: 3,
+  maxArrayLength: 4,
+  maxStringLength: 16,
+  rejectDuplicateKeys: true,
+})
+app.use('/json-guarded', (req: Request, res: Response) => guardedJsonMiddleware.run(req, res))
+
+app.post('/json-guarded', async (req: Request, res: Response) => {
+  res.status(200).send(req.body)
+})
+
 // RAW middleware
 const rawMiddleware = new RawMiddleware()
 app.use('/raw-echo', (req: Request, res: Response) => rawMiddleware.run(req, res))
@@ -199,12 +290,81 @@ app.get('/redirect', async (_req: Request, res: Response) => {
   res.redirect('https://example.com')
 })
 
+// JSON values
+app.get('/json-values', async (_req: Request, res: Response) => {
+  res.json({
+    float: 1.5,
+    floats: [0.1, 2.5],
+    date: new Date(0),
+    missing: undefined,
+    password: 'hunter2',
+    html: '<b>&</b>',
+    nested: { toJSON: () => 'nested' },
+  })
+})
+
+app.get('/json-bigint', async (_req: Request, res: Response) => {
+  try {
+    res.json({ big: 1n })
+  } catch (e) {
+    res.status(500).send((e as Error).message)
+  }
+})
+
+app.get('/send-float', async (_req: Request, res: Response) => {
+  res.send(1.5)
+})
+
+// Problem details documents
+app.get('/problem', async (_req: Request, res: Response) => {
+  res.problem({
+    status: 403,
+    type: 'https://example.com/probs/out-of-credit',
+    detail: 'Your current balance is 30, but that costs 50.',
+    instance: '/account/12345/msgs/abc',
+    balance: 30,
+  })
+})
+
+// JSONP, with the callback name from the `callback` query parameter
+app.get('/jsonp', async (_req: Request, res: Response) => {
+  res.jsonp({ user: 'tobi', password: 'hunter2', separators: '\u2028\u2029' })
+})
+
+// ETag and freshness
+app.get('/etag', async (_req: Request, res: Response) => {
+  res.send('cacheable body')
+})
+
+app.get('/fresh', async (req: Request, res: Response) => {
+  res.set('ETag', '"fresh-v1"')
+  res.json({ fresh: req.fresh, stale: req.stale })
+})
+
+// Preconditions
+const article = { etag: '"article-v1"', lastModified: 'Sat, 01 Jan 2000 00:00:00 GMT' }
+
+app.get('/preconditions', async (req: Request, res: Response) => {
+  if (!res.checkPreconditions(article)) return
+  res.json({ range: req.get('Range') })
+})
+
+app.put('/preconditions', async (_req: Request, res: Response) => {
+  if (!res.checkPreconditions(article)) return
+  res.status(204).end()
+})
+
 // Range
 app.get('/range', async (req: Request, res: Response) => {
   console.log('JS: GET /range callback called.')
   res.json(req.range(1000, { combine: true }))
 })
 
+app.get('/range-buffer', async (_req: Request, res: Response) => {
+  res.set('Content-Type', 'text/plain')
+  res.send(Buffer.from('abcdefghij'))
+})
+
 // Send a file
 app.get('/send-file/{dotfiles}/{name}', async (req: Request, res: Response) => {
   const options = {
@@ -229,6 +389,72 @@ app.get('/folder', async (_req: Request, res: Response) => {
   await res.sendFile('/', options)
 })
 
+// Send a file from an in-memory bundle, over the `public` directory
+const bundleJs = "console.log('bundled')"
+const bundle = VirtualFileSystem.overlay([
+  VirtualFileSystem.fromFiles({
+    'bundle.js': bundleJs,
+    'bundle.js.br': brotliCompressSync(bundleJs),
+    '.env': 'SECRET=1',
+  }),
+  path.join(__dirname, 'public'),
+])
+app.get('/bundle/{name}', async (req: Request, res: Response) => {
+  await res.sendFile((req.params as any).name, { root: bundle, maxAge: '1d' })
+})
+
+// Render views with the built-in Jinja-style engine, and with a JS engine
+app.set('views', path.join(__dirname, 'views'))
+app.set('view engine', 'jinja')
+app.locals.set('site', 'hyperjs')
+app.engine('tmpl', (file: string, locals: Record<string, unknown>, callback: (err: Error | null, html?: string) => void) => {
+  readFile(file, 'utf8')
+    .then((template) => callback(null, template.replace('{{name}}', String(locals.name))))
+    .catch(callback)
+})
+app.get('/render', async (_req: Request, res: Response) => {
+  res.locals.set('user', 'tobi')
+  await res.render('index', { title: 'Items', items: ['<b>first</b>', 'second'] })
+})
+app.get('/render-engine', async (_req: Request, res: Response) => {
+  await res.render('hello.tmpl', { name: 'Tobi' })
+})
+// Variables set on `req.locals` by a middleware are those of `res.locals`
+app.use('/render-locals', (req: Request, _res: Response) => {
+  req.locals.set('user', 'loki')
+  return true
+})
+app.get('/render-locals', async (_req: Request, res: Response) => {
+  await res.render('index', { title: 'Locals', items: [] })
+})
+// Locals keep the JS values themselves, e.g. a connection handle
+class Connection {
+  query(sql: string) {
+    return `rows of ${sql}`
+  }
+}
+const connection = new Connection()
+app.use('/locals-identity', (req: Request, _res: Response) => {
+  req.locals.set('db', connection)
+  req.locals.set('user', { name: 'tobi' })
+  return true
+})
+app.get('/locals-identity', async (req: Request, res: Response) => {
+  const db = res.locals.get('db') as Connection
+  const user = req.locals.get('user') as { admin?: boolean }
+  user.admin = true
+  res.json({
+    same: db === connection,
+    rows: db.query('SELECT 1'),
+    admin: (res.locals.get('user') as { admin?: boolean }).admin,
+  })
+})
+app.get('/render-callback', async (_req: Request, res: Response) => {
+  await res.render('missing', (err: Error | null) => {
+    res.status(500).send(err?.message)
+  })
+})
+
 // Download a file
 app.get('/download/{dotfiles}/{name}', async (req: Request, res: Response) => {
   const options = {
@@ -244,6 +470,49 @@ app.get('/download/{dotfiles}/{name}', async (req: Request, res: Response) => {
   await res.download(fileName, options)
 })
 
+// Compression
+const compressionMiddleware = new CompressionMiddleware({ threshold: '1kb' })
+const compressible = { items: Array.from({ length: 100 }, (_, i) => ({ id: i, name: `item ${i}` })) }
+app.use('/compressed', (req: Request, res: Response) => compressionMiddleware.run(req, res))
+app.get('/compressed', async (_req: Request, res: Response) => {
+  res.set('ETag', '"compressible"')
+  res.json(compressible)
+})
+app.use('/compressed-small', (req: Request, res: Response) => compressionMiddleware.run(req, res))
+app.get('/compressed-small', async (_req: Request, res: Response) => {
+  res.json({ id: 0 })
+})
+app.use('/compressed-no-transform', (req: Request, res: Response) => compressionMiddleware.run(req, res))
+app.get('/compressed-no-transform', async (_req: Request, res: Response) => {
+  res.set('Cache-Control', 'no-transform')
+  res.json(compressible)
+})
+app.use('/compressed-invalid-vary', (req: Request, res: Response) => compressionMiddleware.run(req, res))
+app.get('/compressed-invalid-vary', async (_req: Request, res: Response) => {
+  res.set('Vary', 'Accépt')
+  res.json(compressible)
+})
+
+// Access log: JSON lines kept in memory, read back by the tests
+const accessLogLines: unknown[] = []
+const accessLog = new AccessLogMiddleware({
+  format: 'json',
+  tokens: { tenant: (req: Request) => (req.get('x-tenant') as string) || null },
+  skip: (_req: Request, res: Response) => res.statusCode === 204,
+  stream: (line: string) => accessLogLines.push(JSON.parse(line)),
+})
+app.use('/logged', (req: Request, res: Response) => accessLog.run(req, res))
+app.get('/logged', async (_req: Request, res: Response) => {
+  res.send('logged')
+})
+app.use('/logged-quiet', (req: Request, res: Response) => accessLog.run(req, res))
+app.get('/logged-quiet', async (_req: Request, res: Response) => {
+  res.status(204).end()
+})
+app.get('/access-log', async (_req: Request, res: Response) => {
+  res.json(accessLogLines)
+})
+
 // Cookie testing endpoints
 // set-cookie
 const cookieParserMiddleware = new CookieParserMiddleware(null, {})
@@ -254,10 +523,47 @@ app.get('/cookie/show', async (req: Request, res: Response) => {
   res.json(req.cookies as any)
 })
 
+// next(), next('route') and next(err)
+app.get('/next/{id}', [
+  (req: Request, _res: Response, next: (err?: unknown) => void) => {
+    next((req.params as any).id === 'other' ? 'route' : undefined)
+  },
+  async (req: Request, res: Response) => {
+    res.send(`first route ${(req.params as any).id}`)
+  },
+])
+app.get('/next/{id}', async (_req: Request, res: Response) => {
+  res.send('second route')
+})
+// next() called after the handler returned, and the response ended later
+app.get('/next-later', [
+  (_req: Request, _res: Response, next: (err?: unknown) => void) => {
+    setTimeout(() => next(), 10)
+  },
+  (_req: Request, res: Response, _next: (err?: unknown) => void) => {
+    setTimeout(() => res.send('sent later'), 10)
+  },
+])
+app.get('/next-error', (_req: Request, _res: Response, next: (err?: unknown) => void) => {
+  next(Object.assign(new Error('Gone for good'), { status: 410, headers: { 'Retry-After': '120' } }))
+})
+app.get('/http-error/{id}', (req: Request, _res: Response) => {
+  throw new HttpError(404, `No such user: ${req.params.id}`)
+})
+app.get('/http-error-busy', async (_req: Request, _res: Response) => {
+  throw new HttpError(StatusCode.serviceUnavailable(), 'Busy', { expose: true, headers: { 'Retry-After': '5' } })
+})
+
 // ============================================================================
 // AFTER-ROUTES APPLICATION-WIDE MIDDLEWARE DEFINITIONS
 // ============================================================================
 
+// Sets a cookie on the requests asking for one, cached static files included
+app.use(null, (req: Request, res: Response) => {
+  if (req.query.login !== undefined) res.cookie('session', 'secret')
+  return true
+})
+
 // Static middleware
 const staticMiddleware = new StaticMiddleware('public', {
   dotfiles: 'ignore',
@@ -266,12 +572,29 @@ const staticMiddleware = new StaticMiddleware('public', {
   index: false,
   redirect: false,
   fallthrough: true,
+  directoryListing: true,
+  cache: { revalidate: 0 },
+  spaFallback: { file: 'public/index.html', include: ['/spa/**'], exclude: ['/spa/api/**'] },
   setHeaders(res: Response, _path: string, _stat: FileStat) {
     res.set('x-timestamp', Date.now().toString())
   },
 })
 app.use(null, (req: Request, res: Response) => staticMiddleware.run(req, res))
 
+// ============================================================================
+// ERROR HANDLERS
+// ============================================================================
+
+// Errors of the requests asking for JSON are sent as JSON, the others are
+// left to the default final handler
+app.useError((err: any, req: Request, res: Response, next: (err?: unknown) => void) => {
+  if (req.get('x-error-format') !== 'json') return next(err)
+  res.status(err.status ?? 500).json({ error: err.message })
+})
+
+// The errors left unhandled with status 410 are rendered by a view
+app.errorPage(410, 'error')
+
 // ============================================================================
 // SERVER STARTUP
 // ============================================================================
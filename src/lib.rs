@@ -1,9 +1,12 @@
 #![deny(clippy::all)]
 
 pub mod bytes;
+pub mod locals;
 pub mod middlewares;
 pub mod request;
 pub mod response;
 pub mod server;
 pub mod utilities;
 pub mod version;
+pub mod view;
+pub mod virtual_file_system;
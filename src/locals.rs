@@ -0,0 +1,212 @@
+use std::{
+  collections::BTreeMap,
+  sync::{Arc, Mutex},
+};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{Map, Value};
+
+use crate::utilities::{self, JsValueRef};
+
+/// A variable: the JS value itself when set from JS, or a JSON value when
+/// set by a native middleware, replaced by its JS value once read from JS
+#[derive(Clone, Debug)]
+enum Local {
+  Js(Arc<JsValueRef>),
+  Json(Value),
+}
+
+/// Variables available to the views rendered by `res.render()`, e.g. the
+/// application-wide `server.locals` and the per-request `res.locals`. The JS
+/// values themselves are stored: `get()` returns the very value given to
+/// `set()`, be it a function, a class instance or a connection handle.
+///
+/// `req.locals` and `res.locals` are the same map, shared by all the
+/// middlewares handling a request, whether written in JS or native.
+///
+/// Unlike Express' `res.locals`, this is not a plain object: the variables
+/// are read and written with `get()`, `set()` and `assign()`, and assigning a
+/// property, e.g. `res.locals.user = user`, sets no variable.
+///
+/// ```javascript
+/// server.locals.set('title', 'My App')
+///
+/// app.use((req, res) => {
+///   req.locals.set('db', db)
+///   return true
+/// })
+///
+/// app.get('/', async (req, res) => {
+///   res.send(await res.locals.get('db').query('SELECT 1'))
+/// })
+/// ```
+#[napi]
+#[derive(Clone, Debug, Default)]
+pub struct Locals {
+  inner: Arc<Mutex<BTreeMap<String, Local>>>,
+}
+
+impl Locals {
+  fn with_inner<F, T>(&self, f: F) -> Result<T>
+  where
+    F: FnOnce(&mut BTreeMap<String, Local>) -> Result<T>,
+  {
+    match self.inner.lock() {
+      Ok(mut inner) => f(&mut inner),
+      Err(e) => Err(Error::new(
+        Status::GenericFailure,
+        format!("Could not obtain lock on locals. {e}"),
+      )),
+    }
+  }
+
+  /// The JS value of `local`. Must be called on the JS thread, without the
+  /// lock held, as reading a value may run JS code.
+  fn to_napi(&self, env: &Env, name: &str, local: Local) -> Result<Unknown<'static>> {
+    match local {
+      Local::Js(value) => value.get(env),
+      Local::Json(json) => {
+        let value = utilities::json_to_napi(env, json)?;
+        let reference = Arc::new(JsValueRef::new(value));
+        self.with_inner(|locals| {
+          if let Some(local @ Local::Json(_)) = locals.get_mut(name) {
+            *local = Local::Js(reference);
+          }
+          Ok(())
+        })?;
+        Ok(value)
+      }
+    }
+  }
+
+  /// The variables as an object. Must be called on the JS thread.
+  pub fn to_object(&self, env: &Env) -> Result<Object<'static>> {
+    let locals = self.with_inner(|locals| Ok(locals.clone()))?;
+    let mut object = Object::new(env)?;
+    for (name, local) in locals {
+      object.set_named_property(&name, self.to_napi(env, &name, local)?)?;
+    }
+    Ok(object)
+  }
+
+  /// A copy of the variables, converted like `JSON.stringify` does it. Must
+  /// be called on the JS thread.
+  pub fn to_map(&self, env: &Env) -> Result<Map<String, Value>> {
+    match utilities::to_json_value(env, self.to_object(env)?.to_unknown())? {
+      Value::Object(map) => Ok(map),
+      _ => Ok(Map::new()),
+    }
+  }
+
+  /// A copy of variable `name`, converted like `JSON.stringify` does it.
+  /// Must be called on the JS thread.
+  pub fn get_value(&self, env: &Env, name: &str) -> Result<Option<Value>> {
+    let Some(local) = self.with_inner(|locals| Ok(locals.get(name).cloned()))? else {
+      return Ok(None);
+    };
+    match local {
+      Local::Js(value) => utilities::to_json_value(env, value.get(env)?).map(Some),
+      Local::Json(json) => Ok(Some(json)),
+    }
+  }
+
+  /// Set variable `name` to a JSON `value`. Can be called on any thread.
+  pub fn set_value(&self, name: impl Into<String>, value: Value) -> Result<()> {
+    self.with_inner(|locals| {
+      locals.insert(name.into(), Local::Json(value));
+      Ok(())
+    })
+  }
+}
+
+#[napi]
+impl Locals {
+  #[napi(constructor)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The value of variable `name`, `undefined` when it is not set
+  #[napi(ts_return_type = "unknown")]
+  pub fn get(&self, env: Env, name: String) -> Result<Either<Unknown<'static>, Undefined>> {
+    match self.with_inner(|locals| Ok(locals.get(&name).cloned()))? {
+      Some(local) => Ok(Either::A(self.to_napi(&env, &name, local)?)),
+      None => Ok(Either::B(())),
+    }
+  }
+
+  #[napi(ts_args_type = "name: string, value: unknown")]
+  pub fn set(&self, name: String, value: Unknown) -> Result<()> {
+    let value = Local::Js(Arc::new(JsValueRef::new(value)));
+    self.with_inner(|locals| {
+      locals.insert(name, value);
+      Ok(())
+    })
+  }
+
+  #[napi]
+  pub fn has(&self, name: String) -> Result<bool> {
+    self.with_inner(|locals| Ok(locals.contains_key(&name)))
+  }
+
+  /// Remove variable `name`, returns whether it was set
+  #[napi]
+  pub fn delete(&self, name: String) -> Result<bool> {
+    self.with_inner(|locals| Ok(locals.remove(&name).is_some()))
+  }
+
+  #[napi]
+  pub fn clear(&self) -> Result<()> {
+    self.with_inner(|locals| {
+      locals.clear();
+      Ok(())
+    })
+  }
+
+  /// Set the properties of `values` as variables
+  #[napi(ts_args_type = "values: Record<string, unknown>")]
+  pub fn assign(&self, values: Unknown) -> Result<()> {
+    if values.get_type()? != ValueType::Object {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "locals.assign() expects an object",
+      ));
+    }
+    let values: Object = unsafe { values.cast()? };
+    let mut assigned = Vec::new();
+    for name in Object::keys(&values)? {
+      let value = values.get_named_property::<Unknown>(&name)?;
+      assigned.push((name, Local::Js(Arc::new(JsValueRef::new(value)))));
+    }
+    self.with_inner(|locals| {
+      locals.extend(assigned);
+      Ok(())
+    })
+  }
+
+  /// The variables as an object
+  #[napi(js_name = "toJSON", ts_return_type = "Record<string, unknown>")]
+  pub fn to_json(&self, env: Env) -> Result<Object<'static>> {
+    self.to_object(&env)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_shared_values() {
+    let locals = Locals::default();
+    let shared = locals.clone();
+    locals.set_value("user", json!({ "name": "tobi" })).unwrap();
+    assert!(shared.has("user".to_owned()).unwrap());
+    assert!(!shared.has("missing".to_owned()).unwrap());
+    assert!(!Locals::default().has("user".to_owned()).unwrap());
+
+    assert!(shared.delete("user".to_owned()).unwrap());
+    assert!(!locals.has("user".to_owned()).unwrap());
+  }
+}
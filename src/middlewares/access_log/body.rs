@@ -0,0 +1,157 @@
+use std::{
+  pin::Pin,
+  task::{Context, Poll, ready},
+};
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use http_body_util::BodyExt;
+use hyper::{HeaderMap, Response as LibResponse, StatusCode};
+
+use super::AccessLog;
+use crate::response::CrateBody;
+
+/// The body of a logged response: counts the bytes sent, and writes the
+/// access logs once the body is complete, or dropped when the client goes
+/// away.
+pub struct LoggedBody {
+  body: CrateBody,
+  status: StatusCode,
+  headers: HeaderMap,
+  bytes: u64,
+  access_logs: Vec<AccessLog>,
+}
+
+impl LoggedBody {
+  /// Write `access_logs` once the body of `response` is sent
+  pub fn wrap(
+    response: LibResponse<CrateBody>,
+    access_logs: Vec<AccessLog>,
+  ) -> LibResponse<CrateBody> {
+    if access_logs.is_empty() {
+      return response;
+    }
+    let status = response.status();
+    let headers = response.headers().clone();
+    response.map(|body| {
+      CrateBody::Stream(
+        LoggedBody {
+          body,
+          status,
+          headers,
+          bytes: 0,
+          access_logs,
+        }
+        .boxed_unsync(),
+      )
+    })
+  }
+
+  fn write_logs(&mut self) {
+    for access_log in std::mem::take(&mut self.access_logs) {
+      access_log.write(self.status, &self.headers, self.bytes);
+    }
+  }
+}
+
+impl HttpBody for LoggedBody {
+  type Data = Bytes;
+
+  type Error = std::io::Error;
+
+  fn poll_frame(
+    self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+    let this = self.get_mut();
+    let frame = ready!(Pin::new(&mut this.body).poll_frame(cx));
+    if let Some(Ok(frame)) = &frame
+      && let Some(data) = frame.data_ref()
+    {
+      this.bytes += data.len() as u64;
+    }
+    if frame.is_none() || this.body.is_end_stream() {
+      this.write_logs();
+    }
+    Poll::Ready(frame)
+  }
+
+  fn is_end_stream(&self) -> bool {
+    self.body.is_end_stream()
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    self.body.size_hint()
+  }
+}
+
+impl Drop for LoggedBody {
+  fn drop(&mut self) {
+    self.write_logs();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{collections::HashMap, fs, path::Path, sync::Arc, time::Instant};
+
+  use http_body_util::StreamBody;
+  use hyper::{Method, Version};
+
+  use super::*;
+  use crate::middlewares::access_log::{
+    AccessLogger,
+    format::LogFormat,
+    sink::{LogSink, RotatingFile},
+  };
+
+  fn access_log(path: &Path) -> AccessLog {
+    let logger = AccessLogger {
+      format: LogFormat::parse(":method :url :status :res[content-length]").unwrap(),
+      tokens: HashMap::new(),
+      used_tokens: Vec::new(),
+      skip: None,
+      sink: LogSink::File(RotatingFile::open(path.to_path_buf(), None, None, None).unwrap()),
+    };
+    AccessLog {
+      logger: Arc::new(logger),
+      start: Instant::now(),
+      id: "0".to_owned(),
+      method: Method::GET,
+      url: "/stream".to_owned(),
+      version: Version::HTTP_11,
+      remote_addr: None,
+      headers: HeaderMap::new(),
+      custom: HashMap::new(),
+    }
+  }
+
+  fn streamed_response() -> LibResponse<CrateBody> {
+    let chunks = ["hello", " world"].map(|chunk| Ok(Frame::data(Bytes::from(chunk))));
+    let body = StreamBody::new(futures::stream::iter(chunks));
+    LibResponse::new(CrateBody::Stream(body.boxed_unsync()))
+  }
+
+  #[tokio::test]
+  async fn test_log_once_sent() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    let response = LoggedBody::wrap(streamed_response(), vec![access_log(&path)]);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "hello world");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "GET /stream 200 11\n");
+  }
+
+  #[test]
+  fn test_log_when_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    drop(LoggedBody::wrap(
+      streamed_response(),
+      vec![access_log(&path)],
+    ));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "GET /stream 200 0\n");
+  }
+}
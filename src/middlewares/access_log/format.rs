@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use hyper::header::{AUTHORIZATION, HeaderMap, REFERER, USER_AGENT};
+use hyper::{Method, StatusCode, Version};
+use napi::{Error, Result, Status};
+use regex::Regex;
+use serde_json::{Map, Value, json};
+
+const COMBINED: &str = r#":remote-addr - :remote-user [:date[clf]] ":method :url HTTP/:http-version" :status :res[content-length] ":referrer" ":user-agent""#;
+const COMMON: &str = r#":remote-addr - :remote-user [:date[clf]] ":method :url HTTP/:http-version" :status :res[content-length]"#;
+const SHORT: &str = ":remote-addr :remote-user :method :url HTTP/:http-version :status :res[content-length] - :response-time ms";
+const TINY: &str = ":method :url :status :res[content-length] - :response-time ms";
+
+lazy_static::lazy_static! {
+  static ref TOKEN: Regex = Regex::new(r":([-\w]{2,})(?:\[([^\]]+)\])?").unwrap();
+}
+
+/// A custom token used by a format: its name and its argument, e.g.
+/// `("tenant", None)` for `:tenant`
+pub(super) type TokenKey = (String, Option<String>);
+
+/// A part of a format string
+#[derive(Debug, PartialEq)]
+pub(super) enum Part {
+  Literal(String),
+  /// A token like `:status` or `:req[accept]`, replaced by its value
+  Token {
+    name: String,
+    arg: Option<String>,
+  },
+}
+
+/// The format of the lines of an access log
+#[derive(Debug, PartialEq)]
+pub(super) enum LogFormat {
+  /// A format string of tokens, e.g. `combined`
+  Tokens(Vec<Part>),
+  /// Concise output colored by status, for development
+  Dev,
+  /// One JSON object per line
+  Json,
+}
+
+impl LogFormat {
+  /// Parse a predefined format name, or a format string of tokens
+  pub fn parse(format: &str) -> Result<Self> {
+    let format = match format {
+      "combined" => COMBINED,
+      "common" => COMMON,
+      "short" => SHORT,
+      "tiny" => TINY,
+      "dev" => return Ok(LogFormat::Dev),
+      "json" => return Ok(LogFormat::Json),
+      format if !TOKEN.is_match(format) => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("Unknown access log format \"{format}\""),
+        ));
+      }
+      format => format,
+    };
+
+    let mut parts = Vec::new();
+    let mut last = 0;
+    for captures in TOKEN.captures_iter(format) {
+      let token = captures.get(0).unwrap();
+      if token.start() > last {
+        parts.push(Part::Literal(format[last..token.start()].to_owned()));
+      }
+      parts.push(Part::Token {
+        name: captures[1].to_owned(),
+        arg: captures.get(2).map(|arg| arg.as_str().to_owned()),
+      });
+      last = token.end();
+    }
+    if last < format.len() {
+      parts.push(Part::Literal(format[last..].to_owned()));
+    }
+    Ok(LogFormat::Tokens(parts))
+  }
+
+  /// The custom tokens the format uses, among `names`. The JSON format uses
+  /// all of them, without argument.
+  pub fn custom_tokens<'a>(&'a self, names: impl Iterator<Item = &'a String>) -> Vec<TokenKey> {
+    let names = names.collect::<Vec<_>>();
+    match self {
+      LogFormat::Tokens(parts) => parts
+        .iter()
+        .filter_map(|part| match part {
+          Part::Token { name, arg } if names.contains(&name) => Some((name.clone(), arg.clone())),
+          _ => None,
+        })
+        .collect(),
+      LogFormat::Dev => Vec::new(),
+      LogFormat::Json => names.into_iter().map(|name| (name.clone(), None)).collect(),
+    }
+  }
+
+  /// The line logged for `record`, without line break
+  pub fn format(&self, record: &LogRecord) -> String {
+    match self {
+      LogFormat::Tokens(parts) => parts
+        .iter()
+        .map(|part| match part {
+          Part::Literal(literal) => literal.clone(),
+          Part::Token { name, arg } => record
+            .token(name, arg.as_deref())
+            .unwrap_or_else(|| "-".to_owned()),
+        })
+        .collect(),
+      LogFormat::Dev => {
+        let color = match record.status.as_u16() {
+          500.. => 31,
+          400.. => 33,
+          300.. => 36,
+          200.. => 32,
+          _ => 0,
+        };
+        format!(
+          "\x1b[0m{} {} \x1b[{color}m{}\x1b[0m {} ms - {}\x1b[0m",
+          record.method,
+          record.url,
+          record.status.as_u16(),
+          record.response_time(3),
+          record.content_length().unwrap_or_else(|| "-".to_owned()),
+        )
+      }
+      LogFormat::Json => {
+        let mut line = Map::new();
+        line.insert("time".to_owned(), json!(record.date("iso")));
+        line.insert("id".to_owned(), json!(record.id));
+        line.insert("remoteAddr".to_owned(), json!(record.remote_addr));
+        line.insert("remoteUser".to_owned(), json!(record.remote_user()));
+        line.insert("method".to_owned(), json!(record.method.as_str()));
+        line.insert("url".to_owned(), json!(record.url));
+        line.insert(
+          "httpVersion".to_owned(),
+          json!(http_version(record.version)),
+        );
+        line.insert("status".to_owned(), json!(record.status.as_u16()));
+        line.insert(
+          "responseTime".to_owned(),
+          json!((record.response_time.as_secs_f64() * 1e6).round() / 1e3),
+        );
+        line.insert("bytes".to_owned(), json!(record.bytes));
+        line.insert("referrer".to_owned(), json!(record.referrer()));
+        line.insert(
+          "userAgent".to_owned(),
+          json!(record.header(record.request_headers, USER_AGENT.as_str())),
+        );
+        for ((name, _), value) in record.custom {
+          line.insert(name.clone(), json!(value));
+        }
+        Value::Object(line).to_string()
+      }
+    }
+  }
+}
+
+/// A request and its response, as logged
+pub(super) struct LogRecord<'a> {
+  pub id: &'a str,
+  pub method: &'a Method,
+  /// The original URL of the request
+  pub url: &'a str,
+  pub version: Version,
+  /// `req.ip`
+  pub remote_addr: Option<&'a str>,
+  pub request_headers: &'a HeaderMap,
+  pub status: StatusCode,
+  pub response_headers: &'a HeaderMap,
+  /// The size of the body sent, when it is known
+  pub bytes: Option<u64>,
+  /// The time from the middleware call until the body was sent
+  pub response_time: Duration,
+  pub date: SystemTime,
+  /// The values of the custom tokens
+  pub custom: &'a HashMap<TokenKey, Option<String>>,
+}
+
+impl LogRecord<'_> {
+  /// The value of token `:name[arg]`, `None` when it has none
+  fn token(&self, name: &str, arg: Option<&str>) -> Option<String> {
+    if let Some(value) = self.custom.get(&(name.to_owned(), arg.map(str::to_owned))) {
+      return value.clone();
+    }
+    match name {
+      "date" => Some(self.date(arg.unwrap_or("web"))),
+      "http-version" => Some(http_version(self.version).to_owned()),
+      "id" => Some(self.id.to_owned()),
+      "method" => Some(self.method.to_string()),
+      "referrer" => self.referrer(),
+      "remote-addr" => self.remote_addr.map(str::to_owned),
+      "remote-user" => self.remote_user(),
+      "req" => self.header(self.request_headers, arg?),
+      "res" => match arg? {
+        header if header.eq_ignore_ascii_case("content-length") => self.content_length(),
+        header => self.header(self.response_headers, header),
+      },
+      "response-time" => Some(
+        self.response_time(
+          arg
+            .and_then(|digits| digits.parse().ok())
+            .unwrap_or(3)
+            .min(6),
+        ),
+      ),
+      "status" => Some(self.status.as_u16().to_string()),
+      "url" => Some(self.url.to_owned()),
+      "user-agent" => self.header(self.request_headers, USER_AGENT.as_str()),
+      _ => None,
+    }
+  }
+
+  /// The values of header `name`, joined by commas
+  fn header(&self, headers: &HeaderMap, name: &str) -> Option<String> {
+    let values = headers
+      .get_all(name)
+      .iter()
+      .filter_map(|value| value.to_str().ok())
+      .collect::<Vec<_>>();
+    (!values.is_empty()).then(|| values.join(", "))
+  }
+
+  /// The `Content-Length` of the response, or the size of its body when it
+  /// has none
+  fn content_length(&self) -> Option<String> {
+    self
+      .header(self.response_headers, "content-length")
+      .or_else(|| self.bytes.map(|bytes| bytes.to_string()))
+  }
+
+  fn referrer(&self) -> Option<String> {
+    self
+      .header(self.request_headers, REFERER.as_str())
+      .or_else(|| self.header(self.request_headers, "referrer"))
+  }
+
+  /// The user of the `Basic` authentication of the request
+  fn remote_user(&self) -> Option<String> {
+    let authorization = self.request_headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, credentials) = authorization.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+      return None;
+    }
+    let credentials = decode_base64(credentials.trim())?;
+    let credentials = String::from_utf8(credentials).ok()?;
+    let (user, _) = credentials.split_once(':')?;
+    Some(user.to_owned())
+  }
+
+  /// The response time in milliseconds, with `digits` decimals
+  fn response_time(&self, digits: usize) -> String {
+    format!("{:.digits$}", self.response_time.as_secs_f64() * 1e3)
+  }
+
+  /// The date of the response in `format`: `clf`, `iso` or `web`
+  fn date(&self, format: &str) -> String {
+    let date = DateTime::<Utc>::from(self.date);
+    match format {
+      "clf" => date.format("%d/%b/%Y:%H:%M:%S +0000").to_string(),
+      "iso" => date.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+      _ => httpdate::fmt_http_date(self.date),
+    }
+  }
+}
+
+fn http_version(version: Version) -> &'static str {
+  match version {
+    Version::HTTP_09 => "0.9",
+    Version::HTTP_10 => "1.0",
+    Version::HTTP_2 => "2.0",
+    Version::HTTP_3 => "3.0",
+    _ => "1.1",
+  }
+}
+
+/// Decode standard, padded or unpadded, base 64
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+  let mut output = Vec::with_capacity(input.len() * 3 / 4);
+  let mut buffer = 0u32;
+  let mut bits = 0;
+  for byte in input.trim_end_matches('=').bytes() {
+    let value = match byte {
+      b'A'..=b'Z' => byte - b'A',
+      b'a'..=b'z' => byte - b'a' + 26,
+      b'0'..=b'9' => byte - b'0' + 52,
+      b'+' => 62,
+      b'/' => 63,
+      _ => return None,
+    };
+    buffer = (buffer << 6) | u32::from(value);
+    bits += 6;
+    if bits >= 8 {
+      bits -= 8;
+      output.push((buffer >> bits) as u8);
+    }
+  }
+  Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use hyper::header::HeaderValue;
+
+  fn format(format: &str, custom: &HashMap<TokenKey, Option<String>>) -> String {
+    let mut request_headers = HeaderMap::new();
+    request_headers.insert(USER_AGENT, HeaderValue::from_static("curl/8.5.0"));
+    // aladdin:opensesame
+    request_headers.insert(
+      AUTHORIZATION,
+      HeaderValue::from_static("Basic YWxhZGRpbjpvcGVuc2VzYW1l"),
+    );
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert("content-type", HeaderValue::from_static("text/plain"));
+    let record = LogRecord {
+      id: "7",
+      method: &Method::GET,
+      url: "/users?page=2",
+      version: Version::HTTP_11,
+      remote_addr: Some("127.0.0.1"),
+      request_headers: &request_headers,
+      status: StatusCode::NOT_FOUND,
+      response_headers: &response_headers,
+      bytes: Some(42),
+      response_time: Duration::from_micros(1500),
+      date: SystemTime::UNIX_EPOCH + Duration::from_secs(971_186_136),
+      custom,
+    };
+    LogFormat::parse(format).unwrap().format(&record)
+  }
+
+  #[test]
+  fn test_predefined_formats() {
+    let custom = HashMap::new();
+    assert_eq!(
+      format("combined", &custom),
+      r#"127.0.0.1 - aladdin [10/Oct/2000:13:55:36 +0000] "GET /users?page=2 HTTP/1.1" 404 42 "-" "curl/8.5.0""#
+    );
+    assert_eq!(
+      format("tiny", &custom),
+      "GET /users?page=2 404 42 - 1.500 ms"
+    );
+    assert_eq!(
+      format("dev", &custom),
+      "\x1b[0mGET /users?page=2 \x1b[33m404\x1b[0m 1.500 ms - 42\x1b[0m"
+    );
+  }
+
+  #[test]
+  fn test_tokens() {
+    let mut custom = HashMap::new();
+    custom.insert(("tenant".to_owned(), None), Some("acme".to_owned()));
+    assert_eq!(
+      format(
+        ":id :tenant :res[content-type] :req[accept] :response-time[0] :date[iso]",
+        &custom
+      ),
+      "7 acme text/plain - 2 2000-10-10T13:55:36.000Z"
+    );
+    assert!(LogFormat::parse("fancy").is_err());
+  }
+
+  #[test]
+  fn test_json() {
+    let mut custom = HashMap::new();
+    custom.insert(("tenant".to_owned(), None), None);
+    let line: Value = serde_json::from_str(&format("json", &custom)).unwrap();
+    assert_eq!(
+      line,
+      json!({
+        "time": "2000-10-10T13:55:36.000Z",
+        "id": "7",
+        "remoteAddr": "127.0.0.1",
+        "remoteUser": "aladdin",
+        "method": "GET",
+        "url": "/users?page=2",
+        "httpVersion": "1.1",
+        "status": 404,
+        "responseTime": 1.5,
+        "bytes": 42,
+        "referrer": null,
+        "userAgent": "curl/8.5.0",
+        "tenant": null,
+      })
+    );
+  }
+
+  #[test]
+  fn test_decode_base64() {
+    assert_eq!(decode_base64("aGk=").unwrap(), b"hi");
+    assert_eq!(decode_base64("aGk").unwrap(), b"hi");
+    assert!(decode_base64("a*").is_none());
+  }
+}
@@ -0,0 +1,424 @@
+mod body;
+mod format;
+mod sink;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use byte_unit::Byte;
+use hyper::{HeaderMap, Method, StatusCode, Version};
+use napi::{
+  bindgen_prelude::*,
+  threadsafe_function::{ThreadsafeCallContext, ThreadsafeFunction},
+};
+use napi_derive::napi;
+
+use crate::{request::Request, response::Response, utilities};
+pub use body::LoggedBody;
+use format::{LogFormat, LogRecord, TokenKey};
+use sink::{LogSink, RotatingFile};
+
+type JsSkipFn<'a> = Function<'a, FnArgs<(Request, Response)>, bool>;
+
+type ThreadsafeSkipFn = ThreadsafeFunction<
+  FnArgs<(Request, Response)>,
+  bool,
+  FnArgs<(Request, Response)>,
+  Status,
+  false,
+  false,
+  0,
+>;
+
+type JsTokenFn<'a> = Function<'a, FnArgs<(Request, Response, Option<String>)>, Option<String>>;
+
+type ThreadsafeTokenFn = ThreadsafeFunction<
+  FnArgs<(Request, Response, Option<String>)>,
+  Option<String>,
+  FnArgs<(Request, Response, Option<String>)>,
+  Status,
+  false,
+  false,
+  0,
+>;
+
+type JsWriteFn<'a> = Function<'a, String, ()>;
+
+#[napi(object)]
+pub struct JsAccessLogFile {
+  /// The path of the log file, created if it does not exist. Lines are
+  /// appended to it.
+  pub path: String,
+
+  /// The size of the file, once reached, at which the file is rotated: it is
+  /// renamed after the time of the rotation, e.g.
+  /// `access.log.20261019-134501`, and a new file is created. If this is a
+  /// number, then the value specifies the number of bytes; if it is a
+  /// string, the value is passed to the
+  /// [bytes](https://docs.rs/byte-unit/latest/byte_unit/) library for
+  /// parsing.
+  ///
+  /// Default = no rotation by size
+  pub size: Option<Either<i64, String>>,
+
+  /// The time after which the file is rotated. If this is a number, then the
+  /// value specifies the number of milliseconds; if it is a string, the value
+  /// is passed to the [ms](https://www.npmjs.org/package/ms) library for
+  /// parsing, e.g. `"1d"`.
+  ///
+  /// Default = no rotation by time
+  pub interval: Option<Either<i64, String>>,
+
+  /// The number of rotated files to keep, the oldest ones are removed.
+  ///
+  /// Default = all of them
+  pub max_files: Option<u32>,
+}
+
+#[napi(object)]
+pub struct JsAccessLogOptions<'a> {
+  /// The format of the lines: `combined`, `common`, `short`, `tiny`, `dev`
+  /// (colored by status), `json` (one JSON object per line), or a format
+  /// string of tokens, e.g. `:method :url :status - :response-time ms`.
+  ///
+  /// The tokens are the ones of
+  /// [morgan](https://www.npmjs.com/package/morgan): `:date[format]`,
+  /// `:http-version`, `:method`, `:referrer`, `:remote-addr`,
+  /// `:remote-user`, `:req[header]`, `:res[header]`,
+  /// `:response-time[digits]`, `:status`, `:url` and `:user-agent`, plus
+  /// `:id`, the ID of the request, and the custom `tokens`. Tokens without a
+  /// value are logged as `-`. `:res[content-length]` is the size of the body
+  /// when the response has no `Content-Length`.
+  ///
+  /// Default = "combined"
+  pub format: Option<String>,
+
+  /// Custom tokens, each called as `token(req, res, arg)` once the response
+  /// is complete, for `:name[arg]`, and returning the value of the token.
+  /// The `json` format has a member for each of them.
+  pub tokens: Option<HashMap<String, JsTokenFn<'a>>>,
+
+  /// This option, if supplied, is called as `skip(req, res)` once the
+  /// response is complete; the request is not logged when it returns `true`.
+  pub skip: Option<JsSkipFn<'a>>,
+
+  /// Where the lines are written: `"stdout"`, `"stderr"`, a file, or a
+  /// function called as `write(line)` for every line, without line break.
+  ///
+  /// Default = "stdout"
+  pub stream: Option<Either3<String, JsWriteFn<'a>, JsAccessLogFile>>,
+}
+
+/// The options of an access log middleware
+struct AccessLogger {
+  format: LogFormat,
+  tokens: HashMap<String, ThreadsafeTokenFn>,
+  /// The custom tokens used by `format`
+  used_tokens: Vec<TokenKey>,
+  skip: Option<ThreadsafeSkipFn>,
+  sink: LogSink,
+}
+
+impl JsAccessLogOptions<'_> {
+  fn to_access_logger(&self) -> Result<AccessLogger> {
+    let format = LogFormat::parse(self.format.as_deref().unwrap_or("combined"))?;
+
+    let mut tokens = HashMap::new();
+    for (name, token_fn) in self.tokens.iter().flatten() {
+      let tsfn = token_fn.build_threadsafe_function().build_callback(
+        |ctx: ThreadsafeCallContext<FnArgs<(Request, Response, Option<String>)>>| Ok(ctx.value),
+      )?;
+      tokens.insert(name.to_owned(), tsfn);
+    }
+    let used_tokens = format.custom_tokens(tokens.keys());
+
+    let skip =
+      match &self.skip {
+        Some(skip_fn) => Some(skip_fn.build_threadsafe_function().build_callback(
+          |ctx: ThreadsafeCallContext<FnArgs<(Request, Response)>>| Ok(ctx.value),
+        )?),
+        None => None,
+      };
+
+    let sink = match &self.stream {
+      None => LogSink::Stdout,
+      Some(Either3::A(stream)) => match stream.as_str() {
+        "stdout" => LogSink::Stdout,
+        "stderr" => LogSink::Stderr,
+        stream => {
+          return Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid stream value: \"{stream}\", expected \"stdout\" or \"stderr\""),
+          ));
+        }
+      },
+      Some(Either3::B(write_fn)) => LogSink::Function(
+        write_fn
+          .build_threadsafe_function()
+          .build_callback(|ctx: ThreadsafeCallContext<String>| Ok(ctx.value))?,
+      ),
+      Some(Either3::C(file)) => LogSink::File(file.to_rotating_file()?),
+    };
+
+    Ok(AccessLogger {
+      format,
+      tokens,
+      used_tokens,
+      skip,
+      sink,
+    })
+  }
+}
+
+impl JsAccessLogFile {
+  fn to_rotating_file(&self) -> Result<RotatingFile> {
+    let max_size = match &self.size {
+      None => None,
+      Some(Either::A(size)) => match u64::try_from(*size) {
+        Ok(size) => Some(size),
+        Err(_) => {
+          return Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid size value: {size}, expected a number of bytes"),
+          ));
+        }
+      },
+      Some(Either::B(size)) => {
+        let size = utilities::decimal_to_binary_unit(size);
+        match Byte::from_str(&size) {
+          Ok(size) => Some(size.as_u64()),
+          Err(e) => {
+            return Err(Error::new(
+              Status::InvalidArg,
+              format!("Invalid size value: {e}"),
+            ));
+          }
+        }
+      }
+    };
+
+    let interval = match &self.interval {
+      None => None,
+      Some(Either::A(interval)) => match u64::try_from(*interval) {
+        Ok(interval) => Some(Duration::from_millis(interval)),
+        Err(_) => {
+          return Err(Error::new(
+            Status::InvalidArg,
+            format!("Invalid interval value: {interval}, expected a number of milliseconds"),
+          ));
+        }
+      },
+      Some(Either::B(interval)) => Some(utilities::parse_duration(interval).ok_or_else(|| {
+        Error::new(
+          Status::InvalidArg,
+          format!("Invalid interval value: \"{interval}\""),
+        )
+      })?),
+    };
+
+    RotatingFile::open(
+      PathBuf::from(&self.path),
+      max_size,
+      interval,
+      self.max_files.map(|max_files| max_files as usize),
+    )
+    .map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Failed to open the access log file {}: {e}", self.path),
+      )
+    })
+  }
+}
+
+/// Returns the access log middleware using the given `options`, logging the
+/// requests that traverse through the middleware once their response is
+/// complete, like [morgan](https://www.npmjs.com/package/morgan) does.
+///
+/// The response time is measured from the middleware call until the body of
+/// the response is sent, and the bytes sent are counted as the body streams.
+/// Requests whose middleware chain fails are logged too, with
+/// the response sent to the client.
+///
+/// ```javascript
+/// const accessLog = new AccessLogMiddleware({
+///   format: 'json',
+///   stream: { path: 'access.log', size: '10mb', maxFiles: 5 },
+///   skip: (req, res) => res.statusCode < 400,
+/// })
+/// app.use(null, (req, res) => accessLog.run(req, res))
+/// ```
+#[napi]
+pub struct AccessLogMiddleware {
+  logger: Arc<AccessLogger>,
+}
+
+#[napi]
+impl AccessLogMiddleware {
+  #[napi(constructor)]
+  pub fn new(options: Option<JsAccessLogOptions>) -> Result<Self> {
+    let logger = match options {
+      Some(options) => options.to_access_logger()?,
+      None => JsAccessLogOptions {
+        format: None,
+        tokens: None,
+        skip: None,
+        stream: None,
+      }
+      .to_access_logger()?,
+    };
+    Ok(AccessLogMiddleware {
+      logger: Arc::new(logger),
+    })
+  }
+
+  /// Starts timing the request; the request is logged once its response is
+  /// complete. Always returns `true`.
+  #[napi]
+  pub fn run(&self, request: &Request, response: &Response) -> Result<bool> {
+    log::debug!(
+      "Request ID: {} | Access Log Middleware | Called!",
+      request.id()
+    );
+
+    let remote_addr = request.ip()?;
+    let (method, url, version, headers) = request.with_inner(|w_req| {
+      let request = w_req.inner()?;
+      Ok((
+        request.method().to_owned(),
+        request.uri().to_string(),
+        request.version(),
+        request.headers().to_owned(),
+      ))
+    })?;
+    let access_log = AccessLog {
+      logger: self.logger.clone(),
+      start: Instant::now(),
+      id: request.id(),
+      method,
+      url,
+      version,
+      remote_addr,
+      headers,
+      custom: HashMap::new(),
+    };
+    response.with_inner(|w_res| {
+      w_res.add_access_log(access_log);
+      Ok(true)
+    })
+  }
+}
+
+/// A request logged by an access log middleware, written once its response
+/// is complete.
+pub struct AccessLog {
+  logger: Arc<AccessLogger>,
+  start: Instant,
+  id: String,
+  method: Method,
+  url: String,
+  version: Version,
+  remote_addr: Option<String>,
+  headers: HeaderMap,
+  /// The values of the custom tokens, set by `prepare`
+  custom: HashMap<TokenKey, Option<String>>,
+}
+
+impl AccessLog {
+  /// Call the JS functions of the log, `skip` and the custom tokens, with the
+  /// request and its response, before the response is sent. Returns `false`
+  /// when the request is skipped.
+  pub async fn prepare(&mut self, request: &Request, response: &Response) -> bool {
+    if let Some(skip) = &self.logger.skip {
+      match skip
+        .call_async((request.to_owned(), response.to_owned()).into())
+        .await
+      {
+        Ok(true) => return false,
+        Ok(false) => {}
+        Err(e) => log::warn!("Request ID: {} | Access Log | skip failed: {e}", self.id),
+      }
+    }
+
+    for (name, arg) in &self.logger.used_tokens {
+      let Some(token) = self.logger.tokens.get(name) else {
+        continue;
+      };
+      let value = token
+        .call_async((request.to_owned(), response.to_owned(), arg.clone()).into())
+        .await
+        .unwrap_or_else(|e| {
+          log::warn!(
+            "Request ID: {} | Access Log | Token \"{name}\" failed: {e}",
+            self.id
+          );
+          None
+        });
+      self.custom.insert((name.clone(), arg.clone()), value);
+    }
+    true
+  }
+
+  /// Write the line of the request, once the `bytes` of the body of its
+  /// response are sent
+  pub fn write(self, status: StatusCode, response_headers: &HeaderMap, bytes: u64) {
+    let record = LogRecord {
+      id: &self.id,
+      method: &self.method,
+      url: &self.url,
+      version: self.version,
+      remote_addr: self.remote_addr.as_deref(),
+      request_headers: &self.headers,
+      status,
+      response_headers,
+      bytes: Some(bytes),
+      response_time: self.start.elapsed(),
+      date: SystemTime::now(),
+      custom: &self.custom,
+    };
+    let line = self.logger.format.format(&record);
+    if let Err(e) = self.logger.sink.write(line) {
+      log::warn!(
+        "Request ID: {} | Access Log | Failed to write: {e}",
+        self.id
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rotating_file(
+    size: Option<Either<i64, String>>,
+    interval: Option<Either<i64, String>>,
+  ) -> Result<RotatingFile> {
+    let dir = tempfile::tempdir().unwrap();
+    JsAccessLogFile {
+      path: dir.path().join("access.log").display().to_string(),
+      size,
+      interval,
+      max_files: None,
+    }
+    .to_rotating_file()
+  }
+
+  #[test]
+  fn test_rotation_values() {
+    assert!(rotating_file(Some(Either::A(1024)), Some(Either::A(60_000))).is_ok());
+    assert!(
+      rotating_file(
+        Some(Either::B("10mb".to_owned())),
+        Some(Either::B("1d".to_owned()))
+      )
+      .is_ok()
+    );
+    // negative values are rejected rather than wrapped around
+    assert!(rotating_file(Some(Either::A(-1)), None).is_err());
+    assert!(rotating_file(None, Some(Either::A(-1))).is_err());
+  }
+}
@@ -0,0 +1,182 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+
+pub(super) type ThreadsafeWriteFn = ThreadsafeFunction<String, (), String, Status, false, false, 0>;
+
+/// Where the lines of an access log are written
+pub(super) enum LogSink {
+  Stdout,
+  Stderr,
+  File(RotatingFile),
+  /// Called as `write(line)` for every line
+  Function(ThreadsafeWriteFn),
+}
+
+impl LogSink {
+  /// Write `line`, followed by a line break for the streams and files
+  pub fn write(&self, line: String) -> io::Result<()> {
+    match self {
+      LogSink::Stdout => writeln!(io::stdout().lock(), "{line}"),
+      LogSink::Stderr => writeln!(io::stderr().lock(), "{line}"),
+      LogSink::File(file) => file.write(&line),
+      LogSink::Function(write) => match write.call(line, ThreadsafeFunctionCallMode::NonBlocking) {
+        Status::Ok => Ok(()),
+        status => Err(io::Error::other(status.to_string())),
+      },
+    }
+  }
+}
+
+/// A log file, rotated once it reaches `max_size` bytes or every `interval`:
+/// the file is renamed after the time of the rotation, e.g.
+/// `access.log.20261019-134501`, and a new one is created. Only the
+/// `max_files` most recent rotated files are kept.
+pub(super) struct RotatingFile {
+  path: PathBuf,
+  max_size: Option<u64>,
+  interval: Option<Duration>,
+  max_files: Option<usize>,
+  state: Mutex<FileState>,
+}
+
+struct FileState {
+  file: File,
+  size: u64,
+  opened: SystemTime,
+}
+
+impl RotatingFile {
+  pub fn open(
+    path: PathBuf,
+    max_size: Option<u64>,
+    interval: Option<Duration>,
+    max_files: Option<usize>,
+  ) -> io::Result<Self> {
+    let state = FileState::open(&path)?;
+    Ok(Self {
+      path,
+      max_size,
+      interval,
+      max_files,
+      state: Mutex::new(state),
+    })
+  }
+
+  fn write(&self, line: &str) -> io::Result<()> {
+    let mut state = self
+      .state
+      .lock()
+      .map_err(|_| io::Error::other("poisoned lock"))?;
+    let len = line.len() as u64 + 1;
+    let full = self
+      .max_size
+      .is_some_and(|max_size| state.size + len > max_size);
+    let expired = self.interval.is_some_and(|interval| {
+      state
+        .opened
+        .elapsed()
+        .is_ok_and(|elapsed| elapsed >= interval)
+    });
+    // empty files are not rotated
+    if state.size > 0 && (full || expired) {
+      state.file.flush()?;
+      fs::rename(&self.path, self.rotated_path())?;
+      *state = FileState::open(&self.path)?;
+      self.remove_old_files()?;
+    }
+    writeln!(state.file, "{line}")?;
+    state.size += len;
+    Ok(())
+  }
+
+  /// A free path for the file rotated now
+  fn rotated_path(&self) -> PathBuf {
+    let timestamp = DateTime::<Utc>::from(SystemTime::now()).format("%Y%m%d-%H%M%S");
+    let rotated = PathBuf::from(format!("{}.{timestamp}", self.path.display()));
+    (1..)
+      .map(|i| match i {
+        1 => rotated.clone(),
+        i => PathBuf::from(format!("{}.{i}", rotated.display())),
+      })
+      .find(|path| !path.exists())
+      .unwrap_or(rotated)
+  }
+
+  /// Remove the rotated files but the `max_files` most recent ones
+  fn remove_old_files(&self) -> io::Result<()> {
+    let (Some(max_files), Some(file_name)) = (self.max_files, self.path.file_name()) else {
+      return Ok(());
+    };
+    let prefix = format!("{}.", file_name.to_string_lossy());
+    let dir = match self.path.parent() {
+      Some(dir) if !dir.as_os_str().is_empty() => dir,
+      _ => Path::new("."),
+    };
+    let mut rotated = fs::read_dir(dir)?
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+      .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+      .collect::<Vec<_>>();
+    rotated.sort();
+    let excess = rotated.len().saturating_sub(max_files);
+    for (_, path) in rotated.into_iter().take(excess) {
+      fs::remove_file(path)?;
+    }
+    Ok(())
+  }
+}
+
+impl FileState {
+  fn open(path: &Path) -> io::Result<Self> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    Ok(Self {
+      file,
+      size,
+      opened: SystemTime::now(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_rotate_by_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    let file = RotatingFile::open(path.clone(), Some(10), None, Some(1)).unwrap();
+    for line in ["first", "second", "third"] {
+      file.write(line).unwrap();
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+
+    // only the most recent rotated file is kept
+    let rotated = fs::read_dir(dir.path())
+      .unwrap()
+      .map(|entry| entry.unwrap().path())
+      .filter(|entry| *entry != path)
+      .collect::<Vec<_>>();
+    assert_eq!(rotated.len(), 1);
+    assert_eq!(fs::read_to_string(&rotated[0]).unwrap(), "second\n");
+  }
+
+  #[test]
+  fn test_rotate_by_interval() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    let file = RotatingFile::open(path.clone(), None, Some(Duration::ZERO), None).unwrap();
+    file.write("first").unwrap();
+    file.write("second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+  }
+}
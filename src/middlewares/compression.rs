@@ -0,0 +1,147 @@
+use std::{str::FromStr, sync::Arc};
+
+use byte_unit::Byte;
+use hyper::header::ACCEPT_ENCODING;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::{
+  request::Request,
+  response::Response,
+  utilities::{self, CompressionOptions, ResponseCompression},
+};
+
+#[napi(object)]
+pub struct JsCompressionOptions {
+  /// The byte threshold for the response body size before compression is
+  /// considered for the response. If this is a number, then the value
+  /// specifies the number of bytes; if it is a string, the value is passed to
+  /// the [bytes](https://docs.rs/byte-unit/latest/byte_unit/) library for
+  /// parsing. Bodies of an unknown size (e.g. streamed files without a
+  /// `Content-Length`) are always compressed.
+  ///
+  /// Default = "1kb"
+  pub threshold: Option<Either<i64, String>>,
+
+  /// The level of compression to apply, passed to the negotiated encoder: `0`
+  /// to `9` for `gzip` and `deflate`, `0` to `11` for `br` and `1` to `22` for
+  /// `zstd`. Out of range levels are clamped.
+  ///
+  /// Default = the default level of each encoder
+  pub level: Option<i32>,
+
+  /// The media types to compress. This option can be a string or an array of
+  /// strings, each passed to the
+  /// [mime_guess](https://docs.rs/mime_guess/latest/mime_guess/) library: an
+  /// extension name (like `json`), a mime type (like `text/plain`), or a mime
+  /// type with a wildcard (like `*/*` or `text/*`).
+  ///
+  /// Default = the compressible types (`text/*`, JSON, JavaScript, XML, ...)
+  pub typ: Option<Either<String, Vec<String>>>,
+}
+
+impl JsCompressionOptions {
+  fn to_compression_options(&self) -> Result<CompressionOptions> {
+    let mut compression_options = CompressionOptions::default();
+
+    if let Some(threshold) = &self.threshold {
+      match threshold {
+        Either::A(threshold) => match usize::try_from(*threshold) {
+          Ok(threshold) => compression_options.threshold = threshold,
+          Err(_) => {
+            return Err(Error::new(
+              Status::InvalidArg,
+              format!("Invalid threshold value: {threshold}, expected a number of bytes"),
+            ));
+          }
+        },
+        Either::B(threshold) => {
+          let threshold = utilities::decimal_to_binary_unit(threshold);
+          match Byte::from_str(&threshold) {
+            Ok(threshold) => {
+              compression_options.threshold = threshold.as_u64() as usize;
+            }
+            Err(e) => {
+              return Err(Error::new(
+                Status::InvalidArg,
+                format!("Invalid threshold value: {e}"),
+              ));
+            }
+          }
+        }
+      }
+    }
+
+    compression_options.level = self.level;
+
+    if let Some(typ) = &self.typ {
+      compression_options.typ = Some(match typ {
+        Either::A(typ) => vec![typ.to_owned()],
+        Either::B(types) => types.to_owned(),
+      });
+    }
+
+    Ok(compression_options)
+  }
+}
+
+/// Returns the compression middleware using the given `options`. The
+/// middleware will attempt to compress response bodies for all requests that
+/// traverse through the middleware, based on the given `options`.
+///
+/// The encoding is negotiated from the request's `Accept-Encoding` header
+/// among `br`, `zstd`, `gzip` and `deflate`. The response body, including
+/// bodies sent with `res.send()`, `res.json()` or `res.sendFile()`, is
+/// compressed once the response is complete and `Vary: Accept-Encoding` is
+/// added to it.
+///
+/// Responses are not compressed when their `Content-Type` is not matched, their
+/// body is smaller than the threshold, they already have a `Content-Encoding`
+/// (e.g. a precompressed static file), they are partial content, their
+/// `Cache-Control` header has a `no-transform` directive, or the request is a
+/// `HEAD` request. The `ETag` of a compressed response is weakened.
+///
+/// ```javascript
+/// const compression = new CompressionMiddleware({ threshold: '2kb' })
+/// app.use(null, (req, res) => compression.run(req, res))
+/// ```
+#[napi]
+pub struct CompressionMiddleware {
+  options: Arc<CompressionOptions>,
+}
+
+#[napi]
+impl CompressionMiddleware {
+  #[napi(constructor)]
+  pub fn new(options: Option<JsCompressionOptions>) -> Result<Self> {
+    Ok(CompressionMiddleware {
+      options: Arc::new(match options {
+        Some(options) => options.to_compression_options()?,
+        None => CompressionOptions::default(),
+      }),
+    })
+  }
+
+  /// Negotiates the response's encoding; the response is compressed once it
+  /// is complete. Always returns `true`.
+  #[napi]
+  pub fn run(&self, request: &Request, response: &Response) -> Result<bool> {
+    log::debug!(
+      "Request ID: {} | Compression Middleware | Called!",
+      request.id()
+    );
+
+    let compression = request.with_inner(|w_req| {
+      let request = w_req.inner()?;
+      Ok(ResponseCompression::new(
+        self.options.clone(),
+        request.method(),
+        request.headers().get(ACCEPT_ENCODING),
+      ))
+    })?;
+    response.with_inner(|w_res| {
+      w_res.set_compression(compression);
+      Ok(true)
+    })
+  }
+}
@@ -84,7 +84,10 @@ impl CookieParserMiddleware {
 
   #[napi]
   pub async fn run(&self, request: &Request, _response: &Response) -> Result<bool> {
-    log::debug!("CookieParser Middleware | Called!");
+    log::debug!(
+      "Request ID: {} | CookieParser Middleware | Called!",
+      request.id()
+    );
 
     request.with_inner_mut(|w_req| {
       let Some(cookie_header) = w_req.inner()?.headers().get(COOKIE) else {
@@ -1,17 +1,17 @@
 use std::{str::FromStr, sync::Arc};
 
 use byte_unit::Byte;
-use futures::StreamExt;
-use http_body_util::{BodyStream, Limited, combinators::BoxBody};
-use hyper::Request as HyperRequest;
 use napi::{
   bindgen_prelude::*,
   threadsafe_function::{ThreadsafeCallContext, ThreadsafeFunction},
 };
 use napi_derive::napi;
-use serde_json::Value as JsonValue;
 
-use crate::{request::Request, response::Response, utilities};
+use crate::{
+  request::Request,
+  response::Response,
+  utilities::{self, BodyParserError, JsValueRef, JsonParseOptions, PrototypeAction},
+};
 
 type ThreadsafeParseTypeFn =
   ThreadsafeFunction<FnArgs<(Request,)>, bool, FnArgs<(Request,)>, Status, false, false, 0>;
@@ -28,10 +28,15 @@ type ThreadsafeVerifyFn = ThreadsafeFunction<
 
 type JsVerifyFn<'a> = Function<'a, FnArgs<(Request, Response, Buffer, String)>, ()>;
 
+type JsReviverFn<'a> = Function<'a, FnArgs<(String, Unknown<'static>)>, Unknown<'static>>;
+
+type ReviverFnRef = FunctionRef<FnArgs<(String, Unknown<'static>)>, Unknown<'static>>;
+
 #[napi(object)]
 pub struct JsJsonOptions<'a> {
   /// Enables or disables handling deflated (compressed) bodies; when disabled,
-  /// deflated bodies are rejected.
+  /// deflated bodies are rejected with `415 Unsupported Media Type`. The
+  /// `limit` option applies to the inflated body.
   ///
   /// Default = true
   pub inflate: Option<bool>,
@@ -44,10 +49,53 @@ pub struct JsJsonOptions<'a> {
   /// Default = "100kb"
   pub limit: Option<Either<i64, String>>,
 
-  /// TODO: Add support for reviver option
+  /// The `reviver` option behaves like the second argument of `JSON.parse`:
+  /// it is called as `reviver(key, value)` with the holder object as `this`
+  /// for every value of the parsed body, bottom-up, and its return value
+  /// replaces the value (`undefined` removes it), so `req.body` may hold
+  /// values without a JSON form, e.g. a `Date`. An error thrown by the
+  /// reviver fails the parse with a `400` error.
+  ///
+  /// Default = none
+  pub reviver: Option<JsReviverFn<'a>>,
+
+  /// What to do with `__proto__` keys, which can poison the prototype of an
+  /// object built from the body: `"error"` fails the parse with a `400`
+  /// error, `"remove"` drops the key and `"ignore"` keeps it.
+  ///
+  /// Default = "ignore"
+  pub proto_action: Option<String>,
+
+  /// What to do with `constructor` keys whose value is an object with a
+  /// `prototype` key: `"error"`, `"remove"` or `"ignore"`, like
+  /// `protoAction`.
+  ///
+  /// Default = "ignore"
+  pub constructor_action: Option<String>,
+
+  /// Maximum nesting depth of arrays and objects, a top level array or object
+  /// having a depth of 1. Deeper bodies fail with a `400` error.
+  ///
+  /// Default = none
+  pub max_depth: Option<u32>,
+
+  /// Maximum number of elements in an array. Longer arrays fail the parse
+  /// with a `400` error.
   ///
   /// Default = none
-  pub reviver: Option<Function<'a, FnArgs<(String, Object<'static>, Object<'static>)>>>,
+  pub max_array_length: Option<u32>,
+
+  /// Maximum number of characters in a string, whether a key or a value.
+  /// Longer strings fail the parse with a `400` error.
+  ///
+  /// Default = none
+  pub max_string_length: Option<u32>,
+
+  /// Enables or disables failing the parse with a `400` error when an object
+  /// has the same key more than once; when disabled the last value wins.
+  ///
+  /// Default = false
+  pub reject_duplicate_keys: Option<bool>,
 
   /// Enables or disables only accepting arrays and objects; when disabled will
   /// accept anything `serde_json::from_slice` accepts.
@@ -80,7 +128,8 @@ pub struct JsJsonOptions<'a> {
 struct JsonOptions {
   inflate: bool,
   limit: usize,
-  // TODO: reviver
+  parse: JsonParseOptions,
+  reviver: Option<ReviverFnRef>,
   strict: bool,
   typ: Either<Vec<String>, Arc<ThreadsafeParseTypeFn>>,
   verify: Option<Arc<ThreadsafeVerifyFn>>,
@@ -89,8 +138,10 @@ struct JsonOptions {
 impl Default for JsonOptions {
   fn default() -> Self {
     Self {
-      inflate: false,
+      inflate: true,
       limit: 102_400, // 100kb
+      parse: JsonParseOptions::default(),
+      reviver: None,
       strict: true,
       typ: Either::A(vec!["application/json".to_owned()]),
       verify: None,
@@ -128,6 +179,38 @@ impl<'a> JsJsonOptions<'a> {
       }
     }
 
+    if let Some(reviver) = &self.reviver {
+      json_options.reviver = Some(reviver.create_ref()?);
+    }
+
+    if let Some(proto_action) = &self.proto_action {
+      json_options.parse.proto_action =
+        PrototypeAction::try_from(proto_action.as_str()).map_err(|e| {
+          Error::new(
+            Status::InvalidArg,
+            format!("Invalid protoAction value: {e}"),
+          )
+        })?;
+    }
+
+    if let Some(constructor_action) = &self.constructor_action {
+      json_options.parse.constructor_action =
+        PrototypeAction::try_from(constructor_action.as_str()).map_err(|e| {
+          Error::new(
+            Status::InvalidArg,
+            format!("Invalid constructorAction value: {e}"),
+          )
+        })?;
+    }
+
+    json_options.parse.max_depth = self.max_depth.map(|max| max as usize);
+    json_options.parse.max_array_length = self.max_array_length.map(|max| max as usize);
+    json_options.parse.max_string_length = self.max_string_length.map(|max| max as usize);
+
+    if let Some(reject_duplicate_keys) = self.reject_duplicate_keys {
+      json_options.parse.reject_duplicate_keys = reject_duplicate_keys;
+    }
+
     if let Some(strict) = self.strict {
       json_options.strict = strict;
     }
@@ -157,6 +240,71 @@ impl<'a> JsJsonOptions<'a> {
 }
 
 impl JsonOptions {
+  async fn run(&self, request: &Request) -> std::result::Result<bool, BodyParserError> {
+    log::debug!("Request ID: {} | Json Middleware | Called!", request.id());
+
+    // determine if request should be parsed
+    if !self.should_parse(request).await? {
+      return Ok(true);
+    }
+
+    let body = utilities::read_body(request, self.limit, self.inflate).await?;
+
+    // skip requests without bodies
+    if body.is_empty() {
+      return Ok(true);
+    }
+
+    if self.strict
+      && let Some(first) = body.iter().find(|byte| !byte.is_ascii_whitespace())
+      && *first != b'{'
+      && *first != b'['
+    {
+      return Err(BodyParserError::parse_failed(
+        format!(
+          "Unexpected token '{}', expected a JSON array or object",
+          char::from(*first)
+        ),
+        Some(String::from_utf8_lossy(&body).into_owned()),
+      ));
+    }
+
+    let parsed_json = utilities::parse_json(&body, &self.parse).map_err(|e| {
+      BodyParserError::parse_failed(
+        format!("Error parsing JSON body: {e}"),
+        Some(String::from_utf8_lossy(&body).into_owned()),
+      )
+    })?;
+
+    request.with_inner_mut(|req| {
+      req.set_body(Either3::B(parsed_json));
+      Ok(())
+    })?;
+    Ok(true)
+  }
+
+  /// Replace the parsed body of `request` with the result of the reviver,
+  /// kept as a JS value.
+  /// Must be called on the JS thread.
+  fn revive_body(&self, env: &Env, request: &Request) -> Result<()> {
+    let (Some(reviver), Either4::B(body)) = (&self.reviver, request.body(*env)?) else {
+      return Ok(());
+    };
+    let reviver = reviver.borrow_back(env)?;
+    let mut holder = Object::new(env)?;
+    holder.set("", body)?;
+    let revived = revive(&reviver, holder, String::new()).map_err(|e| {
+      let reason = take_pending_exception(env, e).reason;
+      BodyParserError::parse_failed(reason, None).into_napi_error(env)
+    })?;
+    let revived = JsValueRef::new(revived);
+    request.with_inner_mut(|w_req| {
+      w_req.set_revived_body(revived);
+      Ok(())
+    })?;
+    Ok(())
+  }
+
   async fn should_parse(&self, request: &Request) -> Result<bool> {
     let req_content_type = match request.get(hyper::http::header::CONTENT_TYPE.to_string())? {
       Either::A(val) => val,
@@ -176,13 +324,68 @@ impl JsonOptions {
   }
 }
 
+/// Take the exception left pending by a call into JS that threw, so that the
+/// env can be used again, and return it in place of `error`.
+fn take_pending_exception(env: &Env, error: Error) -> Error {
+  if error.status != Status::PendingException {
+    return error;
+  }
+  let mut exception = std::ptr::null_mut();
+  let status = unsafe { napi::sys::napi_get_and_clear_last_exception(env.raw(), &mut exception) };
+  if status != napi::sys::Status::napi_ok || exception.is_null() {
+    return error;
+  }
+  Error::from_unknown_without_coercion(unsafe { Unknown::from_raw_unchecked(env.raw(), exception) })
+}
+
+/// Walk `holder[key]` bottom-up like `JSON.parse` does, replacing each value
+/// with the result of `reviver` and removing it when that is `undefined`.
+fn revive(
+  reviver: &Function<FnArgs<(String, Unknown<'static>)>, Unknown<'static>>,
+  holder: Object<'static>,
+  key: String,
+) -> Result<Unknown<'static>> {
+  let value: Unknown<'static> = holder.get_named_property(&key)?;
+  if value.get_type()? == ValueType::Object {
+    let mut object = value.coerce_to_object()?;
+    let keys = if value.is_array()? {
+      (0..object.get_array_length()?)
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>()
+    } else {
+      let names = object.get_all_property_names(
+        KeyCollectionMode::OwnOnly,
+        KeyFilter::Enumerable,
+        KeyConversion::NumbersToStrings,
+      )?;
+      let mut keys = Vec::new();
+      for index in 0..names.get_array_length()? {
+        let name: Unknown = names.get_element(index)?;
+        if name.get_type()? == ValueType::String {
+          keys.push(name.coerce_to_string()?.into_utf8()?.into_owned()?);
+        }
+      }
+      keys
+    };
+    for key in keys {
+      let revived = revive(reviver, object, key.clone())?;
+      if revived.get_type()? == ValueType::Undefined {
+        object.delete_named_property(&key)?;
+      } else {
+        object.set_named_property(&key, revived)?;
+      }
+    }
+  }
+  reviver.apply(holder, (key, value).into())
+}
+
 /// This is a built-in middleware function in Express. It parses incoming
 /// requests with JSON payloads.
 ///
 /// Returns middleware that only parses JSON and only looks at requests where
 /// the `Content-Type` header matches the `type` option. This parser accepts
-/// any Unicode encoding of the body and supports automatic inflation of `gzip`
-/// and `deflate` encodings.
+/// any Unicode encoding of the body and supports automatic inflation of `gzip`,
+/// `deflate`, `br` and `zstd` encodings.
 ///
 /// A new `body` object containing the parsed data is populated on the
 /// `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -197,7 +400,7 @@ impl JsonOptions {
 /// > user-input.
 #[napi]
 pub struct JsonMiddleware {
-  options: JsonOptions,
+  options: Arc<JsonOptions>,
 }
 
 #[napi]
@@ -205,63 +408,37 @@ impl JsonMiddleware {
   #[napi(constructor)]
   pub fn new(options: Option<JsJsonOptions>) -> Result<Self> {
     Ok(JsonMiddleware {
-      options: match options {
+      options: Arc::new(match options {
         Some(options) => options.to_json_options()?,
         None => JsonOptions::default(),
-      },
+      }),
     })
   }
 
+  /// Parses the request's body, rejecting with a body-parser style error
+  /// (`status`, `type`, ...) when it is too large, malformed or in an
+  /// unsupported encoding.
   #[napi]
-  pub async fn run(&self, request: &Request, _response: &Response) -> Result<bool> {
-    log::debug!("Json Middleware | Called!");
-
-    // determine if request should be parsed
-    if !self.options.should_parse(request).await? {
-      return Ok(true);
-    }
-
-    let hyper_request = request.with_inner_mut(|req| req.take_inner())?;
-    let (parts, body) = hyper_request.into_parts();
-    let mut body_stream = BodyStream::new(Limited::new(body, self.options.limit));
-
-    let mut body = Vec::new();
-    while let Some(data) = body_stream.next().await {
-      let data = data
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
-        .into_data()
-        .map_err(|_| Error::new(Status::GenericFailure, "Encountered a non-data frame."))?;
-      body.extend_from_slice(&data);
-    }
-
-    request.with_inner_mut(|w_req| {
-      w_req.set_inner(HyperRequest::from_parts(parts, BoxBody::new(body_stream)));
-      Ok(())
-    })?;
-
-    // skip requests without bodies
-    if body.is_empty() {
-      return Ok(true);
-    }
-
-    if self.options.strict && body[0] != b'{' && body[0] != b'[' {
-      return Err(Error::new(
-        Status::GenericFailure,
-        "Expected body to be a JSON array or object.",
-      ));
-    }
-
-    let parsed_json: JsonValue = serde_json::from_slice(&body).map_err(|e| {
-      Error::new(
-        Status::GenericFailure,
-        format!("Error parsing JSON body: {e}"),
-      )
-    })?;
-
-    request.with_inner_mut(|req| {
-      req.set_body(Either3::B(parsed_json));
-      Ok(())
-    })?;
-    Ok(true)
+  pub fn run<'env>(
+    &self,
+    env: &'env Env,
+    request: &Request,
+    _response: &Response,
+  ) -> Result<PromiseRaw<'env, bool>> {
+    let options = self.options.clone();
+    let request = request.clone();
+    env.spawn_future_with_callback(
+      {
+        let options = options.clone();
+        let request = request.clone();
+        async move { Ok(options.run(&request).await) }
+      },
+      // the reviver is a JS function, so it runs on the JS thread
+      move |env, result| {
+        let next = result.map_err(|e| e.into_napi_error(env))?;
+        options.revive_body(env, &request)?;
+        Ok(next)
+      },
+    )
   }
 }
@@ -1,6 +1,10 @@
+mod access_log;
+mod compression;
 mod cookie_parser;
 mod json;
 mod raw;
 mod static_;
 mod text;
 mod urlencoded;
+
+pub use access_log::{AccessLog, LoggedBody};
@@ -1,16 +1,17 @@
 use std::{str::FromStr, sync::Arc};
 
 use byte_unit::Byte;
-use futures::StreamExt;
-use http_body_util::{BodyStream, Limited, combinators::BoxBody};
-use hyper::Request as HyperRequest;
 use napi::{
   bindgen_prelude::*,
   threadsafe_function::{ThreadsafeCallContext, ThreadsafeFunction},
 };
 use napi_derive::napi;
 
-use crate::{request::Request, response::Response, utilities};
+use crate::{
+  request::Request,
+  response::Response,
+  utilities::{self, BodyParserError},
+};
 
 type ThreadsafeParseTypeFn =
   ThreadsafeFunction<FnArgs<(Request,)>, bool, FnArgs<(Request,)>, Status, false, false, 0>;
@@ -30,7 +31,8 @@ type JsVerifyFn<'a> = Function<'a, FnArgs<(Request, Response, Buffer, String)>,
 #[napi(object)]
 pub struct JsRawOptions<'a> {
   /// Enables or disables handling deflated (compressed) bodies; when disabled,
-  /// deflated bodies are rejected.
+  /// deflated bodies are rejected with `415 Unsupported Media Type`. The
+  /// `limit` option applies to the inflated body.
   ///
   /// Default = true
   pub inflate: Option<bool>,
@@ -75,7 +77,7 @@ struct RawOptions {
 impl Default for RawOptions {
   fn default() -> Self {
     Self {
-      inflate: false,
+      inflate: true,
       limit: 102_400, // 100kb
       typ: Either::A(vec!["application/octet-stream".to_owned()]),
       verify: None,
@@ -138,6 +140,29 @@ impl<'a> JsRawOptions<'a> {
 }
 
 impl RawOptions {
+  async fn run(&self, request: &Request) -> std::result::Result<bool, BodyParserError> {
+    log::debug!("Request ID: {} | Raw Middleware | Called!", request.id());
+
+    // determine if request should be parsed
+    if !self.should_parse(request).await? {
+      return Ok(true);
+    }
+
+    let body = utilities::read_body(request, self.limit, self.inflate).await?;
+
+    // skip requests without bodies
+    if body.is_empty() {
+      return Ok(true);
+    }
+
+    request.with_inner_mut(|req| {
+      req.set_body(Either3::C(body));
+      Ok(())
+    })?;
+
+    Ok(true)
+  }
+
   async fn should_parse(&self, request: &Request) -> Result<bool> {
     let req_content_type = match request.get(hyper::http::header::CONTENT_TYPE.to_string())? {
       Either::A(val) => val,
@@ -162,8 +187,8 @@ impl RawOptions {
 ///
 /// Returns middleware that only parses JSON and only looks at requests where
 /// the `Content-Type` header matches the `type` option. This parser accepts
-/// any Unicode encoding of the body and supports automatic inflation of `gzip`
-/// and `deflate` encodings.
+/// any Unicode encoding of the body and supports automatic inflation of `gzip`,
+/// `deflate`, `br` and `zstd` encodings.
 ///
 /// A new `body` object containing the parsed data is populated on the
 /// `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -178,7 +203,7 @@ impl RawOptions {
 /// > user-input.
 #[napi]
 pub struct RawMiddleware {
-  options: RawOptions,
+  options: Arc<RawOptions>,
 }
 
 #[napi]
@@ -186,50 +211,28 @@ impl RawMiddleware {
   #[napi(constructor)]
   pub fn new(options: Option<JsRawOptions>) -> Result<Self> {
     Ok(RawMiddleware {
-      options: match options {
+      options: Arc::new(match options {
         Some(options) => options.to_raw_options()?,
         None => RawOptions::default(),
-      },
+      }),
     })
   }
 
+  /// Reads the request's body, rejecting with a body-parser style error
+  /// (`status`, `type`, ...) when it is too large or in an unsupported
+  /// encoding.
   #[napi]
-  pub async fn run(&self, request: &Request, _response: &Response) -> Result<bool> {
-    log::debug!("Raw Middleware | Called!");
-
-    // determine if request should be parsed
-    if !self.options.should_parse(request).await? {
-      return Ok(true);
-    }
-
-    let hyper_request = request.with_inner_mut(|req| req.take_inner())?;
-    let (parts, body) = hyper_request.into_parts();
-    let mut body_stream = BodyStream::new(Limited::new(body, self.options.limit));
-
-    let mut body = Vec::new();
-    while let Some(data) = body_stream.next().await {
-      let data = data
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
-        .into_data()
-        .map_err(|_| Error::new(Status::GenericFailure, "Encountered a non-data frame."))?;
-      body.extend_from_slice(&data);
-    }
-
-    request.with_inner_mut(|w_req| {
-      w_req.set_inner(HyperRequest::from_parts(parts, BoxBody::new(body_stream)));
-      Ok(())
-    })?;
-
-    // skip requests without bodies
-    if body.is_empty() {
-      return Ok(true);
-    }
-
-    request.with_inner_mut(|req| {
-      req.set_body(Either3::C(body));
-      Ok(())
-    })?;
-
-    Ok(true)
+  pub fn run<'env>(
+    &self,
+    env: &'env Env,
+    request: &Request,
+    _response: &Response,
+  ) -> Result<PromiseRaw<'env, bool>> {
+    let options = self.options.clone();
+    let request = request.clone();
+    env.spawn_future_with_callback(
+      async move { Ok(options.run(&request).await) },
+      |env, result| result.map_err(|e| e.into_napi_error(env)),
+    )
   }
 }
@@ -0,0 +1,406 @@
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs::{self, Metadata},
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+  time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use hyper::{
+  HeaderMap,
+  header::{
+    ACCEPT_RANGES, CACHE_CONTROL, CONTENT_TYPE, ETAG, HeaderName, HeaderValue, LAST_MODIFIED,
+  },
+};
+
+use crate::utilities::{self, ContentEncoding};
+
+/// Compressed variants precomputed for compressible files
+const VARIANTS: [ContentEncoding; 2] = [ContentEncoding::Brotli, ContentEncoding::Gzip];
+
+/// The headers of the file kept with it. The other headers of the response
+/// that filled the cache, e.g. a `Set-Cookie` of another middleware, belong to
+/// that request only; `setHeaders` is called again for every cached response.
+const CACHED_HEADERS: [HeaderName; 5] = [
+  CONTENT_TYPE,
+  ETAG,
+  LAST_MODIFIED,
+  CACHE_CONTROL,
+  ACCEPT_RANGES,
+];
+
+/// Files smaller than this are not worth compressing
+const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Options of the static file cache
+#[derive(Debug, Clone)]
+pub struct StaticCacheOptions {
+  /// Total size, in bytes, of the cached bodies and their compressed variants
+  pub max_size: usize,
+  /// Size, in bytes, of the largest file to cache
+  pub max_file_size: usize,
+  /// How long a cached file is served before its modification time is checked
+  /// again
+  pub revalidate: Duration,
+}
+
+impl Default for StaticCacheOptions {
+  fn default() -> Self {
+    Self {
+      max_size: 32 * 1024 * 1024,
+      max_file_size: 256 * 1024,
+      revalidate: Duration::from_secs(1),
+    }
+  }
+}
+
+/// A file held in memory, with the headers it was served with
+pub struct CachedFile {
+  /// The path of the file, relative to the root, given to `setHeaders`
+  pub served_path: PathBuf,
+  /// The file on disk
+  pub path: PathBuf,
+  /// The metadata of the file when it was read
+  pub metadata: Metadata,
+  /// The response headers, except `Content-Length`, `Content-Encoding` and
+  /// `Vary`
+  pub headers: HeaderMap,
+  pub body: Bytes,
+  /// Compressed variants of `body`
+  pub variants: Vec<(ContentEncoding, Bytes)>,
+}
+
+impl CachedFile {
+  /// Read the file at `path` into memory. `headers` are the headers the file
+  /// was served with, of which only the `CACHED_HEADERS` are kept; its `ETag`,
+  /// if any, is recomputed from the metadata of the file, as the served file
+  /// may have been a precompressed variant.
+  pub fn load(served_path: &Path, path: &Path, served_headers: HeaderMap) -> std::io::Result<Self> {
+    let metadata = fs::metadata(path)?;
+    let body = Bytes::from(fs::read(path)?);
+    // the file changed while it was read
+    if fs::metadata(path)?.modified().ok() != metadata.modified().ok() {
+      return Err(std::io::Error::other("file modified while being cached"));
+    }
+
+    let mut headers = HeaderMap::new();
+    for name in CACHED_HEADERS {
+      if let Some(value) = served_headers.get(&name) {
+        headers.insert(name, value.clone());
+      }
+    }
+    if headers.contains_key(ETAG)
+      && let Some(etag) = file_etag(&metadata)
+    {
+      headers.insert(ETAG, etag);
+    }
+
+    let compressible = headers
+      .get(CONTENT_TYPE)
+      .and_then(|content_type| content_type.to_str().ok())
+      .is_some_and(utilities::is_compressible);
+    let mut variants = Vec::new();
+    if compressible && body.len() >= COMPRESSION_THRESHOLD {
+      for encoding in VARIANTS {
+        let compressed = utilities::compress_bytes(&body, encoding)?;
+        if compressed.len() < body.len() {
+          variants.push((encoding, compressed));
+        }
+      }
+    }
+
+    Ok(Self {
+      served_path: served_path.to_path_buf(),
+      path: path.to_path_buf(),
+      metadata,
+      headers,
+      body,
+      variants,
+    })
+  }
+
+  /// The bytes held in memory for this file
+  fn size(&self) -> usize {
+    self.body.len()
+      + self
+        .variants
+        .iter()
+        .map(|(_, variant)| variant.len())
+        .sum::<usize>()
+  }
+
+  /// Whether the file on disk is still the one held in memory
+  fn is_current(&self) -> bool {
+    match fs::metadata(&self.path) {
+      Ok(metadata) => {
+        metadata.len() == self.metadata.len()
+          && metadata.modified().ok() == self.metadata.modified().ok()
+      }
+      Err(_) => false,
+    }
+  }
+
+  /// The body to send for a request accepting `accept_encoding`, and its
+  /// `Content-Encoding`
+  pub fn negotiate(&self, accept_encoding: Option<&str>) -> (Option<ContentEncoding>, Bytes) {
+    let available = self
+      .variants
+      .iter()
+      .map(|(encoding, _)| *encoding)
+      .collect::<Vec<_>>();
+    let encoding = accept_encoding
+      .and_then(|accept_encoding| ContentEncoding::negotiate_among(accept_encoding, &available));
+    let body = self
+      .variants
+      .iter()
+      .find(|(variant, _)| Some(*variant) == encoding)
+      .map_or_else(|| self.body.clone(), |(_, variant)| variant.clone());
+    (encoding, body)
+  }
+
+  /// Whether the response varies with `Accept-Encoding`
+  pub fn has_variants(&self) -> bool {
+    !self.variants.is_empty()
+  }
+}
+
+/// The `ETag` hyper-staticfile gives the file described by `metadata`, so that
+/// cached and uncached responses of a file share the same `ETag`, even when
+/// the uncached one is a precompressed variant
+fn file_etag(metadata: &Metadata) -> Option<HeaderValue> {
+  let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+  HeaderValue::from_str(&format!(
+    "W/\"{:x}-{:x}.{:x}\"",
+    metadata.len(),
+    modified.as_secs(),
+    modified.subsec_nanos()
+  ))
+  .ok()
+}
+
+struct CacheEntry {
+  file: Arc<CachedFile>,
+  /// Recency of the entry, the key of the entry in `CacheState::recency`
+  tick: u64,
+  /// When the file on disk was last checked
+  checked: Instant,
+}
+
+#[derive(Default)]
+struct CacheState {
+  entries: HashMap<String, CacheEntry>,
+  /// Keys of the entries, from the least to the most recently used
+  recency: BTreeMap<u64, String>,
+  tick: u64,
+  size: usize,
+}
+
+impl CacheState {
+  fn touch(&mut self, key: &str) {
+    self.tick += 1;
+    let tick = self.tick;
+    if let Some(entry) = self.entries.get_mut(key) {
+      self.recency.remove(&entry.tick);
+      self.recency.insert(tick, key.to_owned());
+      entry.tick = tick;
+    }
+  }
+
+  fn remove(&mut self, key: &str) {
+    if let Some(entry) = self.entries.remove(key) {
+      self.recency.remove(&entry.tick);
+      self.size -= entry.file.size();
+    }
+  }
+}
+
+/// An in-memory cache of small static files, keyed by request path and
+/// bounded by the total size of the cached bodies. The least recently used
+/// files are evicted first.
+///
+/// Cached files are served without touching the disk; their modification
+/// time and size are checked again at most once per `revalidate` period, and
+/// changed or removed files are dropped from the cache.
+pub struct StaticCache {
+  options: StaticCacheOptions,
+  state: Mutex<CacheState>,
+}
+
+impl StaticCache {
+  pub fn new(options: StaticCacheOptions) -> Self {
+    Self {
+      options,
+      state: Default::default(),
+    }
+  }
+
+  /// The cached file for request path `key`, if it is still current
+  pub fn get(&self, key: &str) -> Option<Arc<CachedFile>> {
+    let (file, checked) = {
+      let mut state = self.state.lock().ok()?;
+      let entry = state.entries.get(key)?;
+      let found = (entry.file.clone(), entry.checked);
+      state.touch(key);
+      found
+    };
+    if checked.elapsed() < self.options.revalidate {
+      return Some(file);
+    }
+
+    // check the file on disk without holding the lock
+    let current = file.is_current();
+    let mut state = self.state.lock().ok()?;
+    match current {
+      true => {
+        if let Some(entry) = state.entries.get_mut(key)
+          && Arc::ptr_eq(&entry.file, &file)
+        {
+          entry.checked = Instant::now();
+        }
+        Some(file)
+      }
+      false => {
+        if state
+          .entries
+          .get(key)
+          .is_some_and(|entry| Arc::ptr_eq(&entry.file, &file))
+        {
+          state.remove(key);
+        }
+        None
+      }
+    }
+  }
+
+  /// Cache `file` for request path `key`, evicting the least recently used
+  /// files to make room for it
+  pub fn insert(&self, key: String, file: CachedFile) {
+    let size = file.size();
+    if size > self.options.max_size {
+      return;
+    }
+    let Ok(mut state) = self.state.lock() else {
+      return;
+    };
+    state.remove(&key);
+    while state.size + size > self.options.max_size {
+      let Some((_, oldest)) = state.recency.pop_first() else {
+        break;
+      };
+      if let Some(entry) = state.entries.remove(&oldest) {
+        state.size -= entry.file.size();
+      }
+    }
+
+    state.tick += 1;
+    let tick = state.tick;
+    state.recency.insert(tick, key.clone());
+    state.size += size;
+    state.entries.insert(
+      key,
+      CacheEntry {
+        file: Arc::new(file),
+        tick,
+        checked: Instant::now(),
+      },
+    );
+  }
+
+  /// Whether a file of `size` bytes can be cached
+  pub fn accepts(&self, size: u64) -> bool {
+    size <= self.options.max_file_size as u64
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{io::Read, thread, time::Duration};
+
+  use flate2::read::GzDecoder;
+  use hyper::header::{CONTENT_LENGTH, SET_COOKIE};
+  use tempfile::TempDir;
+
+  use super::*;
+
+  fn cache(max_size: usize, revalidate: Duration) -> StaticCache {
+    StaticCache::new(StaticCacheOptions {
+      max_size,
+      max_file_size: max_size,
+      revalidate,
+    })
+  }
+
+  fn load(directory: &TempDir, name: &str, content: &str) -> CachedFile {
+    let path = directory.path().join(name);
+    fs::write(&path, content).unwrap();
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    headers.insert(ETAG, HeaderValue::from_static("W/\"stale\""));
+    headers.insert(CONTENT_LENGTH, HeaderValue::from_static("0"));
+    headers.insert(SET_COOKIE, HeaderValue::from_static("session=secret"));
+    CachedFile::load(Path::new(name), &path, headers).unwrap()
+  }
+
+  #[test]
+  fn test_load() {
+    let directory = TempDir::new().unwrap();
+    let file = load(&directory, "small.txt", "hello");
+    assert_eq!(file.body, "hello");
+    assert!(file.variants.is_empty());
+    assert!(!file.headers.contains_key(CONTENT_LENGTH));
+    // the headers of the request that filled the cache are not kept
+    assert!(!file.headers.contains_key(SET_COOKIE));
+    assert_eq!(file.headers[ETAG], file_etag(&file.metadata).unwrap());
+
+    let content = "compressible ".repeat(200);
+    let file = load(&directory, "large.txt", &content);
+    assert_eq!(file.variants.len(), 2);
+    let (encoding, body) = file.negotiate(Some("gzip, deflate"));
+    assert_eq!(encoding, Some(ContentEncoding::Gzip));
+    let mut decompressed = String::new();
+    GzDecoder::new(&body[..])
+      .read_to_string(&mut decompressed)
+      .unwrap();
+    assert_eq!(decompressed, content);
+    assert_eq!(file.negotiate(Some("identity")), (None, file.body.clone()));
+    assert_eq!(file.negotiate(None), (None, file.body.clone()));
+  }
+
+  #[test]
+  fn test_lru_eviction() {
+    let directory = TempDir::new().unwrap();
+    let cache = cache(10, Duration::from_secs(60));
+    cache.insert("/a".to_owned(), load(&directory, "a", "aaaa"));
+    cache.insert("/b".to_owned(), load(&directory, "b", "bbbb"));
+    // `/a` becomes the most recently used file
+    assert!(cache.get("/a").is_some());
+    cache.insert("/c".to_owned(), load(&directory, "c", "cccc"));
+
+    assert!(cache.get("/a").is_some());
+    assert!(cache.get("/b").is_none());
+    assert!(cache.get("/c").is_some());
+
+    // too large to be cached
+    cache.insert("/d".to_owned(), load(&directory, "d", "ddddddddddd"));
+    assert!(cache.get("/d").is_none());
+    assert!(cache.get("/a").is_some());
+  }
+
+  #[test]
+  fn test_revalidate() {
+    let directory = TempDir::new().unwrap();
+    let cache = cache(1024, Duration::ZERO);
+    cache.insert("/a".to_owned(), load(&directory, "a", "first"));
+    assert!(cache.get("/a").is_some());
+
+    // ensure a different modification time on coarse file systems
+    thread::sleep(Duration::from_millis(20));
+    fs::write(directory.path().join("a"), "second version").unwrap();
+    assert!(cache.get("/a").is_none());
+
+    cache.insert("/b".to_owned(), load(&directory, "b", "b"));
+    fs::remove_file(directory.path().join("b")).unwrap();
+    assert!(cache.get("/b").is_none());
+  }
+}
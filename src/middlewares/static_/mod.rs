@@ -1,6 +1,14 @@
+mod cache;
+mod spa_fallback;
 mod task;
 
-use std::{fs::Metadata, path::Path, sync::Arc, time::SystemTime};
+use std::{
+  str::FromStr,
+  sync::Arc,
+  time::{Duration, SystemTime},
+};
+
+use byte_unit::Byte;
 
 use napi::{
   bindgen_prelude::*,
@@ -8,7 +16,16 @@ use napi::{
 };
 use napi_derive::napi;
 
-use crate::{request::Request, response::Response, utilities::FileSendOptions};
+use crate::{
+  request::Request,
+  response::Response,
+  utilities::{
+    self, DirectoryListingOptions, FileMetadata, FileSendOptions, ListingSort, StaticFs,
+  },
+  virtual_file_system::VirtualFileSystem,
+};
+use cache::{StaticCache, StaticCacheOptions};
+use spa_fallback::{PathPattern, SpaFallbackOptions};
 use task::StaticMiddlewareTask;
 
 type SetHeadersFnParams = FnArgs<(Response, String, FileStat)>;
@@ -20,11 +37,11 @@ type JsSetHeadersFn<'a> = Function<'a, SetHeadersFnParams, ()>;
 
 #[napi]
 pub struct FileStat {
-  inner: Metadata,
+  inner: FileMetadata,
 }
 
-impl From<Metadata> for FileStat {
-  fn from(value: Metadata) -> Self {
+impl From<FileMetadata> for FileStat {
+  fn from(value: FileMetadata) -> Self {
     Self { inner: value }
   }
 }
@@ -33,61 +50,210 @@ impl From<Metadata> for FileStat {
 impl FileStat {
   #[napi]
   pub fn is_directory(&self) -> bool {
-    self.inner.is_dir()
+    self.inner.is_dir
   }
 
   #[napi]
   pub fn is_file(&self) -> bool {
-    self.inner.is_file()
+    self.inner.is_file
   }
 
   #[napi]
   pub fn is_symbolic_link(&self) -> bool {
-    self.inner.is_symlink()
+    self.inner.is_symlink
   }
 
   #[napi(getter)]
   pub fn size(&self) -> u32 {
-    self.inner.len() as u32
+    self.inner.size as u32
   }
 
   #[napi(getter)]
   pub fn atime_ms(&self) -> Result<BigInt> {
-    let accessed = self
-      .inner
-      .accessed()
-      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-    let accessed = accessed
-      .duration_since(SystemTime::UNIX_EPOCH)
-      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-    Ok(accessed.as_millis().into())
+    epoch_ms(self.inner.accessed, "access")
   }
 
   #[napi(getter)]
   pub fn mtime_ms(&self) -> Result<BigInt> {
-    let modified = self
-      .inner
-      .modified()
-      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-    let modified = modified
-      .duration_since(SystemTime::UNIX_EPOCH)
-      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-    Ok(modified.as_millis().into())
+    epoch_ms(self.inner.modified, "modification")
   }
 
   #[napi(getter)]
   pub fn birthtime_ms(&self) -> Result<BigInt> {
-    let created = self
-      .inner
-      .created()
-      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-    let created = created
-      .duration_since(SystemTime::UNIX_EPOCH)
-      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-    Ok(created.as_millis().into())
+    epoch_ms(self.inner.created, "creation")
+  }
+}
+
+/// Milliseconds since the UNIX epoch of a file's `kind` time, which files
+/// held in memory may lack
+fn epoch_ms(time: Option<SystemTime>, kind: &str) -> Result<BigInt> {
+  let time = time.ok_or_else(|| {
+    Error::new(
+      Status::GenericFailure,
+      format!("The {kind} time is not available for this file"),
+    )
+  })?;
+  let time = time
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+  Ok(time.as_millis().into())
+}
+
+#[napi(object)]
+pub struct JsDirectoryListingOptions {
+  /// Show an icon in front of each entry of HTML listings.
+  ///
+  /// Default: true
+  pub icons: Option<bool>,
+
+  /// The order of the entries: "name", "size" or "modified" (most recent
+  /// first). Directories are always listed first.
+  ///
+  /// Default: "name"
+  pub sort: Option<String>,
+}
+
+impl TryFrom<&JsDirectoryListingOptions> for DirectoryListingOptions {
+  type Error = Error;
+
+  fn try_from(value: &JsDirectoryListingOptions) -> Result<Self> {
+    let mut listing_options = DirectoryListingOptions::default();
+
+    if let Some(icons) = value.icons {
+      listing_options.icons = icons;
+    }
+
+    if let Some(sort) = &value.sort {
+      listing_options.sort = match sort.as_str() {
+        "name" => ListingSort::Name,
+        "size" => ListingSort::Size,
+        "modified" => ListingSort::Modified,
+        _ => {
+          return Err(Error::new(
+            Status::InvalidArg,
+            "Invalid value for directoryListing.sort. Valid values: name, size, modified",
+          ));
+        }
+      };
+    }
+
+    Ok(listing_options)
+  }
+}
+
+#[napi(object)]
+pub struct JsStaticCacheOptions {
+  /// The total size of the cached files, including their compressed variants.
+  /// If this is a number, then the value specifies the number of bytes; if it
+  /// is a string, the value is passed to the
+  /// [bytes](https://docs.rs/byte-unit/latest/byte_unit/) library for
+  /// parsing.
+  ///
+  /// Default: "32mb"
+  pub max_size: Option<Either<i64, String>>,
+
+  /// The size of the largest file to cache, as a number of bytes or a string
+  /// like `maxSize`.
+  ///
+  /// Default: "256kb"
+  pub max_file_size: Option<Either<i64, String>>,
+
+  /// How long, in milliseconds, a cached file is served before its
+  /// modification time is checked again. Set to `0` to check the file on
+  /// every request.
+  ///
+  /// Default: 1000
+  pub revalidate: Option<u32>,
+}
+
+impl TryFrom<&JsStaticCacheOptions> for StaticCacheOptions {
+  type Error = Error;
+
+  fn try_from(value: &JsStaticCacheOptions) -> Result<Self> {
+    let mut cache_options = StaticCacheOptions::default();
+
+    if let Some(max_size) = &value.max_size {
+      cache_options.max_size = parse_size(max_size, "maxSize")?;
+    }
+
+    if let Some(max_file_size) = &value.max_file_size {
+      cache_options.max_file_size = parse_size(max_file_size, "maxFileSize")?;
+    }
+
+    if let Some(revalidate) = value.revalidate {
+      cache_options.revalidate = Duration::from_millis(revalidate as u64);
+    }
+
+    Ok(cache_options)
+  }
+}
+
+fn parse_size(size: &Either<i64, String>, name: &str) -> Result<usize> {
+  match size {
+    Either::A(size) => Ok((*size).max(0) as usize),
+    Either::B(size) => Byte::from_str(&utilities::decimal_to_binary_unit(size))
+      .map(|size| size.as_u64() as usize)
+      .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid {name} value: {e}"))),
+  }
+}
+
+#[napi(object)]
+pub struct JsSpaFallbackOptions {
+  /// The file sent in place of missing files, resolved like a request path.
+  ///
+  /// Default: "index.html"
+  pub file: Option<String>,
+
+  /// Patterns of the request paths eligible for the fallback, where `*`
+  /// matches within a path segment and `**` across segments. Every path is
+  /// eligible when empty.
+  ///
+  /// Default: []
+  pub include: Option<Vec<String>>,
+
+  /// Patterns of the request paths that never get the fallback, e.g.
+  /// `['/api/**']`.
+  ///
+  /// Default: []
+  pub exclude: Option<Vec<String>>,
+}
+
+impl TryFrom<&JsSpaFallbackOptions> for SpaFallbackOptions {
+  type Error = Error;
+
+  fn try_from(value: &JsSpaFallbackOptions) -> Result<Self> {
+    let mut spa_fallback_options = SpaFallbackOptions::default();
+
+    if let Some(file) = &value.file {
+      spa_fallback_options.file = file.into();
+    }
+
+    if let Some(include) = &value.include {
+      spa_fallback_options.include = parse_patterns(include, "include")?;
+    }
+
+    if let Some(exclude) = &value.exclude {
+      spa_fallback_options.exclude = parse_patterns(exclude, "exclude")?;
+    }
+
+    Ok(spa_fallback_options)
   }
 }
 
+fn parse_patterns(patterns: &[String], name: &str) -> Result<Vec<PathPattern>> {
+  patterns
+    .iter()
+    .map(|pattern| {
+      PathPattern::new(pattern).map_err(|e| {
+        Error::new(
+          Status::InvalidArg,
+          format!("Invalid spaFallback.{name} pattern \"{pattern}\": {e}"),
+        )
+      })
+    })
+    .collect()
+}
+
 #[napi(object)]
 pub struct JsStaticOptions<'a> {
   /// Determines how dotfiles (files or directories that begin with a dot “.”)
@@ -157,10 +323,10 @@ pub struct JsStaticOptions<'a> {
   pub last_modified: Option<bool>,
 
   /// Set the max-age property of the Cache-Control header in milliseconds or a
-  /// string in [jackdauer](https://docs.rs/jackdauer/0.1.2/jackdauer/) format.
+  /// string in [ms](https://www.npmjs.org/package/ms) format, e.g. `'1d'`.
   ///
   /// Default: 0
-  pub max_age: Option<u32>,
+  pub max_age: Option<Either<i64, String>>,
 
   /// Redirect to trailing “/” when the pathname is a directory.
   ///
@@ -199,6 +365,41 @@ pub struct JsStaticOptions<'a> {
   ///
   /// Default: true
   pub cache_control: Option<bool>,
+
+  /// List the content of directories without an index file, e.g. for
+  /// internal file shares. The listing is rendered as HTML, JSON or plain
+  /// text, negotiated from the request's `Accept` header. Dotfiles are only
+  /// listed when `dotfiles` is "allow".
+  ///
+  /// Set to `true` or to an object of options to enable listings.
+  ///
+  /// Default: false
+  pub directory_listing: Option<Either<bool, JsDirectoryListingOptions>>,
+
+  /// Keep small files in memory, along with their `Content-Type`, `ETag`,
+  /// `Last-Modified`, `Cache-Control` and `Accept-Ranges` headers and their
+  /// `br` and `gzip` compressed variants, so that hot assets are served
+  /// without touching the disk. The headers set by other middlewares, e.g.
+  /// cookies, are not cached. The least recently used files are
+  /// evicted once `maxSize` is reached, and changed files are reloaded once
+  /// their modification time is checked again.
+  ///
+  /// Set to `true` or to an object of options to enable the cache.
+  ///
+  /// Default: false
+  pub cache: Option<Either<bool, JsStaticCacheOptions>>,
+
+  /// Serve a single-page application: missing files are answered with its
+  /// `index.html`, sent with `Cache-Control: no-cache`, so that client-side
+  /// routes can be loaded directly. Only `GET` and `HEAD` requests accepting
+  /// `text/html`, for paths without a file extension, get the fallback;
+  /// missing assets like `/main.js` are still `404`s. Exclude API routes with
+  /// the `exclude` option.
+  ///
+  /// Set to `true` or to an object of options to enable the fallback.
+  ///
+  /// Default: false
+  pub spa_fallback: Option<Either<bool, JsSpaFallbackOptions>>,
 }
 
 impl<'a> TryFrom<&JsStaticOptions<'a>> for StaticOptions {
@@ -253,8 +454,8 @@ impl<'a> TryFrom<&JsStaticOptions<'a>> for StaticOptions {
       text_options.last_modified = last_modified;
     }
 
-    if let Some(max_age) = value.max_age {
-      text_options.max_age = max_age as u64;
+    if let Some(max_age) = &value.max_age {
+      text_options.max_age = utilities::parse_max_age(max_age, "maxAge")?;
     }
 
     if let Some(redirect) = value.redirect {
@@ -276,6 +477,31 @@ impl<'a> TryFrom<&JsStaticOptions<'a>> for StaticOptions {
       text_options.cache_control = cache_control;
     }
 
+    if let Some(directory_listing) = &value.directory_listing {
+      text_options.directory_listing = match directory_listing {
+        Either::A(true) => Some(DirectoryListingOptions::default()),
+        Either::A(false) => None,
+        Either::B(listing_options) => Some(listing_options.try_into()?),
+      };
+    }
+
+    if let Some(cache) = &value.cache {
+      let cache_options = match cache {
+        Either::A(true) => Some(StaticCacheOptions::default()),
+        Either::A(false) => None,
+        Either::B(cache_options) => Some(cache_options.try_into()?),
+      };
+      text_options.cache = cache_options.map(|options| Arc::new(StaticCache::new(options)));
+    }
+
+    if let Some(spa_fallback) = &value.spa_fallback {
+      text_options.spa_fallback = match spa_fallback {
+        Either::A(true) => Some(SpaFallbackOptions::default()),
+        Either::A(false) => None,
+        Either::B(spa_fallback_options) => Some(spa_fallback_options.try_into()?),
+      };
+    }
+
     Ok(text_options)
   }
 }
@@ -294,6 +520,12 @@ pub struct StaticOptions {
   pub set_headers: Option<Arc<ThreadsafeSetHeadersFn>>,
   pub accept_ranges: bool,
   pub cache_control: bool,
+  pub directory_listing: Option<DirectoryListingOptions>,
+  /// Shared by the clones of the options
+  pub cache: Option<Arc<StaticCache>>,
+  pub spa_fallback: Option<SpaFallbackOptions>,
+  /// Files served in place of the root directory
+  pub fs: Option<StaticFs>,
 }
 
 impl Default for StaticOptions {
@@ -311,6 +543,10 @@ impl Default for StaticOptions {
       set_headers: None,
       accept_ranges: true,
       cache_control: true,
+      directory_listing: None,
+      cache: None,
+      spa_fallback: None,
+      fs: None,
     }
   }
 }
@@ -327,6 +563,8 @@ impl From<&StaticOptions> for FileSendOptions {
       index: value.index.to_owned(),
       extensions: value.extensions.to_owned(),
       dotfiles: value.dotfiles.to_owned(),
+      directory_listing: value.directory_listing.to_owned(),
+      fs: value.fs.to_owned(),
       ..FileSendOptions::default()
     }
   }
@@ -340,10 +578,11 @@ impl From<&StaticOptions> for FileSendOptions {
 /// > cache to improve performance of serving static assets.
 ///
 /// The `root` argument specifies the root directory from which to serve static
-/// assets. The function determines the file to serve by combining `req.url`
-/// with the provided `root` directory. When a file is not found, instead of
-/// sending a 404 response, it instead calls `next()` to move on to the next
-/// middleware, allowing for stacking and fall-backs.
+/// assets, or a `VirtualFileSystem` of files held in memory. The function
+/// determines the file to serve by combining `req.url` with the provided
+/// `root`. When a file is not found, instead of sending a 404 response, it
+/// instead calls `next()` to move on to the next middleware, allowing for
+/// stacking and fall-backs.
 ///
 /// Example of using the StaticMiddleware
 /// Here is an example of using the Static middleware with an elaborate options object:
@@ -365,36 +604,40 @@ impl From<&StaticOptions> for FileSendOptions {
 /// ```
 #[napi]
 pub struct StaticMiddleware {
-  root: String,
   options: StaticOptions,
 }
 
 #[napi]
 impl StaticMiddleware {
   #[napi(constructor)]
-  pub fn new(root: String, options: Option<JsStaticOptions>) -> Result<Self> {
-    Ok(StaticMiddleware {
-      root,
-      options: match &options {
-        Some(options) => StaticOptions::try_from(options)?,
-        None => StaticOptions::default(),
-      },
-    })
+  pub fn new(
+    root: Either<String, &VirtualFileSystem>,
+    options: Option<JsStaticOptions>,
+  ) -> Result<Self> {
+    let mut options = match &options {
+      Some(options) => StaticOptions::try_from(options)?,
+      None => StaticOptions::default(),
+    };
+    if let Either::B(fs) = root {
+      options.fs = Some(fs.owned_inner());
+    }
+    Ok(StaticMiddleware { options })
   }
 
   #[napi]
-  pub fn run(
+  pub fn run<'env>(
     &self,
+    env: &'env Env,
     request: &Request,
     response: &mut Response,
-  ) -> Result<AsyncTask<StaticMiddlewareTask>> {
-    log::debug!("Static Middleware | Called!");
+  ) -> Result<PromiseRaw<'env, bool>> {
+    log::debug!("Request ID: {} | Static Middleware | Called!", request.id());
 
-    Ok(AsyncTask::new(StaticMiddlewareTask {
+    let mut task = StaticMiddlewareTask {
       response: response.to_owned(),
       request: request.to_owned(),
-      root: Path::new(&self.root).to_owned(),
       options: self.options.to_owned(),
-    }))
+    };
+    env.spawn_future(async move { task.run().await })
   }
 }
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use hyper::{HeaderMap, Method, header::ACCEPT};
+use regex::Regex;
+
+use crate::request::WrappedRequest;
+
+/// A pattern of request paths, where `*` matches within a path segment and
+/// `**` matches across segments. A trailing `/**` also matches the path
+/// without it, e.g. `/api/**` matches `/api` and `/api/users/1`.
+#[derive(Debug, Clone)]
+pub struct PathPattern(Regex);
+
+impl PathPattern {
+  pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+    let (pattern, subtree) = match pattern.strip_suffix("/**") {
+      Some(prefix) => (prefix, true),
+      None => (pattern, false),
+    };
+
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(char) = chars.next() {
+      match char {
+        '*' if chars.peek() == Some(&'*') => {
+          chars.next();
+          regex.push_str(".*");
+        }
+        '*' => regex.push_str("[^/]*"),
+        '?' => regex.push_str("[^/]"),
+        _ => regex.push_str(&regex::escape(char.encode_utf8(&mut [0; 4]))),
+      }
+    }
+    if subtree {
+      regex.push_str("(?:/.*)?");
+    }
+    regex.push('$');
+
+    Regex::new(&regex).map(Self)
+  }
+
+  pub fn matches(&self, path: &str) -> bool {
+    self.0.is_match(path)
+  }
+}
+
+/// Options of the single-page application fallback
+#[derive(Debug, Clone)]
+pub struct SpaFallbackOptions {
+  /// The file served in place of missing files, resolved like a request path
+  pub file: PathBuf,
+  /// Request paths eligible for the fallback. Every path when empty.
+  pub include: Vec<PathPattern>,
+  /// Request paths never given the fallback, e.g. API routes
+  pub exclude: Vec<PathPattern>,
+}
+
+impl Default for SpaFallbackOptions {
+  fn default() -> Self {
+    Self {
+      file: PathBuf::from("index.html"),
+      include: Vec::new(),
+      exclude: Vec::new(),
+    }
+  }
+}
+
+impl SpaFallbackOptions {
+  /// Whether a missing file at `path` is answered with the fallback file:
+  /// the request is a `GET` or `HEAD` navigation accepting HTML, for an
+  /// included path without a file extension.
+  pub fn applies(&self, request: &WrappedRequest, path: &str) -> bool {
+    let Ok(inner) = request.inner() else {
+      return false;
+    };
+    if inner.method() != Method::GET && inner.method() != Method::HEAD {
+      return false;
+    }
+    if !accepts_html(request, inner.headers()) {
+      return false;
+    }
+
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    if file_name.contains('.') {
+      return false;
+    }
+
+    (self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(path)))
+      && !self.exclude.iter().any(|pattern| pattern.matches(path))
+  }
+}
+
+/// Whether the request explicitly accepts HTML. Requests without an `Accept`
+/// header are not navigations.
+fn accepts_html(request: &WrappedRequest, headers: &HeaderMap) -> bool {
+  headers.contains_key(ACCEPT)
+    && request
+      .accepts(vec!["html".to_owned()])
+      .is_ok_and(|accepted| accepted.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use http_body_util::{BodyExt, Empty, combinators::BoxBody};
+
+  use super::*;
+
+  fn pattern(pattern: &str) -> PathPattern {
+    PathPattern::new(pattern).unwrap()
+  }
+
+  fn request(method: Method, accept: Option<&str>) -> WrappedRequest {
+    let mut builder = hyper::Request::builder().method(method);
+    if let Some(accept) = accept {
+      builder = builder.header(ACCEPT, accept);
+    }
+    builder
+      .body(BoxBody::new(Empty::<Bytes>::new().map_err(|e| match e {})))
+      .unwrap()
+      .into()
+  }
+
+  #[test]
+  fn test_path_pattern() {
+    assert!(pattern("/api/**").matches("/api"));
+    assert!(pattern("/api/**").matches("/api/users/1"));
+    assert!(!pattern("/api/**").matches("/apis"));
+    assert!(pattern("/app/*").matches("/app/settings"));
+    assert!(!pattern("/app/*").matches("/app/settings/profile"));
+    assert!(pattern("/**/admin").matches("/a/b/admin"));
+    assert!(pattern("/v?/*").matches("/v2/home"));
+    assert!(!pattern("/docs.html").matches("/docsxhtml"));
+  }
+
+  #[test]
+  fn test_applies() {
+    let options = SpaFallbackOptions {
+      exclude: vec![pattern("/api/**")],
+      ..Default::default()
+    };
+    let navigation = request(
+      Method::GET,
+      Some("text/html,application/xhtml+xml,*/*;q=0.8"),
+    );
+    assert!(options.applies(&navigation, "/dashboard/settings"));
+    assert!(options.applies(&navigation, "/"));
+    assert!(!options.applies(&navigation, "/main.js"));
+    assert!(!options.applies(&navigation, "/api/users"));
+
+    assert!(options.applies(&request(Method::HEAD, Some("*/*")), "/about"));
+    assert!(!options.applies(&request(Method::POST, Some("text/html")), "/about"));
+    assert!(!options.applies(&request(Method::GET, Some("application/json")), "/about"));
+    assert!(!options.applies(&request(Method::GET, None), "/about"));
+
+    let options = SpaFallbackOptions {
+      include: vec![pattern("/app/**")],
+      ..Default::default()
+    };
+    assert!(options.applies(&navigation, "/app/home"));
+    assert!(!options.applies(&navigation, "/home"));
+  }
+}
@@ -1,34 +1,39 @@
-use std::path::PathBuf;
+use std::{path::Path, sync::Arc};
 
 use headers_core::HeaderValue;
 use hyper::{
-  StatusCode,
-  header::{ALLOW, CONTENT_LENGTH},
+  Method, StatusCode,
+  header::{
+    ACCEPT_ENCODING, ALLOW, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, RANGE,
+  },
 };
 use napi::bindgen_prelude::*;
-use tokio::runtime::Runtime;
 
-use super::StaticOptions;
+use super::{
+  StaticOptions,
+  cache::{CachedFile, StaticCache},
+};
 use crate::{
   middlewares::static_::FileStat,
   request::Request,
   response::Response,
-  utilities::{FileSendTask, parse_url::RequestExt},
+  utilities::{self, FileMetadata, FileSendOptions, FileSendTask, parse_url::RequestExt},
 };
 
 pub struct StaticMiddlewareTask {
   pub response: Response,
   pub request: Request,
-  pub root: PathBuf,
   pub options: StaticOptions,
 }
 
-impl Task for StaticMiddlewareTask {
-  type Output = bool;
-  type JsValue = bool;
-
-  fn compute(&mut self) -> Result<Self::Output> {
-    log::debug!("StaticMiddleware: Computing ...");
+impl StaticMiddlewareTask {
+  /// Serve the requested file. Returns whether the next middleware should be
+  /// called.
+  pub async fn run(&mut self) -> Result<bool> {
+    log::debug!(
+      "Request ID: {} | StaticMiddleware: Computing ...",
+      self.request.id()
+    );
     let request_method = self.request.method()?;
     if request_method.as_str() != "GET" && request_method.as_str() != "HEAD" {
       if self.options.fallthrough {
@@ -67,13 +72,36 @@ impl Task for StaticMiddlewareTask {
       path = String::with_capacity(0);
     }
 
+    // serve hot files from memory
+    if let Some(cache) = &self.options.cache
+      && let Some(file) = cache.get(&path)
+      && self.send_cached(&file)?
+    {
+      self
+        .set_headers(
+          &file.served_path,
+          FileMetadata::disk(&file.metadata, file.path.clone()),
+        )
+        .await?;
+      return Ok(false);
+    }
+
+    let mut options: FileSendOptions = (&self.options).into();
+    if let Some(spa_fallback) = &self.options.spa_fallback
+      && self
+        .request
+        .with_inner(|w_req| Ok(spa_fallback.applies(w_req, &path)))?
+    {
+      options.fallback = Some(spa_fallback.file.clone());
+    }
+
     let mut file_send_task = FileSendTask {
       response: self.response.to_owned(),
-      path,
-      options: (&self.options).into(),
+      path: path.clone(),
+      options,
     };
 
-    let file_serve_result = file_send_task.compute()?;
+    let file_serve_result = file_send_task.send().await?;
 
     let forward_error = !self.options.fallthrough;
     let response = file_send_task.response.to_owned();
@@ -86,32 +114,135 @@ impl Task for StaticMiddlewareTask {
       return Ok(true);
     }
 
-    if let Some(set_headers_fn) = &self.options.set_headers
-      && let Some(file_serve_result) = file_serve_result
-    {
-      let path = file_serve_result
-        .served_path
-        .to_str()
-        .ok_or(Error::new(
-          Status::GenericFailure,
-          "Support for non-UTF-8 paths not implemented yet.",
-        ))?
-        .to_owned();
-      let file_stat: FileStat = file_serve_result.file_stat.into();
-      // Create Tokio runtime
-      let rt = Runtime::new()?;
-      //   TODO: Figure out how to get `path` and `file_stat`
-      rt.block_on(async {
-        set_headers_fn
-          .call_async((response, path, file_stat).into())
-          .await
-      })?;
+    if let Some(file_serve_result) = file_serve_result {
+      // the fallback file depends on the request's `Accept` header
+      if let Some(cache) = &self.options.cache
+        && !file_serve_result.fallback
+      {
+        self.fill_cache(
+          cache,
+          path,
+          &file_serve_result.served_path,
+          &file_serve_result.file_stat,
+        )?;
+      }
+      self
+        .set_headers(&file_serve_result.served_path, file_serve_result.file_stat)
+        .await?;
     }
 
     Ok(false)
   }
 
-  fn resolve(&mut self, _env: Env, compute_output: Self::Output) -> Result<Self::JsValue> {
-    Ok(compute_output)
+  /// Call the `setHeaders` option, if any, for the served file
+  async fn set_headers(&self, served_path: &Path, metadata: FileMetadata) -> Result<()> {
+    let Some(set_headers_fn) = &self.options.set_headers else {
+      return Ok(());
+    };
+    let path = served_path
+      .to_str()
+      .ok_or(Error::new(
+        Status::GenericFailure,
+        "Support for non-UTF-8 paths not implemented yet.",
+      ))?
+      .to_owned();
+    let file_stat: FileStat = metadata.into();
+    set_headers_fn
+      .call_async((self.response.to_owned(), path, file_stat).into())
+      .await
+  }
+
+  /// Send `file` from memory. Returns `false`, without sending anything, for
+  /// range requests, which are served from disk.
+  fn send_cached(&self, file: &CachedFile) -> Result<bool> {
+    let (method, accept_encoding, fresh) = match self.request.with_inner(|w_req| {
+      let request = w_req.inner()?;
+      if request.headers().contains_key(RANGE) {
+        return Ok(None);
+      }
+      let accept_encoding = request
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+      let fresh = utilities::is_fresh(
+        request.method(),
+        request.headers(),
+        StatusCode::OK,
+        &file.headers,
+      );
+      Ok(Some((request.method().clone(), accept_encoding, fresh)))
+    })? {
+      Some(request) => request,
+      None => return Ok(false),
+    };
+
+    self.response.with_inner(|w_res| {
+      let inner = w_res.inner()?;
+      *inner.status_mut() = StatusCode::OK;
+      inner.headers_mut().extend(file.headers.clone());
+      if file.has_variants() {
+        w_res.vary("Accept-Encoding".to_owned())?;
+      }
+
+      let inner = w_res.inner()?;
+      if fresh {
+        *inner.status_mut() = StatusCode::NOT_MODIFIED;
+        inner.headers_mut().remove(CONTENT_TYPE);
+        return w_res.end(None);
+      }
+
+      let (encoding, body) = file.negotiate(accept_encoding.as_deref());
+      let headers = inner.headers_mut();
+      if let Some(encoding) = encoding {
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.token()));
+      }
+      headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+      match method == Method::HEAD {
+        true => w_res.end(None),
+        false => w_res.end(Some(body)),
+      }
+    })?;
+    Ok(true)
+  }
+
+  /// Cache the file served for `key` in the background, when it is small, on
+  /// disk and was sent whole
+  fn fill_cache(
+    &self,
+    cache: &Arc<StaticCache>,
+    key: String,
+    served_path: &Path,
+    metadata: &FileMetadata,
+  ) -> Result<()> {
+    let Some(file_path) = &metadata.disk_path else {
+      return Ok(());
+    };
+    if !cache.accepts(metadata.size) {
+      return Ok(());
+    }
+    let headers = self.response.with_inner(|w_res| {
+      let inner = w_res.inner()?;
+      let whole = inner.status() == StatusCode::OK && !inner.headers().contains_key(CONTENT_RANGE);
+      Ok(whole.then(|| inner.headers().clone()))
+    })?;
+    let Some(headers) = headers else {
+      return Ok(());
+    };
+
+    let cache = cache.clone();
+    let request_id = self.request.id();
+    let served_path = served_path.to_path_buf();
+    let file_path = file_path.clone();
+    tokio::task::spawn_blocking(move || {
+      match CachedFile::load(&served_path, &file_path, headers) {
+        Ok(file) => cache.insert(key, file),
+        Err(e) => log::debug!(
+          "Request ID: {request_id} | StaticMiddleware: not caching {}: {e}",
+          file_path.display()
+        ),
+      }
+    });
+    Ok(())
   }
 }
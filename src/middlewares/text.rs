@@ -1,16 +1,17 @@
 use std::{str::FromStr, sync::Arc};
 
 use byte_unit::Byte;
-use futures::StreamExt;
-use http_body_util::{BodyStream, Limited, combinators::BoxBody};
-use hyper::Request as HyperRequest;
 use napi::{
   bindgen_prelude::*,
   threadsafe_function::{ThreadsafeCallContext, ThreadsafeFunction},
 };
 use napi_derive::napi;
 
-use crate::{request::Request, response::Response, utilities};
+use crate::{
+  request::Request,
+  response::Response,
+  utilities::{self, BodyParserError},
+};
 
 type ThreadsafeVerifyFn = ThreadsafeFunction<
   FnArgs<(Request, Response, Buffer, String)>,
@@ -27,13 +28,16 @@ type JsVerifyFn<'a> = Function<'a, FnArgs<(Request, Response, Buffer, String)>,
 #[napi(object)]
 pub struct JsTextOptions<'a> {
   /// Specify the default character set for the text content if the charset is
-  /// not specified in the `Content-Type` header of the request.
+  /// not specified in the `Content-Type` header of the request. Any
+  /// [WHATWG encoding label](https://encoding.spec.whatwg.org/#names-and-labels)
+  /// is accepted, e.g. `latin1` or `shift_jis`.
   ///
   /// Default = "utf-8"
   pub default_charset: Option<String>,
 
   /// Enables or disables handling deflated (compressed) bodies; when disabled,
-  /// deflated bodies are rejected.
+  /// deflated bodies are rejected with `415 Unsupported Media Type`. The
+  /// `limit` option applies to the inflated body.
   ///
   /// Default = true
   pub inflate: Option<bool>,
@@ -69,7 +73,13 @@ impl<'a> JsTextOptions<'a> {
     let mut text_options = TextOptions::default();
 
     if let Some(default_charset) = &self.default_charset {
-      text_options.default_charset = default_charset.to_owned();
+      if utilities::lookup_charset(default_charset).is_none() {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("Unsupported default charset: {default_charset}"),
+        ));
+      }
+      text_options.default_charset = default_charset.to_lowercase();
     }
 
     if let Some(inflate) = self.inflate {
@@ -134,6 +144,49 @@ impl Default for TextOptions {
 }
 
 impl TextOptions {
+  async fn run(
+    &self,
+    request: &Request,
+    response: &Response,
+  ) -> std::result::Result<bool, BodyParserError> {
+    log::debug!("Request ID: {} | Text Middleware | Called!", request.id());
+
+    // determine if request should be parsed
+    if !self.should_parse(request)? {
+      return Ok(true);
+    }
+
+    // reject bodies in charsets that cannot be decoded before reading them
+    let charset = self.charset(request)?;
+    let Some(encoding) = utilities::lookup_charset(&charset) else {
+      return Err(BodyParserError::charset_unsupported(charset));
+    };
+
+    let body = utilities::read_body(request, self.limit, self.inflate).await?;
+
+    // skip requests without bodies
+    if body.is_empty() {
+      return Ok(true);
+    }
+
+    if let Some(verify) = self.verify.clone() {
+      let body_buf = Buffer::from(body.as_slice());
+      verify
+        .call_async((request.to_owned(), response.to_owned(), body_buf, charset).into())
+        .await
+        .map_err(BodyParserError::verify_failed)?;
+    }
+
+    let req_inner = utilities::decode_charset(&body, encoding);
+
+    request.with_inner_mut(|w_req| {
+      w_req.set_body(Either3::A(req_inner));
+      Ok(())
+    })?;
+
+    Ok(true)
+  }
+
   fn should_parse(&self, request: &Request) -> Result<bool> {
     let req_content_type = match request.get(hyper::http::header::CONTENT_TYPE.to_string())? {
       Either::A(val) => val,
@@ -141,6 +194,16 @@ impl TextOptions {
     };
     Ok(utilities::type_is(&req_content_type, &[&self.typ]).is_some())
   }
+
+  /// The charset named by the request's `Content-Type`, falling back to
+  /// `default_charset`.
+  fn charset(&self, request: &Request) -> Result<String> {
+    let charset = match request.get(hyper::http::header::CONTENT_TYPE.to_string())? {
+      Either::A(content_type) => utilities::content_type_charset(&content_type),
+      Either::B(_) => None,
+    };
+    Ok(charset.unwrap_or_else(|| self.default_charset.to_owned()))
+  }
 }
 
 /// This is a built-in middleware function in Express. It parses incoming
@@ -148,8 +211,10 @@ impl TextOptions {
 ///
 /// Returns middleware that parses all bodies as a string and only looks at
 /// requests where the `Content-Type` header matches the `type` option. This
-/// parser accepts any Unicode encoding of the body and supports automatic
-/// inflation of `gzip` and `deflate` encodings.
+/// parser decodes the body using the `charset` of the `Content-Type` header
+/// (or `defaultCharset`), accepting any encoding from the WHATWG Encoding
+/// Standard, and supports automatic inflation of `gzip`, `deflate`, `br` and
+/// `zstd` encodings. Bodies in an unknown charset are rejected with `415`.
 ///
 /// A new `body` string containing the parsed data is populated on the
 /// `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -164,7 +229,7 @@ impl TextOptions {
 /// > is recommended.
 #[napi]
 pub struct TextMiddleware {
-  options: TextOptions,
+  options: Arc<TextOptions>,
 }
 
 #[napi]
@@ -172,75 +237,29 @@ impl TextMiddleware {
   #[napi(constructor)]
   pub fn new(options: Option<JsTextOptions>) -> Result<Self> {
     Ok(TextMiddleware {
-      options: match options {
+      options: Arc::new(match options {
         Some(options) => options.to_text_options()?,
         None => TextOptions::default(),
-      },
+      }),
     })
   }
 
+  /// Parses the request's body, rejecting with a body-parser style error
+  /// (`status`, `type`, ...) when it is too large or in an unsupported
+  /// encoding or charset.
   #[napi]
-  pub async fn run(&self, request: &Request, response: &Response) -> Result<bool> {
-    log::debug!("Text Middleware | Called!");
-
-    // determine if request should be parsed
-    let should_parse = self.options.should_parse(request)?;
-
-    // determine if request should be parsed
-    if !should_parse {
-      return Ok(true);
-    }
-
-    let hyper_request = request.with_inner_mut(|w_req| w_req.take_inner())?;
-    let (parts, body) = hyper_request.into_parts();
-    let mut body_stream = BodyStream::new(Limited::new(body, self.options.limit));
-
-    let mut body = Vec::new();
-
-    while let Some(data) = body_stream.next().await {
-      let data = data
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
-        .into_data()
-        .map_err(|_| Error::new(Status::GenericFailure, "Encountered a non-data frame."))?;
-      body.extend_from_slice(&data);
-    }
-
-    request.with_inner_mut(|w_req| {
-      w_req.set_inner(HyperRequest::from_parts(parts, BoxBody::new(body_stream)));
-      Ok(())
-    })?;
-
-    // skip requests without bodies
-    if body.is_empty() {
-      return Ok(true);
-    }
-
-    if let Some(verify) = self.options.verify.clone() {
-      let body_buf = Buffer::from(body.as_slice());
-      verify
-        .call_async(
-          (
-            request.to_owned(),
-            response.to_owned(),
-            body_buf,
-            self.options.default_charset.to_owned(), // TODO: Pass in actual encoding value
-          )
-            .into(),
-        )
-        .await?;
-    }
-
-    // TODO: Support multiple text encodings. See iconv-lite npm package.
-    //     : See encoding_rs crate
-
-    let req_inner =
-      String::from_utf8(body).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-
-    request.with_inner_mut(|w_req| {
-      w_req.set_body(Either3::A(req_inner));
-      Ok(())
-    })?;
-
-    Ok(true)
+  pub fn run<'env>(
+    &self,
+    env: &'env Env,
+    request: &Request,
+    response: &Response,
+  ) -> Result<PromiseRaw<'env, bool>> {
+    let options = self.options.clone();
+    let request = request.clone();
+    let response = response.clone();
+    env.spawn_future_with_callback(
+      async move { Ok(options.run(&request, &response).await) },
+      |env, result| result.map_err(|e| e.into_napi_error(env)),
+    )
   }
 }
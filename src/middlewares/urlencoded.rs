@@ -1,15 +1,19 @@
 use std::{str::FromStr, sync::Arc};
 
 use byte_unit::Byte;
-use futures::StreamExt;
-use http_body_util::{BodyStream, Limited, combinators::BoxBody};
-use hyper::Request as HyperRequest;
+use encoding_rs::Encoding;
 use napi::bindgen_prelude::*;
 use napi::threadsafe_function::{ThreadsafeCallContext, ThreadsafeFunction};
 use napi_derive::napi;
 
-use crate::utilities::{UrlencodedOptions as UrlencodedParseOptions, parse_urlencoded};
-use crate::{request::Request, response::Response, utilities};
+use crate::utilities::{
+  UrlencodedError, UrlencodedOptions as UrlencodedParseOptions, parse_urlencoded,
+};
+use crate::{
+  request::Request,
+  response::Response,
+  utilities::{self, BodyParserError},
+};
 
 type ThreadsafeParseTypeFn =
   ThreadsafeFunction<FnArgs<(Request,)>, bool, FnArgs<(Request,)>, Status, false, false, 0>;
@@ -39,7 +43,8 @@ pub struct JsUrlencodedOptions<'a> {
   pub extended: Option<bool>,
 
   /// Enables or disables handling deflated (compressed) bodies; when disabled,
-  /// deflated bodies are rejected.
+  /// deflated bodies are rejected with `415 Unsupported Media Type`. The
+  /// `limit` option applies to the inflated body.
   ///
   /// Default = true
   pub inflate: Option<bool>,
@@ -84,6 +89,21 @@ pub struct JsUrlencodedOptions<'a> {
   ///
   /// Default = 32
   pub depth: Option<u8>,
+
+  /// The default charset to parse as, if not specified in the `Content-Type`
+  /// of the request. Supported values are `utf-8` and `iso-8859-1`; other
+  /// charsets are rejected with `415 Unsupported Media Type`.
+  ///
+  /// Default = "utf-8"
+  pub default_charset: Option<String>,
+
+  /// Whether to let the value of the `utf8` parameter take precedence as the
+  /// charset selector. Forms can include a hidden `utf8=✓` field to signal
+  /// the charset they were submitted in; the field is removed from
+  /// `req.body`.
+  ///
+  /// Default = false
+  pub charset_sentinel: Option<bool>,
 }
 
 impl<'a> TryFrom<JsUrlencodedOptions<'a>> for UrlencodedOptions {
@@ -150,6 +170,21 @@ impl<'a> TryFrom<JsUrlencodedOptions<'a>> for UrlencodedOptions {
       urlencoded_options.depth = depth as usize;
     }
 
+    if let Some(default_charset) = &value.default_charset {
+      let default_charset = default_charset.to_lowercase();
+      if supported_charset(&default_charset).is_none() {
+        return Err(Error::new(
+          Status::InvalidArg,
+          "Invalid value for defaultCharset. Valid values: utf-8, iso-8859-1",
+        ));
+      }
+      urlencoded_options.default_charset = default_charset;
+    }
+
+    if let Some(charset_sentinel) = value.charset_sentinel {
+      urlencoded_options.charset_sentinel = charset_sentinel;
+    }
+
     Ok(urlencoded_options)
   }
 }
@@ -162,6 +197,8 @@ struct UrlencodedOptions {
   typ: Either<Vec<String>, Arc<ThreadsafeParseTypeFn>>,
   verify: Option<Arc<ThreadsafeVerifyFn>>,
   depth: usize,
+  default_charset: String,
+  charset_sentinel: bool,
 }
 
 impl Default for UrlencodedOptions {
@@ -174,11 +211,82 @@ impl Default for UrlencodedOptions {
       typ: Either::A(vec!["application/x-www-form-urlencoded".to_owned()]),
       verify: None,
       depth: 32,
+      default_charset: "utf-8".to_owned(),
+      charset_sentinel: false,
     }
   }
 }
 
+/// Only the charsets `qs` understands are accepted for urlencoded bodies.
+fn supported_charset(charset: &str) -> Option<&'static Encoding> {
+  match utilities::lookup_charset(charset)? {
+    encoding if encoding == encoding_rs::UTF_8 || encoding == encoding_rs::WINDOWS_1252 => {
+      Some(encoding)
+    }
+    _ => None,
+  }
+}
+
 impl UrlencodedOptions {
+  async fn run(
+    &self,
+    request: &Request,
+    response: &Response,
+  ) -> std::result::Result<bool, BodyParserError> {
+    log::debug!(
+      "Request ID: {} | Urlencoded Middleware | Called!",
+      request.id()
+    );
+
+    // determine if request should be parsed
+    if !self.should_parse(request).await? {
+      return Ok(true);
+    }
+
+    // reject bodies in charsets that cannot be decoded before reading them
+    let charset = self.charset(request)?;
+    let Some(encoding) = supported_charset(&charset) else {
+      return Err(BodyParserError::charset_unsupported(charset));
+    };
+
+    let body = utilities::read_body(request, self.limit, self.inflate).await?;
+
+    // skip requests without bodies
+    if body.is_empty() {
+      return Ok(true);
+    }
+
+    if let Some(verify) = self.verify.clone() {
+      let body_buf = Buffer::from(body.as_slice());
+      verify
+        .call_async((request.to_owned(), response.to_owned(), body_buf, charset).into())
+        .await
+        .map_err(BodyParserError::verify_failed)?;
+    }
+
+    let req_inner = utilities::decode_charset(&body, encoding);
+
+    let parse_options = UrlencodedParseOptions {
+      charset: encoding,
+      ..self.into()
+    };
+    let parsed_body =
+      parse_urlencoded(req_inner.as_str(), &parse_options).map_err(|e| match e {
+        UrlencodedError::TooManyParameters => BodyParserError::parameters_too_many(),
+        UrlencodedError::DepthExceeded => BodyParserError::depth_exceeded(),
+        UrlencodedError::ParseError(_) => {
+          BodyParserError::parse_failed(e.to_string(), Some(req_inner.clone()))
+        }
+      })?;
+
+    request.with_inner_mut(|w_req| {
+      w_req.set_body(Either3::B(parsed_body));
+      Ok(())
+    })?;
+
+    Ok(true)
+  }
+
   async fn should_parse(&self, request: &Request) -> Result<bool> {
     let req_content_type = match request.get(hyper::http::header::CONTENT_TYPE.to_string())? {
       Either::A(val) => val,
@@ -196,6 +304,16 @@ impl UrlencodedOptions {
       }
     }
   }
+
+  /// The charset named by the request's `Content-Type`, falling back to
+  /// `default_charset`.
+  fn charset(&self, request: &Request) -> Result<String> {
+    let charset = match request.get(hyper::http::header::CONTENT_TYPE.to_string())? {
+      Either::A(content_type) => utilities::content_type_charset(&content_type),
+      Either::B(_) => None,
+    };
+    Ok(charset.unwrap_or_else(|| self.default_charset.to_owned()))
+  }
 }
 
 impl From<&UrlencodedOptions> for UrlencodedParseOptions {
@@ -204,6 +322,8 @@ impl From<&UrlencodedOptions> for UrlencodedParseOptions {
       extended: value.extended,
       parameter_limit: value.parameter_limit,
       depth: value.depth,
+      charset: encoding_rs::UTF_8,
+      charset_sentinel: value.charset_sentinel,
     }
   }
 }
@@ -213,8 +333,8 @@ impl From<&UrlencodedOptions> for UrlencodedParseOptions {
 ///
 /// Returns middleware that only parses urlencoded bodies and only looks at
 /// requests where the `Content-Type` header matches the `type` option. This
-/// parser accepts only UTF-8 encoding of the body and supports automatic
-/// inflation of `gzip` and `deflate` encodings.
+/// parser accepts UTF-8 and ISO-8859-1 encodings of the body and supports
+/// automatic inflation of `gzip`, `deflate`, `br` and `zstd` encodings.
 ///
 /// A new `body` object containing the parsed data is populated on the
 /// `request` object after the middleware (i.e. `req.body`), or `undefined` if
@@ -231,7 +351,7 @@ impl From<&UrlencodedOptions> for UrlencodedParseOptions {
 /// > user-input.
 #[napi]
 pub struct UrlencodedMiddleware {
-  options: UrlencodedOptions,
+  options: Arc<UrlencodedOptions>,
 }
 
 #[napi]
@@ -239,75 +359,29 @@ impl UrlencodedMiddleware {
   #[napi(constructor)]
   pub fn new(options: Option<JsUrlencodedOptions>) -> Result<Self> {
     Ok(UrlencodedMiddleware {
-      options: match options {
+      options: Arc::new(match options {
         Some(options) => UrlencodedOptions::try_from(options)?,
         None => UrlencodedOptions::default(),
-      },
+      }),
     })
   }
 
+  /// Parses the request's body, rejecting with a body-parser style error
+  /// (`status`, `type`, ...) when it is too large, malformed or in an
+  /// unsupported encoding or charset.
   #[napi]
-  pub async fn run(&self, request: &Request, response: &Response) -> Result<bool> {
-    log::debug!("Urlencoded Middleware | Called!");
-
-    // determine if request should be parsed
-    let should_parse = self.options.should_parse(request).await?;
-
-    // determine if request should be parsed
-    if !should_parse {
-      return Ok(true);
-    }
-
-    let hyper_request = request.with_inner_mut(|w_req| w_req.take_inner())?;
-    let (parts, body) = hyper_request.into_parts();
-    let mut body_stream = BodyStream::new(Limited::new(body, self.options.limit));
-
-    let mut body = Vec::new();
-
-    while let Some(data) = body_stream.next().await {
-      let data = data
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
-        .into_data()
-        .map_err(|_| Error::new(Status::GenericFailure, "Encountered a non-data frame."))?;
-      body.extend_from_slice(&data);
-    }
-
-    request.with_inner_mut(|w_req| {
-      w_req.set_inner(HyperRequest::from_parts(parts, BoxBody::new(body_stream)));
-      Ok(())
-    })?;
-
-    // skip requests without bodies
-    if body.is_empty() {
-      return Ok(true);
-    }
-
-    if let Some(verify) = self.options.verify.clone() {
-      let body_buf = Buffer::from(body.as_slice());
-      verify
-        .call_async(
-          (
-            request.to_owned(),
-            response.to_owned(),
-            body_buf,
-            "utf-8".to_owned(),
-          )
-            .into(),
-        )
-        .await?;
-    }
-
-    let req_inner =
-      String::from_utf8(body).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-
-    let parsed_body = parse_urlencoded(req_inner.as_str(), &(&self.options).into())
-      .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-
-    request.with_inner_mut(|w_req| {
-      w_req.set_body(Either3::B(parsed_body));
-      Ok(())
-    })?;
-
-    Ok(true)
+  pub fn run<'env>(
+    &self,
+    env: &'env Env,
+    request: &Request,
+    response: &Response,
+  ) -> Result<PromiseRaw<'env, bool>> {
+    let options = self.options.clone();
+    let request = request.clone();
+    let response = response.clone();
+    env.spawn_future_with_callback(
+      async move { Ok(options.run(&request, &response).await) },
+      |env, result| result.map_err(|e| e.into_napi_error(env)),
+    )
   }
 }
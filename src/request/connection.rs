@@ -0,0 +1,130 @@
+use std::net::{IpAddr, SocketAddr};
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use super::Request;
+
+/// The connection a request was received on
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConnectionInfo {
+  /// The address of the peer, the client or the closest proxy
+  pub remote_addr: Option<SocketAddr>,
+  /// Whether the connection is encrypted with TLS
+  pub encrypted: bool,
+}
+
+impl Request {
+  /// The value of header `name`, when the peer of the connection is a
+  /// trusted proxy
+  pub(super) fn forwarded_header(&self, name: &str) -> Result<Option<String>> {
+    let remote_addr = self.connection.remote_addr.map(|addr| addr.ip());
+    if !self.settings.trust_proxy.trusts(remote_addr, 0) {
+      return Ok(None);
+    }
+    self.with_inner(|w_req| {
+      Ok(
+        w_req
+          .inner()?
+          .headers()
+          .get(name)
+          .and_then(|value| value.to_str().ok())
+          .map(str::to_owned),
+      )
+    })
+  }
+
+  /// The addresses of the request, from the peer of the connection to the
+  /// client, per the `trust proxy` setting
+  fn addresses(&self) -> Result<Vec<Option<IpAddr>>> {
+    let forwarded_for = self.with_inner(|w_req| {
+      let headers = w_req.inner()?.headers();
+      let values = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect::<Vec<_>>();
+      Ok((!values.is_empty()).then(|| values.join(",")))
+    })?;
+    Ok(self.settings.trust_proxy.addresses(
+      self.connection.remote_addr.map(|addr| addr.ip()),
+      forwarded_for.as_deref(),
+    ))
+  }
+}
+
+#[napi]
+impl Request {
+  /// Contains the remote IP address of the request.
+  ///
+  /// When the `trust proxy` setting does not evaluate to `false`, the value
+  /// of this property is derived from the left-most entry in the
+  /// `X-Forwarded-For` header that is not a trusted proxy.
+  ///
+  /// ```javascript
+  /// console.dir(req.ip)
+  /// // => "127.0.0.1"
+  /// ```
+  #[napi(getter)]
+  pub fn ip(&self) -> Result<Option<String>> {
+    let addresses = self.addresses()?;
+    Ok(
+      addresses
+        .last()
+        .copied()
+        .flatten()
+        .map(|address| address.to_string()),
+    )
+  }
+
+  /// When the `trust proxy` setting does not evaluate to `false`, this
+  /// property contains an array of IP addresses specified in the
+  /// `X-Forwarded-For` request header, from the client to the closest
+  /// trusted proxy. Otherwise, it contains an empty array.
+  ///
+  /// For example, if `X-Forwarded-For` is `client, proxy1, proxy2`,
+  /// `req.ips` would be `["client", "proxy1", "proxy2"]`, where `proxy2` is
+  /// the furthest downstream.
+  #[napi(getter)]
+  pub fn ips(&self) -> Result<Vec<String>> {
+    let addresses = self.addresses()?;
+    Ok(
+      addresses
+        .iter()
+        .skip(1)
+        .rev()
+        .map(|address| {
+          address
+            .map(|address| address.to_string())
+            .unwrap_or_default()
+        })
+        .collect(),
+    )
+  }
+
+  /// Contains the request protocol string: either `http` or (for TLS
+  /// requests) `https`.
+  ///
+  /// When the `trust proxy` setting trusts the socket address, the value of
+  /// the `X-Forwarded-Proto` header field, if present, is used instead.
+  #[napi(getter)]
+  pub fn protocol(&self) -> Result<String> {
+    if let Some(proto) = self.forwarded_header("x-forwarded-proto")? {
+      let proto = proto.split(',').next().unwrap_or_default().trim();
+      if !proto.is_empty() {
+        return Ok(proto.to_owned());
+      }
+    }
+    Ok(match self.connection.encrypted {
+      true => "https".to_owned(),
+      false => "http".to_owned(),
+    })
+  }
+
+  /// A Boolean property that is true if a TLS connection is established.
+  /// Equivalent to `req.protocol === 'https'`
+  #[napi(getter)]
+  pub fn secure(&self) -> Result<bool> {
+    Ok(self.protocol()? == "https")
+  }
+}
@@ -0,0 +1,65 @@
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use super::Request;
+use crate::utilities;
+
+#[napi]
+impl Request {
+  /// When the response is still "fresh" in the client's cache `true` is
+  /// returned, otherwise `false` is returned to indicate that the client cache
+  /// is now stale and the full response should be sent.
+  ///
+  /// When a client sends the `Cache-Control: no-cache` request header to
+  /// indicate an end-to-end reload request, this property returns `false`.
+  ///
+  /// The request's `If-None-Match` and `If-Modified-Since` headers are
+  /// checked against the response's `ETag` and `Last-Modified` headers; only
+  /// `GET` and `HEAD` requests answered with a `2xx` or `304` status can be
+  /// fresh.
+  ///
+  /// ```javascript
+  /// console.dir(req.fresh)
+  /// // => true
+  /// ```
+  #[napi(getter)]
+  pub fn fresh(&self) -> Result<bool> {
+    // the request's lock is released before the response's one is taken
+    let (method, request_headers, response) = self.with_inner(|request| {
+      let inner = request.inner()?;
+      Ok((
+        inner.method().to_owned(),
+        inner.headers().to_owned(),
+        request.response.upgrade(),
+      ))
+    })?;
+    let Some(response) = response else {
+      return Ok(false);
+    };
+    let mut response = response.lock().map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Could not obtain lock on response. {e}"),
+      )
+    })?;
+    let response = response.inner()?;
+    Ok(utilities::is_fresh(
+      &method,
+      &request_headers,
+      response.status(),
+      response.headers(),
+    ))
+  }
+
+  /// Indicates whether the request is "stale", and is the opposite of
+  /// `req.fresh`.
+  ///
+  /// ```javascript
+  /// console.dir(req.stale)
+  /// // => true
+  /// ```
+  #[napi(getter)]
+  pub fn stale(&self) -> Result<bool> {
+    Ok(!self.fresh()?)
+  }
+}
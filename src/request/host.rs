@@ -0,0 +1,115 @@
+use std::net::IpAddr;
+
+use hyper::header::HOST;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use super::Request;
+
+#[napi]
+impl Request {
+  /// Contains the host derived from the `Host` HTTP header, including the
+  /// port.
+  ///
+  /// When the `trust proxy` setting trusts the socket address, the value of
+  /// the `X-Forwarded-Host` header field, if present, is used instead.
+  ///
+  /// ```javascript
+  /// // Host: "example.com:3000"
+  /// console.dir(req.host)
+  /// // => 'example.com:3000'
+  /// ```
+  #[napi(getter)]
+  pub fn host(&self) -> Result<Option<String>> {
+    if let Some(host) = self.forwarded_header("x-forwarded-host")? {
+      let host = host.split(',').next().unwrap_or_default().trim();
+      if !host.is_empty() {
+        return Ok(Some(host.to_owned()));
+      }
+    }
+    self.with_inner(|w_req| {
+      let request = w_req.inner()?;
+      Ok(
+        match request.headers().get(HOST) {
+          Some(host) => host.to_str().ok().map(str::to_owned),
+          // HTTP/2 requests give the host in the URI
+          None => request.uri().authority().map(|host| host.to_string()),
+        }
+        .filter(|host| !host.is_empty()),
+      )
+    })
+  }
+
+  /// Contains the hostname derived from `req.host`, without the port.
+  ///
+  /// ```javascript
+  /// // Host: "example.com:3000"
+  /// console.dir(req.hostname)
+  /// // => 'example.com'
+  /// ```
+  #[napi(getter)]
+  pub fn hostname(&self) -> Result<Option<String>> {
+    Ok(self.host()?.map(|host| strip_port(&host).to_owned()))
+  }
+
+  /// An array of subdomains in the domain name of the request.
+  ///
+  /// ```javascript
+  /// // Host: "tobi.ferrets.example.com"
+  /// console.dir(req.subdomains)
+  /// // => ["ferrets", "tobi"]
+  /// ```
+  ///
+  /// The `subdomain offset` setting, which defaults to 2, is used for
+  /// determining the beginning of the subdomain segments.
+  #[napi(getter)]
+  pub fn subdomains(&self) -> Result<Vec<String>> {
+    let Some(hostname) = self.hostname()? else {
+      return Ok(Vec::new());
+    };
+    Ok(subdomains(&hostname, self.settings.subdomain_offset))
+  }
+}
+
+/// `host` without its port; IPv6 addresses keep their brackets
+fn strip_port(host: &str) -> &str {
+  let end = match host.starts_with('[') {
+    true => host.find(']').map(|end| end + 1),
+    false => host.find(':'),
+  };
+  &host[..end.unwrap_or(host.len())]
+}
+
+fn subdomains(hostname: &str, offset: usize) -> Vec<String> {
+  let address = hostname.trim_start_matches('[').trim_end_matches(']');
+  let labels = match address.parse::<IpAddr>() {
+    Ok(_) => vec![hostname.to_owned()],
+    Err(_) => hostname.split('.').rev().map(str::to_owned).collect(),
+  };
+  labels.into_iter().skip(offset).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_strip_port() {
+    assert_eq!(strip_port("example.com:3000"), "example.com");
+    assert_eq!(strip_port("example.com"), "example.com");
+    assert_eq!(strip_port("[::1]:3000"), "[::1]");
+    assert_eq!(strip_port("[::1]"), "[::1]");
+  }
+
+  #[test]
+  fn test_subdomains() {
+    assert_eq!(
+      subdomains("tobi.ferrets.example.com", 2),
+      ["ferrets", "tobi"]
+    );
+    assert_eq!(subdomains("tobi.ferrets.example.com", 3), ["tobi"]);
+    assert_eq!(subdomains("example.com", 2), Vec::<String>::new());
+    assert_eq!(subdomains("127.0.0.1", 0), ["127.0.0.1"]);
+    assert_eq!(subdomains("[::1]", 2), Vec::<String>::new());
+  }
+}
@@ -0,0 +1,41 @@
+use napi_derive::napi;
+
+use super::Request;
+use crate::server::is_valid_request_id;
+
+impl Request {
+  /// The ID of the request: the one in the `request id header` when it comes
+  /// from a trusted proxy and is valid, a generated one otherwise
+  pub(super) fn assign_id(&mut self) {
+    let incoming = self
+      .settings
+      .request_id_header
+      .as_ref()
+      .and_then(|header| self.forwarded_header(header.as_str()).ok().flatten())
+      .filter(|id| is_valid_request_id(id));
+    let id = incoming.unwrap_or_else(|| self.settings.request_id.generate());
+    self.id = id.into();
+  }
+}
+
+#[napi]
+impl Request {
+  /// The ID of the request, also sent in the `X-Request-Id` header of the
+  /// response and written in the logs and error pages of the server.
+  ///
+  /// The ID is taken from the `X-Request-Id` header, or the header of the
+  /// `request id header` setting, when the request comes from a trusted
+  /// proxy (see the `trust proxy` setting). It is generated otherwise, as set
+  /// by the `request id` setting.
+  ///
+  /// ```javascript
+  /// app.use((req, res) => {
+  ///   console.log(`[${req.id}] ${req.method} ${req.url}`)
+  ///   return true
+  /// })
+  /// ```
+  #[napi(getter)]
+  pub fn id(&self) -> String {
+    self.id.to_string()
+  }
+}
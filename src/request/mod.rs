@@ -1,8 +1,13 @@
 mod accepts;
+mod connection;
 pub mod error;
+mod fresh;
 mod get;
+mod host;
+mod id;
 mod method;
 mod params;
+mod query;
 mod range;
 mod wrapped_request;
 
@@ -11,25 +16,58 @@ use std::sync::{Arc, Mutex};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
+pub use connection::ConnectionInfo;
 pub use wrapped_request::WrappedRequest;
 
+use crate::locals::Locals;
+use crate::server::{AppSettings, Application};
 use crate::utilities;
 
 #[napi]
 #[derive(Clone, Debug, Default)]
 pub struct Request {
   inner: Arc<Mutex<WrappedRequest>>,
+  /// The settings of the server handling the request
+  settings: Arc<AppSettings>,
+  /// The connection the request was received on
+  connection: ConnectionInfo,
+  /// The variables of the request, shared with its response
+  locals: Locals,
+  /// The ID of the request, `req.id`
+  id: Arc<str>,
 }
 
 impl From<WrappedRequest> for Request {
   fn from(value: WrappedRequest) -> Self {
     Self {
       inner: Arc::new(Mutex::new(value)),
+      settings: Arc::default(),
+      connection: ConnectionInfo::default(),
+      locals: Locals::default(),
+      id: Arc::default(),
     }
   }
 }
 
 impl Request {
+  pub fn new(
+    request: WrappedRequest,
+    settings: Arc<AppSettings>,
+    connection: ConnectionInfo,
+  ) -> Self {
+    let mut request = Self {
+      settings,
+      connection,
+      ..Self::from(request)
+    };
+    request.assign_id();
+    request
+  }
+
+  pub fn settings(&self) -> &Arc<AppSettings> {
+    &self.settings
+  }
+
   pub fn with_inner_mut<F, T>(&self, f: F) -> Result<T>
   where
     F: FnOnce(&mut WrappedRequest) -> Result<T>,
@@ -74,7 +112,16 @@ impl Request {
   /// other user input.
   #[napi(getter)]
   pub fn body(&self, env: Env) -> Result<Either4<String, Unknown<'static>, Buffer, ()>> {
-    let body = self.with_inner_mut(|req| Ok(req.body.to_owned()))?;
+    let (body, revived_body) = self.with_inner_mut(|req| {
+      let revived_body = match &req.revived_body {
+        Some(revived_body) => Some(revived_body.get(&env)?),
+        None => None,
+      };
+      Ok((req.body.to_owned(), revived_body))
+    })?;
+    if let Some(revived_body) = revived_body {
+      return Ok(Either4::B(revived_body));
+    }
     match body {
       None => Ok(Either4::D(())),
       Some(body) => match body {
@@ -85,6 +132,20 @@ impl Request {
     }
   }
 
+  /// The settings and locals of the server handling the request
+  #[napi(getter)]
+  pub fn app(&self) -> Application {
+    Application::from(self.settings.clone())
+  }
+
+  /// Variables of the request, readable and writable by all the
+  /// middlewares handling it, JS or native, set with
+  /// `req.locals.set(name, value)`. The same map as `res.locals`.
+  #[napi(getter)]
+  pub fn locals(&self) -> Locals {
+    self.locals.to_owned()
+  }
+
   #[napi(getter)]
   pub fn cookies(&self, env: Env) -> Result<Either<Unknown<'static>, ()>> {
     self.with_inner(|w_req| match w_req.cookies.as_ref() {
@@ -94,25 +155,16 @@ impl Request {
   }
 
   //   Properties
-  //   TODO: app
   //   TODO: baseUrl
   //   TODO: body
   //   TODO: cookies
   //   TODO: fresh
-  //   TODO: host
-  //   TODO: hostname
-  //   TODO: ip
-  //   TODO: ips
   //   TODO: originalUrl
   //   TODO: path
-  //   TODO: protocol
-  //   TODO: query
   //   TODO: res
   //   TODO: route
-  //   TODO: secure
   //   TODO: signedCookies
   //   TODO: stale
-  //   TODO: subdomains
   //   TODO: xhr
 
   // Methods
@@ -0,0 +1,55 @@
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{Map, Value};
+
+use super::Request;
+use crate::server::QueryParser;
+use crate::utilities::{self, UrlencodedOptions};
+
+#[napi]
+impl Request {
+  /// This property is an object containing a property for each query string
+  /// parameter in the route. It is parsed per the `query parser` setting:
+  /// flat with `"simple"` (the default), with nested objects and arrays with
+  /// `"extended"`, or by the function the setting is assigned to.
+  ///
+  /// ```javascript
+  /// // GET /search?q=tobi+ferret
+  /// console.dir(req.query.q)
+  /// // => 'tobi ferret'
+  ///
+  /// // GET /shoes?color[]=blue&color[]=black (extended)
+  /// console.dir(req.query.color)
+  /// // => ['blue', 'black']
+  /// ```
+  ///
+  /// As `req.query`'s shape is based on user-controlled input, all
+  /// properties and values in this object are untrusted and should be
+  /// validated before trusting.
+  #[napi(getter, ts_return_type = "Record<string, unknown>")]
+  pub fn query(&self, env: Env) -> Result<Unknown<'static>> {
+    let query =
+      self.with_inner(|w_req| Ok(w_req.inner()?.uri().query().unwrap_or_default().to_owned()))?;
+
+    let extended = match &self.settings.query_parser {
+      QueryParser::Disabled => return utilities::json_to_napi(&env, Value::Object(Map::new())),
+      QueryParser::Function(parser) => {
+        let query = parser.borrow_back(&env)?.call(query)?;
+        return Ok(unsafe { Unknown::from_napi_value(env.raw(), query.raw())? });
+      }
+      QueryParser::Simple => false,
+      QueryParser::Extended => true,
+    };
+    let options = UrlencodedOptions {
+      extended,
+      ..Default::default()
+    };
+    let query = utilities::parse_urlencoded(&query, &options).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to parse the query string: {e}"),
+      )
+    })?;
+    utilities::json_to_napi(&env, query)
+  }
+}
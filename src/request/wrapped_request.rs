@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+  collections::HashMap,
+  sync::{Mutex, Weak},
+};
 
 use bytes::Bytes;
 use http_body_util::{BodyExt, combinators::BoxBody};
@@ -6,7 +9,10 @@ use hyper::{Error as LibError, Request as HyperRequest, body::Body};
 use napi::bindgen_prelude::*;
 use serde_json::Value as JsonValue;
 
-use crate::utilities;
+use crate::{
+  response::WrappedResponse,
+  utilities::{self, JsValueRef},
+};
 
 type RequestInner = HyperRequest<BoxBody<Bytes, Box<dyn std::error::Error + Sync + Send>>>;
 
@@ -15,8 +21,13 @@ pub struct WrappedRequest {
   pub(super) inner: Option<RequestInner>,
   pub(super) params: HashMap<String, String>,
   pub(super) body: Option<Either3<String, JsonValue, Vec<u8>>>,
+  /// The body built by the `reviver` of the JSON parser, returned instead of
+  /// `body` as it may hold values without a JSON form
+  pub(super) revived_body: Option<JsValueRef>,
   pub(super) cookies: Option<JsonValue>,
   pub(super) encrypted_cookies: Option<JsonValue>,
+  /// The response to this request
+  pub(super) response: Weak<Mutex<WrappedResponse>>,
 }
 
 impl Default for WrappedRequest {
@@ -38,8 +49,10 @@ impl<T: BodyExt + Body<Data = Bytes, Error = LibError> + Send + Sync + 'static>
       inner: Some(request),
       params: HashMap::with_capacity(0),
       body: None,
+      revived_body: None,
       cookies: None,
       encrypted_cookies: None,
+      response: Weak::new(),
     }
   }
 }
@@ -86,7 +99,12 @@ impl WrappedRequest {
   }
 
   pub fn set_body(&mut self, body: Either3<String, JsonValue, Vec<u8>>) {
-    self.body = Some(body)
+    self.body = Some(body);
+    self.revived_body = None;
+  }
+
+  pub fn set_revived_body(&mut self, body: JsValueRef) {
+    self.revived_body = Some(body)
   }
 
   pub fn set_cookies(&mut self, extracted_cookies: JsonValue) {
@@ -96,4 +114,8 @@ impl WrappedRequest {
   pub fn set_encrypted_cookies(&mut self, extracted_cookies: JsonValue) {
     self.encrypted_cookies = Some(extracted_cookies)
   }
+
+  pub fn set_response(&mut self, response: Weak<Mutex<WrappedResponse>>) {
+    self.response = response
+  }
 }
@@ -0,0 +1,119 @@
+use etag::EntityTag;
+use hyper::{
+  StatusCode,
+  header::{ETAG, HeaderValue, LAST_MODIFIED, RANGE},
+};
+use napi::{JsDate, bindgen_prelude::*};
+use napi_derive::napi;
+
+use super::Response;
+use crate::utilities::{self, Precondition, Validators};
+
+#[napi(object)]
+pub struct PreconditionOptions {
+  /// The entity tag of the current representation of the resource, e.g.
+  /// `"v1"` or `W/"v1"`. A value without quotes is taken as a strong tag.
+  pub etag: Option<String>,
+  /// The last modification date of the current representation of the
+  /// resource, as a `Date` or an HTTP date string.
+  pub last_modified: Option<Either<JsDate<'static>, String>>,
+}
+
+impl PreconditionOptions {
+  fn to_validators(&self) -> Result<Validators> {
+    let etag = match &self.etag {
+      Some(etag) => Some(
+        etag
+          .parse::<EntityTag>()
+          .or_else(|_| EntityTag::checked_strong(etag))
+          .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid etag \"{etag}\": {e}")))?,
+      ),
+      None => None,
+    };
+    let last_modified = match &self.last_modified {
+      Some(Either::A(date)) => Some(utilities::js_date_to_system_time(date)?),
+      Some(Either::B(date)) => Some(httpdate::parse_http_date(date).map_err(|e| {
+        Error::new(
+          Status::InvalidArg,
+          format!("Invalid lastModified \"{date}\": {e}"),
+        )
+      })?),
+      None => None,
+    };
+    Ok(Validators {
+      etag,
+      last_modified,
+    })
+  }
+}
+
+#[napi]
+impl Response {
+  /// Evaluates the conditional headers of the request (`If-Match`,
+  /// `If-Unmodified-Since`, `If-None-Match`, `If-Modified-Since` and
+  /// `If-Range`) against the validators of the current representation of the
+  /// resource, in the order of RFC 9110.
+  ///
+  /// Returns `true` when the request can be performed. When a precondition
+  /// fails, the response is sent and `false` is returned: `304 Not Modified`
+  /// for `GET` and `HEAD` requests whose representation did not change, `412
+  /// Precondition Failed` otherwise. When `If-Range` does not match, the
+  /// request's `Range` header is dropped, so that the full representation is
+  /// sent.
+  ///
+  /// ```javascript
+  /// app.put('/articles/{id}', (req, res) => {
+  ///   const article = articles.get(req.params.id)
+  ///   if (!res.checkPreconditions({ etag: article.version })) return
+  ///   // update the article
+  /// })
+  /// ```
+  #[napi]
+  pub fn check_preconditions(&self, options: PreconditionOptions) -> Result<bool> {
+    let validators = options.to_validators()?;
+    let precondition = self.request.with_inner(|w_req| {
+      let request = w_req.inner()?;
+      Ok(utilities::evaluate_preconditions(
+        request.method(),
+        request.headers(),
+        &validators,
+      ))
+    })?;
+
+    match precondition {
+      Precondition::Passed => Ok(true),
+      Precondition::IgnoreRange => self.request.with_inner_mut(|w_req| {
+        w_req.inner_mut()?.headers_mut().remove(RANGE);
+        Ok(true)
+      }),
+      Precondition::NotModified => {
+        self.with_inner(|response| {
+          let inner = response.inner()?;
+          *inner.status_mut() = StatusCode::NOT_MODIFIED;
+          if let Some(etag) = &validators.etag {
+            let etag = HeaderValue::from_str(&etag.to_string())
+              .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+            inner.headers_mut().insert(ETAG, etag);
+          }
+          if let Some(last_modified) = validators.last_modified {
+            let last_modified = HeaderValue::from_str(&httpdate::fmt_http_date(last_modified))
+              .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+            inner.headers_mut().insert(LAST_MODIFIED, last_modified);
+          }
+          response.send(None, None, &self.request)
+        })?;
+        Ok(false)
+      }
+      Precondition::Failed => {
+        self.with_inner(|response| {
+          *response.inner()?.status_mut() = StatusCode::PRECONDITION_FAILED;
+          let body = StatusCode::PRECONDITION_FAILED
+            .canonical_reason()
+            .map(|reason| Either::A(reason.to_owned()));
+          response.send(body, None, &self.request)
+        })?;
+        Ok(false)
+      }
+    }
+  }
+}
@@ -16,7 +16,7 @@ impl Response {
   /// | `encode`      | Function | A synchronous function used for cookie value encoding. Defaults to `encodeURIComponent`. |
   /// | `expires`     | Date     | Expiry date of the cookie in GMT. If not specified or set to 0, creates a session cookie. |
   /// | `httpOnly`    | Boolean  | Flags the cookie to be accessible only by the web server. |
-  /// | `maxAge`      | Number   | Convenient option for setting the expiry time relative to the current time in milliseconds. |
+  /// | `maxAge`      | Number or String | Convenient option for setting the expiry time relative to the current time in milliseconds, or as a string in [ms format](https://www.npmjs.org/package/ms), e.g. `'7 days'`. |
   /// | `path`        | String   | Path for the cookie. Defaults to "/". |
   /// | `partitioned` | Boolean  | Indicates that the cookie should be stored using partitioned storage. See [Cookies Having Independent Partitioned State (CHIPS)](https://developer.mozilla.org/en-US/docs/Web/Privacy/Partitioned_cookies) for more details. |
   /// | `priority`    | String   | Value of the "Priority" Set-Cookie attribute. |
@@ -126,6 +126,7 @@ impl WrappedResponse {
 #[cfg(test)]
 mod tests {
   use hyper::header::SET_COOKIE;
+  use napi::Either;
 
   use crate::response::cookie::CookieOptions;
   use crate::utilities::assert_header_exists;
@@ -192,4 +193,32 @@ mod tests {
       "some_cross_domain_cookie=http%3A%2F%2Fmysubdomain.example.com; Domain=example.com; Path=/",
     );
   }
+
+  #[test]
+  fn cookie_max_age() {
+    let mut res = WrappedResponse::default();
+
+    let options = CookieOptions {
+      max_age: Some(Either::B("1h".to_owned())),
+      ..Default::default()
+    };
+    res
+      .cookie("session".to_owned(), "1".to_owned(), Some(options))
+      .unwrap();
+
+    let inner = res.inner().unwrap();
+    let cookie = inner.headers()[SET_COOKIE].to_str().unwrap();
+    assert!(cookie.starts_with("session=1; Expires="));
+    assert!(cookie.ends_with("; Max-Age=3600; Path=/"));
+
+    let options = CookieOptions {
+      max_age: Some(Either::B("1 fortnight".to_owned())),
+      ..Default::default()
+    };
+    assert!(
+      res
+        .cookie("session".to_owned(), "1".to_owned(), Some(options))
+        .is_err()
+    );
+  }
 }
@@ -1,4 +1,4 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use napi::{JsDate, bindgen_prelude::*};
 use napi_derive::napi;
@@ -16,7 +16,7 @@ pub struct CookieOptions {
   pub encode: Option<Function<'static, String, String>>,
   pub expires: Option<Either<JsDate<'static>, ClearCookie>>,
   pub http_only: Option<bool>,
-  pub max_age: Option<i64>,
+  pub max_age: Option<Either<i64, String>>,
   pub path: Option<String>,
   pub partitioned: Option<bool>,
   pub priority: Option<String>,
@@ -27,13 +27,25 @@ pub struct CookieOptions {
 
 impl CookieOptions {
   pub fn mark_for_clearing(&mut self) {
-    self.expires = Some(Either::B(ClearCookie {}))
+    self.expires = Some(Either::B(ClearCookie {}));
+    self.max_age = None;
   }
 
   pub fn get_pairs_as_strings(&self) -> Result<Vec<String>> {
     let mut options_strings = Vec::new();
 
-    if let Some(expires) = &self.expires {
+    // `maxAge` is in milliseconds, `Max-Age` in seconds. It also sets
+    // `Expires`, for clients without `Max-Age` support.
+    let max_age = self
+      .max_age
+      .as_ref()
+      .map(|max_age| utilities::parse_max_age(max_age, "maxAge"))
+      .transpose()?;
+
+    if let Some(max_age) = max_age {
+      let expires = httpdate::fmt_http_date(SystemTime::now() + Duration::from_millis(max_age));
+      options_strings.push(format!("Expires={expires}"));
+    } else if let Some(expires) = &self.expires {
       match expires {
         Either::A(expires) => {
           let expires = httpdate::fmt_http_date(utilities::js_date_to_system_time(expires)?);
@@ -46,8 +58,8 @@ impl CookieOptions {
       }
     }
 
-    if let Some(max_age) = self.max_age {
-      options_strings.push(format!("Max-Age={max_age}"));
+    if let Some(max_age) = max_age {
+      options_strings.push(format!("Max-Age={}", max_age / 1000));
     }
 
     if let Some(domain) = &self.domain {
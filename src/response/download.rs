@@ -5,12 +5,15 @@ use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
 use super::Response;
-use crate::utilities::{self, FileSendOptions, FileSendTask};
+use crate::{
+  utilities::{self, FileSendOptions, FileSendTask},
+  virtual_file_system::VirtualFileSystem,
+};
 
 #[napi(object)]
 pub struct DownloadOptions<'a> {
-  pub max_age: Option<u32>,
-  pub root: Option<String>,
+  pub max_age: Option<Either<i64, String>>,
+  pub root: Option<Either<String, ClassInstance<'a, VirtualFileSystem>>>,
   pub last_modified: Option<bool>,
   pub headers: Option<Object<'a>>,
   pub dotfiles: Option<String>,
@@ -25,12 +28,14 @@ impl<'a> TryFrom<&DownloadOptions<'a>> for FileSendOptions {
   fn try_from(value: &DownloadOptions<'a>) -> Result<Self> {
     let mut options = FileSendOptions::default();
 
-    if let Some(root) = &value.root {
-      options.root = Some(Path::new(root).to_path_buf())
+    match &value.root {
+      Some(Either::A(root)) => options.root = Some(Path::new(root).to_path_buf()),
+      Some(Either::B(fs)) => options.fs = Some(fs.owned_inner()),
+      None => {}
     }
 
-    if let Some(max_age) = value.max_age {
-      options.max_age = max_age as u64;
+    if let Some(max_age) = &value.max_age {
+      options.max_age = utilities::parse_max_age(max_age, "maxAge")?;
     }
 
     if let Some(last_modified) = value.last_modified {
@@ -86,7 +91,7 @@ impl Response {
   /// | Property | Description | Default |
   /// | --- | --- | --- |
   /// | `maxAge` | Sets the max-age property of the `Cache-Control` header in milliseconds or a string in [ms format](https://www.npmjs.org/package/ms) | 0 |
-  /// | `root` | Root directory for relative filenames. | |
+  /// | `root` | Root directory for relative filenames, or a `VirtualFileSystem` to serve them from. | |
   /// | `lastModified` | Sets the `Last-Modified` header to the last modified date of the file on the OS. Set `false` to disable it. | Enabled |
   /// | `headers` | Object containing HTTP headers to serve with the file. | |
   /// | `dotfiles` | Option for serving dotfiles. Possible values are "allow", "deny", "ignore". | "ignore" |
@@ -119,11 +124,12 @@ impl Response {
   /// ```
   ///
   #[napi]
-  pub fn download(
+  pub fn download<'env>(
     &self,
+    env: &'env Env,
     path: String,
     options: Option<DownloadOptions>,
-  ) -> Result<AsyncTask<FileSendTask>> {
+  ) -> Result<PromiseRaw<'env, ()>> {
     let mut file_send_options: FileSendOptions = match &options {
       Some(options) => options.try_into()?,
       None => FileSendOptions::default(),
@@ -140,10 +146,17 @@ impl Response {
       .get_or_insert(HeaderMap::new())
       .insert(CONTENT_DISPOSITION, disposition);
 
-    Ok(AsyncTask::new(FileSendTask {
+    let mut task = FileSendTask {
       response: self.clone(),
       path,
       options: file_send_options,
-    }))
+    };
+    env.spawn_future(async move {
+      task.send().await?;
+      task.response.with_inner(|w_res| {
+        w_res.set_ended();
+        Ok(())
+      })
+    })
   }
 }
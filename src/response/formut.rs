@@ -86,23 +86,23 @@ impl WrappedResponse {
     req: Request,
     mut res: Response,
   ) -> Result<()> {
-    log::debug!("One");
+    log::debug!("Request ID: {} | One", req.id());
     let keys = obj
       .keys()
       .filter(|key| *key != "default")
       .cloned()
       .collect::<Vec<_>>();
 
-    log::debug!("Two");
+    log::debug!("Request ID: {} | Two", req.id());
     res.vary(ACCEPT.as_str().to_owned())?;
 
-    log::debug!("Three");
+    log::debug!("Request ID: {} | Three", req.id());
     let key = req.accepts(Either::B(keys))?.and_then(|v| match v {
       Either::A(val) => Some(val),
       Either::B(vals) => vals.first().cloned(),
     });
 
-    log::debug!("Client ACCEPT key = {key:?}");
+    log::debug!("Request ID: {} | Client ACCEPT key = {key:?}", req.id());
 
     match key {
       Some(key) => {
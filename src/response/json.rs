@@ -1,9 +1,9 @@
-use hyper::header::CONTENT_TYPE;
+use hyper::header::{CONTENT_TYPE, HeaderValue};
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
 use super::{Response, WrappedResponse};
-use crate::utilities;
+use crate::{request::Request, utilities};
 
 #[napi]
 impl Response {
@@ -20,31 +20,37 @@ impl Response {
   /// res.json({ user: 'tobi' })
   /// res.status(500).json({ error: 'message' })
   /// ```
-  #[napi]
-  pub fn json(&mut self, body: Either5<String, i64, bool, Object, Null>, env: Env) -> Result<()> {
-    self.with_inner(|response| response.json(body, env))
+  ///
+  /// The body is serialized exactly like `JSON.stringify` does it (`toJSON`
+  /// methods are called, `undefined` properties are omitted and `BigInt`
+  /// values throw), using the `json replacer`, `json spaces` and `json escape`
+  /// settings of the server.
+  #[napi(ts_args_type = "body: unknown")]
+  pub fn json(&self, body: Unknown, env: Env) -> Result<()> {
+    // serialize before locking the response: `toJSON` methods and the
+    // replacer may use it
+    let body = utilities::stringify_json(&env, body, &self.settings)?;
+    let etag = match &body {
+      Some(body) => self.generate_etag(&env, body.as_bytes(), Some("utf8"))?,
+      None => None,
+    };
+    self.with_inner(|response| response.json(body, etag, &self.request))
   }
 }
 
 impl WrappedResponse {
-  pub fn json(&mut self, body: Either5<String, i64, bool, Object, Null>, env: Env) -> Result<()> {
+  /// Send `body`, already serialized as JSON. `None` sends an empty body.
+  pub fn json(
+    &mut self,
+    body: Option<String>,
+    etag: Option<HeaderValue>,
+    request: &Request,
+  ) -> Result<()> {
     // set `Content-Type` to application/json
     if self.inner()?.headers().get(CONTENT_TYPE).is_none() {
       self.content_type("json".to_owned())?
     }
 
-    let body = match body {
-      Either5::A(value) => serde_json::to_string(&value)
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?,
-      Either5::B(value) => serde_json::to_string(&value)
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?,
-      Either5::C(value) => serde_json::to_string(&value)
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?,
-      Either5::D(value) => utilities::serialize_napi_object(env, value)?,
-      Either5::E(_) => serde_json::to_string(&Option::<String>::None)
-        .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?,
-    };
-
-    self.send(Either6::A(body), env)
+    self.send(body.map(Either::A), etag, request)
   }
 }
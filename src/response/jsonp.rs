@@ -0,0 +1,182 @@
+use hyper::header::{CONTENT_TYPE, HeaderValue, X_CONTENT_TYPE_OPTIONS};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use super::{Response, WrappedResponse};
+use crate::{request::Request, utilities};
+
+#[napi]
+impl Response {
+  /// Sends a JSON response with JSONP support. This method is identical to
+  /// `res.json()`, except that it opts-in to JSONP callback support.
+  ///
+  /// ```javascript
+  /// res.jsonp(null)
+  /// // => callback(null)
+  ///
+  /// res.jsonp({ user: 'tobi' })
+  /// // => callback({ "user": "tobi" })
+  ///
+  /// res.status(500).jsonp({ error: 'message' })
+  /// // => callback({ "error": "message" })
+  /// ```
+  ///
+  /// By default, the JSONP callback name is simply `callback`. Override this
+  /// with the `jsonp callback name` setting.
+  ///
+  /// The following are some examples of JSONP responses using the same code:
+  ///
+  /// ```javascript
+  /// // ?callback=foo
+  /// res.jsonp({ user: 'tobi' })
+  /// // => foo({ "user": "tobi" })
+  ///
+  /// app.set('jsonp callback name', 'cb')
+  ///
+  /// // ?cb=foo
+  /// res.status(500).jsonp({ error: 'message' })
+  /// // => foo({ "error": "message" })
+  /// ```
+  ///
+  /// The callback name is restricted to letters, digits, `_`, `$`, `.`, `[`
+  /// and `]`, and the script is sent as `text/javascript` with
+  /// `X-Content-Type-Options: nosniff`.
+  #[napi(ts_args_type = "body: unknown")]
+  pub fn jsonp(&self, body: Unknown, env: Env) -> Result<()> {
+    // serialize before locking the response: `toJSON` methods and the
+    // replacer may use it
+    let body = utilities::stringify_json(&env, body, &self.settings)?;
+    let callback = self.request.with_inner(|w_req| {
+      Ok(jsonp_callback(
+        w_req.inner()?.uri().query(),
+        &self.settings.jsonp_callback_name,
+      ))
+    })?;
+
+    let body = match &callback {
+      Some(callback) => Some(jsonp_script(callback, body.as_deref())),
+      None => body,
+    };
+    let etag = match &body {
+      Some(body) => self.generate_etag(&env, body.as_bytes(), Some("utf8"))?,
+      None => None,
+    };
+    self.with_inner(|response| response.jsonp(body, callback.is_some(), etag, &self.request))
+  }
+}
+
+impl WrappedResponse {
+  /// Send `body`, serialized as JSON, or already wrapped in a JSONP script
+  /// when `script` is set. `None` sends an empty body.
+  pub fn jsonp(
+    &mut self,
+    body: Option<String>,
+    script: bool,
+    etag: Option<HeaderValue>,
+    request: &Request,
+  ) -> Result<()> {
+    let headers = self.inner()?.headers_mut();
+    if script || !headers.contains_key(CONTENT_TYPE) {
+      headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    }
+    if !script {
+      return self.json(body, etag, request);
+    }
+
+    self.content_type("text/javascript".to_owned())?;
+    self.send(body.map(Either::A), etag, request)
+  }
+}
+
+/// The sanitized callback name of a JSONP request, the first value of the
+/// `name` query parameter
+fn jsonp_callback(query: Option<&str>, name: &str) -> Option<String> {
+  let parameters: Vec<(String, String)> = serde_urlencoded::from_str(query?).ok()?;
+  let (_, callback) = parameters.into_iter().find(|(key, _)| key == name)?;
+  if callback.is_empty() {
+    return None;
+  }
+
+  Some(
+    callback
+      .chars()
+      .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '.' | '[' | ']'))
+      .collect(),
+  )
+}
+
+/// The JSONP script calling `callback` with `json`. U+2028 and U+2029, valid
+/// in JSON strings but not in JavaScript ones before ES2019, are escaped.
+fn jsonp_script(callback: &str, json: Option<&str>) -> String {
+  let json = json
+    .unwrap_or_default()
+    .replace('\u{2028}', "\\u2028")
+    .replace('\u{2029}', "\\u2029");
+  // the comment prevents a Rosetta Flash attack
+  format!("/**/ typeof {callback} === 'function' && {callback}({json});")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_jsonp_callback() {
+    assert_eq!(
+      jsonp_callback(Some("callback=foo"), "callback"),
+      Some("foo".to_owned())
+    );
+    assert_eq!(
+      jsonp_callback(Some("cb=bar&callback=foo&callback=baz"), "callback"),
+      Some("foo".to_owned())
+    );
+    assert_eq!(
+      jsonp_callback(Some("callback=window.cbs%5B0%5D"), "callback"),
+      Some("window.cbs[0]".to_owned())
+    );
+    assert_eq!(
+      jsonp_callback(Some("callback=alert(1)%3B%2F%2F"), "callback"),
+      Some("alert1".to_owned())
+    );
+    assert_eq!(jsonp_callback(Some("callback="), "callback"), None);
+    assert_eq!(jsonp_callback(Some("cb=foo"), "callback"), None);
+    assert_eq!(jsonp_callback(None, "callback"), None);
+  }
+
+  #[test]
+  fn test_jsonp_script() {
+    assert_eq!(
+      jsonp_script("foo", Some(r#"{"user":"tobi"}"#)),
+      r#"/**/ typeof foo === 'function' && foo({"user":"tobi"});"#
+    );
+    assert_eq!(
+      jsonp_script("foo", Some("\"\u{2028}\u{2029}\"")),
+      r#"/**/ typeof foo === 'function' && foo("\u2028\u2029");"#
+    );
+    assert_eq!(
+      jsonp_script("foo", None),
+      "/**/ typeof foo === 'function' && foo();"
+    );
+  }
+
+  #[test]
+  fn test_jsonp_headers() {
+    let request = Request::default();
+
+    let mut response = WrappedResponse::default();
+    response
+      .jsonp(Some("foo(1);".to_owned()), true, None, &request)
+      .unwrap();
+    let headers = response.inner().unwrap().headers();
+    assert_eq!(headers[CONTENT_TYPE], "text/javascript; charset=utf-8");
+    assert_eq!(headers[X_CONTENT_TYPE_OPTIONS], "nosniff");
+
+    let mut response = WrappedResponse::default();
+    response
+      .jsonp(Some("1".to_owned()), false, None, &request)
+      .unwrap();
+    let headers = response.inner().unwrap().headers();
+    assert_eq!(headers[CONTENT_TYPE], "application/json; charset=utf-8");
+    assert_eq!(headers[X_CONTENT_TYPE_OPTIONS], "nosniff");
+  }
+}
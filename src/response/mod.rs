@@ -1,5 +1,6 @@
 mod append;
 mod attachment;
+mod check_preconditions;
 mod clear_cookie;
 mod content_type;
 mod cookie;
@@ -8,9 +9,12 @@ mod download;
 mod formut;
 mod get;
 mod json;
+mod jsonp;
 mod links;
 mod location;
+mod problem;
 mod redirect;
+mod render;
 mod send;
 mod send_file;
 mod send_status;
@@ -26,22 +30,36 @@ use bytes::Bytes;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
+pub use problem::problem_document;
 pub use wrapped_response::{CrateBody, WrappedResponse};
 
+use crate::locals::Locals;
 use crate::request::Request;
+use crate::server::{AppSettings, Application};
 
 #[napi]
 #[derive(Clone, Default)]
 pub struct Response {
   inner: Arc<Mutex<WrappedResponse>>,
   request: Request,
+  settings: Arc<AppSettings>,
+  locals: Locals,
 }
 
 impl Response {
-  pub fn new(request: Request, inner: Option<WrappedResponse>) -> Self {
+  pub fn new(request: Request, settings: Arc<AppSettings>, inner: Option<WrappedResponse>) -> Self {
+    let inner = Arc::new(Mutex::new(inner.unwrap_or_default()));
+    let _ = request.with_inner_mut(|w_req| {
+      w_req.set_response(Arc::downgrade(&inner));
+      Ok(())
+    });
+    // `res.locals` is `req.locals`
+    let locals = request.locals();
     Self {
       request,
-      inner: Arc::new(Mutex::new(inner.unwrap_or_default())),
+      settings,
+      inner,
+      locals,
     }
   }
 
@@ -66,6 +84,12 @@ impl Response {
     Self::default()
   }
 
+  /// The settings and locals of the server handling the request
+  #[napi(getter)]
+  pub fn app(&self) -> Application {
+    Application::from(self.settings.clone())
+  }
+
   #[napi]
   pub fn end(&mut self, data: Option<Either3<String, Buffer, Uint8Array>>) -> Result<()> {
     let data = data.map(|data| match data {
@@ -80,7 +104,4 @@ impl Response {
   pub fn req(&self) -> Request {
     self.request.to_owned()
   }
-
-  // TODO: jsonp()
-  // TODO: render()
 }
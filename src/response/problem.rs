@@ -0,0 +1,125 @@
+use hyper::StatusCode as LibStatusCode;
+use hyper::header::{CONTENT_TYPE, HeaderValue};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{Map, Value, json};
+
+use super::Response;
+use crate::response::status_code::StatusCode;
+use crate::utilities;
+
+#[napi]
+impl Response {
+  /// Sends an RFC 9457 problem details document, with the
+  /// `application/problem+json` content type. The `status` of the problem,
+  /// if any, is set as the status of the response, and is the status of the
+  /// response otherwise. `title` defaults to the reason phrase of the status.
+  ///
+  /// Members other than `type`, `status`, `title`, `detail` and `instance`
+  /// are sent as extension members. The document is serialized like
+  /// `res.json()` does it.
+  ///
+  /// ```javascript
+  /// res.problem({
+  ///   status: 403,
+  ///   type: 'https://example.com/probs/out-of-credit',
+  ///   detail: 'Your current balance is 30, but that costs 50.',
+  ///   instance: '/account/12345/msgs/abc',
+  ///   balance: 30,
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = "problem: { status?: number | StatusCode, type?: string, title?: string, detail?: string, instance?: string, [extension: string]: unknown }"
+  )]
+  pub fn problem(&self, env: Env, problem: Object) -> Result<()> {
+    let status = match problem
+      .get_named_property::<Option<Either<u16, ClassInstance<StatusCode>>>>("status")?
+    {
+      Some(Either::A(status)) => Some(
+        LibStatusCode::from_u16(status)
+          .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?,
+      ),
+      Some(Either::B(status)) => Some(status.inner().to_owned()),
+      None => None,
+    };
+    let status = match status {
+      Some(status) => status,
+      None => self.with_inner(|response| Ok(response.inner()?.status()))?,
+    };
+
+    let Value::Object(document) = utilities::to_json_value(&env, problem.to_unknown())? else {
+      return Err(Error::new(
+        Status::InvalidArg,
+        "A problem details document must be an object",
+      ));
+    };
+    let document = Value::Object(problem_document(status, document));
+    let body = utilities::stringify_json(
+      &env,
+      utilities::json_to_napi(&env, document)?,
+      &self.settings,
+    )?;
+    let etag = match &body {
+      Some(body) => self.generate_etag(&env, body.as_bytes(), Some("utf8"))?,
+      None => None,
+    };
+
+    self.with_inner(|response| {
+      let inner = response.inner()?;
+      *inner.status_mut() = status;
+      inner.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/problem+json"),
+      );
+      response.json(body, etag, &self.request)
+    })
+  }
+}
+
+/// Complete the problem details `document` of a response with `status`: set
+/// its `status`, and its `title` to the reason phrase of the status when it
+/// has none
+pub fn problem_document(
+  status: LibStatusCode,
+  mut document: Map<String, Value>,
+) -> Map<String, Value> {
+  document.insert("status".to_owned(), json!(status.as_u16()));
+  if !document.get("title").is_some_and(Value::is_string)
+    && let Some(reason) = status.canonical_reason()
+  {
+    document.insert("title".to_owned(), json!(reason));
+  }
+  document
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_problem_document() {
+    let document = problem_document(LibStatusCode::FORBIDDEN, Map::new());
+    assert_eq!(
+      Value::Object(document),
+      json!({ "status": 403, "title": "Forbidden" })
+    );
+
+    let Value::Object(document) = json!({
+      "type": "https://example.com/probs/out-of-credit",
+      "title": "You do not have enough credit.",
+      "status": 400,
+      "balance": 30,
+    }) else {
+      unreachable!()
+    };
+    assert_eq!(
+      Value::Object(problem_document(LibStatusCode::FORBIDDEN, document)),
+      json!({
+        "type": "https://example.com/probs/out-of-credit",
+        "title": "You do not have enough credit.",
+        "status": 403,
+        "balance": 30,
+      })
+    );
+  }
+}
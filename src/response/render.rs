@@ -0,0 +1,217 @@
+use std::sync::Mutex;
+
+use futures::{FutureExt, future::BoxFuture};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use super::Response;
+use crate::{locals::Locals, utilities, view::ViewEngine};
+
+type JsRenderCallback<'a> = Function<'a, FnArgs<(Option<Error>, Option<String>)>, Unknown<'static>>;
+
+type RenderCallbackRef = FunctionRef<FnArgs<(Option<Error>, Option<String>)>, Unknown<'static>>;
+
+#[napi]
+impl Response {
+  /// Variables available to the views rendered for this request, over those
+  /// of `server.locals`, set with `res.locals.set(name, value)`
+  #[napi(getter)]
+  pub fn locals(&self) -> Locals {
+    self.locals.to_owned()
+  }
+
+  /// Renders a `view` and sends the rendered HTML string to the client.
+  /// Optional parameters:
+  ///
+  /// - `locals`, an object whose properties define local variables for the
+  ///   view, over those of `server.locals` and `res.locals`.
+  /// - `callback`, a callback function. If provided, the method returns both
+  ///   the possible error and rendered string, but does not perform an
+  ///   automated response. When an error occurs, the method invokes
+  ///   `callback(err)` internally.
+  ///
+  /// The `view` argument is a string that is the file path of the view file
+  /// to render. This can be an absolute path, or a path relative to the
+  /// `views` setting. If the path does not contain a file extension, then
+  /// the `view engine` setting determines the file extension. The view is
+  /// rendered by the engine registered with `server.engine()` for its
+  /// extension; `.jinja` views are rendered by the built-in Jinja-style
+  /// engine, with the locals converted like `JSON.stringify` does it.
+  ///
+  /// > The `view` argument performs file system operations like reading a
+  /// > file from disk and evaluating Node.js modules, and as so for security
+  /// > reasons should not contain input from the end-user.
+  ///
+  /// ```javascript
+  /// // send the rendered view to the client
+  /// await res.render('index')
+  ///
+  /// // if a callback is specified, the rendered HTML string has to be sent
+  /// // explicitly
+  /// await res.render('index', (err, html) => {
+  ///   res.send(html)
+  /// })
+  ///
+  /// // pass a local variable to the view
+  /// await res.render('user', { name: 'Tobi' }, (err, html) => {
+  ///   // ...
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = "view: string, locals?: Record<string, unknown> | ((err: Error | null, html?: string) => void), callback?: (err: Error | null, html?: string) => void"
+  )]
+  pub fn render<'env>(
+    &self,
+    env: &'env Env,
+    view: String,
+    locals: Option<Unknown<'env>>,
+    callback: Option<JsRenderCallback>,
+  ) -> Result<PromiseRaw<'env, ()>> {
+    // `res.render(view, callback)`
+    let (locals, callback) = match locals {
+      Some(locals) if locals.get_type()? == ValueType::Function => {
+        let callback: JsRenderCallback =
+          unsafe { FromNapiValue::from_napi_value(env.raw(), locals.raw())? };
+        (None, Some(callback))
+      }
+      locals => (locals, callback),
+    };
+    let callback = callback.map(|callback| callback.create_ref()).transpose()?;
+
+    let html = match self.start_rendering(env, &view, locals) {
+      Ok(html) => html,
+      // errors are passed to the callback, if any
+      Err(e) if callback.is_some() => async move { Err(e) }.boxed(),
+      Err(e) => return Err(e),
+    };
+
+    let response = self.clone();
+    env.spawn_future_with_callback(
+      async move { Ok(html.await) },
+      // the callback and `etag` setting are JS functions, so the rendered
+      // HTML is delivered on the JS thread
+      move |env, html| deliver(env, &response, callback, html),
+    )
+  }
+}
+
+impl Response {
+  /// Start rendering `view`: the Jinja engine renders on a blocking thread,
+  /// JS engines are called now and resolve the future when done
+  fn start_rendering(
+    &self,
+    env: &Env,
+    view: &str,
+    locals: Option<Unknown>,
+  ) -> Result<BoxFuture<'static, Result<String>>> {
+    let view = self.settings.views.lookup(view)?;
+    Ok(match view.engine {
+      ViewEngine::Jinja => {
+        let mut context = self.settings.locals.to_map(env)?;
+        context.extend(self.locals.to_map(env)?);
+        if let Some(locals) = locals
+          && let Value::Object(locals) = utilities::to_json_value(env, locals)?
+        {
+          context.extend(locals);
+        }
+
+        let views = self.settings.views.clone();
+        async move {
+          tokio::task::spawn_blocking(move || {
+            views.render_jinja(&view.path, Value::Object(context))
+          })
+          .await
+          .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?
+        }
+        .boxed()
+      }
+      ViewEngine::Function(engine) => {
+        // `Object.assign({}, server.locals, res.locals, locals)`, keeping the
+        // JS values themselves
+        let mut options = Object::new(env)?;
+        let shared = [
+          self.settings.locals.to_object(env)?,
+          self.locals.to_object(env)?,
+        ];
+        let locals = match locals {
+          Some(locals) if locals.get_type()? == ValueType::Object => {
+            Some(unsafe { locals.cast()? })
+          }
+          _ => None,
+        };
+        for values in shared.iter().chain(&locals) {
+          for key in Object::keys(values)? {
+            options.set_named_property(&key, values.get_named_property::<Unknown>(&key)?)?;
+          }
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let sender = Mutex::new(Some(sender));
+        let done: Function<FnArgs<(Unknown, Option<String>)>, ()> = env
+          .create_function_from_closure("done", move |ctx| {
+            let result = match ctx.length() {
+              0 => Ok(String::new()),
+              length => match ctx.get::<Unknown>(0)? {
+                error if !matches!(error.get_type()?, ValueType::Undefined | ValueType::Null) => {
+                  Err(Error::from(error))
+                }
+                _ if length == 1 => Ok(String::new()),
+                _ => ctx.get::<Option<String>>(1).map(Option::unwrap_or_default),
+              },
+            };
+            if let Some(sender) = sender.lock().ok().and_then(|mut sender| sender.take()) {
+              let _ = sender.send(result);
+            }
+            Ok(())
+          })?;
+
+        let path = view.path.to_string_lossy().into_owned();
+        let rendered = engine
+          .borrow_back(env)?
+          .call((path, options, done.to_unknown()).into())?;
+        match rendered.get_type()? {
+          ValueType::String => {
+            let html = rendered.coerce_to_string()?.into_utf8()?.into_owned()?;
+            async move { Ok(html) }.boxed()
+          }
+          ValueType::Object if rendered.is_promise()? => {
+            let html: Promise<String> =
+              unsafe { FromNapiValue::from_napi_value(env.raw(), rendered.raw())? };
+            html.boxed()
+          }
+          _ => async move {
+            receiver.await.unwrap_or_else(|_| {
+              Err(Error::new(
+                Status::GenericFailure,
+                "The view engine did not render the view",
+              ))
+            })
+          }
+          .boxed(),
+        }
+      }
+    })
+  }
+}
+
+fn deliver(
+  env: &Env,
+  response: &Response,
+  callback: Option<RenderCallbackRef>,
+  html: Result<String>,
+) -> Result<()> {
+  if let Some(callback) = callback {
+    let callback = callback.borrow_back(env)?;
+    match html {
+      Ok(html) => callback.call((None, Some(html)).into())?,
+      Err(e) => callback.call((Some(e), None).into())?,
+    };
+    return Ok(());
+  }
+
+  let html = html?;
+  let etag = response.generate_etag(env, html.as_bytes(), Some("utf8"))?;
+  response.with_inner(|inner| inner.send(Some(Either::A(html)), etag, &response.request))
+}
@@ -1,12 +1,21 @@
 use bytes::Bytes;
+use etag::EntityTag;
 use hyper::{
   StatusCode,
-  header::{CONTENT_LENGTH, CONTENT_TYPE, HeaderValue, TRANSFER_ENCODING},
+  header::{
+    ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, HeaderValue,
+    TRANSFER_ENCODING,
+  },
 };
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
 use super::{Response, WrappedResponse};
+use crate::{
+  request::Request,
+  server::EtagSetting,
+  utilities::{self, ByteRanges, Validators},
+};
 
 #[napi]
 impl Response {
@@ -50,45 +59,114 @@ impl Response {
   /// res.send({ user: 'tobi' })
   /// res.send([1, 2, 3])
   /// ```
-  #[napi]
-  pub fn send(
-    &mut self,
-    body: Either6<String, i64, bool, Null, Buffer, Object>,
-    env: Env,
-  ) -> Result<()> {
-    self.with_inner(|response| response.send(body, env))
+  ///
+  /// When the parameter is `null` or `undefined`, an empty body is sent.
+  ///
+  /// An `ETag` is generated for the body according to the `etag` setting of
+  /// the server, unless the response already has one. When the request is
+  /// fresh (see `req.fresh`), the response is turned into a
+  /// `304 Not Modified` without a body.
+  ///
+  /// Buffer bodies are sent with `Accept-Ranges: bytes`, unless the header is
+  /// already set to `none`, and `GET` requests for byte ranges are answered
+  /// with `206 Partial Content`, as `multipart/byteranges` when several ranges
+  /// are requested, or with `416 Range Not Satisfiable`. The `If-Range`
+  /// header is checked against the `ETag` and `Last-Modified` headers of the
+  /// response.
+  #[napi(ts_args_type = "body?: unknown")]
+  pub fn send(&self, body: Either3<String, Buffer, Unknown>, env: Env) -> Result<()> {
+    let body = match body {
+      Either3::A(value) => Either::A(value),
+      Either3::B(value) => Either::B(value),
+      Either3::C(value) => match value.get_type()? {
+        ValueType::Undefined | ValueType::Null => {
+          return self.with_inner(|response| response.send(None, None, &self.request));
+        }
+        // set `Content-Type` to application/json if the provided body is an
+        // object, number or boolean.
+        _ => return self.json(value, env),
+      },
+    };
+    let etag = match &body {
+      Either::A(value) => self.generate_etag(&env, value.as_bytes(), Some("utf8"))?,
+      Either::B(value) => self.generate_etag(&env, value, None)?,
+    };
+    self.with_inner(|response| response.send(Some(body), etag, &self.request))
+  }
+}
+
+impl Response {
+  /// Generate the `ETag` of `body` according to the `etag` setting, unless
+  /// the response already has one. Must be called on the JS thread, without
+  /// holding the response's lock, as the setting may be a JS function.
+  pub(crate) fn generate_etag(
+    &self,
+    env: &Env,
+    body: &[u8],
+    encoding: Option<&str>,
+  ) -> Result<Option<HeaderValue>> {
+    if self.with_inner(|response| Ok(response.inner()?.headers().contains_key(ETAG)))? {
+      return Ok(None);
+    }
+    let etag = match &self.settings.etag {
+      EtagSetting::Disabled => return Ok(None),
+      EtagSetting::Weak => EntityTag::weak(EntityTag::from_data(body).tag()).to_string(),
+      EtagSetting::Strong => EntityTag::from_data(body).to_string(),
+      EtagSetting::Function(etag_fn) => {
+        let encoding = match encoding {
+          Some(encoding) => Either::A(encoding.to_owned()),
+          None => Either::B(()),
+        };
+        let etag = etag_fn
+          .borrow_back(env)?
+          .call((Buffer::from(body), encoding).into())?;
+        match etag.get_type()? {
+          ValueType::String => etag.coerce_to_string()?.into_utf8()?.as_str()?.to_owned(),
+          _ => return Ok(None),
+        }
+      }
+    };
+    HeaderValue::from_str(&etag)
+      .map(Some)
+      .map_err(|e| Error::new(Status::InvalidArg, format!("Invalid ETag \"{etag}\": {e}")))
   }
 }
 
 impl WrappedResponse {
+  /// Send a string or bytes `body`. `None` sends an empty body.
+  ///
+  /// `etag` is set unless the response already has an `ETag`; the response
+  /// is turned into a `304 Not Modified` when `request` is fresh. The `Range`
+  /// header of `request` is honored for bytes bodies.
   pub fn send(
     &mut self,
-    body: Either6<String, i64, bool, Null, Buffer, Object>,
-    env: Env,
+    body: Option<Either<String, Buffer>>,
+    etag: Option<HeaderValue>,
+    request: &Request,
   ) -> Result<()> {
+    let ranged = matches!(body, Some(Either::B(_)));
     let mut chunk = match body {
       // set `Content-Type` to text/html if the provided body is a string
-      Either6::A(value) => {
+      Some(Either::A(value)) => {
         if self.inner()?.headers().get(CONTENT_TYPE).is_none() {
           self.content_type("html".to_owned())?
         }
         Either::A(value)
       }
-      // set `Content-Type` to application/json if the provided body is an
-      // object, number or boolean.
-      Either6::B(value) => return self.json(Either5::B(value), env),
-      Either6::C(value) => return self.json(Either5::C(value), env),
-      Either6::D(_) => return self.json(Either5::A("".to_owned()), env),
       // set the `Content-Type` to application/octet-stream if the provided
       // body is a bytes array
-      Either6::E(value) => {
-        log::debug!("RS: Received buffer. Data: {:?}", value.iter().as_slice());
+      Some(Either::B(value)) => {
+        log::debug!(
+          "Request ID: {} | RS: Received buffer. Data: {:?}",
+          request.id(),
+          value.iter().as_slice()
+        );
         if self.inner()?.headers().get(CONTENT_TYPE).is_none() {
           self.content_type("bin".to_owned())?
         }
         Either::B(value)
       }
-      Either6::F(value) => return self.json(Either5::D(value), env),
+      None => Either::B(Buffer::from(Vec::new())),
     };
 
     // write strings in utf-8
@@ -108,9 +186,27 @@ impl WrappedResponse {
       }
     }
 
-    // TODO: Support configuring if ETag should be generated
+    // populate ETag
+    if let Some(etag) = etag
+      && !self.inner()?.headers().contains_key(ETAG)
+    {
+      self.inner()?.headers_mut().insert(ETAG, etag);
+    }
 
-    // TODO: Support freshness detection and header
+    // freshness
+    let response = self.inner()?;
+    let fresh = request.with_inner(|w_req| {
+      let request = w_req.inner()?;
+      Ok(utilities::is_fresh(
+        request.method(),
+        request.headers(),
+        response.status(),
+        response.headers(),
+      ))
+    })?;
+    if fresh {
+      *response.status_mut() = StatusCode::NOT_MODIFIED;
+    }
 
     // strip irrelevant headers
     let status_code = self.inner()?.status();
@@ -124,10 +220,83 @@ impl WrappedResponse {
 
     let chunk = match chunk {
       Either::A(v) => Bytes::copy_from_slice(v.as_bytes()),
-      Either::B(v) => Bytes::copy_from_slice(v.as_ref()),
+      Either::B(v) => {
+        let chunk = Bytes::copy_from_slice(v.as_ref());
+        match ranged {
+          true => self.byte_ranges(chunk, request)?,
+          false => chunk,
+        }
+      }
     };
 
     // TODO: Seal response from further modification
     self.end(Some(chunk))
   }
+
+  /// Answer the `Range` header of `request` from the bytes of `body`, as a
+  /// `206 Partial Content` (single part or `multipart/byteranges`) or a `416
+  /// Range Not Satisfiable`. Returns the body to send.
+  fn byte_ranges(&mut self, body: Bytes, request: &Request) -> Result<Bytes> {
+    let response = self.inner()?;
+    if response.status() != StatusCode::OK {
+      return Ok(body);
+    }
+    let headers = response.headers_mut();
+    match headers.get(ACCEPT_RANGES) {
+      Some(accept_ranges) if accept_ranges.as_bytes() == b"none" => return Ok(body),
+      Some(_) => {}
+      None => {
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+      }
+    }
+
+    let validators = Validators::from_headers(headers);
+    let ranges = request.with_inner(|w_req| {
+      let request = w_req.inner()?;
+      Ok(utilities::byte_ranges(
+        request.method(),
+        request.headers(),
+        body.len(),
+        &validators,
+      ))
+    })?;
+
+    let to_header_value = |value: String| {
+      HeaderValue::from_str(&value).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+    };
+    match ranges {
+      ByteRanges::Full => Ok(body),
+      ByteRanges::Partial(ranges) if ranges.len() == 1 => {
+        let range = &ranges[0];
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+        response.headers_mut().insert(
+          CONTENT_RANGE,
+          to_header_value(utilities::content_range(range, body.len()))?,
+        );
+        Ok(body.slice(range.start..=range.end))
+      }
+      ByteRanges::Partial(ranges) => {
+        let content_type = response
+          .headers()
+          .get(CONTENT_TYPE)
+          .and_then(|content_type| content_type.to_str().ok());
+        let (content_type, body) = utilities::multipart_byteranges(&body, &ranges, content_type);
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+        response
+          .headers_mut()
+          .insert(CONTENT_TYPE, to_header_value(content_type)?);
+        Ok(body)
+      }
+      ByteRanges::Unsatisfiable => {
+        *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+        let headers = response.headers_mut();
+        headers.remove(CONTENT_TYPE);
+        headers.insert(
+          CONTENT_RANGE,
+          to_header_value(utilities::unsatisfied_range(body.len()))?,
+        );
+        Ok(Bytes::new())
+      }
+    }
+  }
 }
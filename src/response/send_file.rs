@@ -4,12 +4,15 @@ use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
 use super::Response;
-use crate::utilities::{self, FileSendOptions, FileSendTask};
+use crate::{
+  utilities::{self, FileSendOptions, FileSendTask},
+  virtual_file_system::VirtualFileSystem,
+};
 
 #[napi(object)]
 pub struct SendFileOptions<'a> {
-  pub max_age: Option<u32>,
-  pub root: Option<String>,
+  pub max_age: Option<Either<i64, String>>,
+  pub root: Option<Either<String, ClassInstance<'a, VirtualFileSystem>>>,
   pub last_modified: Option<bool>,
   pub headers: Option<Object<'a>>,
   pub dotfiles: Option<String>,
@@ -24,12 +27,14 @@ impl<'a> TryFrom<&SendFileOptions<'a>> for FileSendOptions {
   fn try_from(value: &SendFileOptions<'a>) -> Result<Self> {
     let mut options = FileSendOptions::default();
 
-    if let Some(root) = &value.root {
-      options.root = Some(Path::new(root).to_path_buf())
+    match &value.root {
+      Some(Either::A(root)) => options.root = Some(Path::new(root).to_path_buf()),
+      Some(Either::B(fs)) => options.fs = Some(fs.owned_inner()),
+      None => {}
     }
 
-    if let Some(max_age) = value.max_age {
-      options.max_age = max_age as u64;
+    if let Some(max_age) = &value.max_age {
+      options.max_age = utilities::parse_max_age(max_age, "maxAge")?;
     }
 
     if let Some(last_modified) = value.last_modified {
@@ -83,7 +88,7 @@ impl Response {
   /// | Property | Description | Default |
   /// | --- | --- | --- |
   /// | `maxAge` | Sets the max-age property of the `Cache-Control` header in milliseconds or a string in [ms format](https://www.npmjs.org/package/ms) | 0 |
-  /// | `root` | Root directory for relative filenames. | |
+  /// | `root` | Root directory for relative filenames, or a `VirtualFileSystem` to serve them from. | |
   /// | `lastModified` | Sets the `Last-Modified` header to the last modified date of the file on the OS. Set `false` to disable it. | Enabled |
   /// | `headers` | Object containing HTTP headers to serve with the file. | |
   /// | `dotfiles` | Option for serving dotfiles. Possible values are "allow", "deny", "ignore". | "ignore" |
@@ -142,29 +147,37 @@ impl Response {
   /// For more information, or if you have issues or concerns, see
   /// [send](https://github.com/pillarjs/send).
   #[napi]
-  pub fn send_file(
+  pub fn send_file<'env>(
     &self,
+    env: &'env Env,
     path: String,
     options: Option<SendFileOptions>,
-  ) -> Result<AsyncTask<FileSendTask>> {
+  ) -> Result<PromiseRaw<'env, ()>> {
     let file_send_options: FileSendOptions = match &options {
       Some(options) => options.try_into()?,
       None => FileSendOptions::default(),
     };
 
-    let root = options.and_then(|options| options.root);
+    let has_root = options.is_some_and(|options| options.root.is_some());
 
-    if root.is_none() && !Path::new(&path).is_absolute() {
+    if !has_root && !Path::new(&path).is_absolute() {
       return Err(Error::new(
         Status::InvalidArg,
         "path must be absolute or specify root to res.sendFile",
       ));
     }
 
-    Ok(AsyncTask::new(FileSendTask {
+    let mut task = FileSendTask {
       response: self.clone(),
       path,
       options: file_send_options,
-    }))
+    };
+    env.spawn_future(async move {
+      task.send().await?;
+      task.response.with_inner(|w_res| {
+        w_res.set_ended();
+        Ok(())
+      })
+    })
   }
 }
@@ -2,7 +2,7 @@ use hyper::StatusCode as LibStatusCode;
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
-use super::{Response, WrappedResponse};
+use super::Response;
 use crate::response::status_code::StatusCode;
 
 #[napi]
@@ -15,13 +15,7 @@ impl Response {
   /// res.sendStatus(404)
   /// ```
   #[napi]
-  pub fn send_status(&mut self, body: Either<u16, &StatusCode>, env: Env) -> Result<()> {
-    self.with_inner(|response| response.send_status(body, env))
-  }
-}
-
-impl WrappedResponse {
-  pub fn send_status(&mut self, body: Either<u16, &StatusCode>, env: Env) -> Result<()> {
+  pub fn send_status(&self, body: Either<u16, &StatusCode>, env: Env) -> Result<()> {
     let status_code = match body {
       Either::A(value) => {
         LibStatusCode::from_u16(value).map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?
@@ -29,13 +23,15 @@ impl WrappedResponse {
       Either::B(value) => value.inner().to_owned(),
     };
 
-    *self.inner()?.status_mut() = status_code;
-
     let body = match status_code.canonical_reason() {
-      Some(reason) => Either6::A(reason.to_owned()),
-      None => Either6::D(Null),
+      Some(reason) => reason.to_owned(),
+      None => status_code.as_str().to_owned(),
     };
+    let etag = self.generate_etag(&env, body.as_bytes(), Some("utf8"))?;
 
-    self.send(body, env)
+    self.with_inner(|response| {
+      *response.inner()?.status_mut() = status_code;
+      response.send(Some(Either::A(body)), etag, &self.request)
+    })
   }
 }
@@ -19,6 +19,18 @@ impl Response {
     self.with_inner(|response| response.status(body))?;
     Ok(self.clone())
   }
+
+  /// The HTTP status of the response, `200` until it is set.
+  ///
+  /// ```javascript
+  /// if (res.statusCode >= 400) {
+  ///   // ...
+  /// }
+  /// ```
+  #[napi(getter)]
+  pub fn status_code(&self) -> Result<u16> {
+    self.with_inner(|response| Ok(response.inner()?.status().as_u16()))
+  }
 }
 
 impl WrappedResponse {
@@ -1,21 +1,26 @@
 use std::{
   pin::Pin,
+  sync::Arc,
   task::{Poll, ready},
 };
 
 use bytes::Bytes;
 use http_body::Body as HttpBody;
-use http_body_util::Full;
+use http_body_util::{Full, combinators::UnsyncBoxBody};
 use hyper::Response as LibResponse;
 use hyper_staticfile::Body as StaticFileBody;
 use napi::{Error, Result, Status};
+use tokio::sync::Notify;
 
-use crate::utilities::full;
+use crate::middlewares::AccessLog;
+use crate::utilities::{ResponseCompression, StaticFileAccess, full};
 
 pub enum CrateBody {
   Empty,
   Full(Full<Bytes>),
-  StaticFile(StaticFileBody),
+  StaticFile(StaticFileBody<StaticFileAccess>),
+  /// A body transformed on the fly, e.g. compressed
+  Stream(UnsyncBoxBody<Bytes, std::io::Error>),
 }
 
 impl HttpBody for CrateBody {
@@ -33,13 +38,32 @@ impl HttpBody for CrateBody {
         .poll_frame(cx)
         .map(|s| s.map(|s| s.map_err(|never| match never {}))),
       Self::StaticFile(ref mut stream) => Pin::new(stream).poll_frame(cx),
+      Self::Stream(ref mut stream) => Pin::new(stream).poll_frame(cx),
     });
     Poll::Ready(opt)
   }
+
+  fn is_end_stream(&self) -> bool {
+    match self {
+      Self::Empty => true,
+      Self::Full(body) => body.is_end_stream(),
+      Self::StaticFile(body) => body.is_end_stream(),
+      Self::Stream(body) => body.is_end_stream(),
+    }
+  }
+
+  fn size_hint(&self) -> http_body::SizeHint {
+    match self {
+      Self::Empty => http_body::SizeHint::with_exact(0),
+      Self::Full(body) => body.size_hint(),
+      Self::StaticFile(body) => body.size_hint(),
+      Self::Stream(body) => body.size_hint(),
+    }
+  }
 }
 
-impl From<StaticFileBody> for CrateBody {
-  fn from(value: StaticFileBody) -> Self {
+impl From<StaticFileBody<StaticFileAccess>> for CrateBody {
+  fn from(value: StaticFileBody<StaticFileAccess>) -> Self {
     Self::StaticFile(value)
   }
 }
@@ -48,19 +72,37 @@ type ResponseInner = LibResponse<CrateBody>;
 
 pub struct WrappedResponse {
   inner: Option<ResponseInner>,
+  /// Compression applied by `finish`
+  compression: Option<ResponseCompression>,
+  /// The access logs written once the response is complete
+  access_logs: Vec<AccessLog>,
+  /// Whether the response was ended with `end`
+  ended: bool,
+  /// Notifies the middlewares waiting for the response to end
+  ended_notify: Arc<Notify>,
 }
 
 impl Default for WrappedResponse {
   fn default() -> Self {
     Self {
       inner: Some(LibResponse::new(CrateBody::Empty)),
+      compression: None,
+      access_logs: Vec::new(),
+      ended: false,
+      ended_notify: Arc::default(),
     }
   }
 }
 
 impl From<ResponseInner> for WrappedResponse {
   fn from(value: ResponseInner) -> Self {
-    Self { inner: Some(value) }
+    Self {
+      inner: Some(value),
+      compression: None,
+      access_logs: Vec::new(),
+      ended: false,
+      ended_notify: Arc::default(),
+    }
   }
 }
 
@@ -83,12 +125,49 @@ impl WrappedResponse {
     ))
   }
 
+  pub fn set_compression(&mut self, compression: ResponseCompression) {
+    self.compression = Some(compression);
+  }
+
+  pub fn add_access_log(&mut self, access_log: AccessLog) {
+    self.access_logs.push(access_log);
+  }
+
+  pub fn take_access_logs(&mut self) -> Vec<AccessLog> {
+    std::mem::take(&mut self.access_logs)
+  }
+
+  /// Take the complete response, to be sent to the client.
+  pub fn finish(&mut self) -> Result<ResponseInner> {
+    if let Some(compression) = self.compression.take() {
+      compression.apply(self)?;
+    }
+    self.take()
+  }
+
   pub fn end(&mut self, data: Option<Bytes>) -> Result<()> {
     let response = match data {
       Some(data) => self.take()?.map(|_| full(data)),
       None => self.take()?.map(|_| CrateBody::Empty),
     };
     self.inner = Some(response);
+    self.set_ended();
     Ok(())
   }
+
+  /// Mark the response as ended, e.g. once a file is sent into it
+  pub fn set_ended(&mut self) {
+    self.ended = true;
+    self.ended_notify.notify_waiters();
+  }
+
+  /// Notified when the response is ended
+  pub fn ended_notify(&self) -> Arc<Notify> {
+    self.ended_notify.clone()
+  }
+
+  /// Whether the response was ended with `end`, e.g. by `res.send()`
+  pub fn is_ended(&self) -> bool {
+    self.ended
+  }
 }
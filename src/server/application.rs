@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use super::AppSettings;
+use crate::locals::Locals;
+
+/// The settings and locals of the server handling a request, as
+/// `req.app` and `res.app`. They are those of the server when it started
+/// listening.
+///
+/// ```javascript
+/// app.use((req, res) => {
+///   if (req.app.enabled('trust proxy')) {
+///     // ...
+///   }
+///   return true
+/// })
+/// ```
+#[napi]
+#[derive(Clone, Default)]
+pub struct Application {
+  settings: Arc<AppSettings>,
+}
+
+impl From<Arc<AppSettings>> for Application {
+  fn from(settings: Arc<AppSettings>) -> Self {
+    Self { settings }
+  }
+}
+
+#[napi]
+impl Application {
+  /// The value of setting `name`, `undefined` when it is not set
+  #[napi(ts_return_type = "unknown")]
+  pub fn get<'env>(
+    &self,
+    env: &'env Env,
+    name: String,
+  ) -> Result<Either<Unknown<'env>, Undefined>> {
+    Ok(match self.settings.get(env, &name)? {
+      Some(value) => Either::A(value),
+      None => Either::B(()),
+    })
+  }
+
+  /// Whether setting `name` is truthy
+  #[napi]
+  pub fn enabled(&self, env: Env, name: String) -> Result<bool> {
+    self.settings.enabled(&env, &name)
+  }
+
+  /// Whether setting `name` is falsy
+  #[napi]
+  pub fn disabled(&self, env: Env, name: String) -> Result<bool> {
+    self.settings.enabled(&env, &name).map(|enabled| !enabled)
+  }
+
+  /// Variables available to all the views rendered by `res.render()`, set
+  /// with `app.locals.set(name, value)`
+  #[napi(getter)]
+  pub fn locals(&self) -> Locals {
+    self.settings.locals.to_owned()
+  }
+}
@@ -0,0 +1,416 @@
+use std::sync::{Arc, Mutex};
+
+use hyper::StatusCode;
+use napi::bindgen_prelude::*;
+use napi::sys;
+use napi::threadsafe_function::ThreadsafeFunctionCallMode;
+use serde_json::{Map, Value};
+use tokio::sync::oneshot;
+
+use super::{HttpError, ThreadsafeMiddlewareFn};
+use crate::request::Request;
+use crate::response::Response;
+use crate::utilities;
+
+/// How a JS middleware handler settled.
+pub(super) enum MiddlewareOutcome {
+  /// The handler returned, or its promise resolved to, `true`: run the next
+  /// middleware. Error handlers pass the error they were given on.
+  Continue,
+  /// The handler called `next()` or `next(err)`, or threw `err`: run the next
+  /// middleware, or the next error handler when there is an error.
+  Next(Option<HandlerError>),
+  /// The handler called `next('route')`: skip the remaining handlers of the
+  /// route.
+  NextRoute,
+  /// The handler ended the chain.
+  Done,
+}
+
+/// An error thrown by a JS middleware handler, or passed to `next(err)`, or
+/// an error of the server such as the `404 Not Found` of the requests no
+/// middleware handled.
+pub(super) struct HandlerError {
+  /// The error's `status` (or `statusCode`) when it is a valid HTTP error
+  /// status, `500 Internal Server Error` otherwise.
+  pub status: StatusCode,
+  /// The error's `headers`, set on the response by the final handler.
+  pub headers: Vec<(String, String)>,
+  /// Whether the error's `expose` is `true`: its message may be shown to the
+  /// client.
+  pub expose: bool,
+  /// The error's `message`, when it is a string.
+  pub message: Option<String>,
+  /// The error's `stack`, when it is a string.
+  pub stack: Option<String>,
+  /// Whether the error is an HTTP error: it has a valid `status`, like the
+  /// `HttpError`s and the errors of the body parsers, or it is an error of
+  /// the server.
+  pub http_error: bool,
+  /// The error's `properties`, the extra properties of an `HttpError`.
+  pub properties: Option<Map<String, Value>>,
+  /// The error, holding the thrown JS value so that error handlers are given
+  /// the original. `None` for the errors of the server, given to handlers as
+  /// `HttpError`s.
+  pub error: Option<Error>,
+}
+
+impl HandlerError {
+  /// An error of the server, exposed when it is a client error.
+  pub fn from_status(status: StatusCode, message: String) -> Self {
+    Self {
+      status,
+      headers: Vec::new(),
+      expose: status.is_client_error(),
+      message: Some(message),
+      stack: None,
+      http_error: true,
+      properties: None,
+      error: None,
+    }
+  }
+
+  /// Build from the thrown value. Must be called on the JS thread.
+  fn from_js(value: Unknown) -> Self {
+    let status = error_status(&value);
+    Self {
+      status: status.unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+      headers: error_headers(&value).unwrap_or_default(),
+      expose: error_expose(&value),
+      message: error_string(&value, "message"),
+      stack: error_string(&value, "stack"),
+      http_error: status.is_some(),
+      properties: error_properties(&value),
+      error: Some(Error::from_unknown_without_coercion(value)),
+    }
+  }
+
+  /// A copy of the error, sharing the thrown JS value.
+  pub fn try_clone(&self) -> Result<Self> {
+    Ok(Self {
+      status: self.status,
+      headers: self.headers.clone(),
+      expose: self.expose,
+      message: self.message.clone(),
+      stack: self.stack.clone(),
+      http_error: self.http_error,
+      properties: self.properties.clone(),
+      error: self.error.as_ref().map(Error::try_clone).transpose()?,
+    })
+  }
+
+  /// The error as given to error handlers: the thrown JS value, or an
+  /// `HttpError` for the errors of the server. Runs on the JS thread.
+  fn into_js(self, env: &Env) -> Result<sys::napi_value> {
+    unsafe {
+      match self.error {
+        Some(error) => ToNapiValue::to_napi_value(env.raw(), error),
+        None => {
+          let message = self.message.unwrap_or_default();
+          let error = HttpError::from_status(self.status, message);
+          ToNapiValue::to_napi_value(env.raw(), error)
+        }
+      }
+    }
+  }
+}
+
+/// Read the `status` or `statusCode` property of a thrown value, the way
+/// Express' final handler does.
+fn error_status(value: &Unknown) -> Option<StatusCode> {
+  let object = error_object(value)?;
+  ["status", "statusCode"].into_iter().find_map(|name| {
+    let status = object.get_named_property::<Unknown>(name).ok()?;
+    if status.get_type().ok()? != ValueType::Number {
+      return None;
+    }
+    let status = status.coerce_to_number().ok()?.get_double().ok()?;
+    if status.fract() != 0.0 || !(400.0..600.0).contains(&status) {
+      return None;
+    }
+    StatusCode::from_u16(status as u16).ok()
+  })
+}
+
+/// Read the `headers` property of a thrown value, an object of header names
+/// to values.
+fn error_headers(value: &Unknown) -> Option<Vec<(String, String)>> {
+  let headers = error_object(value)?
+    .get_named_property::<Unknown>("headers")
+    .ok()?;
+  if headers.get_type().ok()? != ValueType::Object {
+    return None;
+  }
+  let headers: Object = unsafe { headers.cast().ok()? };
+  let names = Object::keys(&headers).ok()?;
+  Some(
+    names
+      .into_iter()
+      .filter_map(|name| {
+        let value = headers.get_named_property::<Unknown>(&name).ok()?;
+        let value = value.coerce_to_string().ok()?.into_utf8().ok()?;
+        Some((name, value.as_str().ok()?.to_owned()))
+      })
+      .collect(),
+  )
+}
+
+/// Whether the `expose` property of a thrown value is `true`.
+fn error_expose(value: &Unknown) -> bool {
+  error_object(value)
+    .and_then(|object| object.get_named_property::<Unknown>("expose").ok())
+    .is_some_and(|expose| {
+      matches!(expose.get_type(), Ok(ValueType::Boolean))
+        && expose.coerce_to_bool().unwrap_or(false)
+    })
+}
+
+/// Read the `message` or `stack` property of a thrown value, when it is a
+/// string. A thrown string is its own message.
+fn error_string(value: &Unknown, name: &str) -> Option<String> {
+  let property = match value.get_type().ok()? {
+    ValueType::String if name == "message" => *value,
+    _ => error_object(value)?
+      .get_named_property::<Unknown>(name)
+      .ok()?,
+  };
+  if property.get_type().ok()? != ValueType::String {
+    return None;
+  }
+  Some(
+    property
+      .coerce_to_string()
+      .ok()?
+      .into_utf8()
+      .ok()?
+      .as_str()
+      .ok()?
+      .to_owned(),
+  )
+}
+
+/// Read the `properties` property of a thrown value, when it is an object.
+fn error_properties(value: &Unknown) -> Option<Map<String, Value>> {
+  let properties = error_object(value)?
+    .get_named_property::<Unknown>("properties")
+    .ok()?;
+  if properties.get_type().ok()? != ValueType::Object {
+    return None;
+  }
+  let env = Env::from_raw(value.value().env);
+  match utilities::to_json_value(&env, properties).ok()? {
+    Value::Object(properties) => Some(properties),
+    _ => None,
+  }
+}
+
+fn error_object<'env>(value: &Unknown<'env>) -> Option<Object<'env>> {
+  if !matches!(
+    value.get_type().ok()?,
+    ValueType::Object | ValueType::Function
+  ) {
+    return None;
+  }
+  value.coerce_to_object().ok()
+}
+
+/// Whether a handler's settled value asks for the next middleware to run.
+fn continue_flag(value: &Unknown) -> bool {
+  matches!(value.get_type(), Ok(ValueType::Boolean)) && value.coerce_to_bool().unwrap_or(false)
+}
+
+/// The outcome of `next(value)`, the way Express' router reads it.
+fn next_outcome(value: Unknown) -> Result<MiddlewareOutcome> {
+  if value.get_type()? == ValueType::String
+    && value.coerce_to_string()?.into_utf8()?.as_str()? == "route"
+  {
+    return Ok(MiddlewareOutcome::NextRoute);
+  }
+  match value.coerce_to_bool()? {
+    true => Ok(MiddlewareOutcome::Next(Some(HandlerError::from_js(value)))),
+    false => Ok(MiddlewareOutcome::Next(None)),
+  }
+}
+
+/// Delivers the outcome of a call; the first of `next()` being called and
+/// the handler settling wins.
+type Settler = Arc<Mutex<Option<oneshot::Sender<MiddlewareOutcome>>>>;
+
+fn settle_with(settler: &Settler, outcome: MiddlewareOutcome) {
+  if let Some(sender) = settler.lock().ok().and_then(|mut sender| sender.take()) {
+    let _ = sender.send(outcome);
+  }
+}
+
+/// A call of a middleware handler.
+pub(super) struct MiddlewareCall {
+  request: Request,
+  response: Response,
+  /// The error given to an error handler
+  error: Option<HandlerError>,
+  settler: Settler,
+}
+
+/// The arguments of a handler: `(req, res, next)`, or `(err, req, res, next)`
+/// for error handlers.
+pub(super) struct HandlerArgs(Vec<sys::napi_value>);
+
+impl JsValuesTupleIntoVec for HandlerArgs {
+  fn into_vec(self, _env: sys::napi_env) -> Result<Vec<sys::napi_value>> {
+    Ok(self.0)
+  }
+}
+
+/// Build the arguments of a call. Runs on the JS thread.
+pub(super) fn handler_args(env: &Env, call: MiddlewareCall) -> Result<HandlerArgs> {
+  let settler = call.settler;
+  let next: Function<Unknown, ()> = env.create_function_from_closure("next", move |ctx| {
+    let outcome = match ctx.length() {
+      0 => MiddlewareOutcome::Next(None),
+      _ => next_outcome(ctx.get::<Unknown>(0)?)?,
+    };
+    settle_with(&settler, outcome);
+    Ok(())
+  })?;
+
+  let mut args = Vec::with_capacity(4);
+  unsafe {
+    if let Some(error) = call.error {
+      args.push(error.into_js(env)?);
+    }
+    args.push(ToNapiValue::to_napi_value(env.raw(), call.request)?);
+    args.push(ToNapiValue::to_napi_value(env.raw(), call.response)?);
+  }
+  args.push(next.raw());
+  Ok(HandlerArgs(args))
+}
+
+/// Call a JS middleware `handler` and wait for it to call `next()` or
+/// settle, whichever comes first. `error` is given to error handlers.
+///
+/// When the handler takes `next`, it decides whether the chain goes on by
+/// calling it and its return value is ignored: it may call `next()`, or end
+/// the response, after it returned, e.g. from a timer, so its call is only
+/// done once it does either, or throws. Otherwise returning `true` runs the
+/// next middleware. The handler's return value, or the reason its promise
+/// rejected, is inspected on the JS thread so that the properties of thrown
+/// errors (e.g. `status`) can be read.
+pub(super) async fn call_middleware(
+  handler: &ThreadsafeMiddlewareFn,
+  takes_next: bool,
+  request: Request,
+  response: Response,
+  error: Option<HandlerError>,
+) -> Result<MiddlewareOutcome> {
+  let (sender, receiver) = oneshot::channel();
+  let settler: Settler = Arc::new(Mutex::new(Some(sender)));
+  // only the ends of the response during the call are waited for
+  let ended_notify = response.with_inner(|w_res| Ok(w_res.ended_notify()))?;
+  let ended = ended_notify.notified();
+  tokio::pin!(ended);
+  ended.as_mut().enable();
+
+  let call = MiddlewareCall {
+    request,
+    response: response.clone(),
+    error,
+    settler: settler.clone(),
+  };
+  let status = handler.call_with_return_value(
+    call,
+    ThreadsafeFunctionCallMode::NonBlocking,
+    move |result, env| settle(&env, result, settler, takes_next, &response),
+  );
+  if status != Status::Ok {
+    return Err(Error::from_status(status));
+  }
+  let outcome = async {
+    receiver.await.map_err(|_| {
+      Error::new(
+        Status::GenericFailure,
+        "Middleware settled without a result",
+      )
+    })
+  };
+  match takes_next {
+    true => tokio::select! {
+      biased;
+      outcome = outcome => outcome,
+      _ = ended => Ok(MiddlewareOutcome::Done),
+    },
+    false => outcome.await,
+  }
+}
+
+/// The outcome of a handler settling with `value` without calling `next()`,
+/// `None` while a handler taking `next` has yet to call it or to end the
+/// response.
+fn settled_outcome(
+  value: &Unknown,
+  takes_next: bool,
+  response: &Response,
+) -> Option<MiddlewareOutcome> {
+  if takes_next {
+    let ended = response
+      .with_inner(|w_res| Ok(w_res.is_ended()))
+      .unwrap_or(true);
+    return ended.then_some(MiddlewareOutcome::Done);
+  }
+  match continue_flag(value) {
+    true => Some(MiddlewareOutcome::Continue),
+    false => Some(MiddlewareOutcome::Done),
+  }
+}
+
+/// Settle the call with the outcome of a handler settling with `value`, if
+/// any
+fn settle_value(settler: &Settler, value: &Unknown, takes_next: bool, response: &Response) {
+  if let Some(outcome) = settled_outcome(value, takes_next, response) {
+    settle_with(settler, outcome);
+  }
+}
+
+fn settle(
+  env: &Env,
+  result: Result<Unknown<'static>>,
+  settler: Settler,
+  takes_next: bool,
+  response: &Response,
+) -> Result<()> {
+  let value = match result {
+    Ok(value) => value,
+    Err(error) => {
+      // hand the thrown value back to JS to read its properties
+      let thrown = unsafe { ToNapiValue::to_napi_value(env.raw(), error)? };
+      let thrown = unsafe { Unknown::from_raw_unchecked(env.raw(), thrown) };
+      settle_with(
+        &settler,
+        MiddlewareOutcome::Next(Some(HandlerError::from_js(thrown))),
+      );
+      return Ok(());
+    }
+  };
+
+  if !value.is_promise()? {
+    settle_value(&settler, &value, takes_next, response);
+    return Ok(());
+  }
+
+  // only one of the callbacks below runs; both run on the JS thread
+  let on_rejected = settler.clone();
+  let response = response.clone();
+  let promise = PromiseRaw::<Unknown>::new(env.raw(), value.raw());
+  promise
+    .then(move |ctx| {
+      settle_value(&settler, &ctx.value, takes_next, &response);
+      Ok(())
+    })?
+    .catch(move |ctx: CallbackContext<Unknown>| {
+      settle_with(
+        &on_rejected,
+        MiddlewareOutcome::Next(Some(HandlerError::from_js(ctx.value))),
+      );
+      Ok(())
+    })?;
+  Ok(())
+}
@@ -0,0 +1,316 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use askama_escape::escape_html;
+use bytes::Bytes;
+use headers_accept::Accept;
+use hyper::header::{
+  ACCEPT, CONTENT_ENCODING, CONTENT_LANGUAGE, CONTENT_LENGTH, CONTENT_RANGE,
+  CONTENT_SECURITY_POLICY, CONTENT_TYPE, HeaderName, HeaderValue, X_CONTENT_TYPE_OPTIONS,
+};
+use mediatype::{
+  MediaType, Name,
+  names::{APPLICATION, HTML, JSON, TEXT},
+};
+use napi::{Error, Result, Status};
+use serde_json::{Map, Value, json};
+
+use super::call_middleware::{HandlerError, MiddlewareOutcome, call_middleware};
+use super::{AppSettings, ThreadsafeMiddlewareFn};
+use crate::request::Request;
+use crate::response::{Response, problem_document};
+
+const TEXT_HTML: MediaType = MediaType::new(TEXT, HTML);
+const APPLICATION_JSON: MediaType = MediaType::new(APPLICATION, JSON);
+const APPLICATION_PROBLEM_JSON: MediaType =
+  MediaType::from_parts(APPLICATION, Name::new_unchecked("problem"), Some(JSON), &[]);
+
+/// A handler of the errors of one status, registered with
+/// `server.errorPage()`
+#[derive(Clone)]
+pub(super) struct ErrorPage {
+  pub handler: Arc<ThreadsafeMiddlewareFn>,
+  pub takes_next: bool,
+}
+
+/// The format of the error responses of the default final handler
+#[derive(Debug, PartialEq)]
+enum ErrorFormat {
+  Html,
+  Json,
+  /// An RFC 9457 problem details document
+  Problem,
+}
+
+impl ErrorFormat {
+  /// Negotiate the format of an error response from the `Accept` header of a
+  /// request. HTML is preferred, and used when no format is acceptable.
+  fn negotiate(accept: Option<&str>) -> Self {
+    let Some(Ok(accept)) = accept.map(str::parse::<Accept>) else {
+      return ErrorFormat::Html;
+    };
+    match accept.negotiate(&[TEXT_HTML, APPLICATION_JSON, APPLICATION_PROBLEM_JSON]) {
+      Some(media_type) if *media_type == APPLICATION_JSON => ErrorFormat::Json,
+      Some(media_type) if *media_type == APPLICATION_PROBLEM_JSON => ErrorFormat::Problem,
+      _ => ErrorFormat::Html,
+    }
+  }
+}
+
+/// The message shown to the client for `error`. Only the messages of errors
+/// whose `expose` is `true` are shown; other errors show the reason phrase of
+/// their status, but outside of production.
+pub(super) fn client_message(error: &HandlerError, settings: &AppSettings) -> String {
+  let reason = || {
+    error
+      .status
+      .canonical_reason()
+      .unwrap_or("Error")
+      .to_owned()
+  };
+  match error.expose || settings.env != "production" {
+    true => error.message.clone().unwrap_or_else(reason),
+    false => reason(),
+  }
+}
+
+/// The stack trace of `error` and the messages of its causes, shown outside
+/// of production only
+fn client_stack(error: &HandlerError, settings: &AppSettings) -> Option<String> {
+  if settings.env == "production" {
+    return None;
+  }
+  let mut stack = error.stack.clone()?;
+  let mut cause = error
+    .error
+    .as_ref()
+    .and_then(|error| error.cause.as_deref());
+  while let Some(error) = cause {
+    stack.push_str("\n  [cause]: ");
+    stack.push_str(&error.reason);
+    cause = error.cause.as_deref();
+  }
+  Some(stack)
+}
+
+/// The `error` local of the error pages: the `status`, `title` (the reason
+/// phrase of the status) and `message` of the error, the `requestId` of the
+/// request, and the `stack` of the error outside of production
+fn error_locals(error: &HandlerError, request_id: &str, settings: &AppSettings) -> Value {
+  let mut locals = json!({
+    "status": error.status.as_u16(),
+    "title": error.status.canonical_reason().unwrap_or("Error"),
+    "message": client_message(error, settings),
+    "requestId": request_id,
+  });
+  if let Some(stack) = client_stack(error, settings) {
+    locals["stack"] = json!(stack);
+  }
+  locals
+}
+
+/// The HTML page showing error `message`, for the request `request_id`
+pub(super) fn error_page(message: &str, request_id: &str) -> String {
+  let mut escaped = String::with_capacity(message.len());
+  // writing to a `String` cannot fail
+  let _ = escape_html(&mut escaped, message);
+  let mut escaped_id = String::with_capacity(request_id.len());
+  let _ = escape_html(&mut escaped_id, request_id);
+  format!(
+    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Error</title>
+</head>
+<body>
+<pre>{escaped}</pre>
+<p>Request ID: {escaped_id}</p>
+</body>
+</html>
+"#
+  )
+}
+
+/// The final handler: handle the error no error handler handled, or the
+/// `404 Not Found` of the requests no middleware handled, the way Express'
+/// `finalhandler` does it.
+///
+/// The response keeps its headers, but those describing its body, and gets
+/// the status and the `headers` of the error. Then the error page registered
+/// for the status, if any, is called like an error handler, with the error
+/// described by the `error` local. When there is none, or it does not end
+/// the chain, the error is sent by `send_error`.
+pub(super) async fn handle_error(
+  request: &Request,
+  response: &Response,
+  error: HandlerError,
+  settings: &AppSettings,
+  error_page: Option<&ErrorPage>,
+) -> Result<()> {
+  prepare_response(response, &error)?;
+  let Some(error_page) = error_page else {
+    return send_error(request, response, &error, settings);
+  };
+
+  response
+    .locals()
+    .set_value("error", error_locals(&error, &request.id(), settings))?;
+  let outcome = tokio::time::timeout(
+    Duration::from_secs(30),
+    call_middleware(
+      &error_page.handler,
+      error_page.takes_next,
+      request.clone(),
+      response.clone(),
+      Some(error.try_clone()?),
+    ),
+  )
+  .await
+  .map_err(|_| Error::new(Status::GenericFailure, "Error page timeout"))?;
+  match outcome? {
+    MiddlewareOutcome::Done => Ok(()),
+    MiddlewareOutcome::Next(Some(page_error)) => {
+      if let Some(page_error) = &page_error.error {
+        log::debug!(
+          "Request ID: {} | Error page failed: {page_error}",
+          request.id()
+        );
+      }
+      send_error(request, response, &error, settings)
+    }
+    _ => send_error(request, response, &error, settings),
+  }
+}
+
+/// Give the response the status and the headers of `error`, and remove the
+/// headers describing its body
+fn prepare_response(response: &Response, error: &HandlerError) -> Result<()> {
+  response.with_inner(|response| {
+    let inner = response.inner()?;
+    *inner.status_mut() = error.status;
+
+    let headers = inner.headers_mut();
+    for name in [
+      CONTENT_ENCODING,
+      CONTENT_LANGUAGE,
+      CONTENT_LENGTH,
+      CONTENT_RANGE,
+    ] {
+      headers.remove(name);
+    }
+    for (name, value) in &error.headers {
+      if let (Ok(name), Ok(value)) = (
+        HeaderName::from_bytes(name.as_bytes()),
+        HeaderValue::from_str(value),
+      ) {
+        headers.insert(name, value);
+      }
+    }
+    Ok(())
+  })
+}
+
+/// The default error response: the `client_message` of the error, and its
+/// stack trace outside of production, as an HTML page, JSON, or a problem
+/// details document, negotiated from the `Accept` header of the request.
+/// Problems get the `properties` of exposed errors as extension members.
+/// All formats give the ID of the request.
+fn send_error(
+  request: &Request,
+  response: &Response,
+  error: &HandlerError,
+  settings: &AppSettings,
+) -> Result<()> {
+  let accept = request.with_inner(|request| {
+    Ok(
+      request
+        .inner()?
+        .headers()
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map(str::to_owned),
+    )
+  })?;
+  let request_id = request.id();
+  let message = client_message(error, settings);
+  let stack = client_stack(error, settings);
+
+  // the `problem details` setting sends HTTP errors as problems to all
+  let negotiated = !(settings.problem_details && error.http_error);
+  let format = match negotiated {
+    true => ErrorFormat::negotiate(accept.as_deref()),
+    false => ErrorFormat::Problem,
+  };
+
+  let (content_type, body) = match format {
+    ErrorFormat::Html => (
+      "text/html; charset=utf-8",
+      error_page(stack.as_deref().unwrap_or(&message), &request_id),
+    ),
+    ErrorFormat::Json => {
+      let mut body = json!({
+        "status": error.status.as_u16(),
+        "message": message,
+        "requestId": request_id,
+      });
+      if let Some(stack) = stack {
+        body["stack"] = json!(stack);
+      }
+      ("application/json; charset=utf-8", body.to_string())
+    }
+    ErrorFormat::Problem => {
+      let mut document = Map::new();
+      document.insert("type".to_owned(), json!("about:blank"));
+      document.insert("detail".to_owned(), json!(message));
+      document.insert("requestId".to_owned(), json!(request_id));
+      // the extra properties of exposed errors are extension members
+      if let (true, Some(properties)) = (error.expose, &error.properties) {
+        document.extend(properties.clone());
+      }
+      if let Some(stack) = stack {
+        document.insert("stack".to_owned(), json!(stack));
+      }
+      let document = Value::Object(problem_document(error.status, document));
+      ("application/problem+json", document.to_string())
+    }
+  };
+
+  response.with_inner(|response| {
+    if negotiated {
+      response.vary("Accept".to_owned())?;
+    }
+    let headers = response.inner()?.headers_mut();
+    headers.insert(
+      CONTENT_SECURITY_POLICY,
+      HeaderValue::from_static("default-src 'none'"),
+    );
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response.end(Some(Bytes::from(body)))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_negotiate() {
+    assert_eq!(ErrorFormat::negotiate(None), ErrorFormat::Html);
+    assert_eq!(ErrorFormat::negotiate(Some("*/*")), ErrorFormat::Html);
+    assert_eq!(
+      ErrorFormat::negotiate(Some("application/json")),
+      ErrorFormat::Json
+    );
+    assert_eq!(
+      ErrorFormat::negotiate(Some("application/problem+json, application/json;q=0.9")),
+      ErrorFormat::Problem
+    );
+    assert_eq!(
+      ErrorFormat::negotiate(Some("text/html;q=0.5, application/*")),
+      ErrorFormat::Json
+    );
+    assert_eq!(ErrorFormat::negotiate(Some("image/png")), ErrorFormat::Html);
+  }
+}
@@ -1,30 +1,30 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::sync::Arc;
 
 use headers_core::HeaderValue;
 use hyper::StatusCode;
-use hyper::header::{CONTENT_SECURITY_POLICY, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS};
+use hyper::header::{
+  AUTHORIZATION, CONTENT_SECURITY_POLICY, CONTENT_TYPE, COOKIE, HeaderMap, HeaderName,
+  PROXY_AUTHORIZATION, X_CONTENT_TYPE_OPTIONS,
+};
 use hyper::{Request as HyperRequest, Response as HyperResponse, body::Incoming as IncomingBody};
 use matchit::Router;
-use napi::Either;
 
-use super::get_next_id::get_next_id;
-use crate::request::{Request, WrappedRequest};
+use super::call_middleware::{HandlerError, MiddlewareOutcome, call_middleware};
+use super::final_handler::{ErrorPage, client_message, error_page, handle_error};
+use super::routing::match_route;
+use crate::middlewares::LoggedBody;
+use crate::request::{ConnectionInfo, Request, WrappedRequest};
 use crate::response::{CrateBody, Response};
-use crate::server::MiddlewareMeta;
+use crate::server::{AppSettings, MiddlewareMeta};
 use crate::utilities::full;
 
-fn log_napi_error(mut error: &napi::Error) -> String {
-  let mut error_message = error.to_string();
-  while let Some(cause) = error.cause.as_deref() {
-    error_message.push_str("<br> &nbsp; &nbsp;");
-    error_message.push_str(&cause.to_string());
-    error = cause
-  }
-  error_message
-}
-
-fn create_error_500<T: Display>(e: T) -> HyperResponse<CrateBody> {
+fn create_error_page<T: Display>(
+  status: StatusCode,
+  e: T,
+  request_id: &str,
+) -> HyperResponse<CrateBody> {
   let mut response_builder = HyperResponse::builder();
   if let Some(headers) = response_builder.headers_mut() {
     headers.insert(
@@ -37,52 +37,154 @@ fn create_error_500<T: Display>(e: T) -> HyperResponse<CrateBody> {
       HeaderValue::from_static("text/html; charset=utf-8"),
     );
   };
-  let page_content = format!(
-    r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-<meta charset="utf-8">
-<title>Error</title>
-</head>
-<body>
-<pre>Error: {e}</pre>
-</body>
-</html>
-"#
-  );
+  let page_content = error_page(&e.to_string(), request_id);
   response_builder
-    .status(500)
+    .status(status)
     .body(full(page_content))
     .unwrap()
 }
 
+/// The error page of an internal failure, described by `message`. Like the
+/// messages of unhandled errors, `message` is not shown in production.
+fn internal_error_page(
+  message: String,
+  request_id: &str,
+  settings: &AppSettings,
+) -> HyperResponse<CrateBody> {
+  let status = StatusCode::INTERNAL_SERVER_ERROR;
+  let message = client_message(&HandlerError::from_status(status, message), settings);
+  create_error_page(status, message, request_id)
+}
+
+/// The headers of a request as logged, without the values of the headers
+/// holding credentials
+fn redacted_headers(headers: &HeaderMap) -> Vec<(&str, &str)> {
+  headers
+    .iter()
+    .map(|(name, value)| match *name {
+      AUTHORIZATION | COOKIE | PROXY_AUTHORIZATION => (name.as_str(), "[redacted]"),
+      _ => (name.as_str(), value.to_str().unwrap_or("[binary]")),
+    })
+    .collect()
+}
+
 pub(super) async fn handle_http_request(
   req: HyperRequest<IncomingBody>,
   router: Arc<Router<String>>,
   middlewares: Arc<Vec<MiddlewareMeta>>,
+  error_pages: Arc<HashMap<u16, ErrorPage>>,
+  settings: Arc<AppSettings>,
+  connection: ConnectionInfo,
 ) -> std::result::Result<HyperResponse<CrateBody>, Box<dyn std::error::Error + Sync + Send>> {
-  let request_id = get_next_id();
-  log::debug!("Generated request_id={request_id}.");
-
-  log::debug!("--- Handling new HTTP request ---");
   let request_method = req.method().to_owned();
   let request_uri = req.uri().to_owned();
   let request_version = req.version();
+
+  let body_request: WrappedRequest = req.into();
+  let request = Request::new(body_request, settings.clone(), connection);
+  let request_id = request.id();
+  log::debug!("Request ID: {request_id} | --- Handling new HTTP request ---");
   log::debug!(
     "Request ID: {request_id} | Method: {:?}, URI: {:?}, Version: {:?}",
     request_method,
     request_uri,
     request_version
   );
-  log::debug!("Headers: {:?}", req.headers());
+  let _ = request.with_inner(|w_req| {
+    log::debug!(
+      "Request ID: {request_id} | Headers: {:?}",
+      redacted_headers(w_req.inner()?.headers())
+    );
+    Ok(())
+  });
 
-  let body_request: WrappedRequest = req.into();
-  let request = Request::from(body_request);
-  let response = Response::new(request.clone(), None);
+  let response = Response::new(request.clone(), settings.clone(), None);
+  let _ = response.with_inner(|response| {
+    let headers = response.inner()?.headers_mut();
+    if settings.x_powered_by {
+      headers.insert(
+        HeaderName::from_static("x-powered-by"),
+        HeaderValue::from_static("hyperjs"),
+      );
+    }
+    // the ID is echoed for the client and the proxies to correlate logs
+    if let Some(header) = &settings.request_id_header
+      && let Ok(value) = HeaderValue::from_str(&request_id)
+    {
+      headers.insert(header, value);
+    }
+    Ok(())
+  });
+
+  let direct_response = run_middlewares(
+    &request,
+    &response,
+    &router,
+    &middlewares,
+    &error_pages,
+    &settings,
+  )
+  .await;
+
+  // the JS functions of the access logs run before the response is taken
+  let mut access_logs = Vec::new();
+  let added_logs = response
+    .with_inner(|response| Ok(response.take_access_logs()))
+    .unwrap_or_default();
+  for mut access_log in added_logs {
+    if access_log.prepare(&request, &response).await {
+      access_logs.push(access_log);
+    }
+  }
+
+  let resp = match direct_response {
+    Some(resp) => resp,
+    None => finish_response(&response, &request_id, &settings),
+  };
+  // the logs are written once the body is sent
+  Ok(LoggedBody::wrap(resp, access_logs))
+}
+
+/// Run the middlewares matching the request, then the final handler.
+/// Returns the response to send instead of `response`, if any, when the
+/// middlewares could not be run.
+async fn run_middlewares(
+  request: &Request,
+  response: &Response,
+  router: &Router<String>,
+  middlewares: &[MiddlewareMeta],
+  error_pages: &HashMap<u16, ErrorPage>,
+  settings: &AppSettings,
+) -> Option<HyperResponse<CrateBody>> {
+  let request_id = request.id();
+  let (request_method, request_uri) = match request.with_inner(|w_req| {
+    let request = w_req.inner()?;
+    Ok((request.method().to_owned(), request.uri().to_owned()))
+  }) {
+    Ok(request_line) => request_line,
+    Err(e) => {
+      let err_msg = format!("Error getting request's method and URI: {e}");
+      log::debug!("Request ID: {request_id} | {err_msg}.");
+      return Some(internal_error_page(err_msg, &request_id, settings));
+    }
+  };
+
+  // the error passed on to the error handlers, if any
+  let mut error: Option<HandlerError> = None;
+  // the layer skipped by `next('route')`
+  let mut skipped_layer = None;
+  // whether a middleware ended the chain
+  let mut done = false;
+
+  for middleware in middlewares {
+    // error handlers only run to handle an error, and other middlewares only
+    // when there is none
+    if middleware.error_handler != error.is_some() || skipped_layer == Some(middleware.layer) {
+      continue;
+    }
 
-  for middleware in middlewares.as_ref() {
     log::debug!(
-      "Looping through middlewares ({}, {}) ...",
+      "Request ID: {request_id} | Looping through middlewares ({}, {}) ...",
       middleware
         .method
         .as_ref()
@@ -96,33 +198,26 @@ pub(super) async fn handle_http_request(
     // 2. if request & middleware's routes match, save extracted params
     //    in request
     if let Some(middleware_route) = middleware.route.as_ref() {
-      let request_uri_string = request_uri.to_string();
-      match router.at(&request_uri_string) {
-        Ok(router_match) => {
-          let params = router_match.params;
-          let request_route = router_match.value;
+      // routes match the path, whatever the query string
+      match match_route(router, request_uri.path(), settings) {
+        Some((request_route, params)) => {
           match request_route == middleware_route {
             true => {
               // TODO: Avoid setting params if already set e.g if a route has
               // more than one middleware registered for it.
               if let Err(e) = request.with_inner_mut(|w_req| {
-                w_req.set_params(params.iter());
+                w_req.set_params(params.into_iter());
                 Ok(())
               }) {
                 let err_msg = format!("Error setting request parameters: {e}");
                 log::debug!("Request ID: {request_id} | {err_msg}.");
-                return Ok(
-                  HyperResponse::builder()
-                    .status(500)
-                    .body(full(err_msg))
-                    .unwrap(),
-                );
+                return Some(internal_error_page(err_msg, &request_id, settings));
               };
             }
             false => continue,
           }
         }
-        Err(_) => continue,
+        None => continue,
       };
     }
 
@@ -136,12 +231,7 @@ pub(super) async fn handle_http_request(
         Err(e) => {
           let err_msg = format!("Error getting request's method: {e}");
           log::debug!("Request ID: {request_id} | {err_msg}.");
-          return Ok(
-            HyperResponse::builder()
-              .status(500)
-              .body(full(err_msg))
-              .unwrap(),
-          );
+          return Some(internal_error_page(err_msg, &request_id, settings));
         }
       };
       if request_method != middleware_method {
@@ -150,87 +240,126 @@ pub(super) async fn handle_http_request(
     }
 
     log::debug!("Request ID: {request_id} | Calling JS middleware.");
-    let middleware_response = match middleware
-      .handler
-      .call_async((request.clone(), response.clone()).into())
-      .await
-    {
-      Ok(response) => response,
+    log::debug!("Request ID: {request_id} | Waiting for JS middleware (30s timeout)");
+
+    let given_error = match error.as_ref().map(|e| e.try_clone()).transpose() {
+      Ok(given_error) => given_error,
       Err(e) => {
+        let err_msg = format!("Failed to pass the error to the error handler: {e}");
+        log::debug!("Request ID: {request_id} | {err_msg}.");
+        return Some(internal_error_page(err_msg, &request_id, settings));
+      }
+    };
+    let middleware_outcome = match tokio::time::timeout(
+      std::time::Duration::from_secs(30),
+      call_middleware(
+        &middleware.handler,
+        middleware.takes_next,
+        request.clone(),
+        response.clone(),
+        given_error,
+      ),
+    )
+    .await
+    {
+      Ok(Ok(outcome)) => outcome,
+      Ok(Err(e)) => {
         log::debug!("Request ID: {request_id} | JS middleware invocation failed.");
         let err_msg = format!("Failed to invoke middleware: {e}.");
-        return Ok(
+        return Some(internal_error_page(err_msg, &request_id, settings));
+      }
+      Err(e) => {
+        log::debug!("Request ID: {request_id} | JS middleware timeout.");
+        log::debug!("Request ID: {request_id} | {e}");
+
+        return Some(
           HyperResponse::builder()
-            .status(500)
-            .body(full(err_msg))
+            .status(504)
+            .body(full("Middleware timeout"))
             .unwrap(),
         );
       }
     };
 
-    log::debug!("Request ID: {request_id} | JS middleware called successfully.");
-
-    log::debug!("Request ID: {request_id} | Waiting for JS middleware (30s timeout)");
-
-    let middleware_execution_result = match middleware_response {
-      Either::A(continue_flag) => continue_flag,
-      Either::B(promise) => {
-        match tokio::time::timeout(std::time::Duration::from_secs(30), promise).await {
-          Ok(Ok(continue_flag)) => continue_flag,
-          Ok(Err(e)) => {
-            log::debug!("Request ID: {request_id} | Middleware execution failed.",);
-            log::debug!("Request ID: {request_id} | {e}");
-            return Ok(create_error_500(log_napi_error(&e)));
-          }
-          Err(e) => {
-            log::debug!("Request ID: {request_id} | JS middleware timeout.");
-            log::debug!("Request ID: {request_id} | {e}");
-
-            return Ok(
-              HyperResponse::builder()
-                .status(504)
-                .body(full("Middleware timeout"))
-                .unwrap(),
-            );
-          }
+    match middleware_outcome {
+      // error handlers returning `true` pass their error on
+      MiddlewareOutcome::Continue => {}
+      MiddlewareOutcome::Next(next_error) => {
+        if let Some(HandlerError {
+          error: Some(error), ..
+        }) = &next_error
+        {
+          log::debug!("Request ID: {request_id} | Middleware execution failed.");
+          log::debug!("Request ID: {request_id} | {error}");
         }
+        error = next_error;
       }
-    };
+      MiddlewareOutcome::NextRoute => {
+        error = None;
+        skipped_layer = Some(middleware.layer);
+      }
+      MiddlewareOutcome::Done => {
+        error = None;
+        done = true;
+        break;
+      }
+    }
+  }
 
-    log::debug!("Middleware execution result: {middleware_execution_result:?}");
+  // requests no middleware ended, nor responded to, are not found
+  let ended = response
+    .with_inner(|response| Ok(response.is_ended()))
+    .unwrap_or(true);
+  if error.is_none() && !done && !ended {
+    error = Some(HandlerError::from_status(
+      StatusCode::NOT_FOUND,
+      format!("Cannot {request_method} {}", request_uri.path()),
+    ));
+  }
 
-    match middleware_execution_result {
-      Either::A(should_continue) => match should_continue {
-        true => {}
-        false => break,
-      },
-      Either::B(_) => break,
+  if let Some(error) = error {
+    log::debug!("Request ID: {request_id} | Handling the unhandled error.");
+    let error_page = error_pages.get(&error.status.as_u16());
+    let message = client_message(&error, settings);
+    let status = error.status;
+    if let Err(e) = handle_error(request, response, error, settings, error_page).await {
+      log::debug!("Request ID: {request_id} | {e}");
+      return Some(create_error_page(status, message, &request_id));
     }
   }
 
+  None
+}
+
+/// Take the complete `response`, to be sent to the client
+fn finish_response(
+  response: &Response,
+  request_id: &str,
+  settings: &AppSettings,
+) -> HyperResponse<CrateBody> {
   log::debug!("Request ID: {request_id} | Received response from JS");
 
-  let resp = response;
-  let status_code =
-    match resp.with_inner(|response| napi::Result::<StatusCode>::Ok(response.inner()?.status())) {
-      Ok(status_code) => status_code,
-      Err(e) => {
-        log::debug!("Request ID: {request_id} | Inner response acquisition failed.");
-        let err_msg = format!("Failed to acquire the wrapped response: {e}.");
-        return Ok(
-          HyperResponse::builder()
-            .status(500)
-            .body(full(err_msg))
-            .unwrap(),
-        );
-      }
-    };
+  let status_code = match response
+    .with_inner(|response| napi::Result::<StatusCode>::Ok(response.inner()?.status()))
+  {
+    Ok(status_code) => status_code,
+    Err(e) => {
+      log::debug!("Request ID: {request_id} | Inner response acquisition failed.");
+      let err_msg = format!("Failed to acquire the wrapped response: {e}.");
+      return internal_error_page(err_msg, request_id, settings);
+    }
+  };
   log::debug!(
     "Request ID: {request_id} | Responding with status={}.",
     status_code
   );
 
-  let resp = resp.with_inner(|r| r.take()).unwrap();
-
-  Ok(resp)
+  match response.with_inner(|r| r.finish()) {
+    Ok(response) => response,
+    Err(e) => {
+      log::debug!("Request ID: {request_id} | Response finishing failed.");
+      let err_msg = format!("Failed to finish the response: {e}.");
+      internal_error_page(err_msg, request_id, settings)
+    }
+  }
 }
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use hyper::StatusCode as LibStatusCode;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::{Map, Value};
+
+use crate::response::status_code::StatusCode;
+use crate::utilities;
+
+/// An error with an HTTP status, like the ones of the `http-errors` package.
+/// When a handler throws one, or passes one to `next()`, the final handler
+/// responds with its status and headers, and shows its message only when it
+/// is exposed.
+///
+/// ```javascript
+/// server.get('/users/{id}', (req, res) => {
+///   const user = users.get(req.params.id)
+///   if (!user) {
+///     throw new HttpError(404, 'No such user')
+///   }
+///   res.json(user)
+/// })
+/// ```
+#[napi]
+pub struct HttpError {
+  status: LibStatusCode,
+  message: String,
+  expose: bool,
+  headers: Option<HashMap<String, String>>,
+  properties: Map<String, Value>,
+  stack: Option<String>,
+}
+
+impl HttpError {
+  /// An error of `status`, with the default `expose`, created by the server
+  /// rather than thrown by JS code, e.g. the `404 Not Found` error of the
+  /// requests no middleware handled
+  pub fn from_status(status: LibStatusCode, message: String) -> Self {
+    Self {
+      status,
+      message,
+      expose: status.is_client_error(),
+      headers: None,
+      properties: Map::new(),
+      stack: None,
+    }
+  }
+}
+
+#[napi]
+impl HttpError {
+  /// Create an error of `status` (`500` by default), a 4xx or 5xx status.
+  /// `message` defaults to the reason phrase of the status.
+  ///
+  /// `properties` are extra properties of the error. Among them, `expose`
+  /// tells whether the message may be shown to the client, which by default
+  /// it may for 4xx errors only, and `headers` are set on the response.
+  #[napi(
+    constructor,
+    ts_args_type = "status?: number | StatusCode, message?: string, properties?: Record<string, unknown>"
+  )]
+  pub fn new(
+    env: Env,
+    status: Option<Either<u16, &StatusCode>>,
+    message: Option<String>,
+    properties: Option<Object>,
+  ) -> Result<Self> {
+    let status = match status {
+      None => LibStatusCode::INTERNAL_SERVER_ERROR,
+      Some(Either::A(status)) => LibStatusCode::from_u16(status)
+        .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?,
+      Some(Either::B(status)) => status.into(),
+    };
+    if !status.is_client_error() && !status.is_server_error() {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("HttpError status must be a 4xx or 5xx status, got {status}"),
+      ));
+    }
+
+    let message =
+      message.unwrap_or_else(|| status.canonical_reason().unwrap_or_default().to_owned());
+    let mut error = Self::from_status(status, message);
+    error.stack = capture_stack(&env, &error.name(), &error.message)?;
+    if let Some(properties) = properties {
+      for name in Object::keys(&properties)? {
+        let value = properties.get_named_property::<Unknown>(&name)?;
+        match name.as_str() {
+          "expose" => error.expose = value.coerce_to_bool()?,
+          "headers" => error.headers = Some(string_record(value)?),
+          _ => {
+            error
+              .properties
+              .insert(name, utilities::to_json_value(&env, value)?);
+          }
+        }
+      }
+    }
+    Ok(error)
+  }
+
+  /// The status of the error
+  #[napi(getter)]
+  pub fn status(&self) -> u16 {
+    self.status.as_u16()
+  }
+
+  /// The status of the error, an alias of `status`
+  #[napi(getter)]
+  pub fn status_code(&self) -> u16 {
+    self.status.as_u16()
+  }
+
+  #[napi(getter)]
+  pub fn message(&self) -> String {
+    self.message.to_owned()
+  }
+
+  /// The name of the error, from the reason phrase of its status, e.g.
+  /// `NotFoundError`
+  #[napi(getter)]
+  pub fn name(&self) -> String {
+    error_name(self.status)
+  }
+
+  /// Whether the message may be shown to the client
+  #[napi(getter)]
+  pub fn expose(&self) -> bool {
+    self.expose
+  }
+
+  /// Headers to set on the response
+  #[napi(getter)]
+  pub fn headers(&self) -> Option<HashMap<String, String>> {
+    self.headers.to_owned()
+  }
+
+  /// The extra properties the error was created with
+  #[napi(getter, ts_return_type = "Record<string, unknown>")]
+  pub fn properties(&self) -> Value {
+    Value::Object(self.properties.to_owned())
+  }
+
+  /// The stack trace of the code creating the error, like the one of an
+  /// `Error`
+  #[napi(getter)]
+  pub fn stack(&self) -> Option<String> {
+    self.stack.to_owned()
+  }
+
+  #[napi(js_name = "toString")]
+  pub fn as_js_string(&self) -> String {
+    format!("{}: {}", self.name(), self.message)
+  }
+}
+
+/// The stack trace of the JS code calling into the addon, headed by `name`
+/// and `message` like the stack of an `Error`
+fn capture_stack(env: &Env, name: &str, message: &str) -> Result<Option<String>> {
+  let error_constructor: Function<String, Unknown> =
+    env.get_global()?.get_named_property("Error")?;
+  let error = error_constructor.new_instance(message.to_owned())?;
+  let stack = error
+    .coerce_to_object()?
+    .get_named_property::<Unknown>("stack")?;
+  if stack.get_type()? != ValueType::String {
+    return Ok(None);
+  }
+  let stack = stack.coerce_to_string()?.into_utf8()?.as_str()?.to_owned();
+  Ok(Some(match stack.strip_prefix("Error") {
+    Some(stack) => format!("{name}{stack}"),
+    None => stack,
+  }))
+}
+
+/// An object of strings, e.g. `{ 'Retry-After': '120' }`
+fn string_record(value: Unknown) -> Result<HashMap<String, String>> {
+  if value.get_type()? != ValueType::Object {
+    return Err(Error::new(
+      Status::InvalidArg,
+      "HttpError headers must be an object",
+    ));
+  }
+  let object: Object = unsafe { value.cast()? };
+  Object::keys(&object)?
+    .into_iter()
+    .map(|name| {
+      let value = object.get_named_property::<Unknown>(&name)?;
+      let value = value.coerce_to_string()?.into_utf8()?.as_str()?.to_owned();
+      Ok((name, value))
+    })
+    .collect()
+}
+
+/// The name of the errors of `status` in `http-errors`: the words of the
+/// reason phrase, capitalized and joined, ending with `Error`
+fn error_name(status: LibStatusCode) -> String {
+  let Some(reason) = status.canonical_reason() else {
+    return "HttpError".to_owned();
+  };
+  let mut name = reason
+    .split_whitespace()
+    .flat_map(|word| {
+      let mut chars = word.chars();
+      chars
+        .next()
+        .map(|first| first.to_ascii_uppercase())
+        .into_iter()
+        .chain(chars)
+    })
+    .filter(char::is_ascii_alphanumeric)
+    .collect::<String>();
+  if !name.ends_with("Error") {
+    name.push_str("Error");
+  }
+  name
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_error_name() {
+    assert_eq!(error_name(LibStatusCode::NOT_FOUND), "NotFoundError");
+    assert_eq!(error_name(LibStatusCode::IM_A_TEAPOT), "ImATeapotError");
+    assert_eq!(
+      error_name(LibStatusCode::INTERNAL_SERVER_ERROR),
+      "InternalServerError"
+    );
+    assert_eq!(
+      error_name(LibStatusCode::from_u16(599).unwrap()),
+      "HttpError"
+    );
+  }
+}
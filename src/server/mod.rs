@@ -1,9 +1,18 @@
+mod application;
+mod call_middleware;
+mod final_handler;
 mod get_next_id;
 mod handle_http_request;
+mod http_error;
+mod request_id;
+mod routing;
+mod settings;
+mod trust_proxy;
 
 use env_logger::Builder as EnvLoggerBuilder;
 use futures::prelude::*;
 use hyper::Method as LibMethod;
+use hyper::StatusCode as LibStatusCode;
 use hyper::{server::conn::http1, service::service_fn};
 use hyper_util::rt::tokio::{TokioIo, TokioTimer};
 use log::LevelFilter;
@@ -13,13 +22,26 @@ use napi::threadsafe_function::{ThreadsafeCallContext, ThreadsafeFunction};
 use napi_derive::napi;
 use rustls_acme::AcmeConfig;
 use rustls_acme::caches::DirCache;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio_stream::wrappers::TcpListenerStream;
 
-use crate::request::Request;
+use crate::locals::Locals;
+use crate::request::{ConnectionInfo, Request};
 use crate::response::Response;
+use crate::view::{JsEngineFn, ViewEngine};
+pub use application::Application;
+use call_middleware::{HandlerArgs, MiddlewareCall, handler_args};
+use final_handler::ErrorPage;
 use handle_http_request::handle_http_request;
+pub use http_error::HttpError;
+pub use request_id::{RequestIdGenerator, is_valid_request_id};
+pub use settings::{
+  AppSettings, EtagFnRef, EtagSetting, JsonReplacer, JsonReplacerFnRef, QueryParser,
+  QueryParserFnRef,
+};
+pub use trust_proxy::{Subnet, TrustProxy};
 
 // Global state for pending requests
 lazy_static::lazy_static! {
@@ -29,16 +51,15 @@ lazy_static::lazy_static! {
 type JsHandlerFn<'a> =
   Function<'a, FnArgs<(Request, Response)>, Either<Either<bool, ()>, Promise<Either<bool, ()>>>>;
 
-type ThreadsafeMiddlewareFn = ThreadsafeFunction<
-  FnArgs<(Request, Response)>,
+type JsErrorHandlerFn<'a> = Function<
+  'a,
+  FnArgs<(Unknown<'static>, Request, Response)>,
   Either<Either<bool, ()>, Promise<Either<bool, ()>>>,
-  FnArgs<(Request, Response)>,
-  Status,
-  false,
-  false,
-  0,
 >;
 
+type ThreadsafeMiddlewareFn =
+  ThreadsafeFunction<MiddlewareCall, Unknown<'static>, HandlerArgs, Status, false, false, 0>;
+
 #[derive(Clone)]
 pub struct MiddlewareMeta {
   /// The string used to register the middleware in the router.
@@ -54,8 +75,24 @@ pub struct MiddlewareMeta {
   /// Returns:
   ///   true => run the next middleware
   ///   _ => don't run the next middleware
+  ///
+  /// unless it takes `next`, see `takes_next`
   handler: Arc<ThreadsafeMiddlewareFn>,
 
+  /// Whether `handler` declares the `next` parameter, `(req, res, next)` or
+  /// `(err, req, res, next)`. If so, it runs the next middleware by calling
+  /// `next()` and its return value is ignored.
+  takes_next: bool,
+
+  /// Whether `handler` is an error handler, registered with
+  /// `server.useError()`. Error handlers only run when a previous middleware
+  /// failed, and other middlewares only run when none did.
+  error_handler: bool,
+
+  /// The registration the middleware belongs to: the handlers given together
+  /// to a route method share it, and are skipped by `next('route')`
+  layer: usize,
+
   /// The HTTP method to match from the request.
   ///
   /// If Some, associated function (`handler`) is only executed if Request's
@@ -77,6 +114,40 @@ pub struct Server {
   middlewares: Vec<MiddlewareMeta>,
   router: Router<String>,
   acme_config_meta: Option<AcmeConfigMeta>,
+  settings: Arc<AppSettings>,
+  error_pages: HashMap<u16, ErrorPage>,
+}
+
+/// Install the logger of the process, once, and set its level to `level`
+/// unless the `RUST_LOG` environment variable sets it
+fn init_logger(level: LevelFilter) {
+  let from_env = std::env::var_os("RUST_LOG").is_some();
+  let mut builder = EnvLoggerBuilder::new();
+  builder.filter_level(LevelFilter::max()).parse_default_env();
+  if builder.try_init().is_err() {
+    log::debug!("Logger already installed");
+  }
+  if !from_env {
+    log::set_max_level(level);
+  }
+}
+
+/// Build the threadsafe function used to call a middleware `handler`, and
+/// tell whether it takes `next`: more than `params` parameters. Its return
+/// value is taken as is, to be inspected on the JS thread by
+/// `call_middleware`.
+fn build_middleware_tsfn<Args: JsValuesTupleIntoVec, Return>(
+  handler: Function<Args, Return>,
+  params: u32,
+) -> Result<(ThreadsafeMiddlewareFn, bool)> {
+  let length: u32 = handler.coerce_to_object()?.get_named_property("length")?;
+  let value = handler.value();
+  let handler =
+    unsafe { Function::<HandlerArgs, Unknown<'static>>::from_napi_value(value.env, value.value)? };
+  let tsfn = handler.build_threadsafe_function().build_callback(
+    |ctx: ThreadsafeCallContext<MiddlewareCall>| handler_args(&ctx.env, ctx.value),
+  )?;
+  Ok((tsfn, length > params))
 }
 
 impl Server {
@@ -86,12 +157,13 @@ impl Server {
     handler: JsHandlerFn,
     _env: Env,
   ) -> Result<()> {
-    let tsfn = handler
-      .build_threadsafe_function()
-      .build_callback(|ctx: ThreadsafeCallContext<FnArgs<(Request, Response)>>| Ok(ctx.value))?;
+    let (tsfn, takes_next) = build_middleware_tsfn(handler, 2)?;
     self.middlewares.push(MiddlewareMeta {
       route,
       handler: Arc::new(tsfn),
+      takes_next,
+      error_handler: false,
+      layer: self.next_layer(),
       method: None,
     });
     Ok(())
@@ -100,25 +172,40 @@ impl Server {
   fn register_route(
     &mut self,
     route: String,
-    handler: JsHandlerFn,
+    handlers: Either<JsHandlerFn, Vec<JsHandlerFn>>,
     method: LibMethod,
   ) -> Result<()> {
-    let tsfn = handler
-      .build_threadsafe_function()
-      .build_callback(|ctx: ThreadsafeCallContext<FnArgs<(Request, Response)>>| Ok(ctx.value))?;
     if let Err(e) = self.router.insert(route.to_owned(), route.to_owned()) {
       match e {
         InsertError::Conflict { .. } => {}
         _ => return Err(Error::new(Status::GenericFailure, e.to_string())),
       }
     }
-    self.middlewares.push(MiddlewareMeta {
-      route: Some(route),
-      handler: Arc::new(tsfn),
-      method: Some(method),
-    });
+    let handlers = match handlers {
+      Either::A(handler) => vec![handler],
+      Either::B(handlers) => handlers,
+    };
+    let layer = self.next_layer();
+    for handler in handlers {
+      let (tsfn, takes_next) = build_middleware_tsfn(handler, 2)?;
+      self.middlewares.push(MiddlewareMeta {
+        route: Some(route.to_owned()),
+        handler: Arc::new(tsfn),
+        takes_next,
+        error_handler: false,
+        layer,
+        method: Some(method.to_owned()),
+      });
+    }
     Ok(())
   }
+
+  fn next_layer(&self) -> usize {
+    self
+      .middlewares
+      .last()
+      .map_or(0, |middleware| middleware.layer + 1)
+  }
 }
 
 #[napi]
@@ -129,27 +216,75 @@ impl Server {
     Ok(Self {
       middlewares: Vec::new(),
       router: Router::new(),
+      error_pages: HashMap::new(),
       acme_config_meta: None,
+      settings: Arc::default(),
     })
   }
 
-  #[napi]
-  pub fn delete(&mut self, route: String, handler: JsHandlerFn) -> Result<()> {
+  #[napi(ts_args_type = "route: string, handler: JsHandlerFn | JsHandlerFn[]")]
+  pub fn delete(
+    &mut self,
+    route: String,
+    handler: Either<JsHandlerFn, Vec<JsHandlerFn>>,
+  ) -> Result<()> {
     self.register_route(route, handler, LibMethod::DELETE)
   }
 
-  #[napi]
-  pub fn get(&mut self, route: String, handler: JsHandlerFn) -> Result<()> {
-    self.register_route(route, handler, LibMethod::GET)
+  /// Route GET requests to `handler`, or to an array of handlers run in
+  /// order. Handlers taking `next`, `(req, res, next)`, run the next one by
+  /// calling `next()`, skip the remaining handlers of the route with
+  /// `next('route')`, and pass an error to the error handlers with
+  /// `next(err)`. Other handlers run the next one by returning `true`.
+  ///
+  /// Without `handler`, returns the value of setting `route` instead,
+  /// `undefined` when it is not set.
+  ///
+  /// ```javascript
+  /// server.get('/', (req, res) => res.send('hello'))
+  /// server.get('/users/{id}', [
+  ///   (req, res, next) => next(req.params.id === '0' ? 'route' : undefined),
+  ///   (req, res) => res.send(`user ${req.params.id}`),
+  /// ])
+  /// server.get('json spaces')
+  /// // => 2
+  /// ```
+  #[napi(
+    ts_args_type = "route: string, handler?: JsHandlerFn | JsHandlerFn[]",
+    ts_return_type = "unknown"
+  )]
+  pub fn get<'env>(
+    &mut self,
+    env: &'env Env,
+    route: String,
+    handler: Option<Either<JsHandlerFn, Vec<JsHandlerFn>>>,
+  ) -> Result<Either<Unknown<'env>, Undefined>> {
+    match handler {
+      Some(handler) => self
+        .register_route(route, handler, LibMethod::GET)
+        .map(Either::B),
+      None => Ok(match self.settings.get(env, &route)? {
+        Some(value) => Either::A(value),
+        None => Either::B(()),
+      }),
+    }
   }
 
-  #[napi]
-  pub fn post(&mut self, route: String, handler: JsHandlerFn) -> Result<()> {
+  #[napi(ts_args_type = "route: string, handler: JsHandlerFn | JsHandlerFn[]")]
+  pub fn post(
+    &mut self,
+    route: String,
+    handler: Either<JsHandlerFn, Vec<JsHandlerFn>>,
+  ) -> Result<()> {
     self.register_route(route, handler, LibMethod::POST)
   }
 
-  #[napi]
-  pub fn put(&mut self, route: String, handler: JsHandlerFn) -> Result<()> {
+  #[napi(ts_args_type = "route: string, handler: JsHandlerFn | JsHandlerFn[]")]
+  pub fn put(
+    &mut self,
+    route: String,
+    handler: Either<JsHandlerFn, Vec<JsHandlerFn>>,
+  ) -> Result<()> {
     self.register_route(route, handler, LibMethod::PUT)
   }
 
@@ -158,20 +293,257 @@ impl Server {
     self.register_middleware(route, middleware, env)
   }
 
+  /// Register an error handler, called as `handler(err, req, res, next)`
+  /// when a previous middleware threw `err`, its promise rejected with it, or
+  /// it called `next(err)`.
+  ///
+  /// The handler ends the chain, unless it calls `next()` to resume with the
+  /// next middleware or `next(err)` to pass an error to the next error
+  /// handler. Handlers that do not take `next` pass the error on by returning
+  /// `true`. Errors left unhandled are sent by the final handler, with the
+  /// `status` (or `statusCode`) and the `headers` of the error, see
+  /// `server.errorPage()`.
+  ///
+  /// ```javascript
+  /// server.useError((err, req, res) => {
+  ///   res.status(err.status ?? 500).json({ error: err.message })
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = "handler: (err: any, req: Request, res: Response, next: (err?: unknown) => void) => unknown"
+  )]
+  pub fn use_error(&mut self, handler: JsErrorHandlerFn) -> Result<()> {
+    let (tsfn, takes_next) = build_middleware_tsfn(handler, 3)?;
+    self.middlewares.push(MiddlewareMeta {
+      route: None,
+      handler: Arc::new(tsfn),
+      takes_next,
+      error_handler: true,
+      layer: self.next_layer(),
+      method: None,
+    });
+    Ok(())
+  }
+
+  /// Register the error page of `status`, used by the final handler to
+  /// respond to the errors of this status that no error handler handled, and
+  /// for `404`, to the requests that no middleware handled.
+  ///
+  /// The page is either a view, rendered by `res.render()`, or a handler
+  /// called like an error handler; when the handler does not end the chain,
+  /// the default response is sent. The response already has the status and
+  /// the headers of the error, which is described by the `error` local:
+  ///
+  /// - `status`: the status of the error
+  /// - `title`: the reason phrase of the status
+  /// - `message`: the message of the error, when it is exposed or outside of
+  ///   production, or the reason phrase of the status
+  /// - `requestId`: the ID of the request, `req.id`
+  /// - `stack`: the stack trace of the error, outside of production only
+  ///
+  /// Without an error page, the default response is an HTML page, JSON, or a
+  /// problem details document (RFC 9457), negotiated from the `Accept`
+  /// header of the request.
+  ///
+  /// ```javascript
+  /// server.errorPage(404, 'errors/not-found')
+  /// server.errorPage(503, (err, req, res) => {
+  ///   res.json({ retry: true })
+  /// })
+  /// ```
+  #[napi(
+    ts_args_type = "status: number, page: string | ((err: any, req: Request, res: Response, next: (err?: unknown) => void) => unknown)"
+  )]
+  pub fn error_page(
+    &mut self,
+    env: Env,
+    status: u16,
+    page: Either<String, JsErrorHandlerFn>,
+  ) -> Result<()> {
+    let status =
+      LibStatusCode::from_u16(status).map_err(|e| Error::new(Status::InvalidArg, e.to_string()))?;
+    let (tsfn, takes_next) = match page {
+      Either::A(view) => {
+        let render: Function<
+          FnArgs<(Unknown, Unknown, ClassInstance<Response>)>,
+          Unknown<'static>,
+        > = env.create_function_from_closure("renderErrorPage", move |ctx| {
+          let response = ctx.get::<ClassInstance<Response>>(2)?;
+          let rendered = response.render(ctx.env, view.to_owned(), None, None)?;
+          // the handler returns the promise of the rendering
+          Ok(unsafe { Unknown::from_raw_unchecked(ctx.env.raw(), rendered.raw()) })
+        })?;
+        build_middleware_tsfn(render, 3)?
+      }
+      Either::B(handler) => build_middleware_tsfn(handler, 3)?,
+    };
+    self.error_pages.insert(
+      status.as_u16(),
+      ErrorPage {
+        handler: Arc::new(tsfn),
+        takes_next,
+      },
+    );
+    Ok(())
+  }
+
   #[napi]
   pub fn acme_config_meta(&mut self, config: AcmeConfigMeta) {
     self.acme_config_meta = Some(config)
   }
 
+  /// Assign setting `name` to `value`. Other names store any JSON value,
+  /// read with `server.get(name)`. Settings apply to the whole server: there
+  /// are no mounted sub-apps, so they cannot be overridden per sub-app.
+  /// Supported settings:
+  ///
+  /// - `env`: the environment mode. Defaults to `NODE_ENV`, or `development`.
+  ///   In `production`, the final handler hides the messages of errors that
+  ///   are not exposed; otherwise it shows their stack traces
+  /// - `trust proxy`: the proxies trusted to set the `X-Forwarded-For`,
+  ///   `X-Forwarded-Host` and `X-Forwarded-Proto` headers read by `req.ip`,
+  ///   `req.ips`, `req.host`, `req.hostname` and `req.protocol`: `true` for
+  ///   all, a number of hops from the server, or addresses and subnets as a
+  ///   comma-separated string or an array, including the names `loopback`,
+  ///   `linklocal` and `uniquelocal`. Disabled by default
+  /// - `x-powered-by`: send the `X-Powered-By: hyperjs` header. Disabled by
+  ///   default
+  /// - `problem details`: send the HTTP errors left unhandled, such as the
+  ///   `HttpError`s and the errors of the body parsers, as problem details
+  ///   documents (RFC 9457), whatever the `Accept` header of the request.
+  ///   Disabled by default
+  /// - `request id`: how the IDs of requests, `req.id`, are generated:
+  ///   `"counter"` (the default) for a counter of the requests of the
+  ///   process, `"uuid"` for random UUIDs or `"ulid"` for ULIDs
+  /// - `request id header`: the header holding the ID of a request, used
+  ///   instead of a generated one when it comes from a trusted proxy, and
+  ///   set on the response. Defaults to `X-Request-Id`; `false` disables it
+  /// - `log level`: the level of the diagnostic logs of the server, written
+  ///   to stderr: `"off"`, `"error"`, `"warn"` (the default), `"info"`,
+  ///   `"debug"` or `"trace"`. The level is global to the process and set by
+  ///   `server.listen()`; the `RUST_LOG` environment variable overrides it.
+  ///   Requests are logged by the `AccessLogMiddleware`
+  /// - `case sensitive routing`: `/Foo` and `/foo` are different routes.
+  ///   Disabled by default
+  /// - `strict routing`: `/foo` and `/foo/` are different routes. Disabled by
+  ///   default
+  /// - `query parser`: how `req.query` is parsed: `"simple"` (the default),
+  ///   `"extended"` for nested objects and arrays, `false` to disable it, or
+  ///   a function called with the query string that returns `req.query`
+  /// - `subdomain offset`: the number of dot-separated parts of the host
+  ///   removed to access `req.subdomains`. Defaults to 2
+  /// - `json replacer`: the `replacer` argument of `JSON.stringify` used by
+  ///   `res.json()` and `res.jsonp()`, a function or an array of keys
+  /// - `json spaces`: the `space` argument of `JSON.stringify` used by
+  ///   `res.json()` and `res.jsonp()`
+  /// - `json escape`: escape `<`, `>` and `&` as unicode sequences in
+  ///   `res.json()` and `res.jsonp()` output
+  /// - `jsonp callback name`: the query parameter holding the callback name
+  ///   of `res.jsonp()`. Defaults to `callback`
+  /// - `etag`: `ETag` generation of `res.send()`: `true` or `"weak"` (the
+  ///   default) for weak ETags, `"strong"` for strong ones, `false` to disable
+  ///   it, or a function called as `etag(body, encoding)` that returns the
+  ///   `ETag`
+  /// - `views`: the directory, or an array of directories, searched in order
+  ///   for the views of `res.render()`. Defaults to `views`
+  /// - `view engine`: the extension of view names given without one
+  /// - `view cache`: cache the views found and the templates parsed, enabled
+  ///   by default when `NODE_ENV` is `production`
+  ///
+  /// ```javascript
+  /// server.set('json spaces', 2)
+  /// server.set('json escape', true)
+  /// server.set('views', ['views', 'shared/views'])
+  /// server.set('trust proxy', 'loopback, 10.0.0.0/8')
+  /// ```
+  ///
+  /// The routing settings apply to the routes registered before and after
+  /// them; settings assigned after `server.listen()` are ignored.
+  #[napi(ts_args_type = "name: string, value: unknown")]
+  pub fn set(&mut self, name: String, value: Unknown) -> Result<()> {
+    Arc::make_mut(&mut self.settings).set(&name, value)
+  }
+
+  /// Set boolean setting `name` to `true`
+  #[napi]
+  pub fn enable(&mut self, env: Env, name: String) -> Result<()> {
+    let value = true.into_unknown(&env)?;
+    Arc::make_mut(&mut self.settings).set(&name, value)
+  }
+
+  /// Set boolean setting `name` to `false`
+  #[napi]
+  pub fn disable(&mut self, env: Env, name: String) -> Result<()> {
+    let value = false.into_unknown(&env)?;
+    Arc::make_mut(&mut self.settings).set(&name, value)
+  }
+
+  /// Whether setting `name` is truthy
+  ///
+  /// ```javascript
+  /// server.enabled('trust proxy')
+  /// // => false
+  /// ```
+  #[napi]
+  pub fn enabled(&self, env: Env, name: String) -> Result<bool> {
+    self.settings.enabled(&env, &name)
+  }
+
+  /// Whether setting `name` is falsy
+  #[napi]
+  pub fn disabled(&self, env: Env, name: String) -> Result<bool> {
+    self.settings.enabled(&env, &name).map(|enabled| !enabled)
+  }
+
+  /// Register the template engine rendering the views with extension `ext`:
+  /// `'jinja'` for the built-in Jinja-style engine, or a function called as
+  /// `engine(path, locals, callback)` that renders the file at `path` and
+  /// calls `callback(err, html)`, or returns the HTML or a promise of it.
+  ///
+  /// ```javascript
+  /// server.engine('html', 'jinja')
+  /// server.engine('md', async (path, locals) => marked(await readFile(path, 'utf8')))
+  /// ```
+  #[napi(
+    ts_args_type = "ext: string, engine: 'jinja' | ((path: string, locals: Record<string, unknown>, callback: (err: Error | null, html?: string) => void) => string | Promise<string> | void)"
+  )]
+  pub fn engine(&mut self, ext: String, engine: Either<String, JsEngineFn>) -> Result<()> {
+    let engine = match engine {
+      Either::A(name) if name == "jinja" => ViewEngine::Jinja,
+      Either::A(name) => {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!("Unknown built-in engine \"{name}\""),
+        ));
+      }
+      Either::B(engine) => ViewEngine::Function(Arc::new(engine.create_ref()?)),
+    };
+
+    let views = &mut Arc::make_mut(&mut self.settings).views;
+    views
+      .engines
+      .insert(ext.trim_start_matches('.').to_owned(), engine);
+    views.clear_cache();
+    Ok(())
+  }
+
+  /// Variables available to all the views rendered by `res.render()`, set
+  /// with `server.locals.set(name, value)`
+  #[napi(getter)]
+  pub fn locals(&self) -> Locals {
+    self.settings.locals.to_owned()
+  }
+
   #[napi]
   pub fn listen(&self, addr: String) -> Result<()> {
-    let router = Arc::new(self.router.clone());
-    let middlewares = Arc::new(self.middlewares.clone());
+    let mut middlewares = self.middlewares.clone();
+    let router = Arc::new(routing::build_router(&mut middlewares, &self.settings)?);
+    let middlewares = Arc::new(middlewares);
+    let error_pages = Arc::new(self.error_pages.clone());
     let acme_config_meta = self.acme_config_meta.clone();
+    let settings = self.settings.clone();
 
-    EnvLoggerBuilder::new()
-      .filter_level(LevelFilter::max())
-      .init();
+    init_logger(settings.log_level);
 
     std::thread::spawn(move || {
       let rt = tokio::runtime::Runtime::new().unwrap();
@@ -208,9 +580,16 @@ impl Server {
                 }
               };
 
+              // the TLS stream wraps the TCP stream of the connection
+              let connection = ConnectionInfo {
+                remote_addr: tls.get_ref().get_ref().0.get_ref().peer_addr().ok(),
+                encrypted: true,
+              };
               let io = TokioIo::new(tls);
               let router = router.clone();
               let middlewares = middlewares.clone();
+              let error_pages = error_pages.clone();
+              let settings = settings.clone();
 
               tokio::task::spawn(async move {
                 let _ = http1::Builder::new()
@@ -218,7 +597,14 @@ impl Server {
                   .serve_connection(
                     io,
                     service_fn(move |req| {
-                      handle_http_request(req, router.clone(), middlewares.clone())
+                      handle_http_request(
+                        req,
+                        router.clone(),
+                        middlewares.clone(),
+                        error_pages.clone(),
+                        settings.clone(),
+                        connection,
+                      )
                     }),
                   )
                   .await;
@@ -226,10 +612,16 @@ impl Server {
             }
           }
           None => loop {
-            let (socket, _) = tcp_listener.accept().await.unwrap();
+            let (socket, remote_addr) = tcp_listener.accept().await.unwrap();
+            let connection = ConnectionInfo {
+              remote_addr: Some(remote_addr),
+              encrypted: false,
+            };
             let io = TokioIo::new(socket);
             let router = router.clone();
             let middlewares = middlewares.clone();
+            let error_pages = error_pages.clone();
+            let settings = settings.clone();
 
             tokio::task::spawn(async move {
               let _ = http1::Builder::new()
@@ -237,7 +629,14 @@ impl Server {
                 .serve_connection(
                   io,
                   service_fn(move |req| {
-                    handle_http_request(req, router.clone(), middlewares.clone())
+                    handle_http_request(
+                      req,
+                      router.clone(),
+                      middlewares.clone(),
+                      error_pages.clone(),
+                      settings.clone(),
+                      connection,
+                    )
                   }),
                 )
                 .await;
@@ -0,0 +1,126 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use napi::{Error, Result, Status};
+
+use super::get_next_id::get_next_id;
+
+/// The digits of ULIDs, Crockford's base 32
+const CROCKFORD_BASE32: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The `request id` setting: how the IDs of requests are generated
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RequestIdGenerator {
+  /// A counter of the requests handled by the process
+  #[default]
+  Counter,
+  /// Random UUIDs (version 4)
+  Uuid,
+  /// ULIDs, sortable by time of creation
+  Ulid,
+}
+
+impl RequestIdGenerator {
+  pub fn parse(generator: &str) -> Result<Self> {
+    match generator {
+      "counter" => Ok(Self::Counter),
+      "uuid" => Ok(Self::Uuid),
+      "ulid" => Ok(Self::Ulid),
+      generator => Err(Error::new(
+        Status::InvalidArg,
+        format!("Unknown value for setting \"request id\": \"{generator}\""),
+      )),
+    }
+  }
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Counter => "counter",
+      Self::Uuid => "uuid",
+      Self::Ulid => "ulid",
+    }
+  }
+
+  /// Generate the ID of a request. Falls back to the counter when no random
+  /// bytes are available.
+  pub fn generate(&self) -> String {
+    let mut random = [0u8; 16];
+    match self {
+      Self::Uuid if getrandom::fill(&mut random).is_ok() => uuid_v4(random),
+      Self::Ulid if getrandom::fill(&mut random[..10]).is_ok() => {
+        let timestamp = SystemTime::now()
+          .duration_since(UNIX_EPOCH)
+          .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let mut randomness = [0u8; 10];
+        randomness.copy_from_slice(&random[..10]);
+        ulid(timestamp, randomness)
+      }
+      _ => get_next_id().to_string(),
+    }
+  }
+}
+
+/// Whether `id`, received from a proxy, may be used as the ID of a request:
+/// 1 to 200 visible ASCII characters
+pub fn is_valid_request_id(id: &str) -> bool {
+  (1..=200).contains(&id.len()) && id.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// Format `random` as a version 4 UUID
+fn uuid_v4(mut random: [u8; 16]) -> String {
+  random[6] = (random[6] & 0x0f) | 0x40;
+  random[8] = (random[8] & 0x3f) | 0x80;
+  let hex = random
+    .iter()
+    .map(|byte| format!("{byte:02x}"))
+    .collect::<String>();
+  format!(
+    "{}-{}-{}-{}-{}",
+    &hex[..8],
+    &hex[8..12],
+    &hex[12..16],
+    &hex[16..20],
+    &hex[20..]
+  )
+}
+
+/// Format the ULID of `timestamp`, in milliseconds, and `randomness`
+fn ulid(timestamp: u64, randomness: [u8; 10]) -> String {
+  let mut value = u128::from(timestamp & 0xffff_ffff_ffff) << 80;
+  for (i, byte) in randomness.iter().enumerate() {
+    value |= u128::from(*byte) << (72 - 8 * i);
+  }
+  // 26 digits of 5 bits, the first one holding the 3 high bits
+  (0..26)
+    .map(|i| CROCKFORD_BASE32[((value >> (125 - 5 * i)) & 0x1f) as usize] as char)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_uuid_v4() {
+    assert_eq!(uuid_v4([0; 16]), "00000000-0000-4000-8000-000000000000");
+    assert_eq!(uuid_v4([0xff; 16]), "ffffffff-ffff-4fff-bfff-ffffffffffff");
+    let id = RequestIdGenerator::Uuid.generate();
+    assert_eq!(id.len(), 36);
+    assert_eq!(&id[14..15], "4");
+  }
+
+  #[test]
+  fn test_ulid() {
+    assert_eq!(ulid(0, [0; 10]), "00000000000000000000000000");
+    assert_eq!(ulid(1469918176385, [0; 10]), "01ARYZ6S410000000000000000");
+    assert_eq!(ulid(u64::MAX, [0xff; 10]), "7ZZZZZZZZZZZZZZZZZZZZZZZZZ");
+    assert_eq!(RequestIdGenerator::Ulid.generate().len(), 26);
+  }
+
+  #[test]
+  fn test_is_valid_request_id() {
+    assert!(is_valid_request_id("f058ebd6-02f7-4d3f-942e-904344e8cde5"));
+    assert!(!is_valid_request_id(""));
+    assert!(!is_valid_request_id("two words"));
+    assert!(!is_valid_request_id(&"a".repeat(201)));
+  }
+}
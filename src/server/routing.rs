@@ -0,0 +1,140 @@
+use matchit::{InsertError, Router};
+use napi::{Error, Result, Status};
+
+use super::{AppSettings, MiddlewareMeta};
+
+/// `route` as matched under the `case sensitive routing` and `strict routing`
+/// settings: lowercased outside of parameters unless routing is case
+/// sensitive, and without its trailing slash unless routing is strict
+pub fn normalize_route(route: &str, settings: &AppSettings) -> String {
+  let mut normalized = String::with_capacity(route.len());
+  let mut depth = 0usize;
+  for c in route.chars() {
+    match c {
+      '{' => depth += 1,
+      '}' => depth = depth.saturating_sub(1),
+      _ => {}
+    }
+    match depth == 0 && !settings.case_sensitive_routing {
+      true => normalized.push(c.to_ascii_lowercase()),
+      false => normalized.push(c),
+    }
+  }
+  trim_trailing_slash(&mut normalized, settings);
+  normalized
+}
+
+fn trim_trailing_slash(path: &mut String, settings: &AppSettings) {
+  if !settings.strict_routing && path.len() > 1 && path.ends_with('/') {
+    path.pop();
+  }
+}
+
+/// Build the router of the routes of `middlewares`, normalized by
+/// `normalize_route`. The routes of the middlewares are normalized too, so
+/// that they compare equal to the routes matched.
+pub fn build_router(
+  middlewares: &mut [MiddlewareMeta],
+  settings: &AppSettings,
+) -> Result<Router<String>> {
+  let mut router = Router::new();
+  for middleware in middlewares {
+    let Some(route) = middleware.route.as_mut() else {
+      continue;
+    };
+    *route = normalize_route(route, settings);
+    if let Err(e) = router.insert(route.to_owned(), route.to_owned()) {
+      match e {
+        InsertError::Conflict { .. } => {}
+        _ => return Err(Error::new(Status::GenericFailure, e.to_string())),
+      }
+    }
+  }
+  Ok(router)
+}
+
+/// The route matching `path` and its parameters. Parameters keep the case
+/// they have in `path`.
+pub fn match_route<'r>(
+  router: &'r Router<String>,
+  path: &str,
+  settings: &AppSettings,
+) -> Option<(&'r String, Vec<(String, String)>)> {
+  let mut normalized = match settings.case_sensitive_routing {
+    true => path.to_owned(),
+    false => path.to_ascii_lowercase(),
+  };
+  trim_trailing_slash(&mut normalized, settings);
+
+  let matched = router.at(&normalized).ok()?;
+  // lowercasing ASCII keeps the byte offsets, so the parameter values are
+  // read at the same offsets in `path`
+  let params = matched
+    .params
+    .iter()
+    .map(|(key, value)| {
+      let start = value.as_ptr() as usize - normalized.as_ptr() as usize;
+      (key.to_owned(), path[start..start + value.len()].to_owned())
+    })
+    .collect();
+  Some((matched.value, params))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settings(case_sensitive_routing: bool, strict_routing: bool) -> AppSettings {
+    AppSettings {
+      case_sensitive_routing,
+      strict_routing,
+      ..Default::default()
+    }
+  }
+
+  fn router(routes: &[&str], settings: &AppSettings) -> Router<String> {
+    let mut router = Router::new();
+    for route in routes {
+      let route = normalize_route(route, settings);
+      router.insert(route.to_owned(), route).unwrap();
+    }
+    router
+  }
+
+  #[test]
+  fn test_normalize_route() {
+    let default = settings(false, false);
+    assert_eq!(
+      normalize_route("/Users/{userId}/", &default),
+      "/users/{userId}"
+    );
+    assert_eq!(normalize_route("/", &default), "/");
+
+    let strict = settings(true, true);
+    assert_eq!(
+      normalize_route("/Users/{userId}/", &strict),
+      "/Users/{userId}/"
+    );
+  }
+
+  #[test]
+  fn test_match_route() {
+    let default = settings(false, false);
+    let routes = router(&["/users/{userId}", "/files/{*path}"], &default);
+    let (route, params) = match_route(&routes, "/USERS/Tobi/", &default).unwrap();
+    assert_eq!(route, "/users/{userId}");
+    assert_eq!(params, vec![("userId".to_owned(), "Tobi".to_owned())]);
+    let (_, params) = match_route(&routes, "/Files/A/b.TXT", &default).unwrap();
+    assert_eq!(params, vec![("path".to_owned(), "A/b.TXT".to_owned())]);
+
+    let case_sensitive = settings(true, false);
+    let routes = router(&["/Users/{userId}"], &case_sensitive);
+    assert!(match_route(&routes, "/Users/tobi/", &case_sensitive).is_some());
+    assert!(match_route(&routes, "/users/tobi", &case_sensitive).is_none());
+
+    let strict = settings(false, true);
+    let routes = router(&["/users/"], &strict);
+    assert!(match_route(&routes, "/Users/", &strict).is_some());
+    assert!(match_route(&routes, "/users", &strict).is_none());
+  }
+}
@@ -0,0 +1,422 @@
+use std::{collections::HashMap, fmt, path::PathBuf, sync::Arc};
+
+use napi::bindgen_prelude::*;
+use serde_json::{Value, json};
+
+use hyper::header::HeaderName;
+use log::LevelFilter;
+
+use super::request_id::RequestIdGenerator;
+use super::trust_proxy::TrustProxy;
+use crate::{locals::Locals, utilities, view::ViewSettings};
+
+pub type JsonReplacerFnRef = FunctionRef<FnArgs<(String, Unknown<'static>)>, Unknown<'static>>;
+
+/// The `replacer` argument given to `JSON.stringify`
+#[derive(Clone)]
+pub enum JsonReplacer {
+  /// Called as `replacer(key, value)` for every serialized value
+  Function(Arc<JsonReplacerFnRef>),
+  /// Only the listed object keys are serialized
+  Keys(Vec<String>),
+}
+
+type JsEtagFn<'a> = Function<'a, FnArgs<(Buffer, Either<String, Undefined>)>, Unknown<'static>>;
+
+pub type EtagFnRef = FunctionRef<FnArgs<(Buffer, Either<String, Undefined>)>, Unknown<'static>>;
+
+/// How `res.send()` generates the `ETag` of a response
+#[derive(Clone, Default)]
+pub enum EtagSetting {
+  /// No `ETag` is generated
+  Disabled,
+  /// A weak `ETag` is generated from the body
+  #[default]
+  Weak,
+  /// A strong `ETag` is generated from the body
+  Strong,
+  /// Called as `etag(body, encoding)`, returns the `ETag` or a falsy value
+  Function(Arc<EtagFnRef>),
+}
+
+type JsQueryParserFn<'a> = Function<'a, String, Unknown<'static>>;
+
+pub type QueryParserFnRef = FunctionRef<String, Unknown<'static>>;
+
+/// How `req.query` is parsed from the query string
+#[derive(Clone, Default)]
+pub enum QueryParser {
+  /// `req.query` is an empty object
+  Disabled,
+  /// Parsed with `serde_urlencoded`, without nested objects
+  #[default]
+  Simple,
+  /// Parsed with `serde_qs`, with nested objects and arrays
+  Extended,
+  /// Called as `parser(queryString)`, returns `req.query`
+  Function(Arc<QueryParserFnRef>),
+}
+
+/// Application settings, assigned with `server.set(name, value)`.
+#[derive(Clone)]
+pub struct AppSettings {
+  /// `env`: the environment mode, `NODE_ENV` or `development`
+  pub env: String,
+  /// `trust proxy`: the proxies trusted to set the `X-Forwarded-*` headers
+  pub trust_proxy: TrustProxy,
+  /// `x-powered-by`: send the `X-Powered-By: hyperjs` header
+  pub x_powered_by: bool,
+  /// `problem details`: send the HTTP errors left unhandled as problem
+  /// details documents
+  pub problem_details: bool,
+  /// `request id`: how the IDs of requests are generated
+  pub request_id: RequestIdGenerator,
+  /// `request id header`: the header holding the ID of a request, read from
+  /// trusted proxies and set on the response
+  pub request_id_header: Option<HeaderName>,
+  /// `log level`: the level of the diagnostic logs of the server
+  pub log_level: LevelFilter,
+  /// `case sensitive routing`: `/Foo` and `/foo` are different routes
+  pub case_sensitive_routing: bool,
+  /// `strict routing`: `/foo` and `/foo/` are different routes
+  pub strict_routing: bool,
+  /// `query parser`: how `req.query` is parsed
+  pub query_parser: QueryParser,
+  /// `subdomain offset`: the number of dot-separated parts of the host
+  /// removed to access `req.subdomains`
+  pub subdomain_offset: usize,
+  /// `json replacer`: replacer used by `res.json()`
+  pub json_replacer: Option<JsonReplacer>,
+  /// `json spaces`: indentation used by `res.json()`
+  pub json_spaces: Option<Either<u32, String>>,
+  /// `json escape`: escape `<`, `>` and `&` in `res.json()` output
+  pub json_escape: bool,
+  /// `jsonp callback name`: query parameter holding the callback of
+  /// `res.jsonp()`
+  pub jsonp_callback_name: String,
+  /// `etag`: `ETag` generation of `res.send()`
+  pub etag: EtagSetting,
+  /// `views`, `view engine` and `view cache`: how `res.render()` finds views
+  pub views: ViewSettings,
+  /// `server.locals`: variables of all the views rendered
+  pub locals: Locals,
+  /// The settings unknown to the server, stored as JSON
+  pub custom: HashMap<String, Value>,
+}
+
+impl fmt::Debug for AppSettings {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("AppSettings")
+      .field("env", &self.env)
+      .field("trust_proxy", &self.trust_proxy)
+      .field("x_powered_by", &self.x_powered_by)
+      .field("problem_details", &self.problem_details)
+      .field("request_id", &self.request_id)
+      .field("request_id_header", &self.request_id_header)
+      .field("log_level", &self.log_level)
+      .field("case_sensitive_routing", &self.case_sensitive_routing)
+      .field("strict_routing", &self.strict_routing)
+      .field("subdomain_offset", &self.subdomain_offset)
+      .field("custom", &self.custom)
+      .finish_non_exhaustive()
+  }
+}
+
+impl Default for AppSettings {
+  fn default() -> Self {
+    Self {
+      env: std::env::var("NODE_ENV").unwrap_or_else(|_| "development".to_owned()),
+      trust_proxy: TrustProxy::default(),
+      x_powered_by: false,
+      problem_details: false,
+      request_id: RequestIdGenerator::default(),
+      request_id_header: Some(HeaderName::from_static("x-request-id")),
+      log_level: LevelFilter::Warn,
+      case_sensitive_routing: false,
+      strict_routing: false,
+      query_parser: QueryParser::default(),
+      subdomain_offset: 2,
+      json_replacer: None,
+      json_spaces: None,
+      json_escape: false,
+      jsonp_callback_name: "callback".to_owned(),
+      etag: EtagSetting::default(),
+      views: ViewSettings::default(),
+      locals: Locals::default(),
+      custom: HashMap::new(),
+    }
+  }
+}
+
+impl AppSettings {
+  /// Assign setting `name` to `value`. Must be called on the JS thread.
+  pub fn set(&mut self, name: &str, value: Unknown) -> Result<()> {
+    match name {
+      "env" => {
+        self.env = from_unknown(value)
+          .map_err(|_| Error::new(Status::InvalidArg, "Setting \"env\" expects a string"))?
+      }
+      "trust proxy" => self.trust_proxy = trust_proxy(value)?,
+      "x-powered-by" => self.x_powered_by = value.coerce_to_bool()?,
+      "problem details" => self.problem_details = value.coerce_to_bool()?,
+      "request id" => {
+        let generator: String = from_unknown(value).map_err(|_| {
+          Error::new(
+            Status::InvalidArg,
+            "Setting \"request id\" expects a string",
+          )
+        })?;
+        self.request_id = RequestIdGenerator::parse(&generator)?
+      }
+      "request id header" => self.request_id_header = request_id_header(value)?,
+      "log level" => {
+        let level: String = from_unknown(value)
+          .map_err(|_| Error::new(Status::InvalidArg, "Setting \"log level\" expects a string"))?;
+        self.log_level = level.parse().map_err(|_| {
+          Error::new(
+            Status::InvalidArg,
+            format!("Unknown value for setting \"log level\": \"{level}\""),
+          )
+        })?
+      }
+      "case sensitive routing" => self.case_sensitive_routing = value.coerce_to_bool()?,
+      "strict routing" => self.strict_routing = value.coerce_to_bool()?,
+      "query parser" => self.query_parser = query_parser(value)?,
+      "subdomain offset" => {
+        self.subdomain_offset = from_unknown::<u32>(value).map_err(|_| {
+          Error::new(
+            Status::InvalidArg,
+            "Setting \"subdomain offset\" expects a number",
+          )
+        })? as usize
+      }
+      "json replacer" => self.json_replacer = json_replacer(value)?,
+      "json spaces" => {
+        self.json_spaces = from_unknown(value).map_err(|_| {
+          Error::new(
+            Status::InvalidArg,
+            "Setting \"json spaces\" expects a number or a string",
+          )
+        })?
+      }
+      "json escape" => self.json_escape = value.coerce_to_bool()?,
+      "jsonp callback name" => {
+        self.jsonp_callback_name = from_unknown(value).map_err(|_| {
+          Error::new(
+            Status::InvalidArg,
+            "Setting \"jsonp callback name\" expects a string",
+          )
+        })?
+      }
+      "etag" => self.etag = etag_setting(value)?,
+      "views" => {
+        let dirs: Either<String, Vec<String>> = from_unknown(value).map_err(|_| {
+          Error::new(
+            Status::InvalidArg,
+            "Setting \"views\" expects a directory or an array of directories",
+          )
+        })?;
+        self.views.dirs = match dirs {
+          Either::A(dir) => vec![PathBuf::from(dir)],
+          Either::B(dirs) => dirs.into_iter().map(PathBuf::from).collect(),
+        };
+        self.views.clear_cache();
+      }
+      "view engine" => {
+        let ext: Option<String> = from_unknown(value).map_err(|_| {
+          Error::new(
+            Status::InvalidArg,
+            "Setting \"view engine\" expects an extension",
+          )
+        })?;
+        self.views.default_engine = ext.map(|ext| ext.trim_start_matches('.').to_owned());
+        self.views.clear_cache();
+      }
+      "view cache" => {
+        self.views.cache = value.coerce_to_bool()?;
+        self.views.clear_cache();
+      }
+      _ => {
+        let env = Env::from_raw(value.value().env);
+        self
+          .custom
+          .insert(name.to_owned(), utilities::to_json_value(&env, value)?);
+      }
+    }
+    Ok(())
+  }
+
+  /// The value of setting `name`, `None` when it is not set. Must be called
+  /// on the JS thread.
+  pub fn get<'env>(&self, env: &'env Env, name: &str) -> Result<Option<Unknown<'env>>> {
+    let value = match name {
+      "env" => json!(self.env),
+      "trust proxy" => match &self.trust_proxy {
+        TrustProxy::Disabled => json!(false),
+        TrustProxy::All => json!(true),
+        TrustProxy::Hops(hops) => json!(hops),
+        TrustProxy::Subnets(subnets, _) => json!(subnets),
+      },
+      "x-powered-by" => json!(self.x_powered_by),
+      "problem details" => json!(self.problem_details),
+      "request id" => json!(self.request_id.as_str()),
+      "request id header" => match &self.request_id_header {
+        None => json!(false),
+        Some(header) => json!(header.as_str()),
+      },
+      "log level" => json!(self.log_level.as_str().to_lowercase()),
+      "case sensitive routing" => json!(self.case_sensitive_routing),
+      "strict routing" => json!(self.strict_routing),
+      "query parser" => match &self.query_parser {
+        QueryParser::Disabled => json!(false),
+        QueryParser::Simple => json!("simple"),
+        QueryParser::Extended => json!("extended"),
+        QueryParser::Function(parser) => return Ok(Some(parser.borrow_back(env)?.to_unknown())),
+      },
+      "subdomain offset" => json!(self.subdomain_offset),
+      "json replacer" => match &self.json_replacer {
+        None => return Ok(None),
+        Some(JsonReplacer::Keys(keys)) => json!(keys),
+        Some(JsonReplacer::Function(replacer)) => {
+          return Ok(Some(replacer.borrow_back(env)?.to_unknown()));
+        }
+      },
+      "json spaces" => match &self.json_spaces {
+        None => return Ok(None),
+        Some(Either::A(spaces)) => json!(spaces),
+        Some(Either::B(spaces)) => json!(spaces),
+      },
+      "json escape" => json!(self.json_escape),
+      "jsonp callback name" => json!(self.jsonp_callback_name),
+      "etag" => match &self.etag {
+        EtagSetting::Disabled => json!(false),
+        EtagSetting::Weak => json!("weak"),
+        EtagSetting::Strong => json!("strong"),
+        EtagSetting::Function(etag) => return Ok(Some(etag.borrow_back(env)?.to_unknown())),
+      },
+      "views" => match self.views.dirs.as_slice() {
+        [dir] => json!(dir),
+        dirs => json!(dirs),
+      },
+      "view engine" => match &self.views.default_engine {
+        None => return Ok(None),
+        Some(ext) => json!(ext),
+      },
+      "view cache" => json!(self.views.cache),
+      name => match self.custom.get(name) {
+        None => return Ok(None),
+        Some(value) => value.to_owned(),
+      },
+    };
+    utilities::json_to_napi(env, value).map(Some)
+  }
+
+  /// Whether setting `name` is truthy. Must be called on the JS thread.
+  pub fn enabled(&self, env: &Env, name: &str) -> Result<bool> {
+    match self.get(env, name)? {
+      Some(value) => value.coerce_to_bool(),
+      None => Ok(false),
+    }
+  }
+}
+
+fn trust_proxy(value: Unknown) -> Result<TrustProxy> {
+  let invalid = || {
+    Error::new(
+      Status::InvalidArg,
+      "Setting \"trust proxy\" expects a boolean, a number of hops, or addresses and subnets",
+    )
+  };
+  match value.get_type()? {
+    ValueType::Boolean | ValueType::Undefined | ValueType::Null => match value.coerce_to_bool()? {
+      true => Ok(TrustProxy::All),
+      false => Ok(TrustProxy::Disabled),
+    },
+    ValueType::Number => Ok(TrustProxy::Hops(
+      from_unknown::<u32>(value).map_err(|_| invalid())? as usize,
+    )),
+    ValueType::String => TrustProxy::subnets(&[from_unknown::<String>(value)?]),
+    ValueType::Object if value.is_array()? => {
+      TrustProxy::subnets(&from_unknown::<Vec<String>>(value).map_err(|_| invalid())?)
+    }
+    _ => Err(invalid()),
+  }
+}
+
+fn query_parser(value: Unknown) -> Result<QueryParser> {
+  match value.get_type()? {
+    ValueType::Function => {
+      let parser: JsQueryParserFn = from_unknown(value)?;
+      Ok(QueryParser::Function(Arc::new(parser.create_ref()?)))
+    }
+    ValueType::String => match from_unknown::<String>(value)?.as_str() {
+      "simple" => Ok(QueryParser::Simple),
+      "extended" => Ok(QueryParser::Extended),
+      parser => Err(Error::new(
+        Status::InvalidArg,
+        format!("Unknown value for setting \"query parser\": \"{parser}\""),
+      )),
+    },
+    _ if value.coerce_to_bool()? => Ok(QueryParser::Simple),
+    _ => Ok(QueryParser::Disabled),
+  }
+}
+
+fn request_id_header(value: Unknown) -> Result<Option<HeaderName>> {
+  if value.get_type()? != ValueType::String {
+    return match value.coerce_to_bool()? {
+      true => Ok(Some(HeaderName::from_static("x-request-id"))),
+      false => Ok(None),
+    };
+  }
+  let header: String = from_unknown(value)?;
+  HeaderName::from_bytes(header.as_bytes())
+    .map(Some)
+    .map_err(|_| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Invalid header name \"{header}\" for setting \"request id header\""),
+      )
+    })
+}
+
+fn json_replacer(value: Unknown) -> Result<Option<JsonReplacer>> {
+  match value.get_type()? {
+    ValueType::Undefined | ValueType::Null => Ok(None),
+    ValueType::Function => {
+      let replacer: Function<FnArgs<(String, Unknown<'static>)>, Unknown<'static>> =
+        from_unknown(value)?;
+      Ok(Some(JsonReplacer::Function(Arc::new(
+        replacer.create_ref()?,
+      ))))
+    }
+    ValueType::Object if value.is_array()? => Ok(Some(JsonReplacer::Keys(from_unknown(value)?))),
+    _ => Err(Error::new(
+      Status::InvalidArg,
+      "Setting \"json replacer\" expects a function or an array of keys",
+    )),
+  }
+}
+
+fn etag_setting(value: Unknown) -> Result<EtagSetting> {
+  match value.get_type()? {
+    ValueType::Function => {
+      let etag: JsEtagFn = from_unknown(value)?;
+      Ok(EtagSetting::Function(Arc::new(etag.create_ref()?)))
+    }
+    ValueType::String => match from_unknown::<String>(value)?.as_str() {
+      "weak" => Ok(EtagSetting::Weak),
+      "strong" => Ok(EtagSetting::Strong),
+      etag => Err(Error::new(
+        Status::InvalidArg,
+        format!("Unknown value for setting \"etag\": \"{etag}\""),
+      )),
+    },
+    _ if value.coerce_to_bool()? => Ok(EtagSetting::Weak),
+    _ => Ok(EtagSetting::Disabled),
+  }
+}
+
+fn from_unknown<T: FromNapiValue>(value: Unknown) -> Result<T> {
+  unsafe { T::from_napi_value(value.value().env, value.raw()) }
+}
@@ -0,0 +1,193 @@
+use std::net::IpAddr;
+
+use napi::{Error, Result, Status};
+
+/// The `trust proxy` setting: which proxies in front of the server are
+/// trusted to set the `X-Forwarded-*` headers
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TrustProxy {
+  /// The client is the peer of the connection; `X-Forwarded-*` headers are
+  /// ignored
+  #[default]
+  Disabled,
+  /// The client is the left-most entry of `X-Forwarded-For`
+  All,
+  /// The given number of hops from the server are trusted proxies
+  Hops(usize),
+  /// The proxies whose address is in one of the subnets are trusted, given as
+  /// addresses, CIDR subnets or the names `loopback`, `linklocal` and
+  /// `uniquelocal`
+  Subnets(Vec<String>, Vec<Subnet>),
+}
+
+impl TrustProxy {
+  /// Trust the proxies in `subnets`
+  pub fn subnets<S: AsRef<str>>(subnets: &[S]) -> Result<Self> {
+    let names = subnets
+      .iter()
+      .flat_map(|subnets| subnets.as_ref().split(','))
+      .map(|subnet| subnet.trim().to_owned())
+      .filter(|subnet| !subnet.is_empty())
+      .collect::<Vec<_>>();
+    let mut parsed = Vec::with_capacity(names.len());
+    for name in &names {
+      parsed.extend(Subnet::parse(name)?);
+    }
+    Ok(Self::Subnets(names, parsed))
+  }
+
+  /// Whether the `hop`-th address from the server, `address`, is a trusted
+  /// proxy
+  pub fn trusts(&self, address: Option<IpAddr>, hop: usize) -> bool {
+    match self {
+      Self::Disabled => false,
+      Self::All => true,
+      Self::Hops(hops) => hop < *hops,
+      Self::Subnets(_, subnets) => {
+        address.is_some_and(|address| subnets.iter().any(|subnet| subnet.contains(address)))
+      }
+    }
+  }
+
+  /// The addresses of a request, from the peer of the connection to the
+  /// client, up to the first untrusted proxy. The entries of
+  /// `X-Forwarded-For` that are not IP addresses are kept as `None`.
+  pub fn addresses(
+    &self,
+    remote_address: Option<IpAddr>,
+    forwarded_for: Option<&str>,
+  ) -> Vec<Option<IpAddr>> {
+    let mut addresses = vec![remote_address];
+    if let Some(forwarded_for) = forwarded_for {
+      addresses.extend(
+        forwarded_for
+          .split(',')
+          .rev()
+          .map(|address| address.trim().parse().ok()),
+      );
+    }
+
+    if let Some(untrusted) = (0..addresses.len() - 1).find(|&hop| !self.trusts(addresses[hop], hop))
+    {
+      addresses.truncate(untrusted + 1);
+    }
+    addresses
+  }
+}
+
+/// An IP address range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Subnet {
+  address: IpAddr,
+  prefix: u8,
+}
+
+impl Subnet {
+  /// Parse an address, a subnet like `10.0.0.0/8` or `fe80::/10`, or a
+  /// named range
+  pub fn parse(subnet: &str) -> Result<Vec<Self>> {
+    let named: &[&str] = match subnet {
+      "loopback" => &["127.0.0.1/8", "::1/128"],
+      "linklocal" => &["169.254.0.0/16", "fe80::/10"],
+      "uniquelocal" => &["10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16", "fc00::/7"],
+      subnet => return Self::parse_one(subnet).map(|subnet| vec![subnet]),
+    };
+    named.iter().map(|subnet| Self::parse_one(subnet)).collect()
+  }
+
+  fn parse_one(subnet: &str) -> Result<Self> {
+    let invalid = || {
+      Error::new(
+        Status::InvalidArg,
+        format!("Invalid IP address or subnet \"{subnet}\" for setting \"trust proxy\""),
+      )
+    };
+
+    let (address, prefix) = match subnet.split_once('/') {
+      Some((address, prefix)) => (address, Some(prefix)),
+      None => (subnet, None),
+    };
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    let max_prefix = match address {
+      IpAddr::V4(_) => 32,
+      IpAddr::V6(_) => 128,
+    };
+    let prefix = match prefix {
+      Some(prefix) => prefix.parse().map_err(|_| invalid())?,
+      None => max_prefix,
+    };
+    if prefix > max_prefix {
+      return Err(invalid());
+    }
+    Ok(Self { address, prefix })
+  }
+
+  pub fn contains(&self, address: IpAddr) -> bool {
+    // IPv4 addresses mapped to IPv6 are compared as IPv4 ones
+    let address = match address {
+      IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4),
+      address => address,
+    };
+    match (self.address, address) {
+      (IpAddr::V4(subnet), IpAddr::V4(address)) => {
+        let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+        u32::from(subnet) & mask == u32::from(address) & mask
+      }
+      (IpAddr::V6(subnet), IpAddr::V6(address)) => {
+        let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+        u128::from(subnet) & mask == u128::from(address) & mask
+      }
+      _ => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ip(address: &str) -> Option<IpAddr> {
+    address.parse().ok()
+  }
+
+  #[test]
+  fn test_subnets() {
+    let trust = TrustProxy::subnets(&["loopback", "10.0.0.0/8, 2001:db8::/32"]).unwrap();
+    assert!(trust.trusts(ip("127.0.0.1"), 0));
+    assert!(trust.trusts(ip("::1"), 0));
+    assert!(trust.trusts(ip("::ffff:127.0.0.1"), 0));
+    assert!(trust.trusts(ip("10.1.2.3"), 3));
+    assert!(trust.trusts(ip("2001:db8::1"), 0));
+    assert!(!trust.trusts(ip("11.0.0.1"), 0));
+    assert!(!trust.trusts(None, 0));
+
+    assert!(TrustProxy::subnets(&["10.0.0.0/33"]).is_err());
+    assert!(TrustProxy::subnets(&["local"]).is_err());
+  }
+
+  #[test]
+  fn test_addresses() {
+    let forwarded_for = Some("1.1.1.1, 2.2.2.2, 10.0.0.2");
+    let remote = ip("127.0.0.1");
+
+    assert_eq!(
+      TrustProxy::Disabled.addresses(remote, forwarded_for),
+      vec![remote]
+    );
+    assert_eq!(
+      TrustProxy::All.addresses(remote, forwarded_for),
+      vec![remote, ip("10.0.0.2"), ip("2.2.2.2"), ip("1.1.1.1")]
+    );
+    assert_eq!(
+      TrustProxy::Hops(1).addresses(remote, forwarded_for),
+      vec![remote, ip("10.0.0.2")]
+    );
+    assert_eq!(
+      TrustProxy::subnets(&["loopback", "10.0.0.0/8"])
+        .unwrap()
+        .addresses(remote, forwarded_for),
+      vec![remote, ip("10.0.0.2"), ip("2.2.2.2")]
+    );
+    assert_eq!(TrustProxy::All.addresses(remote, None), vec![remote]);
+  }
+}
@@ -0,0 +1,189 @@
+use hyper::StatusCode;
+use napi::bindgen_prelude::*;
+
+/// An error raised by the body parsing middlewares, modelled after the errors
+/// of Express' [body-parser](https://github.com/expressjs/body-parser#errors).
+///
+/// It is thrown to JavaScript as an `Error` carrying `status`, `statusCode`,
+/// `type` and `expose` properties, plus `limit`, `length`, `body`, `charset`
+/// or `encoding` when relevant. The server answers with `status` when such an
+/// error reaches it.
+#[derive(Debug)]
+pub struct BodyParserError {
+  pub status: StatusCode,
+  pub typ: Option<&'static str>,
+  pub message: String,
+  pub limit: Option<usize>,
+  pub length: Option<usize>,
+  pub body: Option<String>,
+  pub charset: Option<String>,
+  pub encoding: Option<String>,
+  /// An unexpected failure, thrown to JavaScript unchanged
+  internal: Option<Box<Error>>,
+}
+
+impl BodyParserError {
+  fn new(status: StatusCode, typ: &'static str, message: impl Into<String>) -> Self {
+    Self {
+      status,
+      typ: Some(typ),
+      message: message.into(),
+      limit: None,
+      length: None,
+      body: None,
+      charset: None,
+      encoding: None,
+      internal: None,
+    }
+  }
+
+  /// The body, once inflated, is larger than `limit` bytes.
+  pub fn entity_too_large(limit: usize, length: Option<usize>) -> Self {
+    Self {
+      limit: Some(limit),
+      length,
+      ..Self::new(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "entity.too.large",
+        "request entity too large",
+      )
+    }
+  }
+
+  /// The body could not be parsed; `body` is the raw (decoded) body, when
+  /// still at hand.
+  pub fn parse_failed(message: impl Into<String>, body: Option<String>) -> Self {
+    Self {
+      body,
+      ..Self::new(StatusCode::BAD_REQUEST, "entity.parse.failed", message)
+    }
+  }
+
+  /// A URL-encoded body has more parameters than `parameterLimit`.
+  pub fn parameters_too_many() -> Self {
+    Self::new(
+      StatusCode::PAYLOAD_TOO_LARGE,
+      "parameters.too.many",
+      "too many parameters",
+    )
+  }
+
+  /// A URL-encoded body nests deeper than `depth`.
+  pub fn depth_exceeded() -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "querystring.parse.rangeError",
+      "The input exceeded the depth",
+    )
+  }
+
+  /// The `Content-Encoding` of the body is unknown, or inflation is disabled.
+  pub fn encoding_unsupported(encoding: String) -> Self {
+    Self {
+      message: format!("unsupported content encoding \"{encoding}\""),
+      encoding: Some(encoding),
+      ..Self::new(
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        "encoding.unsupported",
+        "",
+      )
+    }
+  }
+
+  /// The charset of the body cannot be decoded.
+  pub fn charset_unsupported(charset: String) -> Self {
+    Self {
+      message: format!("unsupported charset \"{}\"", charset.to_uppercase()),
+      charset: Some(charset),
+      ..Self::new(
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        "charset.unsupported",
+        "",
+      )
+    }
+  }
+
+  /// The body could not be inflated according to its `Content-Encoding`.
+  pub fn inflate_failed(message: impl Into<String>) -> Self {
+    Self {
+      typ: None,
+      ..Self::new(StatusCode::BAD_REQUEST, "", message)
+    }
+  }
+
+  /// The client stopped sending the body before it was complete.
+  pub fn request_aborted() -> Self {
+    Self::new(
+      StatusCode::BAD_REQUEST,
+      "request.aborted",
+      "request aborted",
+    )
+  }
+
+  /// The `verify` option threw while checking the body.
+  pub fn verify_failed(error: Error) -> Self {
+    Self::new(StatusCode::FORBIDDEN, "entity.verify.failed", error.reason)
+  }
+
+  /// Whether `message` is safe to show to the client.
+  pub fn expose(&self) -> bool {
+    self.status.is_client_error()
+  }
+
+  /// Create the JavaScript `Error` for this error, to be thrown or rejected
+  /// with on the JavaScript thread.
+  pub fn into_napi_error(self, env: &Env) -> Error {
+    if let Some(internal) = self.internal {
+      return *internal;
+    }
+    match self.to_js_error(env) {
+      Ok(js_error) => Error::from(js_error.to_unknown()),
+      Err(e) => e,
+    }
+  }
+
+  fn to_js_error<'env>(&self, env: &'env Env) -> Result<Object<'env>> {
+    let mut js_error = env.create_error(Error::new(Status::GenericFailure, &self.message))?;
+    js_error.set("status", self.status.as_u16())?;
+    js_error.set("statusCode", self.status.as_u16())?;
+    js_error.set("expose", self.expose())?;
+    if let Some(typ) = self.typ {
+      js_error.set("type", typ)?;
+    }
+    if let Some(limit) = self.limit {
+      js_error.set("limit", limit as f64)?;
+    }
+    if let Some(length) = self.length {
+      js_error.set("length", length as f64)?;
+    }
+    if let Some(body) = &self.body {
+      js_error.set("body", body.as_str())?;
+    }
+    if let Some(charset) = &self.charset {
+      js_error.set("charset", charset.as_str())?;
+    }
+    if let Some(encoding) = &self.encoding {
+      js_error.set("encoding", encoding.as_str())?;
+    }
+    Ok(js_error)
+  }
+}
+
+impl From<Error> for BodyParserError {
+  fn from(error: Error) -> Self {
+    Self {
+      typ: None,
+      message: error.reason.clone(),
+      internal: Some(Box::new(error)),
+      ..Self::new(StatusCode::INTERNAL_SERVER_ERROR, "", "")
+    }
+  }
+}
+
+impl std::fmt::Display for BodyParserError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl std::error::Error for BodyParserError {}
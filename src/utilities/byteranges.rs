@@ -0,0 +1,219 @@
+use std::{
+  hash::{BuildHasher, Hasher, RandomState},
+  time::SystemTime,
+};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use hyper::{
+  HeaderMap, Method,
+  header::{IF_RANGE, RANGE},
+};
+
+use super::{
+  Validators,
+  parse_range::{Range, RangeOptions, RangeParseError, parse_range},
+  preconditions::if_range_passes,
+};
+
+/// How a request for a representation is answered with respect to its `Range`
+/// header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteRanges {
+  /// The whole representation, `200 OK`
+  Full,
+  /// Parts of the representation, `206 Partial Content`. Overlapping and
+  /// adjacent ranges are combined.
+  Partial(Vec<Range>),
+  /// `416 Range Not Satisfiable`
+  Unsatisfiable,
+}
+
+/// Evaluate the `Range` and `If-Range` headers of a request for a
+/// representation of `size` bytes, described by `validators`.
+///
+/// Only `GET` requests for `bytes` ranges are ranged; malformed ranges and
+/// ranges whose `If-Range` does not match are ignored.
+pub fn byte_ranges(
+  method: &Method,
+  request_headers: &HeaderMap,
+  size: usize,
+  validators: &Validators,
+) -> ByteRanges {
+  if method != Method::GET {
+    return ByteRanges::Full;
+  }
+  let Some(range) = request_headers
+    .get(RANGE)
+    .and_then(|value| value.to_str().ok())
+  else {
+    return ByteRanges::Full;
+  };
+  if let Some(if_range) = request_headers
+    .get(IF_RANGE)
+    .and_then(|value| value.to_str().ok())
+    && !if_range_passes(if_range.trim(), validators)
+  {
+    return ByteRanges::Full;
+  }
+
+  match parse_range(size, range, Some(RangeOptions { combine: true })) {
+    Ok(ranges) if ranges.range_type == "bytes" => ByteRanges::Partial(ranges.ranges),
+    Ok(_) | Err(RangeParseError::InvalidFormat) => ByteRanges::Full,
+    Err(RangeParseError::Unsatisfiable) => ByteRanges::Unsatisfiable,
+  }
+}
+
+/// The `Content-Range` of a part of a representation of `size` bytes
+pub fn content_range(range: &Range, size: usize) -> String {
+  format!("bytes {}-{}/{size}", range.start, range.end)
+}
+
+/// The `Content-Range` of a `416 Range Not Satisfiable` response
+pub fn unsatisfied_range(size: usize) -> String {
+  format!("bytes */{size}")
+}
+
+/// Build a `multipart/byteranges` body from the `ranges` of `body`. Returns
+/// the `Content-Type` of the response and its body.
+pub fn multipart_byteranges(
+  body: &[u8],
+  ranges: &[Range],
+  content_type: Option<&str>,
+) -> (String, Bytes) {
+  let boundary = boundary();
+  let mut multipart = BytesMut::new();
+  for range in ranges {
+    multipart.put_slice(format!("--{boundary}\r\n").as_bytes());
+    if let Some(content_type) = content_type {
+      multipart.put_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+    }
+    multipart.put_slice(
+      format!(
+        "Content-Range: {}\r\n\r\n",
+        content_range(range, body.len())
+      )
+      .as_bytes(),
+    );
+    multipart.put_slice(&body[range.start..=range.end]);
+    multipart.put_slice(b"\r\n");
+  }
+  multipart.put_slice(format!("--{boundary}--\r\n").as_bytes());
+
+  (
+    format!("multipart/byteranges; boundary={boundary}"),
+    multipart.freeze(),
+  )
+}
+
+/// A random multipart boundary
+fn boundary() -> String {
+  let mut hasher = RandomState::new().build_hasher();
+  if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+    hasher.write_u128(elapsed.as_nanos());
+  }
+  let high = hasher.finish();
+  hasher.write_u64(high);
+  format!("{high:016x}{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, UNIX_EPOCH};
+
+  use etag::EntityTag;
+  use hyper::header::HeaderValue;
+
+  use super::*;
+
+  fn evaluate(method: Method, pairs: &[(hyper::header::HeaderName, &'static str)]) -> ByteRanges {
+    let headers = pairs
+      .iter()
+      .map(|(name, value)| (name.clone(), HeaderValue::from_static(value)))
+      .collect();
+    let validators = Validators {
+      etag: Some(EntityTag::strong("v1")),
+      last_modified: Some(UNIX_EPOCH + Duration::from_secs(946_684_800)),
+    };
+    byte_ranges(&method, &headers, 100, &validators)
+  }
+
+  #[test]
+  fn test_byte_ranges() {
+    assert_eq!(evaluate(Method::GET, &[]), ByteRanges::Full);
+    assert_eq!(
+      evaluate(Method::GET, &[(RANGE, "bytes=0-9")]),
+      ByteRanges::Partial(vec![Range { start: 0, end: 9 }])
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[(RANGE, "bytes=0-9,5-19,-10")]),
+      ByteRanges::Partial(vec![
+        Range { start: 0, end: 19 },
+        Range { start: 90, end: 99 }
+      ])
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[(RANGE, "bytes=200-300")]),
+      ByteRanges::Unsatisfiable
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[(RANGE, "bytes=nope")]),
+      ByteRanges::Full
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[(RANGE, "items=0-9")]),
+      ByteRanges::Full
+    );
+    assert_eq!(
+      evaluate(Method::HEAD, &[(RANGE, "bytes=0-9")]),
+      ByteRanges::Full
+    );
+  }
+
+  #[test]
+  fn test_if_range() {
+    assert_eq!(
+      evaluate(Method::GET, &[(RANGE, "bytes=0-9"), (IF_RANGE, "\"v1\"")]),
+      ByteRanges::Partial(vec![Range { start: 0, end: 9 }])
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[(RANGE, "bytes=0-9"), (IF_RANGE, "\"v0\"")]),
+      ByteRanges::Full
+    );
+    assert_eq!(
+      evaluate(
+        Method::GET,
+        &[
+          (RANGE, "bytes=0-9"),
+          (IF_RANGE, "Sat, 01 Jan 2000 00:00:00 GMT")
+        ]
+      ),
+      ByteRanges::Partial(vec![Range { start: 0, end: 9 }])
+    );
+  }
+
+  #[test]
+  fn test_multipart_byteranges() {
+    let ranges = [Range { start: 0, end: 1 }, Range { start: 4, end: 5 }];
+    let (content_type, body) = multipart_byteranges(b"abcdef", &ranges, Some("text/plain"));
+    let boundary = content_type
+      .strip_prefix("multipart/byteranges; boundary=")
+      .unwrap();
+    assert_eq!(
+      body,
+      format!(
+        "--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/6\r\n\r\nab\r\n\
+         --{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 4-5/6\r\n\r\nef\r\n\
+         --{boundary}--\r\n"
+      )
+    );
+  }
+
+  #[test]
+  fn test_content_range() {
+    assert_eq!(
+      content_range(&Range { start: 0, end: 9 }, 100),
+      "bytes 0-9/100"
+    );
+    assert_eq!(unsatisfied_range(100), "bytes */100");
+  }
+}
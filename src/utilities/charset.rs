@@ -0,0 +1,66 @@
+use encoding_rs::Encoding;
+use mediatype::{MediaType, ReadParams, names::CHARSET};
+
+/// Extract the `charset` parameter of a `Content-Type` header value, in lower
+/// case.
+pub fn content_type_charset(content_type: &str) -> Option<String> {
+  let media_type = MediaType::parse(content_type.trim()).ok()?;
+  let charset = media_type.get_param(CHARSET)?;
+  Some(charset.unquoted_str().trim().to_lowercase())
+}
+
+/// Look up a character encoding by one of its
+/// [WHATWG labels](https://encoding.spec.whatwg.org/#names-and-labels) (e.g.
+/// `utf-8`, `latin1`, `shift_jis`).
+pub fn lookup_charset(label: &str) -> Option<&'static Encoding> {
+  Encoding::for_label(label.trim().as_bytes())
+}
+
+/// Decode `bytes` with `encoding`, dropping a leading byte order mark.
+/// Malformed sequences are replaced with U+FFFD.
+pub fn decode_charset(bytes: &[u8], encoding: &'static Encoding) -> String {
+  encoding.decode_with_bom_removal(bytes).0.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_content_type_charset() {
+    assert_eq!(
+      content_type_charset("text/plain; charset=UTF-8"),
+      Some("utf-8".to_owned())
+    );
+    assert_eq!(
+      content_type_charset("text/plain; charset=\"Shift_JIS\""),
+      Some("shift_jis".to_owned())
+    );
+    assert_eq!(content_type_charset("text/plain"), None);
+    assert_eq!(content_type_charset("not a media type"), None);
+  }
+
+  #[test]
+  fn test_lookup_charset() {
+    assert_eq!(lookup_charset("utf8"), Some(encoding_rs::UTF_8));
+    assert_eq!(lookup_charset("latin1"), Some(encoding_rs::WINDOWS_1252));
+    assert_eq!(lookup_charset("Shift_JIS"), Some(encoding_rs::SHIFT_JIS));
+    assert_eq!(lookup_charset("klingon"), None);
+  }
+
+  #[test]
+  fn test_decode_charset() {
+    assert_eq!(
+      decode_charset(b"caf\xe9", encoding_rs::WINDOWS_1252),
+      "café"
+    );
+    assert_eq!(
+      decode_charset(
+        b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd",
+        encoding_rs::SHIFT_JIS
+      ),
+      "こんにちは"
+    );
+    assert_eq!(decode_charset(b"\xef\xbb\xbfhi", encoding_rs::UTF_8), "hi");
+  }
+}
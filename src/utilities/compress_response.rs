@@ -0,0 +1,411 @@
+use std::sync::Arc;
+
+use async_compression::Level;
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZlibEncoder, ZstdEncoder};
+use bytes::Bytes;
+use futures::TryStreamExt;
+use http_body::{Body as HttpBody, Frame};
+use http_body_util::{BodyExt, StreamBody};
+use hyper::{
+  Method, StatusCode,
+  header::{
+    CACHE_CONTROL, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, HeaderValue,
+  },
+};
+use napi::Result;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use crate::{
+  response::{CrateBody, WrappedResponse},
+  utilities::type_is,
+};
+
+/// Content codings a response can be compressed with, in order of preference
+/// when a client accepts several of them equally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+  Brotli,
+  Zstd,
+  Gzip,
+  Deflate,
+}
+
+impl ContentEncoding {
+  const PREFERENCE: [Self; 4] = [Self::Brotli, Self::Zstd, Self::Gzip, Self::Deflate];
+
+  pub fn token(self) -> &'static str {
+    match self {
+      Self::Brotli => "br",
+      Self::Zstd => "zstd",
+      Self::Gzip => "gzip",
+      Self::Deflate => "deflate",
+    }
+  }
+
+  /// Pick the encoding a client prefers from its `Accept-Encoding` header.
+  /// Encodings with an equal quality are picked in order of `PREFERENCE`.
+  pub fn negotiate(accept_encoding: &str) -> Option<Self> {
+    Self::negotiate_among(accept_encoding, &Self::PREFERENCE)
+  }
+
+  /// Pick the encoding a client prefers among `available` ones, e.g. the
+  /// precompressed variants of a body.
+  pub fn negotiate_among(accept_encoding: &str, available: &[Self]) -> Option<Self> {
+    let mut codings = Vec::new();
+    for coding in accept_encoding.split(',') {
+      let mut params = coding.split(';');
+      let name = params.next().unwrap_or_default().trim().to_lowercase();
+      if name.is_empty() {
+        continue;
+      }
+      let quality = params
+        .find_map(|param| {
+          let (key, value) = param.split_once('=')?;
+          (key.trim().eq_ignore_ascii_case("q")).then(|| value.trim().parse::<f32>().ok())?
+        })
+        .unwrap_or(1.0);
+      codings.push((name, quality));
+    }
+
+    let quality = |token: &str| {
+      codings
+        .iter()
+        .find(|(name, _)| name == token || (token == "gzip" && name == "x-gzip"))
+        .or_else(|| codings.iter().find(|(name, _)| name == "*"))
+        .map(|(_, quality)| *quality)
+        .unwrap_or(0.0)
+    };
+
+    let mut best: Option<(Self, f32)> = None;
+    for encoding in Self::PREFERENCE
+      .into_iter()
+      .filter(|encoding| available.contains(encoding))
+    {
+      let quality = quality(encoding.token());
+      if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+        best = Some((encoding, quality));
+      }
+    }
+    best.map(|(encoding, _)| encoding)
+  }
+}
+
+/// Options of response compression
+#[derive(Debug, Clone)]
+pub struct CompressionOptions {
+  /// Minimum size, in bytes, of a response body to compress it. Bodies of an
+  /// unknown size are always compressed.
+  pub threshold: usize,
+  /// Compression level, passed to the encoder; `None` uses the encoder's
+  /// default level
+  pub level: Option<i32>,
+  /// Media types to compress, as `type_is` patterns; `None` compresses the
+  /// types known to be compressible (text, JSON, JavaScript, XML, ...)
+  pub typ: Option<Vec<String>>,
+}
+
+impl Default for CompressionOptions {
+  fn default() -> Self {
+    Self {
+      threshold: 1024,
+      level: None,
+      typ: None,
+    }
+  }
+}
+
+impl CompressionOptions {
+  fn should_compress(&self, content_type: &str) -> bool {
+    match &self.typ {
+      Some(types) => {
+        let types = types.iter().map(String::as_str).collect::<Vec<_>>();
+        type_is(content_type, &types).is_some()
+      }
+      None => is_compressible(content_type),
+    }
+  }
+}
+
+/// Whether a media type is known to benefit from compression
+pub fn is_compressible(content_type: &str) -> bool {
+  let essence = content_type
+    .split(';')
+    .next()
+    .unwrap_or_default()
+    .trim()
+    .to_lowercase();
+  let Some((typ, subtype)) = essence.split_once('/') else {
+    return false;
+  };
+  typ == "text"
+    || subtype.ends_with("+json")
+    || subtype.ends_with("+xml")
+    || matches!(
+      subtype,
+      "json"
+        | "javascript"
+        | "ecmascript"
+        | "xml"
+        | "wasm"
+        | "x-www-form-urlencoded"
+        | "graphql"
+        | "x-javascript"
+    )
+}
+
+/// The compression negotiated for a request, applied to its response once the
+/// response is complete.
+pub struct ResponseCompression {
+  options: Arc<CompressionOptions>,
+  /// `None` when the client accepts none of the supported encodings
+  encoding: Option<ContentEncoding>,
+  /// Responses to `HEAD` requests are never compressed
+  head: bool,
+}
+
+impl ResponseCompression {
+  pub fn new(
+    options: Arc<CompressionOptions>,
+    method: &Method,
+    accept_encoding: Option<&HeaderValue>,
+  ) -> Self {
+    let encoding = accept_encoding
+      .and_then(|value| value.to_str().ok())
+      .and_then(ContentEncoding::negotiate);
+    Self {
+      options,
+      encoding,
+      head: method == Method::HEAD,
+    }
+  }
+
+  /// Compress the body of `response`, unless its media type is filtered out,
+  /// it is smaller than the threshold, it is already encoded, it is partial
+  /// content or its `Cache-Control` header has a `no-transform` directive.
+  ///
+  /// A strong `ETag` of a compressed response is weakened, since the
+  /// compressed body is not byte-for-byte identical to the original one.
+  pub fn apply(&self, response: &mut WrappedResponse) -> Result<()> {
+    let headers = response.inner()?.headers();
+    let Some(content_type) = headers
+      .get(CONTENT_TYPE)
+      .and_then(|value| value.to_str().ok())
+    else {
+      return Ok(());
+    };
+    if !self.options.should_compress(content_type) {
+      return Ok(());
+    }
+    let no_transform = headers
+      .get_all(CACHE_CONTROL)
+      .iter()
+      .filter_map(|value| value.to_str().ok())
+      .flat_map(|value| value.split(','))
+      .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"));
+    if no_transform {
+      return Ok(());
+    }
+
+    response.vary("Accept-Encoding".to_owned())?;
+
+    let inner = response.inner()?;
+    let status = inner.status();
+    if status == StatusCode::NO_CONTENT
+      || status == StatusCode::NOT_MODIFIED
+      || status == StatusCode::PARTIAL_CONTENT
+      || inner.headers().contains_key(CONTENT_RANGE)
+    {
+      return Ok(());
+    }
+
+    let length = inner
+      .headers()
+      .get(CONTENT_LENGTH)
+      .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+      .or_else(|| inner.body().size_hint().exact());
+    if length.is_some_and(|length| length < self.options.threshold as u64) {
+      return Ok(());
+    }
+
+    let encoded = inner
+      .headers()
+      .get(CONTENT_ENCODING)
+      .is_some_and(|value| !value.as_bytes().eq_ignore_ascii_case(b"identity"));
+    if encoded || self.head {
+      return Ok(());
+    }
+
+    let Some(encoding) = self.encoding else {
+      return Ok(());
+    };
+
+    let headers = inner.headers_mut();
+    headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.token()));
+    headers.remove(CONTENT_LENGTH);
+    if let Some(etag) = headers.get(ETAG)
+      && !etag.as_bytes().starts_with(b"W/")
+      && let Ok(weak) = HeaderValue::from_bytes(&[b"W/", etag.as_bytes()].concat())
+    {
+      headers.insert(ETAG, weak);
+    }
+
+    let compressed = response
+      .take()?
+      .map(|body| encode_body(body, encoding, self.options.level));
+    response.set_inner(compressed);
+    Ok(())
+  }
+}
+
+/// Compress `bytes` at once, e.g. to precompute the compressed variants of a
+/// cached body
+pub fn compress_bytes(bytes: &[u8], encoding: ContentEncoding) -> std::io::Result<Bytes> {
+  let mut encoder: Box<dyn AsyncRead + Send + Unpin> = match encoding {
+    ContentEncoding::Brotli => Box::new(BrotliEncoder::new(bytes)),
+    ContentEncoding::Zstd => Box::new(ZstdEncoder::new(bytes)),
+    ContentEncoding::Gzip => Box::new(GzipEncoder::new(bytes)),
+    ContentEncoding::Deflate => Box::new(ZlibEncoder::new(bytes)),
+  };
+  let mut compressed = Vec::new();
+  // encoding an in-memory buffer never waits on I/O
+  futures::executor::block_on(encoder.read_to_end(&mut compressed))?;
+  Ok(compressed.into())
+}
+
+fn encode_body(body: CrateBody, encoding: ContentEncoding, level: Option<i32>) -> CrateBody {
+  let level = level.map_or(Level::Default, Level::Precise);
+  let reader = StreamReader::new(body.into_data_stream());
+  let encoder: Box<dyn AsyncRead + Send + Unpin> = match encoding {
+    ContentEncoding::Brotli => Box::new(BrotliEncoder::with_quality(reader, level)),
+    ContentEncoding::Zstd => Box::new(ZstdEncoder::with_quality(reader, level)),
+    ContentEncoding::Gzip => Box::new(GzipEncoder::with_quality(reader, level)),
+    ContentEncoding::Deflate => Box::new(ZlibEncoder::with_quality(reader, level)),
+  };
+  let stream = ReaderStream::new(encoder).map_ok(Frame::data);
+  CrateBody::Stream(StreamBody::new(stream).boxed_unsync())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Read;
+
+  use flate2::read::GzDecoder;
+
+  use hyper::Response as LibResponse;
+
+  use super::*;
+  use crate::utilities::full;
+
+  fn build_response(content_type: &str, body: &'static str) -> LibResponse<CrateBody> {
+    LibResponse::builder()
+      .header(CONTENT_TYPE, content_type)
+      .body(full(body))
+      .unwrap()
+  }
+
+  /// Compress `response` for a `GET` request accepting `accept_encoding`
+  fn compress(accept_encoding: &str, response: LibResponse<CrateBody>) -> LibResponse<CrateBody> {
+    let mut response = WrappedResponse::from(response);
+    compression(accept_encoding).apply(&mut response).unwrap();
+    response.take().unwrap()
+  }
+
+  fn compression(accept_encoding: &str) -> ResponseCompression {
+    ResponseCompression::new(
+      Arc::new(CompressionOptions {
+        threshold: 8,
+        ..Default::default()
+      }),
+      &Method::GET,
+      Some(&HeaderValue::from_str(accept_encoding).unwrap()),
+    )
+  }
+
+  async fn body_bytes(response: LibResponse<CrateBody>) -> Vec<u8> {
+    response
+      .into_body()
+      .collect()
+      .await
+      .unwrap()
+      .to_bytes()
+      .to_vec()
+  }
+
+  #[test]
+  fn test_negotiate() {
+    assert_eq!(
+      ContentEncoding::negotiate("gzip, deflate, br"),
+      Some(ContentEncoding::Brotli)
+    );
+    assert_eq!(
+      ContentEncoding::negotiate("gzip;q=1, br;q=0.5"),
+      Some(ContentEncoding::Gzip)
+    );
+    assert_eq!(
+      ContentEncoding::negotiate("*;q=0.1, br;q=0"),
+      Some(ContentEncoding::Zstd)
+    );
+    assert_eq!(ContentEncoding::negotiate("identity"), None);
+    assert_eq!(ContentEncoding::negotiate(""), None);
+  }
+
+  #[tokio::test]
+  async fn test_compress_gzip() {
+    let mut response = build_response("application/json", r#"{"message":"compress me"}"#);
+    response
+      .headers_mut()
+      .insert(ETAG, HeaderValue::from_static("\"abc\""));
+    let response = compress("gzip", response);
+
+    let headers = response.headers();
+    assert_eq!(headers[CONTENT_ENCODING], "gzip");
+    assert_eq!(headers[ETAG], "W/\"abc\"");
+    assert_eq!(headers[hyper::header::VARY], "Accept-Encoding");
+    assert!(!headers.contains_key(CONTENT_LENGTH));
+
+    let body = body_bytes(response).await;
+    let mut decoded = String::new();
+    GzDecoder::new(body.as_slice())
+      .read_to_string(&mut decoded)
+      .unwrap();
+    assert_eq!(decoded, r#"{"message":"compress me"}"#);
+  }
+
+  #[tokio::test]
+  async fn test_skip_compression() {
+    // below the threshold
+    let response = compress("gzip", build_response("text/plain", "tiny"));
+    assert!(!response.headers().contains_key(CONTENT_ENCODING));
+    assert_eq!(response.headers()[hyper::header::VARY], "Accept-Encoding");
+
+    // not a compressible type
+    let response = compress("gzip", build_response("image/png", "not really a png"));
+    assert!(!response.headers().contains_key(CONTENT_ENCODING));
+    assert!(!response.headers().contains_key(hyper::header::VARY));
+
+    // no-transform
+    let mut no_transform = build_response("text/plain", "some text to send");
+    no_transform
+      .headers_mut()
+      .insert(CACHE_CONTROL, HeaderValue::from_static("no-transform"));
+    let response = compress("gzip", no_transform);
+    assert!(!response.headers().contains_key(CONTENT_ENCODING));
+
+    // already encoded
+    let mut encoded = build_response("text/plain", "some text to send");
+    encoded
+      .headers_mut()
+      .insert(CONTENT_ENCODING, HeaderValue::from_static("br"));
+    let response = compress("gzip", encoded);
+    assert_eq!(response.headers()[CONTENT_ENCODING], "br");
+
+    // no accepted encoding
+    let response = compress(
+      "identity",
+      build_response("text/plain", "some text to send"),
+    );
+    assert!(!response.headers().contains_key(CONTENT_ENCODING));
+    assert_eq!(body_bytes(response).await, b"some text to send");
+  }
+}
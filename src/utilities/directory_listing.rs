@@ -0,0 +1,436 @@
+use std::{cmp::Ordering, fs, io, path::Path, time::SystemTime};
+
+use askama_escape::escape_html;
+use chrono::{DateTime, SecondsFormat, Utc};
+use headers_accept::Accept;
+use mediatype::{
+  MediaType,
+  names::{APPLICATION, HTML, JSON, PLAIN, TEXT},
+};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+
+/// Characters percent-encoded in the links of a listing
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+  .remove(b'-')
+  .remove(b'.')
+  .remove(b'_')
+  .remove(b'~');
+
+const TEXT_HTML: MediaType = MediaType::new(TEXT, HTML);
+const TEXT_PLAIN: MediaType = MediaType::new(TEXT, PLAIN);
+const APPLICATION_JSON: MediaType = MediaType::new(APPLICATION, JSON);
+
+/// The order of the entries of a listing. Directories are always listed
+/// first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ListingSort {
+  /// By name, ascending
+  #[default]
+  Name,
+  /// By size, ascending
+  Size,
+  /// By modification date, most recent first
+  Modified,
+}
+
+/// Options of directory listings
+#[derive(Debug, Clone)]
+pub struct DirectoryListingOptions {
+  /// Show an icon in front of each entry of HTML listings
+  pub icons: bool,
+  /// The order of the entries
+  pub sort: ListingSort,
+}
+
+impl Default for DirectoryListingOptions {
+  fn default() -> Self {
+    Self {
+      icons: true,
+      sort: ListingSort::Name,
+    }
+  }
+}
+
+/// The formats a listing can be rendered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingFormat {
+  Html,
+  Json,
+  Text,
+}
+
+impl ListingFormat {
+  /// Negotiate the format of a listing from the `Accept` header of a request.
+  /// HTML is preferred; `None` when no format is acceptable.
+  pub fn negotiate(accept: Option<&str>) -> Option<Self> {
+    let Some(accept) = accept else {
+      return Some(ListingFormat::Html);
+    };
+    let Ok(accept) = accept.parse::<Accept>() else {
+      return Some(ListingFormat::Html);
+    };
+    match accept.negotiate(&[TEXT_HTML, TEXT_PLAIN, APPLICATION_JSON]) {
+      Some(media_type) if *media_type == TEXT_HTML => Some(ListingFormat::Html),
+      Some(media_type) if *media_type == TEXT_PLAIN => Some(ListingFormat::Text),
+      Some(_) => Some(ListingFormat::Json),
+      None => None,
+    }
+  }
+
+  /// The `Content-Type` of a listing in this format
+  pub fn content_type(&self) -> &'static str {
+    match self {
+      ListingFormat::Html => "text/html; charset=utf-8",
+      ListingFormat::Json => "application/json; charset=utf-8",
+      ListingFormat::Text => "text/plain; charset=utf-8",
+    }
+  }
+}
+
+/// An entry of a directory listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryEntry {
+  pub name: String,
+  pub is_dir: bool,
+  pub size: u64,
+  pub modified: Option<SystemTime>,
+}
+
+/// Read the entries of `directory`, sorted by `sort`. Dotfiles are only
+/// listed when `dotfiles` is `"allow"`, and entries whose name is not valid
+/// UTF-8 are skipped.
+pub fn read_directory(
+  directory: &Path,
+  dotfiles: &str,
+  sort: ListingSort,
+) -> io::Result<Vec<DirectoryEntry>> {
+  let mut entries = Vec::new();
+  for entry in fs::read_dir(directory)? {
+    let entry = entry?;
+    let Ok(name) = entry.file_name().into_string() else {
+      continue;
+    };
+    if name.starts_with('.') && dotfiles != "allow" {
+      continue;
+    }
+    // follow symbolic links, skipping broken ones
+    let Ok(metadata) = fs::metadata(entry.path()) else {
+      continue;
+    };
+    entries.push(DirectoryEntry {
+      name,
+      is_dir: metadata.is_dir(),
+      size: if metadata.is_dir() { 0 } else { metadata.len() },
+      modified: metadata.modified().ok(),
+    });
+  }
+  sort_entries(&mut entries, sort);
+  Ok(entries)
+}
+
+fn sort_entries(entries: &mut [DirectoryEntry], sort: ListingSort) {
+  entries.sort_by(|a, b| {
+    let order = match sort {
+      ListingSort::Name => Ordering::Equal,
+      ListingSort::Size => a.size.cmp(&b.size),
+      ListingSort::Modified => b.modified.cmp(&a.modified),
+    };
+    b.is_dir
+      .cmp(&a.is_dir)
+      .then(order)
+      .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+      .then_with(|| a.name.cmp(&b.name))
+  });
+}
+
+/// Render the listing of `entries`, the content of the directory at URL path
+/// `directory`.
+pub fn render_listing(
+  format: ListingFormat,
+  directory: &str,
+  entries: &[DirectoryEntry],
+  options: &DirectoryListingOptions,
+) -> String {
+  match format {
+    ListingFormat::Html => render_html(directory, entries, options),
+    ListingFormat::Json => render_json(entries),
+    ListingFormat::Text => entries
+      .iter()
+      .map(|entry| match entry.is_dir {
+        true => format!("{}/\n", entry.name),
+        false => format!("{}\n", entry.name),
+      })
+      .collect(),
+  }
+}
+
+fn render_json(entries: &[DirectoryEntry]) -> String {
+  let entries = entries
+    .iter()
+    .map(|entry| {
+      serde_json::json!({
+        "name": entry.name,
+        "type": if entry.is_dir { "directory" } else { "file" },
+        "size": entry.size,
+        "modified": entry.modified.map(|modified| {
+          DateTime::<Utc>::from(modified).to_rfc3339_opts(SecondsFormat::Millis, true)
+        }),
+      })
+    })
+    .collect::<Vec<_>>();
+  serde_json::Value::Array(entries).to_string()
+}
+
+fn render_html(
+  directory: &str,
+  entries: &[DirectoryEntry],
+  options: &DirectoryListingOptions,
+) -> String {
+  let segments = directory
+    .split('/')
+    .filter(|segment| !segment.is_empty())
+    .collect::<Vec<_>>();
+
+  // breadcrumbs, with links relative to the listed directory
+  let mut breadcrumbs = format!("<a href=\"{}\">~</a>", relative_parent(segments.len()));
+  for (i, segment) in segments.iter().enumerate() {
+    breadcrumbs.push_str(&format!(
+      " / <a href=\"{}\">{}</a>",
+      relative_parent(segments.len() - i - 1),
+      escape(segment)
+    ));
+  }
+
+  let mut rows = String::new();
+  if !segments.is_empty() {
+    rows.push_str(&row(options, "&#x2B06;&#xFE0F;", "../", "..", "", ""));
+  }
+  for entry in entries {
+    let mut href = utf8_percent_encode(&entry.name, PATH_SEGMENT).to_string();
+    let mut name = escape(&entry.name);
+    let size = match entry.is_dir {
+      true => String::new(),
+      false => human_size(entry.size),
+    };
+    if entry.is_dir {
+      href.push('/');
+      name.push('/');
+    }
+    let modified = entry
+      .modified
+      .map(|modified| {
+        DateTime::<Utc>::from(modified)
+          .format("%Y-%m-%d %H:%M")
+          .to_string()
+      })
+      .unwrap_or_default();
+    rows.push_str(&row(options, icon(entry), &href, &name, &size, &modified));
+  }
+
+  let title = escape(&format!("/{}", segments.join("/")));
+  format!(
+    "<!DOCTYPE html>\n\
+     <html lang=\"en\">\n\
+     <head>\n\
+     <meta charset=\"utf-8\">\n\
+     <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+     <title>listing directory {title}</title>\n\
+     <style>\n\
+     body {{ font-family: system-ui, sans-serif; margin: 2em; }}\n\
+     table {{ border-collapse: collapse; }}\n\
+     td {{ padding: 0.2em 1em 0.2em 0; }}\n\
+     td.size, td.modified {{ color: #666; white-space: nowrap; }}\n\
+     td.size {{ text-align: right; }}\n\
+     a {{ text-decoration: none; }}\n\
+     </style>\n\
+     </head>\n\
+     <body>\n\
+     <h1>{breadcrumbs}</h1>\n\
+     <table>\n\
+     {rows}\
+     </table>\n\
+     </body>\n\
+     </html>\n"
+  )
+}
+
+fn row(
+  options: &DirectoryListingOptions,
+  icon: &str,
+  href: &str,
+  name: &str,
+  size: &str,
+  modified: &str,
+) -> String {
+  let icon = match options.icons {
+    true => format!("<td class=\"icon\">{icon}</td>"),
+    false => String::new(),
+  };
+  format!(
+    "<tr>{icon}<td class=\"name\"><a href=\"{href}\">{name}</a></td>\
+     <td class=\"size\">{size}</td><td class=\"modified\">{modified}</td></tr>\n"
+  )
+}
+
+/// The link from the listed directory to its ancestor `depth` levels up
+fn relative_parent(depth: usize) -> String {
+  match depth {
+    0 => "./".to_owned(),
+    depth => "../".repeat(depth),
+  }
+}
+
+/// An emoji icon for `entry`, from its media type
+fn icon(entry: &DirectoryEntry) -> &'static str {
+  if entry.is_dir {
+    return "&#x1F4C1;";
+  }
+  let Some(mime) = mime_guess::from_path(&entry.name).first() else {
+    return "&#x1F4C4;";
+  };
+  match (mime.type_().as_str(), mime.subtype().as_str()) {
+    ("image", _) => "&#x1F5BC;&#xFE0F;",
+    ("audio", _) => "&#x1F3B5;",
+    ("video", _) => "&#x1F3AC;",
+    ("font", _) => "&#x1F524;",
+    (
+      "application",
+      "zip" | "gzip" | "x-tar" | "x-7z-compressed" | "x-rar-compressed" | "x-bzip2" | "zstd",
+    ) => "&#x1F4E6;",
+    ("application", "pdf") => "&#x1F4D5;",
+    ("text", "html" | "css" | "javascript") | ("application", "javascript" | "json" | "xml") => {
+      "&#x1F4DD;"
+    }
+    _ => "&#x1F4C4;",
+  }
+}
+
+fn escape(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  // writing to a `String` cannot fail
+  let _ = escape_html(&mut escaped, value);
+  escaped
+}
+
+/// Format `size` bytes for humans, e.g. `1.5 kB`
+pub fn human_size(size: u64) -> String {
+  const UNITS: [&str; 5] = ["kB", "MB", "GB", "TB", "PB"];
+  if size < 1000 {
+    return format!("{size} B");
+  }
+  let mut size = size as f64 / 1000.0;
+  let mut unit = 0;
+  while size >= 1000.0 && unit < UNITS.len() - 1 {
+    size /= 1000.0;
+    unit += 1;
+  }
+  match size < 10.0 {
+    true => format!("{size:.1} {}", UNITS[unit]),
+    false => format!("{size:.0} {}", UNITS[unit]),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, UNIX_EPOCH};
+
+  use super::*;
+
+  fn entry(name: &str, is_dir: bool, size: u64, modified: u64) -> DirectoryEntry {
+    DirectoryEntry {
+      name: name.to_owned(),
+      is_dir,
+      size,
+      modified: Some(UNIX_EPOCH + Duration::from_secs(modified)),
+    }
+  }
+
+  fn names(entries: &[DirectoryEntry]) -> Vec<&str> {
+    entries.iter().map(|entry| entry.name.as_str()).collect()
+  }
+
+  #[test]
+  fn test_negotiate() {
+    assert_eq!(ListingFormat::negotiate(None), Some(ListingFormat::Html));
+    assert_eq!(
+      ListingFormat::negotiate(Some("*/*")),
+      Some(ListingFormat::Html)
+    );
+    assert_eq!(
+      ListingFormat::negotiate(Some("application/json")),
+      Some(ListingFormat::Json)
+    );
+    assert_eq!(
+      ListingFormat::negotiate(Some("text/plain, text/html;q=0.5")),
+      Some(ListingFormat::Text)
+    );
+    assert_eq!(ListingFormat::negotiate(Some("image/png")), None);
+  }
+
+  #[test]
+  fn test_sort_entries() {
+    let mut entries = vec![
+      entry("b.txt", false, 10, 3),
+      entry("A.txt", false, 30, 1),
+      entry("z", true, 0, 0),
+      entry("c.txt", false, 20, 2),
+    ];
+    sort_entries(&mut entries, ListingSort::Name);
+    assert_eq!(names(&entries), ["z", "A.txt", "b.txt", "c.txt"]);
+    sort_entries(&mut entries, ListingSort::Size);
+    assert_eq!(names(&entries), ["z", "b.txt", "c.txt", "A.txt"]);
+    sort_entries(&mut entries, ListingSort::Modified);
+    assert_eq!(names(&entries), ["z", "b.txt", "c.txt", "A.txt"]);
+  }
+
+  #[test]
+  fn test_read_directory() {
+    let directory = tempfile::TempDir::new().unwrap();
+    fs::write(directory.path().join("file.txt"), "hello").unwrap();
+    fs::write(directory.path().join(".hidden"), "").unwrap();
+    fs::create_dir(directory.path().join("sub")).unwrap();
+
+    let entries = read_directory(directory.path(), "ignore", ListingSort::Name).unwrap();
+    assert_eq!(names(&entries), ["sub", "file.txt"]);
+    assert!(entries[0].is_dir);
+    assert_eq!(entries[1].size, 5);
+
+    let entries = read_directory(directory.path(), "allow", ListingSort::Name).unwrap();
+    assert_eq!(names(&entries), ["sub", ".hidden", "file.txt"]);
+  }
+
+  #[test]
+  fn test_render_listing() {
+    let entries = [entry("sub", true, 0, 0), entry("<a&b>.txt", false, 1500, 0)];
+    let options = DirectoryListingOptions::default();
+
+    let html = render_listing(ListingFormat::Html, "/files/", &entries, &options);
+    assert!(html.contains("<title>listing directory /files</title>"));
+    assert!(html.contains("<a href=\"../\">..</a>"));
+    assert!(html.contains("<a href=\"sub/\">sub/</a>"));
+    assert!(html.contains(&format!(
+      "<a href=\"%3Ca%26b%3E.txt\">{}</a>",
+      escape("<a&b>.txt")
+    )));
+    assert!(!html.contains("<a&b>"));
+    assert!(html.contains("1.5 kB"));
+
+    let text = render_listing(ListingFormat::Text, "/", &entries, &options);
+    assert_eq!(text, "sub/\n<a&b>.txt\n");
+
+    let json = render_listing(ListingFormat::Json, "/", &entries, &options);
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json[0]["type"], "directory");
+    assert_eq!(json[1]["size"], 1500);
+    assert_eq!(json[1]["modified"], "1970-01-01T00:00:00.000Z");
+  }
+
+  #[test]
+  fn test_human_size() {
+    assert_eq!(human_size(0), "0 B");
+    assert_eq!(human_size(999), "999 B");
+    assert_eq!(human_size(1500), "1.5 kB");
+    assert_eq!(human_size(25_000), "25 kB");
+    assert_eq!(human_size(3_200_000_000), "3.2 GB");
+  }
+}
@@ -2,6 +2,7 @@ use std::{
   fs::{self, File},
   io::Write,
   path::PathBuf,
+  time::{Duration, SystemTime},
 };
 
 use bytes::Bytes;
@@ -10,11 +11,10 @@ use hyper::{
   HeaderMap, Method, Request as HyperRequest, StatusCode,
   header::{self, HeaderValue},
 };
-use napi::bindgen_prelude::Task;
 use tempfile::TempDir;
 
 use super::{FileSendOptions, FileSendTask};
-use crate::{request::WrappedRequest, response::Response};
+use crate::{request::WrappedRequest, response::Response, utilities::StaticFs};
 
 /// Test helper to create a test directory structure
 struct TestFixture {
@@ -53,8 +53,8 @@ impl TestFixture {
 // Test 1: Basic File Serving
 // ============================================================================
 
-#[test]
-fn test_serve_basic_file() {
+#[tokio::test]
+async fn test_serve_basic_file() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Hello, World!");
 
@@ -71,7 +71,7 @@ fn test_serve_basic_file() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   // Verify response
   task
@@ -91,8 +91,8 @@ fn test_serve_basic_file() {
 // Test 2: Directory Index Files
 // ============================================================================
 
-#[test]
-fn test_directory_index_default() {
+#[tokio::test]
+async fn test_directory_index_default() {
   let fixture = TestFixture::new();
   fixture.create_dir("public");
   fixture.create_file("public/index.html", "<html>Index</html>");
@@ -111,7 +111,7 @@ fn test_directory_index_default() {
     path: "/public/".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -123,8 +123,8 @@ fn test_directory_index_default() {
     .unwrap();
 }
 
-#[test]
-fn test_directory_index_multiple() {
+#[tokio::test]
+async fn test_directory_index_multiple() {
   let fixture = TestFixture::new();
   fixture.create_dir("public");
   fixture.create_file("public/default.html", "<html>Default</html>");
@@ -147,7 +147,7 @@ fn test_directory_index_multiple() {
     path: "/public/".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   // Should serve default.html since index.html and index.htm don't exist
   task
@@ -160,8 +160,8 @@ fn test_directory_index_multiple() {
     .unwrap();
 }
 
-#[test]
-fn test_directory_no_index() {
+#[tokio::test]
+async fn test_directory_no_index() {
   let fixture = TestFixture::new();
   fixture.create_dir("public");
 
@@ -179,7 +179,7 @@ fn test_directory_no_index() {
     path: "/public/".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   // Should return 404
   task
@@ -196,8 +196,8 @@ fn test_directory_no_index() {
 // Test 3: Extension Fallback
 // ============================================================================
 
-#[test]
-fn test_extension_fallback() {
+#[tokio::test]
+async fn test_extension_fallback() {
   let fixture = TestFixture::new();
   fixture.create_file("about.html", "<html>About</html>");
 
@@ -215,7 +215,7 @@ fn test_extension_fallback() {
     path: "/about".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   // Should find about.html
   task
@@ -228,8 +228,8 @@ fn test_extension_fallback() {
     .unwrap();
 }
 
-#[test]
-fn test_extension_fallback_priority() {
+#[tokio::test]
+async fn test_extension_fallback_priority() {
   let fixture = TestFixture::new();
   fixture.create_file("page.html", "HTML version");
   fixture.create_file("page.htm", "HTM version");
@@ -248,7 +248,7 @@ fn test_extension_fallback_priority() {
     path: "/page".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   // Should serve .html first (based on priority order)
   task
@@ -261,8 +261,8 @@ fn test_extension_fallback_priority() {
     .unwrap();
 }
 
-#[test]
-fn test_no_extension_fallback_when_file_exists() {
+#[tokio::test]
+async fn test_no_extension_fallback_when_file_exists() {
   let fixture = TestFixture::new();
   fixture.create_file("exact", "Exact file");
   fixture.create_file("exact.html", "HTML file");
@@ -281,7 +281,7 @@ fn test_no_extension_fallback_when_file_exists() {
     path: "/exact".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   // Should serve the exact file, not the .html version
   task
@@ -298,8 +298,8 @@ fn test_no_extension_fallback_when_file_exists() {
 // Test 4: Dotfile Handling
 // ============================================================================
 
-#[test]
-fn test_dotfile_allow() {
+#[tokio::test]
+async fn test_dotfile_allow() {
   let fixture = TestFixture::new();
   fixture.create_file(".secret", "Secret content");
 
@@ -317,7 +317,7 @@ fn test_dotfile_allow() {
     path: "/.secret".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -329,8 +329,8 @@ fn test_dotfile_allow() {
     .unwrap();
 }
 
-#[test]
-fn test_dotfile_deny() {
+#[tokio::test]
+async fn test_dotfile_deny() {
   let fixture = TestFixture::new();
   fixture.create_file(".secret", "Secret content");
 
@@ -348,7 +348,7 @@ fn test_dotfile_deny() {
     path: "/.secret".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -361,8 +361,8 @@ fn test_dotfile_deny() {
     .unwrap();
 }
 
-#[test]
-fn test_dotfile_ignore() {
+#[tokio::test]
+async fn test_dotfile_ignore() {
   let fixture = TestFixture::new();
   fixture.create_file(".secret", "Secret content");
 
@@ -380,7 +380,7 @@ fn test_dotfile_ignore() {
     path: "/.secret".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -393,8 +393,8 @@ fn test_dotfile_ignore() {
     .unwrap();
 }
 
-#[test]
-fn test_dotfile_in_path() {
+#[tokio::test]
+async fn test_dotfile_in_path() {
   let fixture = TestFixture::new();
   fixture.create_dir(".hidden");
   fixture.create_file(".hidden/file.txt", "Hidden file");
@@ -413,7 +413,7 @@ fn test_dotfile_in_path() {
     path: "/.hidden/file.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -429,8 +429,8 @@ fn test_dotfile_in_path() {
 // Test 5: Cache Control Headers
 // ============================================================================
 
-#[test]
-fn test_cache_control_enabled() {
+#[tokio::test]
+async fn test_cache_control_enabled() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Content");
 
@@ -449,7 +449,7 @@ fn test_cache_control_enabled() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -466,8 +466,8 @@ fn test_cache_control_enabled() {
     .unwrap();
 }
 
-#[test]
-fn test_cache_control_disabled() {
+#[tokio::test]
+async fn test_cache_control_disabled() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Content");
 
@@ -485,7 +485,7 @@ fn test_cache_control_disabled() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -498,8 +498,8 @@ fn test_cache_control_disabled() {
     .unwrap();
 }
 
-#[test]
-fn test_cache_control_immutable() {
+#[tokio::test]
+async fn test_cache_control_immutable() {
   let fixture = TestFixture::new();
   fixture.create_file("app.js", "// App code");
 
@@ -519,7 +519,7 @@ fn test_cache_control_immutable() {
     path: "/app.js".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -538,8 +538,8 @@ fn test_cache_control_immutable() {
 // Test 6: ETag and Last-Modified Headers
 // ============================================================================
 
-#[test]
-fn test_etag_header() {
+#[tokio::test]
+async fn test_etag_header() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Content");
 
@@ -557,7 +557,7 @@ fn test_etag_header() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -569,8 +569,8 @@ fn test_etag_header() {
     .unwrap();
 }
 
-#[test]
-fn test_etag_disabled() {
+#[tokio::test]
+async fn test_etag_disabled() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Content");
 
@@ -588,7 +588,7 @@ fn test_etag_disabled() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -600,8 +600,8 @@ fn test_etag_disabled() {
     .unwrap();
 }
 
-#[test]
-fn test_last_modified_header() {
+#[tokio::test]
+async fn test_last_modified_header() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Content");
 
@@ -619,7 +619,7 @@ fn test_last_modified_header() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -631,8 +631,8 @@ fn test_last_modified_header() {
     .unwrap();
 }
 
-#[test]
-fn test_last_modified_disabled() {
+#[tokio::test]
+async fn test_last_modified_disabled() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Content");
 
@@ -650,7 +650,7 @@ fn test_last_modified_disabled() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -666,8 +666,8 @@ fn test_last_modified_disabled() {
 // Test 7: Accept-Ranges Header
 // ============================================================================
 
-#[test]
-fn test_accept_ranges_enabled() {
+#[tokio::test]
+async fn test_accept_ranges_enabled() {
   let fixture = TestFixture::new();
   fixture.create_file("video.mp4", "fake video data");
 
@@ -685,7 +685,7 @@ fn test_accept_ranges_enabled() {
     path: "/video.mp4".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -697,8 +697,8 @@ fn test_accept_ranges_enabled() {
     .unwrap();
 }
 
-#[test]
-fn test_accept_ranges_disabled() {
+#[tokio::test]
+async fn test_accept_ranges_disabled() {
   let fixture = TestFixture::new();
   fixture.create_file("video.mp4", "fake video data");
 
@@ -716,7 +716,7 @@ fn test_accept_ranges_disabled() {
     path: "/video.mp4".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -732,8 +732,8 @@ fn test_accept_ranges_disabled() {
 // Test 8: Custom Headers
 // ============================================================================
 
-#[test]
-fn test_custom_headers() {
+#[tokio::test]
+async fn test_custom_headers() {
   let fixture = TestFixture::new();
   fixture.create_file("test.txt", "Content");
 
@@ -754,7 +754,7 @@ fn test_custom_headers() {
     path: "/test.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -774,8 +774,8 @@ fn test_custom_headers() {
 // Test 9: Error Handling
 // ============================================================================
 
-#[test]
-fn test_file_not_found() {
+#[tokio::test]
+async fn test_file_not_found() {
   let fixture = TestFixture::new();
 
   let options = FileSendOptions {
@@ -791,7 +791,7 @@ fn test_file_not_found() {
     path: "/nonexistent.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -803,8 +803,8 @@ fn test_file_not_found() {
     .unwrap();
 }
 
-#[test]
-fn test_path_traversal_blocked() {
+#[tokio::test]
+async fn test_path_traversal_blocked() {
   let fixture = TestFixture::new();
 
   // Create a file outside the root
@@ -826,7 +826,7 @@ fn test_path_traversal_blocked() {
     path: "/../secret.txt".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -846,8 +846,8 @@ fn test_path_traversal_blocked() {
 // Test 10: Range HyperRequests (from hyper-staticfile)
 // ============================================================================
 
-#[test]
-fn test_range_request_supported() {
+#[tokio::test]
+async fn test_range_request_supported() {
   let fixture = TestFixture::new();
   fixture.create_file("large.bin", "0123456789".repeat(100).as_str());
 
@@ -875,7 +875,7 @@ fn test_range_request_supported() {
     path: "/large.bin".to_owned(),
   };
 
-  task.compute().unwrap();
+  task.send().await.unwrap();
 
   task
     .response
@@ -889,6 +889,367 @@ fn test_range_request_supported() {
     .unwrap();
 }
 
+// ============================================================================
+// Test 11: Directory Listing
+// ============================================================================
+
+#[tokio::test]
+async fn test_directory_listing() {
+  let fixture = TestFixture::new();
+  fixture.create_file("docs/readme.txt", "read me");
+  fixture.create_file("docs/.hidden", "secret");
+  fixture.create_dir("docs/sub");
+
+  let options = FileSendOptions {
+    root: Some(fixture.root()),
+    index: None,
+    directory_listing: Some(Default::default()),
+    ..Default::default()
+  };
+
+  let request = create_empty_get_request();
+  let response = create_mock_response_with_request(request);
+  let mut task = FileSendTask {
+    response,
+    options,
+    path: "/docs/".to_owned(),
+  };
+
+  task.send().await.unwrap();
+
+  let body = task
+    .response
+    .with_inner(|w_res| {
+      let inner = w_res.take()?;
+      assert_eq!(inner.status(), StatusCode::OK);
+      assert_eq!(
+        inner.headers()[header::CONTENT_TYPE],
+        "text/html; charset=utf-8"
+      );
+      Ok(inner.into_body())
+    })
+    .unwrap();
+  let body = body.collect().await.unwrap().to_bytes();
+  let body = String::from_utf8(body.to_vec()).unwrap();
+  assert!(body.contains("<a href=\"readme.txt\">readme.txt</a>"));
+  assert!(body.contains("<a href=\"sub/\">sub/</a>"));
+  assert!(!body.contains(".hidden"));
+}
+
+async fn serve_range(
+  accept_ranges: bool,
+  headers: &[(header::HeaderName, &str)],
+) -> (StatusCode, HeaderMap, Option<HeaderValue>) {
+  let fixture = TestFixture::new();
+  fixture.create_file("large.bin", "0123456789".repeat(100).as_str());
+  serve_range_from(&fixture, accept_ranges, headers).await
+}
+
+async fn serve_range_from(
+  fixture: &TestFixture,
+  accept_ranges: bool,
+  headers: &[(header::HeaderName, &str)],
+) -> (StatusCode, HeaderMap, Option<HeaderValue>) {
+  let options = FileSendOptions {
+    root: Some(fixture.root()),
+    accept_ranges,
+    ..Default::default()
+  };
+
+  let mut request_builder = HyperRequest::builder()
+    .method(Method::GET)
+    .uri("/large.bin");
+  for (name, value) in headers {
+    request_builder = request_builder.header(name, *value);
+  }
+  let request = request_builder
+    .body(BoxBody::new(Empty::<Bytes>::new().map_err(|e| match e {})))
+    .unwrap();
+
+  let response = create_mock_response_with_request(request);
+  let mut task = FileSendTask {
+    response,
+    options,
+    path: "/large.bin".to_owned(),
+  };
+
+  task.send().await.unwrap();
+
+  // the request keeps its `Range` header
+  let range = task
+    .response
+    .req()
+    .with_inner(|w_req| Ok(w_req.inner()?.headers().get(header::RANGE).cloned()))
+    .unwrap();
+  task
+    .response
+    .with_inner(|w_res| {
+      let inner = w_res.inner()?;
+      Ok((inner.status(), inner.headers().clone(), range))
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_multiple_ranges() {
+  let (status, headers, range) = serve_range(true, &[(header::RANGE, "bytes=0-9,20-29")]).await;
+  assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+  assert!(
+    headers[header::CONTENT_TYPE]
+      .to_str()
+      .unwrap()
+      .starts_with("multipart/byteranges; boundary=")
+  );
+  assert_eq!(range.unwrap(), "bytes=0-9,20-29");
+}
+
+#[tokio::test]
+async fn test_overlapping_ranges_are_combined() {
+  let (status, headers, _) = serve_range(true, &[(header::RANGE, "bytes=0-9,5-19")]).await;
+  assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+  assert_eq!(headers[header::CONTENT_RANGE], "bytes 0-19/1000");
+  assert_eq!(headers[header::CONTENT_TYPE], "application/octet-stream");
+}
+
+#[tokio::test]
+async fn test_unsatisfiable_range() {
+  let (status, headers, _) = serve_range(true, &[(header::RANGE, "bytes=2000-3000")]).await;
+  assert_eq!(status, StatusCode::RANGE_NOT_SATISFIABLE);
+  assert_eq!(headers[header::CONTENT_RANGE], "bytes */1000");
+}
+
+#[tokio::test]
+async fn test_range_ignored_when_disabled() {
+  let (status, headers, _) = serve_range(false, &[(header::RANGE, "bytes=0-9")]).await;
+  assert_eq!(status, StatusCode::OK);
+  assert!(!headers.contains_key(header::CONTENT_RANGE));
+}
+
+#[tokio::test]
+async fn test_range_ignored_when_if_range_mismatches() {
+  let (status, _, _) = serve_range(
+    true,
+    &[
+      (header::RANGE, "bytes=0-9"),
+      (header::IF_RANGE, "\"stale\""),
+    ],
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_range_ignored_when_if_range_is_weak_etag() {
+  let fixture = TestFixture::new();
+  fixture.create_file("large.bin", "0123456789".repeat(100).as_str());
+  let (_, headers, _) = serve_range_from(&fixture, true, &[]).await;
+  let etag = headers[header::ETAG].to_str().unwrap().to_owned();
+  assert!(etag.starts_with("W/"));
+
+  let (status, headers, _) = serve_range_from(
+    &fixture,
+    true,
+    &[(header::RANGE, "bytes=0-9"), (header::IF_RANGE, &etag)],
+  )
+  .await;
+  assert_eq!(status, StatusCode::OK);
+  assert!(!headers.contains_key(header::CONTENT_RANGE));
+  assert_eq!(headers[header::CONTENT_LENGTH], "1000");
+}
+
+#[tokio::test]
+async fn test_range_served_when_if_range_matches_last_modified() {
+  let fixture = TestFixture::new();
+  fixture.create_file("large.bin", "0123456789".repeat(100).as_str());
+  let (_, headers, _) = serve_range_from(&fixture, true, &[]).await;
+  let last_modified = headers[header::LAST_MODIFIED].to_str().unwrap().to_owned();
+
+  let (status, headers, _) = serve_range_from(
+    &fixture,
+    true,
+    &[
+      (header::RANGE, "bytes=0-9"),
+      (header::IF_RANGE, &last_modified),
+    ],
+  )
+  .await;
+  assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+  assert_eq!(headers[header::CONTENT_RANGE], "bytes 0-9/1000");
+}
+
+// ============================================================================
+// Test 12: Fallback File
+// ============================================================================
+
+async fn serve_with_fallback(path: &str) -> (StatusCode, HeaderMap, bool) {
+  let fixture = TestFixture::new();
+  fixture.create_file("index.html", "<html>App</html>");
+  fixture.create_file("about.html", "<html>About</html>");
+  fixture.create_dir("assets");
+
+  let options = FileSendOptions {
+    root: Some(fixture.root()),
+    max_age: 60_000,
+    extensions: Some(vec!["html".to_string()]),
+    fallback: Some(PathBuf::from("index.html")),
+    ..Default::default()
+  };
+
+  let request = create_empty_get_request();
+  let response = create_mock_response_with_request(request);
+  let mut task = FileSendTask {
+    response,
+    options,
+    path: path.to_owned(),
+  };
+
+  let result = task.send().await.unwrap();
+  let (status, headers) = task
+    .response
+    .with_inner(|w_res| {
+      let inner = w_res.inner()?;
+      Ok((inner.status(), inner.headers().clone()))
+    })
+    .unwrap();
+  (
+    status,
+    headers,
+    result.is_some_and(|result| result.fallback),
+  )
+}
+
+#[tokio::test]
+async fn test_fallback_file() {
+  let (status, headers, fallback) = serve_with_fallback("/dashboard/settings").await;
+  assert_eq!(status, StatusCode::OK);
+  assert!(fallback);
+  assert_eq!(headers[header::CACHE_CONTROL], "no-cache");
+  assert_eq!(headers[header::CONTENT_TYPE], "text/html");
+
+  // directories without an index file
+  let (status, _, fallback) = serve_with_fallback("/assets/").await;
+  assert_eq!(status, StatusCode::OK);
+  assert!(fallback);
+
+  // files found through `extensions` are served as usual
+  let (status, headers, fallback) = serve_with_fallback("/about").await;
+  assert_eq!(status, StatusCode::OK);
+  assert!(!fallback);
+  assert_eq!(headers[header::CACHE_CONTROL], "public, max-age=60");
+}
+
+// ============================================================================
+// Test 13: Virtual File System
+// ============================================================================
+
+fn memory_fs() -> StaticFs {
+  let files = [
+    ("index.html", "<html>Bundled</html>"),
+    ("app.js", "console.log('bundled')"),
+    ("app.js.br", "brotli"),
+    ("docs/guide.html", "<html>Guide</html>"),
+    (".env", "SECRET=1"),
+  ];
+  StaticFs::memory(
+    files
+      .into_iter()
+      .map(|(path, content)| (path.to_owned(), Bytes::from(content))),
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+  )
+}
+
+async fn serve_from_fs(
+  fs: StaticFs,
+  path: &str,
+  headers: &[(header::HeaderName, &str)],
+) -> (StatusCode, HeaderMap, Bytes) {
+  let options = FileSendOptions {
+    fs: Some(fs),
+    extensions: Some(vec!["html".to_string()]),
+    ..Default::default()
+  };
+
+  let mut request_builder = HyperRequest::builder().method(Method::GET).uri(path);
+  for (name, value) in headers {
+    request_builder = request_builder.header(name, *value);
+  }
+  let request = request_builder
+    .body(BoxBody::new(Empty::<Bytes>::new().map_err(|e| match e {})))
+    .unwrap();
+
+  let response = create_mock_response_with_request(request);
+  let mut task = FileSendTask {
+    response,
+    options,
+    path: path.to_owned(),
+  };
+
+  task.send().await.unwrap();
+
+  let response = task.response.with_inner(|w_res| w_res.take()).unwrap();
+  let (parts, body) = response.into_parts();
+  let body = body.collect().await.unwrap().to_bytes();
+  (parts.status, parts.headers, body)
+}
+
+#[tokio::test]
+async fn test_memory_files() {
+  let (status, headers, body) = serve_from_fs(memory_fs(), "/app.js", &[]).await;
+  assert_eq!(status, StatusCode::OK);
+  assert_eq!(body, "console.log('bundled')");
+  assert_eq!(
+    headers[header::LAST_MODIFIED],
+    "Tue, 14 Nov 2023 22:13:20 GMT"
+  );
+  assert!(headers.contains_key(header::ETAG));
+
+  // conditional requests
+  let (status, _, _) = serve_from_fs(
+    memory_fs(),
+    "/app.js",
+    &[(header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")],
+  )
+  .await;
+  assert_eq!(status, StatusCode::NOT_MODIFIED);
+
+  // precompressed variants
+  let (_, headers, body) =
+    serve_from_fs(memory_fs(), "/app.js", &[(header::ACCEPT_ENCODING, "br")]).await;
+  assert_eq!(headers[header::CONTENT_ENCODING], "br");
+  assert_eq!(body, "brotli");
+
+  // ranges
+  let (status, headers, body) =
+    serve_from_fs(memory_fs(), "/app.js", &[(header::RANGE, "bytes=0-6")]).await;
+  assert_eq!(status, StatusCode::PARTIAL_CONTENT);
+  assert_eq!(headers[header::CONTENT_RANGE], "bytes 0-6/22");
+  assert_eq!(body, "console");
+
+  // index files and extensions
+  let (_, _, body) = serve_from_fs(memory_fs(), "/", &[]).await;
+  assert_eq!(body, "<html>Bundled</html>");
+  let (_, _, body) = serve_from_fs(memory_fs(), "/docs/guide", &[]).await;
+  assert_eq!(body, "<html>Guide</html>");
+
+  // dotfiles are ignored by default
+  let (status, _, _) = serve_from_fs(memory_fs(), "/.env", &[]).await;
+  assert_eq!(status, StatusCode::NOT_FOUND);
+  let (status, _, _) = serve_from_fs(memory_fs(), "/missing.js", &[]).await;
+  assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_overlay_files() {
+  let fixture = TestFixture::new();
+  fixture.create_file("app.js", "console.log('local')");
+
+  let fs = StaticFs::overlay([StaticFs::disk(fixture.root()), memory_fs()]);
+  let (_, _, body) = serve_from_fs(fs.clone(), "/app.js", &[]).await;
+  assert_eq!(body, "console.log('local')");
+  let (_, _, body) = serve_from_fs(fs, "/", &[]).await;
+  assert_eq!(body, "<html>Bundled</html>");
+}
+
 // ============================================================================
 // Test Helpers
 // ============================================================================
@@ -906,5 +1267,5 @@ fn create_mock_response_with_request(
   request: HyperRequest<BoxBody<Bytes, hyper::Error>>,
 ) -> Response {
   let w_request: WrappedRequest = request.into();
-  Response::new(w_request.into(), None)
+  Response::new(w_request.into(), Default::default(), None)
 }
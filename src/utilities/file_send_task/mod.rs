@@ -5,21 +5,27 @@ mod parity_tests;
 mod requested_path;
 
 use std::{
-  fs::Metadata,
   path::{Component, Path, PathBuf},
   sync::{Arc, Mutex},
-  time::Instant,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+use etag::EntityTag;
+
 use hyper::{
   HeaderMap, StatusCode,
   body::Bytes,
   header::{self, HeaderValue},
 };
+use hyper_staticfile::{ResolveParams, ResolveResult};
 use napi::bindgen_prelude::*;
-use tokio::runtime::Runtime;
 
-use crate::response::Response;
+use crate::{
+  response::Response,
+  utilities::{
+    self, ByteRanges, DirectoryListingOptions, FileMetadata, ListingFormat, StaticFs, Validators,
+  },
+};
 use requested_path::RequestedPath;
 
 /// Configuration options for file serving (matching JavaScript send() options)
@@ -28,6 +34,9 @@ pub struct FileSendOptions {
   /// Root directory to serve files from
   pub root: Option<PathBuf>,
 
+  /// Files to serve in place of the `root` directory, e.g. held in memory
+  pub fs: Option<StaticFs>,
+
   /// Maximum age for caching in milliseconds
   pub max_age: u64,
 
@@ -40,7 +49,7 @@ pub struct FileSendOptions {
   /// Whether to send Last-Modified header (always true in hyper-staticfile)
   pub last_modified: bool,
 
-  /// Whether to accept range requests (always true in hyper-staticfile)
+  /// Whether to accept range requests
   pub accept_ranges: bool,
 
   /// Whether to add 'immutable' directive to Cache-Control
@@ -57,12 +66,20 @@ pub struct FileSendOptions {
 
   /// Custom headers to add to response
   pub headers: Option<HeaderMap>,
+
+  /// List directories without an index file
+  pub directory_listing: Option<DirectoryListingOptions>,
+
+  /// File served, with `Cache-Control: no-cache`, in place of missing files.
+  /// Resolved like a request path.
+  pub fallback: Option<PathBuf>,
 }
 
 impl Default for FileSendOptions {
   fn default() -> Self {
     Self {
       root: None,
+      fs: None,
       max_age: 0,
       cache_control: true,
       etag: true,
@@ -73,6 +90,8 @@ impl Default for FileSendOptions {
       extensions: None,
       dotfiles: "ignore".to_string(),
       headers: None,
+      directory_listing: None,
+      fallback: None,
     }
   }
 }
@@ -130,30 +149,79 @@ impl FileSendTask {
   }
 }
 
+impl FileSendTask {
+  /// Send the listing of `directory`, at URL path `request_path`
+  async fn list_directory(
+    &self,
+    directory: &Path,
+    request_path: &str,
+    format: ListingFormat,
+    listing_options: &DirectoryListingOptions,
+  ) -> Result<()> {
+    let (path, dotfiles, sort) = (
+      directory.to_path_buf(),
+      self.options.dotfiles.clone(),
+      listing_options.sort,
+    );
+    let entries =
+      match tokio::task::spawn_blocking(move || utilities::read_directory(&path, &dotfiles, sort))
+        .await
+        .map_err(std::io::Error::other)
+        .and_then(|entries| entries)
+      {
+        Ok(entries) => entries,
+        Err(e) => {
+          log::error!("Request ID: {} | {e}", self.response.req().id());
+          let status = match e.kind() {
+            std::io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+            _ => StatusCode::NOT_FOUND,
+          };
+          return self.error(status, None);
+        }
+      };
+    let listing = utilities::render_listing(format, request_path, &entries, listing_options);
+
+    self.response.with_inner(|w_res| {
+      let inner = w_res.inner()?;
+      *inner.status_mut() = StatusCode::OK;
+      let headers = inner.headers_mut();
+      headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+      );
+      headers.append(header::VARY, HeaderValue::from_static("Accept"));
+      if let Some(custom_headers) = &self.options.headers {
+        headers.extend(custom_headers.clone());
+      }
+      w_res.end(Some(Bytes::from(listing)))
+    })
+  }
+}
+
 pub struct FileServeResult {
+  /// The path of the served file, relative to the root
   pub served_path: PathBuf,
-  pub file_stat: Metadata,
+  pub file_stat: FileMetadata,
+  /// Whether the fallback file was served in place of a missing file
+  pub fallback: bool,
 }
 
-impl Task for FileSendTask {
-  type Output = Option<FileServeResult>;
-  type JsValue = ();
-
-  fn compute(&mut self) -> Result<Self::Output> {
-    // Get the root directory
-    let root = match &self.options.root {
-      Some(root) => root.clone(),
-      None => std::env::current_dir().map_err(|e| {
+impl FileSendTask {
+  /// Serve the file at `path` into the response. Returns the served file,
+  /// when a file was found.
+  pub async fn send(&mut self) -> Result<Option<FileServeResult>> {
+    // Get the files to serve, by default those of the root directory
+    let fs = match (&self.options.fs, &self.options.root) {
+      (Some(fs), _) => fs.clone(),
+      (None, Some(root)) => StaticFs::disk(root),
+      (None, None) => StaticFs::disk(std::env::current_dir().map_err(|e| {
         Error::new(
           Status::GenericFailure,
           format!("Error accessing current directory: {e}"),
         )
-      })?,
+      })?),
     };
 
-    // Create Tokio runtime
-    let rt = Runtime::new()?;
-
     // Extract request from wrapper
     let mut request = self
       .response
@@ -171,14 +239,14 @@ impl Task for FileSendTask {
         *request.uri_mut() = path;
       }
       Err(e) => {
-        log::error!("{e}");
+        log::error!("Request ID: {} | {e}", self.response.req().id());
         self.error(StatusCode::FORBIDDEN, None)?;
         return Ok(None);
       }
     }
 
     // Create resolver with all configuration
-    let mut resolver = hyper_staticfile::Resolver::new(&root);
+    let mut resolver = hyper_staticfile::Resolver::with_opener(fs.clone());
 
     // Enable all encodings (gzip, brotli, zstd)
     resolver.allowed_encodings = hyper_staticfile::AcceptEncoding::all();
@@ -205,41 +273,74 @@ impl Task for FileSendTask {
       }
     }
 
+    // 2. List directories without an index file, on disk only
+    if let Some(listing_options) = &self.options.directory_listing
+      && let Some(root) = fs.disk_root()
+      && request_path.ends_with('/')
+    {
+      let directory = root.join(&sanitized_path);
+      let has_index = self
+        .options
+        .index
+        .iter()
+        .flatten()
+        .any(|index| directory.join(index).is_file());
+      if directory.is_dir() && !has_index {
+        let format = ListingFormat::negotiate(
+          request
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok()),
+        );
+        self.response.req().with_inner_mut(|w_req| {
+          w_req.set_inner(request);
+          Ok(())
+        })?;
+        let Some(format) = format else {
+          self.error(StatusCode::NOT_ACCEPTABLE, None)?;
+          return Ok(None);
+        };
+        self
+          .list_directory(&directory, &request_path, format, listing_options)
+          .await?;
+        return Ok(None);
+      }
+    }
+
     // Configure rewrite hook for dotfiles, index, and extensions
     let options_clone = self.options.clone();
-    let root_clone = root.clone();
+    let fs_clone = fs.clone();
     let file_serve_result: Arc<Mutex<Option<FileServeResult>>> = Default::default();
     let file_serve_result_clone = file_serve_result.clone();
 
     resolver.set_rewrite(move |mut params| {
       let options = options_clone.clone();
-      let root = root_clone.clone();
+      let fs = fs_clone.clone();
       let instant = Instant::now();
       let file_serve_result = file_serve_result_clone.clone();
 
       async move {
-        // 2. Handle index files for directories
+        // 3. Handle index files for directories
         if params.is_dir_request {
           match options.index {
             Some(ref index_files) => {
               for index_name in index_files {
-                let mut test_path = root.clone();
-                test_path.push(&params.path);
-                test_path.push(index_name);
-
-                // Check if file exists using tokio::fs
-                if let Ok(metadata) = tokio::fs::metadata(&test_path).await
-                  && metadata.is_file()
+                // Check if the index file exists
+                if let Ok(metadata) = fs.metadata(&params.path.join(index_name)).await
+                  && metadata.is_file
                 {
                   params.path.push(index_name);
                   params.is_dir_request = false;
                   {
-                    let mut file_serve_result = file_serve_result.lock().map_err(|_| {
-                      std::io::Error::other("failed to obtain lock on file_serve_result: {e}")
+                    let mut file_serve_result = file_serve_result.lock().map_err(|e| {
+                      std::io::Error::other(format!(
+                        "failed to obtain lock on file_serve_result: {e}"
+                      ))
                     })?;
                     let _ = file_serve_result.insert(FileServeResult {
                       served_path: params.path.to_owned(),
                       file_stat: metadata,
+                      fallback: false,
                     });
                   }
                   return Ok(params);
@@ -247,6 +348,11 @@ impl Task for FileSendTask {
               }
             }
             None => {
+              if let Some(fallback) = &options.fallback
+                && rewrite_to_fallback(&mut params, &fs, fallback, &file_serve_result).await?
+              {
+                return Ok(params);
+              }
               let improbable_name = instant.elapsed().as_nanos().to_string();
               params.path.push(improbable_name);
               params.is_dir_request = false;
@@ -254,38 +360,38 @@ impl Task for FileSendTask {
             }
           }
           // If no index found, let hyper-staticfile handle it (will return 404)
+          if let Some(fallback) = &options.fallback {
+            rewrite_to_fallback(&mut params, &fs, fallback, &file_serve_result).await?;
+          }
           return Ok(params);
         }
 
-        // 3. Handle extension fallback
+        // 4. Handle extension fallback
         if let Some(extensions) = &options.extensions {
-          let mut test_path = root.clone();
-          test_path.push(&params.path);
-
           // Check if original path exists
-          if tokio::fs::metadata(&test_path).await.is_err() {
+          if fs.metadata(&params.path).await.is_err() {
             // Try each extension
             for ext in extensions {
-              let mut ext_path = test_path.clone().into_os_string();
+              let mut ext_path = params.path.clone().into_os_string();
               ext_path.push(".");
               ext_path.push(ext);
               let ext_path: PathBuf = ext_path.into();
 
-              if let Ok(metadata) = tokio::fs::metadata(&ext_path).await
-                && metadata.is_file()
+              if let Ok(metadata) = fs.metadata(&ext_path).await
+                && metadata.is_file
               {
                 // Found file with extension
-                let mut new_path = params.path.clone().into_os_string();
-                new_path.push(".");
-                new_path.push(ext);
-                params.path = new_path.into();
+                params.path = ext_path;
                 {
-                  let mut file_serve_result = file_serve_result.lock().map_err(|_| {
-                    std::io::Error::other("failed to obtain lock on file_serve_result: {e}")
+                  let mut file_serve_result = file_serve_result.lock().map_err(|e| {
+                    std::io::Error::other(format!(
+                      "failed to obtain lock on file_serve_result: {e}"
+                    ))
                   })?;
                   let _ = file_serve_result.insert(FileServeResult {
                     served_path: params.path.to_owned(),
                     file_stat: metadata,
+                    fallback: false,
                   });
                 }
                 return Ok(params);
@@ -294,21 +400,95 @@ impl Task for FileSendTask {
           }
         }
 
+        // 5. Record the requested file
+        match fs.metadata(&params.path).await {
+          Ok(metadata) if metadata.is_file => {
+            let mut file_serve_result = file_serve_result.lock().map_err(|e| {
+              std::io::Error::other(format!("failed to obtain lock on file_serve_result: {e}"))
+            })?;
+            let _ = file_serve_result.insert(FileServeResult {
+              served_path: params.path.to_owned(),
+              file_stat: metadata,
+              fallback: false,
+            });
+          }
+          Ok(_) => {}
+          // 6. Serve the fallback file in place of a missing file
+          Err(_) => {
+            if let Some(fallback) = &options.fallback {
+              rewrite_to_fallback(&mut params, &fs, fallback, &file_serve_result).await?;
+            }
+          }
+        }
+
         Ok(params)
       }
     });
 
     // Resolve the request using hyper-staticfile
-    let result = rt.block_on(async { resolver.resolve_request(&request).await })?;
+    let result = resolver.resolve_request(&request).await?;
+
+    // Evaluate byte ranges here rather than in hyper-staticfile, which streams
+    // the ranges given in a normalized `Range` header, so that overlapping
+    // ranges are combined, `acceptRanges` is honored and unsatisfiable ranges
+    // get a `Content-Range`
+    let range_headers = (
+      request.headers_mut().remove(header::RANGE),
+      request.headers_mut().remove(header::IF_RANGE),
+    );
+    if let (Some(range), ResolveResult::Found(file)) = (&range_headers.0, &result)
+      && self.options.accept_ranges
+    {
+      let mut headers = request.headers().clone();
+      headers.insert(header::RANGE, range.clone());
+      if let Some(if_range) = &range_headers.1 {
+        headers.insert(header::IF_RANGE, if_range.clone());
+      }
+      let validators = Validators {
+        etag: file_etag(file.size, file.modified),
+        last_modified: file.modified,
+      };
+      let size = file.size as usize;
+      match utilities::byte_ranges(request.method(), &headers, size, &validators) {
+        ByteRanges::Full => {}
+        ByteRanges::Partial(ranges) => {
+          let ranges = ranges
+            .iter()
+            .map(|range| format!("{}-{}", range.start, range.end))
+            .collect::<Vec<_>>()
+            .join(",");
+          if let Ok(range) = HeaderValue::from_str(&format!("bytes={ranges}")) {
+            request.headers_mut().insert(header::RANGE, range);
+          }
+        }
+        ByteRanges::Unsatisfiable => {
+          restore_range_headers(&mut request, range_headers);
+          self.response.req().with_inner_mut(|w_req| {
+            w_req.set_inner(request);
+            Ok(())
+          })?;
+          let mut headers = HeaderMap::new();
+          if let Ok(content_range) = HeaderValue::from_str(&utilities::unsatisfied_range(size)) {
+            headers.insert(header::CONTENT_RANGE, content_range);
+          }
+          self.error(StatusCode::RANGE_NOT_SATISFIABLE, Some(headers))?;
+          return Ok(None);
+        }
+      }
+    }
 
     // Build response with cache headers
     let cache_headers = if self.options.cache_control {
       // Convert milliseconds to seconds
-      Some((self.options.max_age / 1000) as u32)
+      Some(u32::try_from(self.options.max_age / 1000).unwrap_or(u32::MAX))
     } else {
       None
     };
 
+    let content_type = match &result {
+      ResolveResult::Found(file) => file.content_type.clone(),
+      _ => None,
+    };
     let mut response = hyper_staticfile::ResponseBuilder::new()
       .request(&request)
       .cache_headers(cache_headers)
@@ -318,6 +498,17 @@ impl Task for FileSendTask {
 
     // Post-process response headers
 
+    // hyper-staticfile omits the `Content-Type` of single range responses
+    if response.status() == StatusCode::PARTIAL_CONTENT
+      && !response.headers().contains_key(header::CONTENT_TYPE)
+      && let Some(content_type) = content_type
+      && let Ok(content_type) = HeaderValue::from_str(&content_type)
+    {
+      response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, content_type);
+    }
+
     // Add 'immutable' directive to Cache-Control if needed
     if self.options.immutable
       && self.options.cache_control
@@ -333,6 +524,18 @@ impl Task for FileSendTask {
       }
     }
 
+    // The fallback file stands in for many paths and must be revalidated
+    let served_fallback = file_serve_result
+      .lock()
+      .map_err(|_| std::io::Error::other("failed to obtain lock on file_serve_result"))?
+      .as_ref()
+      .is_some_and(|file_serve_result| file_serve_result.fallback);
+    if served_fallback && self.options.cache_control {
+      response
+        .headers_mut()
+        .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    }
+
     // Remove ETag if disabled (though hyper-staticfile always generates it)
     if !self.options.etag {
       response.headers_mut().remove(header::ETAG);
@@ -363,6 +566,7 @@ impl Task for FileSendTask {
     }
 
     // Put request back
+    restore_range_headers(&mut request, range_headers);
     self.response.req().with_inner_mut(|w_req| {
       w_req.set_inner(request);
       Ok(())
@@ -377,15 +581,76 @@ impl Task for FileSendTask {
     let file_serve_result = {
       file_serve_result
         .lock()
-        .map_err(|_| std::io::Error::other("failed to obtain lock on file_serve_result: {e}"))?
+        .map_err(|e| {
+          std::io::Error::other(format!("failed to obtain lock on file_serve_result: {e}"))
+        })?
         .take()
     };
 
     Ok(file_serve_result)
   }
+}
+
+/// Rewrite `params` to the `fallback` file, resolved in `fs`, and record it as
+/// the served file. Returns `false`, leaving `params` untouched, when the
+/// fallback file does not exist.
+async fn rewrite_to_fallback(
+  params: &mut ResolveParams,
+  fs: &StaticFs,
+  fallback: &Path,
+  file_serve_result: &Mutex<Option<FileServeResult>>,
+) -> std::io::Result<bool> {
+  let served_path = fallback
+    .components()
+    .filter(|component| matches!(component, Component::Normal(_)))
+    .collect::<PathBuf>();
+  let metadata = match fs.metadata(&served_path).await {
+    Ok(metadata) if metadata.is_file => metadata,
+    _ => return Ok(false),
+  };
+
+  params.path = served_path.clone();
+  params.is_dir_request = false;
+  let mut file_serve_result = file_serve_result
+    .lock()
+    .map_err(|_| std::io::Error::other("failed to obtain lock on file_serve_result"))?;
+  let _ = file_serve_result.insert(FileServeResult {
+    served_path,
+    file_stat: metadata,
+    fallback: true,
+  });
+  Ok(true)
+}
 
-  fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
-    Ok(())
+/// The `ETag` hyper-staticfile gives to a file of `size` bytes modified at
+/// `modified`
+fn file_etag(size: u64, modified: Option<SystemTime>) -> Option<EntityTag> {
+  let modified = modified?.duration_since(UNIX_EPOCH).ok()?;
+  // hyper-staticfile ignores the modification times of the first seconds
+  if modified < Duration::from_secs(2) {
+    return None;
+  }
+  let tag = format!(
+    "{:x}-{:x}.{:x}",
+    size,
+    modified.as_secs(),
+    modified.subsec_nanos()
+  );
+  Some(EntityTag::weak(&tag))
+}
+
+/// Put back the `Range` and `If-Range` headers removed from `request`
+fn restore_range_headers<B>(
+  request: &mut hyper::Request<B>,
+  (range, if_range): (Option<HeaderValue>, Option<HeaderValue>),
+) {
+  let headers = request.headers_mut();
+  headers.remove(header::RANGE);
+  if let Some(range) = range {
+    headers.insert(header::RANGE, range);
+  }
+  if let Some(if_range) = if_range {
+    headers.insert(header::IF_RANGE, if_range);
   }
 }
 
@@ -12,7 +12,6 @@ use std::{
 use bytes::Bytes;
 use http_body_util::{BodyExt, Empty, combinators::BoxBody};
 use hyper::{HeaderMap, Method, Request as HyperRequest, StatusCode, header};
-use napi::Task;
 use tempfile::TempDir;
 
 use crate::{request::WrappedRequest, response::Response};
@@ -58,8 +57,8 @@ impl TestEnv {
 // Rust: FileSendOptions::default()
 // ============================================================================
 
-#[test]
-fn parity_default_options() {
+#[tokio::test]
+async fn parity_default_options() {
   // JavaScript defaults:
   // - acceptRanges: true
   // - cacheControl: true
@@ -100,8 +99,8 @@ fn parity_default_options() {
 // Expected: 403 Forbidden for .dotfiles
 // ============================================================================
 
-#[test]
-fn parity_dotfiles_deny_returns_403() {
+#[tokio::test]
+async fn parity_dotfiles_deny_returns_403() {
   let env = TestEnv::new();
   env.write_file(".secret", "password123");
 
@@ -111,7 +110,7 @@ fn parity_dotfiles_deny_returns_403() {
     ..Default::default()
   };
 
-  let response = serve_file("/.secret", opts);
+  let response = serve_file("/.secret", opts).await;
 
   assert_eq!(
     response.status,
@@ -126,8 +125,8 @@ fn parity_dotfiles_deny_returns_403() {
 // Expected: 404 Not Found for .dotfiles
 // ============================================================================
 
-#[test]
-fn parity_dotfiles_ignore_returns_404() {
+#[tokio::test]
+async fn parity_dotfiles_ignore_returns_404() {
   let env = TestEnv::new();
   env.write_file(".secret", "password123");
 
@@ -137,7 +136,7 @@ fn parity_dotfiles_ignore_returns_404() {
     ..Default::default()
   };
 
-  let response = serve_file("/.secret", opts);
+  let response = serve_file("/.secret", opts).await;
 
   assert_eq!(
     response.status,
@@ -152,8 +151,8 @@ fn parity_dotfiles_ignore_returns_404() {
 // Expected: 200 OK, file served
 // ============================================================================
 
-#[test]
-fn parity_dotfiles_allow_serves_file() {
+#[tokio::test]
+async fn parity_dotfiles_allow_serves_file() {
   let env = TestEnv::new();
   env.write_file(".well-known/security.txt", "Contact: security@example.com");
 
@@ -163,7 +162,7 @@ fn parity_dotfiles_allow_serves_file() {
     ..Default::default()
   };
 
-  let response = serve_file("/.well-known/security.txt", opts);
+  let response = serve_file("/.well-known/security.txt", opts).await;
 
   assert_eq!(
     response.status,
@@ -178,8 +177,8 @@ fn parity_dotfiles_allow_serves_file() {
 // Expected: Serves first existing index file
 // ============================================================================
 
-#[test]
-fn parity_index_tries_multiple_files() {
+#[tokio::test]
+async fn parity_index_tries_multiple_files() {
   let env = TestEnv::new();
   env.mkdir("public");
   // Create only the third index option
@@ -195,7 +194,7 @@ fn parity_index_tries_multiple_files() {
     ..Default::default()
   };
 
-  let response = serve_file("/public/", opts);
+  let response = serve_file("/public/", opts).await;
 
   assert_eq!(
     response.status,
@@ -210,8 +209,8 @@ fn parity_index_tries_multiple_files() {
 // Expected: Serves first in list that exists
 // ============================================================================
 
-#[test]
-fn parity_index_respects_priority() {
+#[tokio::test]
+async fn parity_index_respects_priority() {
   let env = TestEnv::new();
   env.mkdir("public");
   env.write_file("public/first.html", "First");
@@ -223,7 +222,7 @@ fn parity_index_respects_priority() {
     ..Default::default()
   };
 
-  let response = serve_file("/public/", opts);
+  let response = serve_file("/public/", opts).await;
 
   assert_eq!(
     response.status,
@@ -239,8 +238,8 @@ fn parity_index_respects_priority() {
 // Expected: 404 for directory requests
 // ============================================================================
 
-#[test]
-fn parity_index_false_returns_404() {
+#[tokio::test]
+async fn parity_index_false_returns_404() {
   let env = TestEnv::new();
   env.mkdir("public");
   env.write_file("public/index.html", "<html>Index</html>");
@@ -251,7 +250,7 @@ fn parity_index_false_returns_404() {
     ..Default::default()
   };
 
-  let response = serve_file("/public/", opts);
+  let response = serve_file("/public/", opts).await;
 
   assert_eq!(
     response.status,
@@ -266,8 +265,8 @@ fn parity_index_false_returns_404() {
 // Expected: /about -> /about.html if exists
 // ============================================================================
 
-#[test]
-fn parity_extensions_appends_to_path() {
+#[tokio::test]
+async fn parity_extensions_appends_to_path() {
   let env = TestEnv::new();
   env.write_file("about.html", "<html>About Us</html>");
 
@@ -277,7 +276,7 @@ fn parity_extensions_appends_to_path() {
     ..Default::default()
   };
 
-  let response = serve_file("/about", opts);
+  let response = serve_file("/about", opts).await;
 
   assert_eq!(
     response.status,
@@ -292,8 +291,8 @@ fn parity_extensions_appends_to_path() {
 // Expected: /file -> /file (not /file.html) if /file exists
 // ============================================================================
 
-#[test]
-fn parity_extensions_prefer_exact_match() {
+#[tokio::test]
+async fn parity_extensions_prefer_exact_match() {
   let env = TestEnv::new();
   env.write_file("readme", "Exact file");
   env.write_file("readme.html", "<html>HTML version</html>");
@@ -304,7 +303,7 @@ fn parity_extensions_prefer_exact_match() {
     ..Default::default()
   };
 
-  let response = serve_file("/readme", opts);
+  let response = serve_file("/readme", opts).await;
 
   assert_eq!(
     response.status,
@@ -320,8 +319,8 @@ fn parity_extensions_prefer_exact_match() {
 // Expected: Cache-Control: public, max-age=86400
 // ============================================================================
 
-#[test]
-fn parity_maxage_sets_cache_control() {
+#[tokio::test]
+async fn parity_maxage_sets_cache_control() {
   let env = TestEnv::new();
   env.write_file("app.js", "console.log('app');");
 
@@ -332,7 +331,7 @@ fn parity_maxage_sets_cache_control() {
     ..Default::default()
   };
 
-  let response = serve_file("/app.js", opts);
+  let response = serve_file("/app.js", opts).await;
 
   assert_eq!(response.status, StatusCode::OK);
   assert!(
@@ -365,8 +364,8 @@ fn parity_maxage_sets_cache_control() {
 // Expected: Cache-Control: public, max-age=31536000, immutable
 // ============================================================================
 
-#[test]
-fn parity_immutable_adds_directive() {
+#[tokio::test]
+async fn parity_immutable_adds_directive() {
   let env = TestEnv::new();
   env.write_file("bundle.abc123.js", "/* bundled */");
 
@@ -378,7 +377,7 @@ fn parity_immutable_adds_directive() {
     ..Default::default()
   };
 
-  let response = serve_file("/bundle.abc123.js", opts);
+  let response = serve_file("/bundle.abc123.js", opts).await;
 
   let cache_value = response
     .headers
@@ -400,8 +399,8 @@ fn parity_immutable_adds_directive() {
 // Expected: No Cache-Control header
 // ============================================================================
 
-#[test]
-fn parity_cache_control_false_omits_header() {
+#[tokio::test]
+async fn parity_cache_control_false_omits_header() {
   let env = TestEnv::new();
   env.write_file("dynamic.json", "{}");
 
@@ -411,7 +410,7 @@ fn parity_cache_control_false_omits_header() {
     ..Default::default()
   };
 
-  let response = serve_file("/dynamic.json", opts);
+  let response = serve_file("/dynamic.json", opts).await;
 
   assert!(
     !response.headers.contains_key(header::CACHE_CONTROL),
@@ -425,8 +424,8 @@ fn parity_cache_control_false_omits_header() {
 // Expected: No ETag header
 // ============================================================================
 
-#[test]
-fn parity_etag_false_omits_header() {
+#[tokio::test]
+async fn parity_etag_false_omits_header() {
   let env = TestEnv::new();
   env.write_file("file.txt", "content");
 
@@ -436,7 +435,7 @@ fn parity_etag_false_omits_header() {
     ..Default::default()
   };
 
-  let response = serve_file("/file.txt", opts);
+  let response = serve_file("/file.txt", opts).await;
 
   assert!(
     !response.headers.contains_key(header::ETAG),
@@ -450,8 +449,8 @@ fn parity_etag_false_omits_header() {
 // Expected: No Last-Modified header
 // ============================================================================
 
-#[test]
-fn parity_last_modified_false_omits_header() {
+#[tokio::test]
+async fn parity_last_modified_false_omits_header() {
   let env = TestEnv::new();
   env.write_file("file.txt", "content");
 
@@ -461,7 +460,7 @@ fn parity_last_modified_false_omits_header() {
     ..Default::default()
   };
 
-  let response = serve_file("/file.txt", opts);
+  let response = serve_file("/file.txt", opts).await;
 
   assert!(
     !response.headers.contains_key(header::LAST_MODIFIED),
@@ -475,8 +474,8 @@ fn parity_last_modified_false_omits_header() {
 // Expected: No Accept-Ranges header
 // ============================================================================
 
-#[test]
-fn parity_accept_ranges_false_omits_header() {
+#[tokio::test]
+async fn parity_accept_ranges_false_omits_header() {
   let env = TestEnv::new();
   env.write_file("video.mp4", "fake video");
 
@@ -486,7 +485,7 @@ fn parity_accept_ranges_false_omits_header() {
     ..Default::default()
   };
 
-  let response = serve_file("/video.mp4", opts);
+  let response = serve_file("/video.mp4", opts).await;
 
   assert!(
     !response.headers.contains_key(header::ACCEPT_RANGES),
@@ -500,8 +499,8 @@ fn parity_accept_ranges_false_omits_header() {
 // Expected: 403 or 404, file outside root not served
 // ============================================================================
 
-#[test]
-fn parity_path_traversal_blocked() {
+#[tokio::test]
+async fn parity_path_traversal_blocked() {
   let env = TestEnv::new();
 
   // Try to access parent directory
@@ -510,7 +509,7 @@ fn parity_path_traversal_blocked() {
     ..Default::default()
   };
 
-  let response = serve_file("/../../../etc/passwd", opts);
+  let response = serve_file("/../../../etc/passwd", opts).await;
 
   assert!(
     response.status == StatusCode::FORBIDDEN || response.status == StatusCode::NOT_FOUND,
@@ -525,8 +524,8 @@ fn parity_path_traversal_blocked() {
 // Expected: 301 redirect to /folder/
 // ============================================================================
 
-#[test]
-fn parity_directory_redirects_with_slash() {
+#[tokio::test]
+async fn parity_directory_redirects_with_slash() {
   let env = TestEnv::new();
   env.mkdir("public");
   env.write_file("public/index.html", "<html>Index</html>");
@@ -536,7 +535,7 @@ fn parity_directory_redirects_with_slash() {
     ..Default::default()
   };
 
-  let response = serve_file("/public", opts);
+  let response = serve_file("/public", opts).await;
 
   assert_eq!(
     response.status,
@@ -555,8 +554,8 @@ fn parity_directory_redirects_with_slash() {
 // Expected: 404 Not Found
 // ============================================================================
 
-#[test]
-fn parity_nonexistent_file_returns_404() {
+#[tokio::test]
+async fn parity_nonexistent_file_returns_404() {
   let env = TestEnv::new();
 
   let opts = FileSendOptions {
@@ -564,7 +563,7 @@ fn parity_nonexistent_file_returns_404() {
     ..Default::default()
   };
 
-  let response = serve_file("/does-not-exist.txt", opts);
+  let response = serve_file("/does-not-exist.txt", opts).await;
 
   assert_eq!(
     response.status,
@@ -583,7 +582,7 @@ struct MockResponse {
 }
 
 /// Serve a file and return simplified response for testing
-fn serve_file(path: &str, options: FileSendOptions) -> MockResponse {
+async fn serve_file(path: &str, options: FileSendOptions) -> MockResponse {
   // This is a simplified helper - actual implementation would:
   // 1. Create proper Request
   let request = create_empty_get_request();
@@ -595,7 +594,7 @@ fn serve_file(path: &str, options: FileSendOptions) -> MockResponse {
     options,
   };
   // 3. Execute compute()
-  file_send_task.compute().unwrap();
+  file_send_task.send().await.unwrap();
   // 4. Extract status, headers, body from Response
 
   let status = file_send_task
@@ -628,5 +627,5 @@ fn create_mock_response_with_request(
   request: HyperRequest<BoxBody<Bytes, hyper::Error>>,
 ) -> Response {
   let w_request: WrappedRequest = request.into();
-  Response::new(w_request.into(), None)
+  Response::new(w_request.into(), Default::default(), None)
 }
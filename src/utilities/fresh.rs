@@ -0,0 +1,168 @@
+use hyper::{
+  HeaderMap, Method, StatusCode,
+  header::{CACHE_CONTROL, ETAG, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+
+/// Whether the response to a request is still fresh in the client's cache,
+/// in which case it can be answered with `304 Not Modified`.
+///
+/// Only `GET` and `HEAD` requests answered with a `2xx` or `304` status can be
+/// fresh, as in Express' `req.fresh`.
+pub fn is_fresh(
+  method: &Method,
+  request_headers: &HeaderMap,
+  status: StatusCode,
+  response_headers: &HeaderMap,
+) -> bool {
+  if method != Method::GET && method != Method::HEAD {
+    return false;
+  }
+  if !status.is_success() && status != StatusCode::NOT_MODIFIED {
+    return false;
+  }
+  fresh(request_headers, response_headers)
+}
+
+/// Check the conditional headers of a request against the `ETag` and
+/// `Last-Modified` headers of its response, like the npm `fresh` package.
+pub fn fresh(request_headers: &HeaderMap, response_headers: &HeaderMap) -> bool {
+  let modified_since = header(request_headers, IF_MODIFIED_SINCE);
+  let none_match = header(request_headers, IF_NONE_MATCH);
+
+  // unconditional request
+  if modified_since.is_none() && none_match.is_none() {
+    return false;
+  }
+
+  // always return stale when the client asks for an end-to-end reload
+  if header(request_headers, CACHE_CONTROL).is_some_and(|cache_control| {
+    cache_control
+      .split(',')
+      .any(|directive| directive.trim().eq_ignore_ascii_case("no-cache"))
+  }) {
+    return false;
+  }
+
+  if let Some(none_match) = none_match
+    && none_match != "*"
+  {
+    let Some(etag) = header(response_headers, ETAG) else {
+      return false;
+    };
+    let strip_weak = |tag: &str| tag.strip_prefix("W/").unwrap_or(tag).to_owned();
+    let etag = strip_weak(etag);
+    let matches = none_match
+      .split(',')
+      .map(|tag| strip_weak(tag.trim()))
+      .any(|tag| tag == etag);
+    if !matches {
+      return false;
+    }
+  }
+
+  if let Some(modified_since) = modified_since {
+    let last_modified = header(response_headers, LAST_MODIFIED)
+      .and_then(|last_modified| httpdate::parse_http_date(last_modified).ok());
+    let modified_since = httpdate::parse_http_date(modified_since).ok();
+    match (last_modified, modified_since) {
+      (Some(last_modified), Some(modified_since)) if last_modified <= modified_since => {}
+      _ => return false,
+    }
+  }
+
+  true
+}
+
+fn header(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+  headers
+    .get(name)
+    .and_then(|value| value.to_str().ok())
+    .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+  use hyper::header::HeaderValue;
+
+  use super::*;
+
+  fn headers(pairs: &[(HeaderName, &'static str)]) -> HeaderMap {
+    pairs
+      .iter()
+      .map(|(name, value)| (name.clone(), HeaderValue::from_static(value)))
+      .collect()
+  }
+
+  #[test]
+  fn test_unconditional() {
+    assert!(!fresh(&HeaderMap::new(), &headers(&[(ETAG, "\"foo\"")])));
+  }
+
+  #[test]
+  fn test_if_none_match() {
+    let response = headers(&[(ETAG, "\"foo\"")]);
+    assert!(fresh(&headers(&[(IF_NONE_MATCH, "\"foo\"")]), &response));
+    assert!(fresh(
+      &headers(&[(IF_NONE_MATCH, "\"bar\", \"foo\"")]),
+      &response
+    ));
+    assert!(fresh(&headers(&[(IF_NONE_MATCH, "W/\"foo\"")]), &response));
+    assert!(fresh(&headers(&[(IF_NONE_MATCH, "*")]), &response));
+    assert!(!fresh(&headers(&[(IF_NONE_MATCH, "\"bar\"")]), &response));
+    assert!(!fresh(
+      &headers(&[(IF_NONE_MATCH, "\"foo\"")]),
+      &HeaderMap::new()
+    ));
+    assert!(fresh(
+      &headers(&[(IF_NONE_MATCH, "\"foo\"")]),
+      &headers(&[(ETAG, "W/\"foo\"")])
+    ));
+  }
+
+  #[test]
+  fn test_if_modified_since() {
+    let response = headers(&[(LAST_MODIFIED, "Sat, 01 Jan 2000 00:00:00 GMT")]);
+    assert!(fresh(
+      &headers(&[(IF_MODIFIED_SINCE, "Sat, 01 Jan 2000 00:00:00 GMT")]),
+      &response
+    ));
+    assert!(fresh(
+      &headers(&[(IF_MODIFIED_SINCE, "Sun, 02 Jan 2000 00:00:00 GMT")]),
+      &response
+    ));
+    assert!(!fresh(
+      &headers(&[(IF_MODIFIED_SINCE, "Fri, 31 Dec 1999 00:00:00 GMT")]),
+      &response
+    ));
+    assert!(!fresh(
+      &headers(&[(IF_MODIFIED_SINCE, "not a date")]),
+      &response
+    ));
+  }
+
+  #[test]
+  fn test_no_cache() {
+    let request = headers(&[(IF_NONE_MATCH, "\"foo\""), (CACHE_CONTROL, "no-cache")]);
+    assert!(!fresh(&request, &headers(&[(ETAG, "\"foo\"")])));
+  }
+
+  #[test]
+  fn test_is_fresh() {
+    let request = headers(&[(IF_NONE_MATCH, "\"foo\"")]);
+    let response = headers(&[(ETAG, "\"foo\"")]);
+    assert!(is_fresh(&Method::GET, &request, StatusCode::OK, &response));
+    assert!(is_fresh(&Method::HEAD, &request, StatusCode::OK, &response));
+    assert!(!is_fresh(
+      &Method::POST,
+      &request,
+      StatusCode::OK,
+      &response
+    ));
+    assert!(!is_fresh(
+      &Method::GET,
+      &request,
+      StatusCode::NOT_FOUND,
+      &response
+    ));
+  }
+}
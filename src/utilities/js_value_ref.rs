@@ -0,0 +1,26 @@
+use std::fmt;
+
+use napi::bindgen_prelude::*;
+
+/// A reference to a JS value that can be moved to and dropped on any thread.
+/// The value is created and read back on the JS thread.
+pub struct JsValueRef(Error);
+
+impl JsValueRef {
+  /// Keep a reference to `value`. Must be called on the JS thread.
+  pub fn new(value: Unknown) -> Self {
+    Self(Error::from_unknown_without_coercion(value))
+  }
+
+  /// The referenced value. Must be called on the JS thread.
+  pub fn get(&self, env: &Env) -> Result<Unknown<'static>> {
+    let value = unsafe { ToNapiValue::to_napi_value(env.raw(), self.0.try_clone()?)? };
+    Ok(unsafe { Unknown::from_raw_unchecked(env.raw(), value) })
+  }
+}
+
+impl fmt::Debug for JsValueRef {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("JsValueRef")
+  }
+}
@@ -11,7 +11,7 @@ mod assert_header_exists;
 pub use assert_header_exists::assert_header_exists;
 
 mod serialize_napi_object;
-pub use serialize_napi_object::serialize_napi_object;
+pub use serialize_napi_object::{serialize_napi_object, stringify_json, to_json_value};
 
 mod body_from_status_code;
 pub use body_from_status_code::body_from_status_code;
@@ -46,13 +46,59 @@ pub use object_to_header_map::object_to_header_map;
 mod decimal_to_binary_unit;
 pub use decimal_to_binary_unit::decimal_to_binary_unit;
 
+mod parse_duration;
+pub use parse_duration::{parse_duration, parse_max_age};
+
+// `fastparse` scans the query with a nested `if` that newer clippy
+// versions flag as collapsible
+#[allow(clippy::collapsible_match)]
 pub mod parse_url;
 
 mod file_send_task;
 pub use file_send_task::{FileSendOptions, FileSendTask};
 
 mod parse_urlencoded;
-pub use parse_urlencoded::{UrlencodedOptions, parse_urlencoded};
+pub use parse_urlencoded::{UrlencodedError, UrlencodedOptions, parse_urlencoded};
 
 mod extract_cookies;
 pub use extract_cookies::{RequestCookies, extract_cookies};
+
+mod read_body;
+pub use read_body::read_body;
+
+mod body_parser_error;
+pub use body_parser_error::BodyParserError;
+
+mod parse_json;
+pub use parse_json::{JsonParseOptions, PrototypeAction, parse_json};
+
+mod charset;
+pub use charset::{content_type_charset, decode_charset, lookup_charset};
+
+mod compress_response;
+pub use compress_response::{
+  CompressionOptions, ContentEncoding, ResponseCompression, compress_bytes, is_compressible,
+};
+
+mod fresh;
+pub use fresh::{fresh, is_fresh};
+
+mod preconditions;
+pub use preconditions::{Precondition, Validators, evaluate_preconditions};
+
+mod byteranges;
+pub use byteranges::{
+  ByteRanges, byte_ranges, content_range, multipart_byteranges, unsatisfied_range,
+};
+
+mod directory_listing;
+pub use directory_listing::{
+  DirectoryEntry, DirectoryListingOptions, ListingFormat, ListingSort, read_directory,
+  render_listing,
+};
+
+mod static_fs;
+pub use static_fs::{FileMetadata, StaticFile, StaticFileAccess, StaticFs};
+
+mod js_value_ref;
+pub use js_value_ref::JsValueRef;
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use napi::{Either, Error, Result, Status};
+
+const SECOND: f64 = 1000.0;
+const MINUTE: f64 = SECOND * 60.0;
+const HOUR: f64 = MINUTE * 60.0;
+const DAY: f64 = HOUR * 24.0;
+const WEEK: f64 = DAY * 7.0;
+const YEAR: f64 = DAY * 365.25;
+
+/// Parse a duration in the format of the [ms](https://www.npmjs.org/package/ms)
+/// package, e.g. `"2h"`, `"7 days"`, `"1.5 hours"` or `"1y"`. Numbers without
+/// a unit are milliseconds. Negative durations are zero.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+  let input = input.trim();
+  let unit_start = input
+    .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+    .unwrap_or(input.len());
+  let (value, unit) = input.split_at(unit_start);
+  let value: f64 = value.parse().ok()?;
+
+  let unit_ms = match unit.trim_start().to_lowercase().as_str() {
+    "" | "ms" | "msec" | "msecs" | "millisecond" | "milliseconds" => 1.0,
+    "s" | "sec" | "secs" | "second" | "seconds" => SECOND,
+    "m" | "min" | "mins" | "minute" | "minutes" => MINUTE,
+    "h" | "hr" | "hrs" | "hour" | "hours" => HOUR,
+    "d" | "day" | "days" => DAY,
+    "w" | "week" | "weeks" => WEEK,
+    "y" | "yr" | "yrs" | "year" | "years" => YEAR,
+    _ => return None,
+  };
+
+  Duration::try_from_secs_f64((value * unit_ms).max(0.0) / SECOND).ok()
+}
+
+/// The milliseconds of the `name` option, a max-age given as a number of
+/// milliseconds or as a duration string like `"1d"`. Negative max-ages are
+/// zero.
+pub fn parse_max_age(max_age: &Either<i64, String>, name: &str) -> Result<u64> {
+  match max_age {
+    Either::A(max_age) => Ok((*max_age).max(0) as u64),
+    Either::B(max_age) => parse_duration(max_age)
+      .map(|duration| duration.as_millis().min(u64::MAX as u128) as u64)
+      .ok_or_else(|| {
+        Error::new(
+          Status::InvalidArg,
+          format!(
+            "Invalid {name} value \"{max_age}\": expected milliseconds or a duration like \"1d\""
+          ),
+        )
+      }),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_duration() {
+    assert_eq!(parse_duration("100"), Some(Duration::from_millis(100)));
+    assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7200)));
+    assert_eq!(parse_duration("7 days"), Some(Duration::from_secs(604_800)));
+    assert_eq!(parse_duration("1.5 Hours"), Some(Duration::from_secs(5400)));
+    assert_eq!(parse_duration("1y"), Some(Duration::from_secs(31_557_600)));
+    assert_eq!(parse_duration(".5s"), Some(Duration::from_millis(500)));
+    assert_eq!(parse_duration("-1d"), Some(Duration::ZERO));
+
+    assert_eq!(parse_duration(""), None);
+    assert_eq!(parse_duration("1 fortnight"), None);
+    assert_eq!(parse_duration("d"), None);
+    assert_eq!(parse_duration("1..2s"), None);
+  }
+
+  #[test]
+  fn test_parse_max_age() {
+    assert_eq!(parse_max_age(&Either::A(-5), "maxAge").unwrap(), 0);
+    assert_eq!(
+      parse_max_age(&Either::B("2 years".to_owned()), "maxAge").unwrap(),
+      63_115_200_000
+    );
+    assert!(parse_max_age(&Either::B("soon".to_owned()), "maxAge").is_err());
+  }
+}
@@ -0,0 +1,292 @@
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+
+/// What to do with keys that could poison an object's prototype once the
+/// parsed value reaches JavaScript.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PrototypeAction {
+  /// Keep the key
+  #[default]
+  Ignore,
+  /// Drop the key and its value
+  Remove,
+  /// Fail the parse
+  Error,
+}
+
+impl TryFrom<&str> for PrototypeAction {
+  type Error = String;
+
+  fn try_from(value: &str) -> Result<Self, String> {
+    match value {
+      "ignore" => Ok(Self::Ignore),
+      "remove" => Ok(Self::Remove),
+      "error" => Ok(Self::Error),
+      _ => Err(format!(
+        "Expected \"error\", \"remove\" or \"ignore\", found \"{value}\""
+      )),
+    }
+  }
+}
+
+/// Options for JSON parsing. Every check is disabled by default.
+#[derive(Debug, Clone, Default)]
+pub struct JsonParseOptions {
+  /// Handling of `__proto__` keys
+  pub proto_action: PrototypeAction,
+  /// Handling of `constructor` keys whose value is an object with a
+  /// `prototype` key
+  pub constructor_action: PrototypeAction,
+  /// Maximum nesting depth of arrays and objects; a top level array or object
+  /// has a depth of 1
+  pub max_depth: Option<usize>,
+  /// Maximum number of elements in an array
+  pub max_array_length: Option<usize>,
+  /// Maximum number of characters in a string, key or value
+  pub max_string_length: Option<usize>,
+  /// Fail the parse when an object has the same key more than once instead of
+  /// keeping the last value
+  pub reject_duplicate_keys: bool,
+}
+
+/// Parse `body` as JSON, enforcing the structural limits of `options` while
+/// parsing.
+pub fn parse_json(body: &[u8], options: &JsonParseOptions) -> serde_json::Result<Value> {
+  let mut deserializer = serde_json::Deserializer::from_slice(body);
+  let value = ValueSeed { options, depth: 0 }.deserialize(&mut deserializer)?;
+  deserializer.end()?;
+  Ok(value)
+}
+
+#[derive(Clone, Copy)]
+struct ValueSeed<'a> {
+  options: &'a JsonParseOptions,
+  /// Depth of the value's parent
+  depth: usize,
+}
+
+impl ValueSeed<'_> {
+  fn check_string<E: de::Error>(&self, value: &str) -> Result<(), E> {
+    match self.options.max_string_length {
+      Some(max) if value.chars().count() > max => Err(E::custom(format!(
+        "string length exceeds the maximum of {max}"
+      ))),
+      _ => Ok(()),
+    }
+  }
+
+  fn nested<E: de::Error>(self) -> Result<Self, E> {
+    let depth = self.depth + 1;
+    match self.options.max_depth {
+      Some(max) if depth > max => Err(E::custom(format!(
+        "nesting depth exceeds the maximum of {max}"
+      ))),
+      _ => Ok(Self { depth, ..self }),
+    }
+  }
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+  type Value = Value;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+    deserializer.deserialize_any(self)
+  }
+}
+
+impl<'de> Visitor<'de> for ValueSeed<'_> {
+  type Value = Value;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("any valid JSON value")
+  }
+
+  fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+    Ok(Value::Bool(value))
+  }
+
+  fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+    Ok(Value::Number(value.into()))
+  }
+
+  fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+    Ok(Value::Number(value.into()))
+  }
+
+  fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+    Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+  }
+
+  fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+    self.check_string(value)?;
+    Ok(Value::String(value.to_owned()))
+  }
+
+  fn visit_string<E: de::Error>(self, value: String) -> Result<Value, E> {
+    self.check_string(&value)?;
+    Ok(Value::String(value))
+  }
+
+  fn visit_unit<E>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+    let seed = self.nested()?;
+    let mut values = Vec::new();
+    while let Some(value) = seq.next_element_seed(seed)? {
+      if let Some(max) = self.options.max_array_length
+        && values.len() == max
+      {
+        return Err(de::Error::custom(format!(
+          "array length exceeds the maximum of {max}"
+        )));
+      }
+      values.push(value);
+    }
+    Ok(Value::Array(values))
+  }
+
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+    let seed = self.nested()?;
+    let mut values = Map::new();
+    while let Some(key) = map.next_key::<String>()? {
+      self.check_string(&key)?;
+      if self.options.reject_duplicate_keys && values.contains_key(&key) {
+        return Err(de::Error::custom(format!("duplicate key \"{key}\"")));
+      }
+      if key == "__proto__" {
+        match self.options.proto_action {
+          PrototypeAction::Ignore => {}
+          PrototypeAction::Remove => {
+            map.next_value::<IgnoredAny>()?;
+            continue;
+          }
+          PrototypeAction::Error => {
+            return Err(de::Error::custom(
+              "object contains forbidden prototype property",
+            ));
+          }
+        }
+      }
+      let value = map.next_value_seed(seed)?;
+      if key == "constructor"
+        && let Value::Object(constructor) = &value
+        && constructor.contains_key("prototype")
+      {
+        match self.options.constructor_action {
+          PrototypeAction::Ignore => {}
+          PrototypeAction::Remove => continue,
+          PrototypeAction::Error => {
+            return Err(de::Error::custom(
+              "object contains forbidden prototype property",
+            ));
+          }
+        }
+      }
+      values.insert(key, value);
+    }
+    Ok(Value::Object(values))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  fn parse(body: &str, options: &JsonParseOptions) -> serde_json::Result<Value> {
+    parse_json(body.as_bytes(), options)
+  }
+
+  #[test]
+  fn test_default_options() {
+    let options = JsonParseOptions::default();
+    assert_eq!(
+      parse(r#"{"a":[1,-2,3.5,"x",null,true],"__proto__":{}}"#, &options).unwrap(),
+      json!({"a": [1, -2, 3.5, "x", null, true], "__proto__": {}})
+    );
+    assert_eq!(
+      parse(r#"{"a":1,"a":2}"#, &options).unwrap(),
+      json!({"a": 2})
+    );
+    assert!(parse(r#"{"a":1} x"#, &options).is_err());
+  }
+
+  #[test]
+  fn test_proto_action() {
+    let mut options = JsonParseOptions {
+      proto_action: PrototypeAction::Remove,
+      ..Default::default()
+    };
+    assert_eq!(
+      parse(r#"{"a":{"__proto__":{"x":1},"b":2}}"#, &options).unwrap(),
+      json!({"a": {"b": 2}})
+    );
+    options.proto_action = PrototypeAction::Error;
+    assert!(parse(r#"{"a":{"__proto__":{}}}"#, &options).is_err());
+  }
+
+  #[test]
+  fn test_constructor_action() {
+    let mut options = JsonParseOptions {
+      constructor_action: PrototypeAction::Remove,
+      ..Default::default()
+    };
+    assert_eq!(
+      parse(
+        r#"{"constructor":{"prototype":{"x":1}},"b":{"constructor":1}}"#,
+        &options
+      )
+      .unwrap(),
+      json!({"b": {"constructor": 1}})
+    );
+    options.constructor_action = PrototypeAction::Error;
+    assert!(parse(r#"{"constructor":{"prototype":{}}}"#, &options).is_err());
+  }
+
+  #[test]
+  fn test_max_depth() {
+    let options = JsonParseOptions {
+      max_depth: Some(2),
+      ..Default::default()
+    };
+    assert!(parse(r#"{"a":[1]}"#, &options).is_ok());
+    assert!(parse(r#"{"a":[[1]]}"#, &options).is_err());
+    assert!(parse("1", &options).is_ok());
+  }
+
+  #[test]
+  fn test_max_array_length() {
+    let options = JsonParseOptions {
+      max_array_length: Some(2),
+      ..Default::default()
+    };
+    assert!(parse("[1,2]", &options).is_ok());
+    assert!(parse("[1,2,3]", &options).is_err());
+  }
+
+  #[test]
+  fn test_max_string_length() {
+    let options = JsonParseOptions {
+      max_string_length: Some(3),
+      ..Default::default()
+    };
+    assert!(parse(r#"{"abc":"été"}"#, &options).is_ok());
+    assert!(parse(r#"{"abcd":1}"#, &options).is_err());
+    assert!(parse(r#"["abcd"]"#, &options).is_err());
+  }
+
+  #[test]
+  fn test_reject_duplicate_keys() {
+    let options = JsonParseOptions {
+      reject_duplicate_keys: true,
+      ..Default::default()
+    };
+    assert!(parse(r#"{"a":1,"b":{"a":2}}"#, &options).is_ok());
+    assert!(parse(r#"{"a":1,"a":2}"#, &options).is_err());
+  }
+}
@@ -70,17 +70,17 @@ pub fn parse_range(
     if start_str.is_empty() {
       if let Some(end_val) = end {
         start = Some(size.saturating_sub(end_val));
-        end = Some(size - 1);
+        end = Some(size.saturating_sub(1));
       }
     } else if end_str.is_empty() {
-      end = Some(size - 1);
+      end = Some(size.saturating_sub(1));
     }
 
     // Limit last-byte-pos to current length
     if let Some(end_val) = end
-      && end_val > size - 1
+      && end_val > size.saturating_sub(1)
     {
-      end = Some(size - 1);
+      end = Some(size.saturating_sub(1));
     }
 
     // Invalid format range
@@ -3,10 +3,30 @@
  * Inspired by body-parser urlencoded
  */
 
+use encoding_rs::Encoding;
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode, utf8_percent_encode};
 use serde_json::Value;
 use std::error::Error;
 use std::fmt;
 
+/// `utf8=✓` as sent by a form submitted in UTF-8
+const UTF8_SENTINEL: &str = "%E2%9C%93";
+
+/// `utf8=✓` as sent by a form submitted in ISO-8859-1, where the check mark
+/// is replaced by the numeric entity `&#10003;`
+const ISO_SENTINEL: &str = "%26%2310003%3B";
+
+/// Characters escaped when re-encoding transcoded keys and values. Brackets
+/// are kept so nested keys still parse in extended mode.
+const COMPONENT: &AsciiSet = &CONTROLS
+  .add(b' ')
+  .add(b'"')
+  .add(b'#')
+  .add(b'%')
+  .add(b'&')
+  .add(b'+')
+  .add(b'=');
+
 /// Error types for URL-encoded parsing
 #[derive(Debug)]
 pub enum UrlencodedError {
@@ -36,6 +56,11 @@ pub struct UrlencodedOptions {
   pub parameter_limit: usize,
   /// Maximum depth for nested objects (only used when extended is true)
   pub depth: usize,
+  /// Character set the percent-encoded keys and values are decoded with
+  pub charset: &'static Encoding,
+  /// Detect the character set from a `utf8=✓` parameter, which is removed
+  /// from the parsed result
+  pub charset_sentinel: bool,
 }
 
 impl Default for UrlencodedOptions {
@@ -44,6 +69,8 @@ impl Default for UrlencodedOptions {
       extended: true,
       parameter_limit: 1000,
       depth: 32,
+      charset: encoding_rs::UTF_8,
+      charset_sentinel: false,
     }
   }
 }
@@ -68,8 +95,67 @@ fn parameter_count(body: &str, limit: usize) -> Option<usize> {
   Some(count)
 }
 
+/// Remove the `utf8` sentinel parameter from `body`, returning the charset
+/// it indicates, if any.
+fn take_charset_sentinel(body: &str) -> (String, Option<&'static Encoding>) {
+  let mut charset = None;
+  let mut found = false;
+  let parts = body
+    .split('&')
+    .filter(|part| {
+      if found {
+        return true;
+      }
+      let Some(value) = part.strip_prefix("utf8=") else {
+        return true;
+      };
+      found = true;
+      if value == UTF8_SENTINEL {
+        charset = Some(encoding_rs::UTF_8);
+      } else if value == ISO_SENTINEL {
+        charset = Some(encoding_rs::WINDOWS_1252);
+      }
+      false
+    })
+    .collect::<Vec<_>>();
+  (parts.join("&"), charset)
+}
+
+/// Re-encode the percent-encoded keys and values of `body` from `charset` to
+/// UTF-8, so they can be handed to the UTF-8 only parsers.
+fn transcode(body: &str, charset: &'static Encoding) -> String {
+  body
+    .split('&')
+    .map(|pair| {
+      pair
+        .splitn(2, '=')
+        .map(|component| {
+          let component = component.replace('+', " ");
+          let bytes = percent_decode(component.as_bytes()).collect::<Vec<_>>();
+          let (decoded, _) = charset.decode_without_bom_handling(&bytes);
+          utf8_percent_encode(&decoded, COMPONENT).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("=")
+    })
+    .collect::<Vec<_>>()
+    .join("&")
+}
+
 /// Parse URL-encoded body into a generic Value
 pub fn parse_urlencoded(body: &str, options: &UrlencodedOptions) -> Result<Value, UrlencodedError> {
+  let mut charset = options.charset;
+  let mut body = body.to_owned();
+  if options.charset_sentinel {
+    let (stripped_body, sentinel_charset) = take_charset_sentinel(&body);
+    body = stripped_body;
+    charset = sentinel_charset.unwrap_or(charset);
+  }
+  if charset != encoding_rs::UTF_8 {
+    body = transcode(&body, charset);
+  }
+  let body = body.as_str();
+
   if body.is_empty() {
     return Ok(Value::Object(serde_json::Map::new()));
   }
@@ -158,6 +244,58 @@ mod tests {
     assert!(matches!(result, Err(UrlencodedError::TooManyParameters)));
   }
 
+  #[test]
+  fn test_latin1_charset() {
+    let options = UrlencodedOptions {
+      extended: false,
+      charset: encoding_rs::WINDOWS_1252,
+      ..UrlencodedOptions::default()
+    };
+    let result = parse_urlencoded("name=Ren%E9e+Fran%E7oise", &options).unwrap();
+
+    assert_eq!(result["name"], "Renée Françoise");
+  }
+
+  #[test]
+  fn test_latin1_charset_extended() {
+    let options = UrlencodedOptions {
+      charset: encoding_rs::WINDOWS_1252,
+      ..UrlencodedOptions::default()
+    };
+    let result = parse_urlencoded("user[name]=Ren%E9e", &options).unwrap();
+
+    assert_eq!(result["user"]["name"], "Renée");
+  }
+
+  #[test]
+  fn test_charset_sentinel() {
+    let options = UrlencodedOptions {
+      extended: false,
+      charset_sentinel: true,
+      ..UrlencodedOptions::default()
+    };
+    let result = parse_urlencoded("utf8=%26%2310003%3B&name=%E9", &options).unwrap();
+    assert_eq!(result, json!({ "name": "é" }));
+
+    let options = UrlencodedOptions {
+      charset: encoding_rs::WINDOWS_1252,
+      ..options
+    };
+    let result = parse_urlencoded("utf8=%E2%9C%93&name=%C3%A9", &options).unwrap();
+    assert_eq!(result, json!({ "name": "é" }));
+  }
+
+  #[test]
+  fn test_charset_sentinel_disabled() {
+    let options = UrlencodedOptions {
+      extended: false,
+      ..UrlencodedOptions::default()
+    };
+    let result = parse_urlencoded("utf8=%E2%9C%93&name=x", &options).unwrap();
+
+    assert_eq!(result, json!({ "utf8": "✓", "name": "x" }));
+  }
+
   #[test]
   fn test_empty_body() {
     let options = UrlencodedOptions::default();
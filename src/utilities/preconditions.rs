@@ -0,0 +1,300 @@
+use std::time::SystemTime;
+
+use etag::EntityTag;
+use hyper::{
+  HeaderMap, Method,
+  header::{
+    ETAG, HeaderName, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, IF_UNMODIFIED_SINCE,
+    LAST_MODIFIED, RANGE,
+  },
+};
+
+/// The validators of the selected representation of a resource
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+  pub etag: Option<EntityTag>,
+  pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+  /// The validators announced by the `ETag` and `Last-Modified` headers of a
+  /// response
+  pub fn from_headers(response_headers: &HeaderMap) -> Self {
+    Validators {
+      etag: header(response_headers, ETAG).and_then(|etag| etag.parse().ok()),
+      last_modified: header(response_headers, LAST_MODIFIED)
+        .and_then(|last_modified| httpdate::parse_http_date(last_modified).ok()),
+    }
+  }
+}
+
+/// The result of evaluating the preconditions of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition {
+  /// Every precondition passed, the request can be performed
+  Passed,
+  /// Every precondition passed but `If-Range` did not match: the request can
+  /// be performed, ignoring its `Range` header
+  IgnoreRange,
+  /// Answer with `304 Not Modified`
+  NotModified,
+  /// Answer with `412 Precondition Failed`
+  Failed,
+}
+
+/// Evaluate the conditional headers of a request against `validators`, in the
+/// order of RFC 9110 section 13.2.2. The selected representation is assumed to
+/// exist, so `If-Match: *` passes and `If-None-Match: *` fails.
+pub fn evaluate_preconditions(
+  method: &Method,
+  request_headers: &HeaderMap,
+  validators: &Validators,
+) -> Precondition {
+  let safe = method == Method::GET || method == Method::HEAD;
+
+  // 1. If-Match, else 2. If-Unmodified-Since
+  if let Some(if_match) = header(request_headers, IF_MATCH) {
+    if !if_match_passes(if_match, validators.etag.as_ref()) {
+      return Precondition::Failed;
+    }
+  } else if let Some(since) = header(request_headers, IF_UNMODIFIED_SINCE)
+    && let Ok(since) = httpdate::parse_http_date(since)
+    && let Some(last_modified) = validators.last_modified
+    && truncate(last_modified) > since
+  {
+    return Precondition::Failed;
+  }
+
+  // 3. If-None-Match, else 4. If-Modified-Since
+  if let Some(if_none_match) = header(request_headers, IF_NONE_MATCH) {
+    if !if_none_match_passes(if_none_match, validators.etag.as_ref()) {
+      return match safe {
+        true => Precondition::NotModified,
+        false => Precondition::Failed,
+      };
+    }
+  } else if safe
+    && let Some(since) = header(request_headers, IF_MODIFIED_SINCE)
+    && let Ok(since) = httpdate::parse_http_date(since)
+    && let Some(last_modified) = validators.last_modified
+    && truncate(last_modified) <= since
+  {
+    return Precondition::NotModified;
+  }
+
+  // 5. If-Range, only for ranged GET requests
+  if method == Method::GET
+    && request_headers.contains_key(RANGE)
+    && let Some(if_range) = header(request_headers, IF_RANGE)
+    && !if_range_passes(if_range, validators)
+  {
+    return Precondition::IgnoreRange;
+  }
+
+  Precondition::Passed
+}
+
+fn header(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+  headers
+    .get(name)
+    .and_then(|value| value.to_str().ok())
+    .map(str::trim)
+}
+
+/// Parse a list of entity tags, skipping invalid ones
+fn entity_tags(list: &str) -> impl Iterator<Item = EntityTag> {
+  list
+    .split(',')
+    .filter_map(|tag| tag.trim().parse::<EntityTag>().ok())
+}
+
+/// `If-Match` passes when its value is `*` or when one of its tags strongly
+/// matches the current one
+fn if_match_passes(if_match: &str, etag: Option<&EntityTag>) -> bool {
+  if_match == "*" || etag.is_some_and(|etag| entity_tags(if_match).any(|tag| tag.strong_eq(etag)))
+}
+
+/// `If-None-Match` fails when its value is `*` or when one of its tags weakly
+/// matches the current one
+fn if_none_match_passes(if_none_match: &str, etag: Option<&EntityTag>) -> bool {
+  if_none_match != "*"
+    && !etag.is_some_and(|etag| entity_tags(if_none_match).any(|tag| tag.weak_eq(etag)))
+}
+
+/// `If-Range` passes when its entity tag strongly matches the current one, or
+/// when its date is exactly the last modification date. A weak tag never
+/// matches: the weak `ETag` of files is validated by their `Last-Modified`
+/// date instead
+pub(crate) fn if_range_passes(if_range: &str, validators: &Validators) -> bool {
+  if if_range.starts_with('"') || if_range.starts_with("W/") {
+    return match (if_range.parse::<EntityTag>(), &validators.etag) {
+      (Ok(tag), Some(etag)) => tag.strong_eq(etag),
+      _ => false,
+    };
+  }
+  match (
+    httpdate::parse_http_date(if_range),
+    validators.last_modified,
+  ) {
+    (Ok(date), Some(last_modified)) => truncate(last_modified) == date,
+    _ => false,
+  }
+}
+
+/// HTTP dates have a one second resolution
+fn truncate(time: SystemTime) -> SystemTime {
+  httpdate::parse_http_date(&httpdate::fmt_http_date(time)).unwrap_or(time)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, UNIX_EPOCH};
+
+  use hyper::header::HeaderValue;
+
+  use super::*;
+
+  const DATE: &str = "Sat, 01 Jan 2000 00:00:00 GMT";
+  const EARLIER: &str = "Fri, 31 Dec 1999 00:00:00 GMT";
+
+  fn validators() -> Validators {
+    Validators {
+      etag: Some(EntityTag::strong("v1")),
+      last_modified: Some(UNIX_EPOCH + Duration::from_secs(946_684_800)),
+    }
+  }
+
+  fn evaluate(method: Method, pairs: &[(HeaderName, &'static str)]) -> Precondition {
+    let headers = pairs
+      .iter()
+      .map(|(name, value)| (name.clone(), HeaderValue::from_static(value)))
+      .collect();
+    evaluate_preconditions(&method, &headers, &validators())
+  }
+
+  #[test]
+  fn test_unconditional() {
+    assert_eq!(evaluate(Method::GET, &[]), Precondition::Passed);
+    assert_eq!(evaluate(Method::PUT, &[]), Precondition::Passed);
+  }
+
+  #[test]
+  fn test_if_match() {
+    assert_eq!(
+      evaluate(Method::PUT, &[(IF_MATCH, "\"v0\", \"v1\"")]),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(Method::PUT, &[(IF_MATCH, "*")]),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(Method::PUT, &[(IF_MATCH, "\"v0\"")]),
+      Precondition::Failed
+    );
+    // weak tags never match strongly
+    assert_eq!(
+      evaluate(Method::PUT, &[(IF_MATCH, "W/\"v1\"")]),
+      Precondition::Failed
+    );
+    // If-Match takes precedence over If-Unmodified-Since
+    assert_eq!(
+      evaluate(
+        Method::PUT,
+        &[(IF_MATCH, "\"v1\""), (IF_UNMODIFIED_SINCE, EARLIER)]
+      ),
+      Precondition::Passed
+    );
+  }
+
+  #[test]
+  fn test_if_unmodified_since() {
+    assert_eq!(
+      evaluate(Method::DELETE, &[(IF_UNMODIFIED_SINCE, DATE)]),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(Method::DELETE, &[(IF_UNMODIFIED_SINCE, EARLIER)]),
+      Precondition::Failed
+    );
+    assert_eq!(
+      evaluate(Method::DELETE, &[(IF_UNMODIFIED_SINCE, "not a date")]),
+      Precondition::Passed
+    );
+  }
+
+  #[test]
+  fn test_if_none_match() {
+    assert_eq!(
+      evaluate(Method::GET, &[(IF_NONE_MATCH, "W/\"v1\"")]),
+      Precondition::NotModified
+    );
+    assert_eq!(
+      evaluate(Method::HEAD, &[(IF_NONE_MATCH, "*")]),
+      Precondition::NotModified
+    );
+    assert_eq!(
+      evaluate(Method::POST, &[(IF_NONE_MATCH, "*")]),
+      Precondition::Failed
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[(IF_NONE_MATCH, "\"v0\"")]),
+      Precondition::Passed
+    );
+    // If-None-Match takes precedence over If-Modified-Since
+    assert_eq!(
+      evaluate(
+        Method::GET,
+        &[(IF_NONE_MATCH, "\"v0\""), (IF_MODIFIED_SINCE, DATE)]
+      ),
+      Precondition::Passed
+    );
+  }
+
+  #[test]
+  fn test_if_modified_since() {
+    assert_eq!(
+      evaluate(Method::GET, &[(IF_MODIFIED_SINCE, DATE)]),
+      Precondition::NotModified
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[(IF_MODIFIED_SINCE, EARLIER)]),
+      Precondition::Passed
+    );
+    // only evaluated for GET and HEAD
+    assert_eq!(
+      evaluate(Method::PUT, &[(IF_MODIFIED_SINCE, DATE)]),
+      Precondition::Passed
+    );
+  }
+
+  #[test]
+  fn test_if_range() {
+    let range = (RANGE, "bytes=0-10");
+    assert_eq!(
+      evaluate(Method::GET, &[range.clone(), (IF_RANGE, "\"v1\"")]),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[range.clone(), (IF_RANGE, "\"v0\"")]),
+      Precondition::IgnoreRange
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[range.clone(), (IF_RANGE, "W/\"v1\"")]),
+      Precondition::IgnoreRange
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[range.clone(), (IF_RANGE, DATE)]),
+      Precondition::Passed
+    );
+    assert_eq!(
+      evaluate(Method::GET, &[range, (IF_RANGE, EARLIER)]),
+      Precondition::IgnoreRange
+    );
+    // ignored without a Range header
+    assert_eq!(
+      evaluate(Method::GET, &[(IF_RANGE, "\"v0\"")]),
+      Precondition::Passed
+    );
+  }
+}
@@ -0,0 +1,228 @@
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder, ZlibDecoder, ZstdDecoder};
+use futures::TryStreamExt;
+use http_body_util::{BodyExt, Empty};
+use hyper::{
+  Request as HyperRequest,
+  header::{CONTENT_ENCODING, CONTENT_LENGTH, HeaderValue},
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::StreamReader;
+
+use crate::{request::Request, utilities::BodyParserError};
+
+/// Content codings that can be inflated when reading a request body
+enum ContentCoding {
+  Identity,
+  Gzip,
+  Deflate,
+  Brotli,
+  Zstd,
+}
+
+impl ContentCoding {
+  fn from_header(value: Option<&HeaderValue>) -> std::result::Result<Self, String> {
+    let Some(value) = value else {
+      return Ok(Self::Identity);
+    };
+    let value = value
+      .to_str()
+      .map_err(|_| String::from_utf8_lossy(value.as_bytes()).into_owned())?
+      .trim()
+      .to_lowercase();
+    match value.as_str() {
+      "" | "identity" => Ok(Self::Identity),
+      "gzip" | "x-gzip" => Ok(Self::Gzip),
+      "deflate" => Ok(Self::Deflate),
+      "br" => Ok(Self::Brotli),
+      "zstd" => Ok(Self::Zstd),
+      _ => Err(value),
+    }
+  }
+}
+
+/// Reads the whole body of `request`, inflating it according to its
+/// `Content-Encoding` header.
+///
+/// `limit` is applied to the inflated body, so a small compressed payload
+/// cannot expand past it. When `inflate` is `false` or the encoding is not one
+/// of `gzip`, `deflate`, `br` or `zstd`, an `encoding.unsupported` error is
+/// returned.
+///
+/// The request's body is consumed either way.
+pub async fn read_body(
+  request: &Request,
+  limit: usize,
+  inflate: bool,
+) -> std::result::Result<Vec<u8>, BodyParserError> {
+  let hyper_request = request.with_inner_mut(|w_req| w_req.take_inner())?;
+  let (parts, body) = hyper_request.into_parts();
+  let content_encoding = parts.headers.get(CONTENT_ENCODING).cloned();
+  let content_length = parts
+    .headers
+    .get(CONTENT_LENGTH)
+    .and_then(|length| length.to_str().ok()?.parse::<usize>().ok());
+  request.with_inner_mut(|w_req| {
+    let consumed = Empty::new().map_err(|never| match never {}).boxed();
+    w_req.set_inner(HyperRequest::from_parts(parts, consumed));
+    Ok(())
+  })?;
+
+  let coding = match ContentCoding::from_header(content_encoding.as_ref()) {
+    Ok(ContentCoding::Identity) => ContentCoding::Identity,
+    Ok(_) if !inflate => {
+      let encoding = content_encoding
+        .as_ref()
+        .map(|value| {
+          String::from_utf8_lossy(value.as_bytes())
+            .trim()
+            .to_lowercase()
+        })
+        .unwrap_or_default();
+      return Err(BodyParserError::encoding_unsupported(encoding));
+    }
+    Ok(coding) => coding,
+    Err(encoding) => return Err(BodyParserError::encoding_unsupported(encoding)),
+  };
+
+  // fail early on an uncompressed body whose declared length is too large
+  if let ContentCoding::Identity = coding
+    && let Some(length) = content_length
+    && length > limit
+  {
+    return Err(BodyParserError::entity_too_large(limit, Some(length)));
+  }
+
+  // keep the transport's errors distinguishable from the decoders' ones
+  let reader = StreamReader::new(body.into_data_stream().map_err(std::io::Error::other));
+  let mut reader: Box<dyn AsyncRead + Send + Unpin> = match coding {
+    ContentCoding::Identity => Box::new(reader),
+    ContentCoding::Gzip => Box::new(GzipDecoder::new(reader)),
+    ContentCoding::Deflate => Box::new(ZlibDecoder::new(reader)),
+    ContentCoding::Brotli => Box::new(BrotliDecoder::new(reader)),
+    ContentCoding::Zstd => Box::new(ZstdDecoder::new(reader)),
+  };
+
+  // read one byte past the limit to tell an exact fit from an overflow
+  let mut body = Vec::new();
+  if let Err(e) = (&mut reader)
+    .take(limit as u64 + 1)
+    .read_to_end(&mut body)
+    .await
+  {
+    if e.get_ref().is_some_and(|inner| inner.is::<hyper::Error>()) {
+      return Err(BodyParserError::request_aborted());
+    }
+    return Err(BodyParserError::inflate_failed(e.to_string()));
+  }
+
+  if body.len() > limit {
+    return Err(BodyParserError::entity_too_large(limit, content_length));
+  }
+
+  Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Write;
+
+  use bytes::Bytes;
+  use flate2::{
+    Compression,
+    write::{GzEncoder, ZlibEncoder},
+  };
+  use http_body_util::Full;
+  use hyper::Request as LibRequest;
+
+  use hyper::StatusCode;
+
+  use super::*;
+  use crate::request::WrappedRequest;
+
+  fn create_request(encoding: Option<&str>, body: Vec<u8>) -> Request {
+    let mut builder = LibRequest::builder();
+    if let Some(encoding) = encoding {
+      builder = builder.header(CONTENT_ENCODING, encoding);
+    }
+    let req = builder
+      .body(Full::new(Bytes::from(body)).map_err(|never| match never {}))
+      .unwrap();
+    Request::from(WrappedRequest::from(req))
+  }
+
+  fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_identity_body() {
+    let request = create_request(None, b"hello".to_vec());
+    let body = read_body(&request, 100, true).await.unwrap();
+    assert_eq!(body, b"hello".to_vec());
+  }
+
+  #[tokio::test]
+  async fn test_gzip_body() {
+    let request = create_request(Some("gzip"), gzip(b"{\"a\":1}"));
+    let body = read_body(&request, 100, true).await.unwrap();
+    assert_eq!(body, b"{\"a\":1}".to_vec());
+  }
+
+  #[tokio::test]
+  async fn test_deflate_body() {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"deflated").unwrap();
+    let request = create_request(Some("deflate"), encoder.finish().unwrap());
+    let body = read_body(&request, 100, true).await.unwrap();
+    assert_eq!(body, b"deflated".to_vec());
+  }
+
+  #[tokio::test]
+  async fn test_limit_applies_to_inflated_size() {
+    let bomb = gzip(&[b'a'; 10_000]);
+    assert!(bomb.len() < 100);
+    let request = create_request(Some("gzip"), bomb);
+    let error = read_body(&request, 100, true).await.unwrap_err();
+    assert_eq!(error.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error.typ, Some("entity.too.large"));
+    assert_eq!(error.limit, Some(100));
+  }
+
+  #[tokio::test]
+  async fn test_declared_length_too_large() {
+    let req = LibRequest::builder()
+      .header(CONTENT_LENGTH, "1000")
+      .body(Full::new(Bytes::from_static(b"hello")).map_err(|never| match never {}))
+      .unwrap();
+    let request = Request::from(WrappedRequest::from(req));
+    let error = read_body(&request, 100, true).await.unwrap_err();
+    assert_eq!(error.status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error.length, Some(1000));
+  }
+
+  #[tokio::test]
+  async fn test_corrupt_gzip_body() {
+    let request = create_request(Some("gzip"), b"not gzip".to_vec());
+    let error = read_body(&request, 100, true).await.unwrap_err();
+    assert_eq!(error.status, StatusCode::BAD_REQUEST);
+  }
+
+  #[tokio::test]
+  async fn test_inflate_disabled() {
+    let request = create_request(Some("gzip"), gzip(b"hello"));
+    let error = read_body(&request, 100, false).await.unwrap_err();
+    assert_eq!(error.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(error.typ, Some("encoding.unsupported"));
+    assert_eq!(error.encoding.as_deref(), Some("gzip"));
+  }
+
+  #[tokio::test]
+  async fn test_unknown_encoding() {
+    let request = create_request(Some("compress"), b"hello".to_vec());
+    let error = read_body(&request, 100, true).await.unwrap_err();
+    assert_eq!(error.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(error.message, "unsupported content encoding \"compress\"");
+  }
+}
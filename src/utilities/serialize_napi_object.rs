@@ -1,19 +1,84 @@
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
+use crate::server::{AppSettings, JsonReplacer};
+
+/// `JSON.stringify(value, replacer, space)`
+type JsonStringifyFn<'a> = Function<
+  'a,
+  FnArgs<(
+    Unknown<'a>,
+    Option<Either<Unknown<'a>, Vec<String>>>,
+    Option<Either<u32, String>>,
+  )>,
+  Option<String>,
+>;
+
+/// Serialize `value` with `JSON.stringify`. Returns `undefined` when the value
+/// has no JSON representation (e.g. `undefined` or a function).
 #[napi]
-pub fn serialize_napi_object(env: Env, obj: Object) -> Result<String> {
-  let global = env.get_global()?;
-  let json: Object = global.get_named_property("JSON")?;
-  let stringify: Function = json.get_named_property("stringify")?;
-
-  // Convert Object to Unknown
-  let obj_unknown = unsafe { Unknown::from_napi_value(env.raw(), obj.raw())? };
-  let result: Unknown = stringify.call(obj_unknown)?;
-
-  result
-    .coerce_to_string()?
-    .into_utf8()?
-    .as_str()
-    .map(|s| s.to_owned())
+pub fn serialize_napi_object(env: Env, value: Unknown) -> Result<Either<String, Undefined>> {
+  let json = stringify_json(&env, value, &AppSettings::default())?;
+  Ok(json.map_or(Either::B(()), Either::A))
+}
+
+/// Serialize `value` with `JSON.stringify`, using the `json replacer`, `json
+/// spaces` and `json escape` settings. Must be called on the JS thread; an
+/// error thrown while serializing (e.g. by a `BigInt` or a `toJSON` method) is
+/// left pending.
+pub fn stringify_json(env: &Env, value: Unknown, settings: &AppSettings) -> Result<Option<String>> {
+  let json: Object = env.get_global()?.get_named_property("JSON")?;
+  let stringify: JsonStringifyFn = json.get_named_property("stringify")?;
+
+  let replacer = match &settings.json_replacer {
+    Some(JsonReplacer::Function(replacer)) => {
+      Some(Either::A(replacer.borrow_back(env)?.to_unknown()))
+    }
+    Some(JsonReplacer::Keys(keys)) => Some(Either::B(keys.to_owned())),
+    None => None,
+  };
+  let body = stringify.call((value, replacer, settings.json_spaces.clone()).into())?;
+
+  Ok(match body {
+    Some(body) if settings.json_escape => Some(escape_json(&body)),
+    body => body,
+  })
+}
+
+/// Convert `value` as `JSON.stringify` does it: `toJSON` methods are called,
+/// and functions and `undefined` properties are omitted. A value without a
+/// JSON representation is `null`.
+pub fn to_json_value(env: &Env, value: Unknown) -> Result<serde_json::Value> {
+  match stringify_json(env, value, &AppSettings::default())? {
+    Some(json) => Ok(serde_json::from_str(&json)?),
+    None => Ok(serde_json::Value::Null),
+  }
+}
+
+/// Escape the characters of a JSON string that could be interpreted as HTML.
+fn escape_json(json: &str) -> String {
+  let mut escaped = String::with_capacity(json.len());
+  for c in json.chars() {
+    match c {
+      '<' => escaped.push_str("\\u003c"),
+      '>' => escaped.push_str("\\u003e"),
+      '&' => escaped.push_str("\\u0026"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_escape_json() {
+    assert_eq!(
+      escape_json(r#"{"html":"<p>a & b</p>"}"#),
+      r#"{"html":"\u003cp\u003ea \u0026 b\u003c/p\u003e"}"#
+    );
+    assert_eq!(escape_json("[1,2]"), "[1,2]");
+  }
 }
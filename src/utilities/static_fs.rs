@@ -0,0 +1,496 @@
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  fs::{File, Metadata},
+  io::{self, Cursor, Read, Seek, SeekFrom},
+  path::{Component, Path, PathBuf},
+  pin::Pin,
+  sync::Arc,
+  task::{Context, Poll},
+  time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use chrono::NaiveDate;
+use futures::future::BoxFuture;
+use hyper_staticfile::vfs::{
+  FileAccess, FileOpener, FileWithMetadata, IntoFileAccess, TokioFileAccess, TokioFileOpener,
+};
+use tokio::io::AsyncSeek;
+
+/// The metadata of a file served from a [`StaticFs`]
+#[derive(Debug, Clone)]
+pub struct FileMetadata {
+  pub size: u64,
+  pub modified: Option<SystemTime>,
+  pub accessed: Option<SystemTime>,
+  pub created: Option<SystemTime>,
+  pub is_dir: bool,
+  pub is_file: bool,
+  pub is_symlink: bool,
+  /// The path of the file on disk, `None` for files held in memory
+  pub disk_path: Option<PathBuf>,
+}
+
+impl FileMetadata {
+  /// The metadata of the file at `path` on disk
+  pub fn disk(metadata: &Metadata, path: PathBuf) -> Self {
+    Self {
+      size: metadata.len(),
+      modified: metadata.modified().ok(),
+      accessed: metadata.accessed().ok(),
+      created: metadata.created().ok(),
+      is_dir: metadata.is_dir(),
+      is_file: metadata.is_file(),
+      is_symlink: metadata.is_symlink(),
+      disk_path: Some(path),
+    }
+  }
+
+  fn memory(size: u64, modified: Option<SystemTime>, is_dir: bool) -> Self {
+    Self {
+      size,
+      modified,
+      accessed: None,
+      created: None,
+      is_dir,
+      is_file: !is_dir,
+      is_symlink: false,
+      disk_path: None,
+    }
+  }
+}
+
+struct MemoryFile {
+  data: Bytes,
+  modified: Option<SystemTime>,
+}
+
+/// Files held in memory, with the directories leading to them
+#[derive(Default)]
+struct MemoryFiles {
+  files: HashMap<PathBuf, MemoryFile>,
+  directories: HashSet<PathBuf>,
+}
+
+impl MemoryFiles {
+  fn add(&mut self, path: &str, data: Bytes, modified: Option<SystemTime>) {
+    let path = normalize(path);
+    if path.as_os_str().is_empty() {
+      return;
+    }
+    self
+      .directories
+      .extend(path.ancestors().skip(1).map(Path::to_path_buf));
+    self.files.insert(path, MemoryFile { data, modified });
+  }
+
+  fn add_directory(&mut self, path: &str) {
+    self
+      .directories
+      .extend(normalize(path).ancestors().map(Path::to_path_buf));
+  }
+}
+
+#[derive(Clone)]
+enum Layer {
+  Disk(PathBuf),
+  Memory(Arc<MemoryFiles>),
+}
+
+impl fmt::Debug for Layer {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Layer::Disk(root) => f.debug_tuple("Disk").field(root).finish(),
+      Layer::Memory(memory_files) => f
+        .debug_struct("Memory")
+        .field("files", &memory_files.files.len())
+        .finish(),
+    }
+  }
+}
+
+/// The files served by `StaticMiddleware` and `res.sendFile`: a directory on
+/// disk, files held in memory, e.g. extracted from an archive, or layers of
+/// those, where the first layer holding a path serves it.
+#[derive(Debug, Clone)]
+pub struct StaticFs {
+  layers: Arc<Vec<Layer>>,
+}
+
+impl StaticFs {
+  /// The files under the `root` directory
+  pub fn disk(root: impl Into<PathBuf>) -> Self {
+    Self {
+      layers: Arc::new(vec![Layer::Disk(root.into())]),
+    }
+  }
+
+  /// Files held in memory, given as `(path, content)` pairs, all last modified
+  /// at `modified`
+  pub fn memory(files: impl IntoIterator<Item = (String, Bytes)>, modified: SystemTime) -> Self {
+    let mut memory_files = MemoryFiles::default();
+    for (path, data) in files {
+      memory_files.add(&path, data, Some(modified));
+    }
+    Self::from_memory(memory_files)
+  }
+
+  /// The files of a `.tar` or `.zip` archive, read into memory once
+  pub fn archive(path: &Path) -> io::Result<Self> {
+    let mut archive = File::open(path)?;
+    let mut magic = [0; 4];
+    let is_zip = archive.read(&mut magic)? == 4 && &magic == b"PK\x03\x04";
+    archive.seek(SeekFrom::Start(0))?;
+
+    let memory_files = match is_zip {
+      true => read_zip(archive)?,
+      false => read_tar(archive)?,
+    };
+    Ok(Self::from_memory(memory_files))
+  }
+
+  /// The layers of `layers`, in order
+  pub fn overlay(layers: impl IntoIterator<Item = StaticFs>) -> Self {
+    Self {
+      layers: Arc::new(
+        layers
+          .into_iter()
+          .flat_map(|fs| fs.layers.to_vec())
+          .collect(),
+      ),
+    }
+  }
+
+  fn from_memory(mut memory_files: MemoryFiles) -> Self {
+    memory_files.directories.insert(PathBuf::new());
+    Self {
+      layers: Arc::new(vec![Layer::Memory(Arc::new(memory_files))]),
+    }
+  }
+
+  /// The root directory, when the files are those of a single directory on
+  /// disk
+  pub fn disk_root(&self) -> Option<&Path> {
+    match self.layers.as_slice() {
+      [Layer::Disk(root)] => Some(root),
+      _ => None,
+    }
+  }
+
+  /// The metadata of the file or directory at the sanitized `path`
+  pub async fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+    for layer in self.layers.iter() {
+      match layer {
+        Layer::Disk(root) => {
+          let disk_path = root.join(path);
+          match tokio::fs::metadata(&disk_path).await {
+            Ok(metadata) => return Ok(FileMetadata::disk(&metadata, disk_path)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+          }
+        }
+        Layer::Memory(memory_files) => {
+          if let Some(file) = memory_files.files.get(path) {
+            return Ok(FileMetadata::memory(
+              file.data.len() as u64,
+              file.modified,
+              false,
+            ));
+          }
+          if memory_files.directories.contains(path) {
+            return Ok(FileMetadata::memory(0, None, true));
+          }
+        }
+      }
+    }
+    Err(io::Error::from(io::ErrorKind::NotFound))
+  }
+}
+
+impl FileOpener for StaticFs {
+  type File = StaticFile;
+  type Future = BoxFuture<'static, io::Result<FileWithMetadata<StaticFile>>>;
+
+  fn open(&self, path: &Path) -> Self::Future {
+    let fs = self.clone();
+    let path = path.to_path_buf();
+    Box::pin(async move {
+      for layer in fs.layers.iter() {
+        match layer {
+          Layer::Disk(root) => match TokioFileOpener::new(root).open(&path).await {
+            Ok(file) => {
+              return Ok(FileWithMetadata {
+                handle: StaticFile::Disk(file.handle),
+                size: file.size,
+                modified: file.modified,
+                is_dir: file.is_dir,
+              });
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+          },
+          Layer::Memory(memory_files) => {
+            if let Some(file) = memory_files.files.get(&path) {
+              return Ok(FileWithMetadata {
+                handle: StaticFile::Memory(Cursor::new(file.data.clone())),
+                size: file.data.len() as u64,
+                modified: file.modified,
+                is_dir: false,
+              });
+            }
+            if memory_files.directories.contains(&path) {
+              return Ok(FileWithMetadata {
+                handle: StaticFile::Memory(Cursor::new(Bytes::new())),
+                size: 0,
+                modified: None,
+                is_dir: true,
+              });
+            }
+          }
+        }
+      }
+      Err(io::Error::from(io::ErrorKind::NotFound))
+    })
+  }
+}
+
+/// An open file of a [`StaticFs`]
+pub enum StaticFile {
+  Disk(tokio::fs::File),
+  Memory(Cursor<Bytes>),
+}
+
+impl IntoFileAccess for StaticFile {
+  type Output = StaticFileAccess;
+
+  fn into_file_access(self) -> Self::Output {
+    match self {
+      StaticFile::Disk(file) => StaticFileAccess::Disk(TokioFileAccess::new(file)),
+      StaticFile::Memory(cursor) => StaticFileAccess::Memory(cursor),
+    }
+  }
+}
+
+/// Streams the content of a [`StaticFile`]
+pub enum StaticFileAccess {
+  Disk(TokioFileAccess),
+  Memory(Cursor<Bytes>),
+}
+
+impl AsyncSeek for StaticFileAccess {
+  fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+    match self.get_mut() {
+      StaticFileAccess::Disk(file) => Pin::new(file).start_seek(position),
+      StaticFileAccess::Memory(cursor) => Pin::new(cursor).start_seek(position),
+    }
+  }
+
+  fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+    match self.get_mut() {
+      StaticFileAccess::Disk(file) => Pin::new(file).poll_complete(cx),
+      StaticFileAccess::Memory(cursor) => Pin::new(cursor).poll_complete(cx),
+    }
+  }
+}
+
+impl FileAccess for StaticFileAccess {
+  fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, len: usize) -> Poll<io::Result<Bytes>> {
+    match self.get_mut() {
+      StaticFileAccess::Disk(file) => Pin::new(file).poll_read(cx, len),
+      StaticFileAccess::Memory(cursor) => Pin::new(cursor).poll_read(cx, len),
+    }
+  }
+}
+
+/// `path` relative to the root of the files, without `.` and `..`
+/// components
+fn normalize(path: &str) -> PathBuf {
+  Path::new(path)
+    .components()
+    .fold(PathBuf::new(), |mut normalized, component| {
+      match component {
+        Component::Normal(name) => normalized.push(name),
+        Component::ParentDir => {
+          normalized.pop();
+        }
+        _ => {}
+      }
+      normalized
+    })
+}
+
+fn read_tar(archive: File) -> io::Result<MemoryFiles> {
+  let mut memory_files = MemoryFiles::default();
+  let mut archive = tar::Archive::new(archive);
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    let path = entry.path()?.to_string_lossy().into_owned();
+    let entry_type = entry.header().entry_type();
+    if entry_type.is_dir() {
+      memory_files.add_directory(&path);
+    } else if entry_type.is_file() {
+      let modified = entry
+        .header()
+        .mtime()
+        .ok()
+        .map(|mtime| UNIX_EPOCH + Duration::from_secs(mtime));
+      let mut data = Vec::with_capacity(entry.size() as usize);
+      entry.read_to_end(&mut data)?;
+      memory_files.add(&path, data.into(), modified);
+    }
+  }
+  Ok(memory_files)
+}
+
+fn read_zip(archive: File) -> io::Result<MemoryFiles> {
+  let mut memory_files = MemoryFiles::default();
+  let mut archive = zip::ZipArchive::new(archive).map_err(io::Error::other)?;
+  for index in 0..archive.len() {
+    let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+    // skip entries escaping the archive
+    let Some(path) = entry.enclosed_name() else {
+      continue;
+    };
+    let path = path.to_string_lossy().into_owned();
+    if entry.is_dir() {
+      memory_files.add_directory(&path);
+    } else if entry.is_file() {
+      // zip archives record local times, taken as UTC
+      let modified = entry.last_modified().and_then(|modified| {
+        NaiveDate::from_ymd_opt(
+          modified.year().into(),
+          modified.month().into(),
+          modified.day().into(),
+        )?
+        .and_hms_opt(
+          modified.hour().into(),
+          modified.minute().into(),
+          modified.second().into(),
+        )
+        .map(|modified| SystemTime::from(modified.and_utc()))
+      });
+      let mut data = Vec::with_capacity(entry.size() as usize);
+      entry.read_to_end(&mut data)?;
+      memory_files.add(&path, data.into(), modified);
+    }
+  }
+  Ok(memory_files)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{fs, io::Write};
+
+  use tempfile::TempDir;
+
+  use super::*;
+
+  async fn read(fs: &StaticFs, path: &str) -> io::Result<Bytes> {
+    let file = fs.open(Path::new(path)).await?;
+    match file.handle {
+      StaticFile::Disk(_) => Ok(Bytes::from(fs::read(
+        fs.metadata(Path::new(path)).await?.disk_path.unwrap(),
+      )?)),
+      StaticFile::Memory(cursor) => Ok(cursor.into_inner()),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_memory() {
+    let modified = UNIX_EPOCH + Duration::from_secs(1_000_000);
+    let fs = StaticFs::memory(
+      [
+        ("/index.html".to_owned(), Bytes::from("<html>")),
+        ("assets/../assets/app.js".to_owned(), Bytes::from("app()")),
+      ],
+      modified,
+    );
+
+    assert_eq!(read(&fs, "index.html").await.unwrap(), "<html>");
+    assert_eq!(read(&fs, "assets/app.js").await.unwrap(), "app()");
+    let metadata = fs.metadata(Path::new("assets/app.js")).await.unwrap();
+    assert_eq!(metadata.size, 5);
+    assert_eq!(metadata.modified, Some(modified));
+    assert!(metadata.disk_path.is_none());
+    assert!(fs.metadata(Path::new("assets")).await.unwrap().is_dir);
+    assert!(fs.open(Path::new("")).await.unwrap().is_dir);
+    assert_eq!(
+      fs.metadata(Path::new("missing.js"))
+        .await
+        .unwrap_err()
+        .kind(),
+      io::ErrorKind::NotFound
+    );
+  }
+
+  #[tokio::test]
+  async fn test_tar() {
+    let directory = TempDir::new().unwrap();
+    let path = directory.path().join("bundle.tar");
+    let mut builder = tar::Builder::new(File::create(&path).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_mtime(1_000_000);
+    header.set_mode(0o644);
+    builder
+      .append_data(&mut header, "./dist/app.js", &b"app()"[..])
+      .unwrap();
+    builder.finish().unwrap();
+    drop(builder);
+
+    let fs = StaticFs::archive(&path).unwrap();
+    assert_eq!(read(&fs, "dist/app.js").await.unwrap(), "app()");
+    let metadata = fs.metadata(Path::new("dist/app.js")).await.unwrap();
+    assert_eq!(
+      metadata.modified,
+      Some(UNIX_EPOCH + Duration::from_secs(1_000_000))
+    );
+    assert!(fs.metadata(Path::new("dist")).await.unwrap().is_dir);
+  }
+
+  #[tokio::test]
+  async fn test_zip() {
+    let directory = TempDir::new().unwrap();
+    let path = directory.path().join("bundle.zip");
+    let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+    let options = zip::write::SimpleFileOptions::default()
+      .compression_method(zip::CompressionMethod::Deflated)
+      .last_modified_time(zip::DateTime::from_date_and_time(2020, 1, 2, 3, 4, 6).unwrap());
+    writer.start_file("dist/index.html", options).unwrap();
+    writer.write_all(b"<html>").unwrap();
+    writer.finish().unwrap();
+
+    let fs = StaticFs::archive(&path).unwrap();
+    assert_eq!(read(&fs, "dist/index.html").await.unwrap(), "<html>");
+    let metadata = fs.metadata(Path::new("dist/index.html")).await.unwrap();
+    assert_eq!(
+      metadata.modified,
+      Some(UNIX_EPOCH + Duration::from_secs(1_577_934_246))
+    );
+  }
+
+  #[tokio::test]
+  async fn test_overlay() {
+    let directory = TempDir::new().unwrap();
+    fs::write(directory.path().join("index.html"), "disk").unwrap();
+    fs::write(directory.path().join("robots.txt"), "disk").unwrap();
+    let memory = StaticFs::memory(
+      [("index.html".to_owned(), Bytes::from("memory"))],
+      SystemTime::now(),
+    );
+
+    let fs = StaticFs::overlay([memory, StaticFs::disk(directory.path())]);
+    assert!(fs.disk_root().is_none());
+    assert_eq!(read(&fs, "index.html").await.unwrap(), "memory");
+    assert_eq!(read(&fs, "robots.txt").await.unwrap(), "disk");
+    assert!(
+      fs.metadata(Path::new("robots.txt"))
+        .await
+        .unwrap()
+        .disk_path
+        .is_some()
+    );
+    assert!(fs.open(Path::new("missing")).await.is_err());
+  }
+}
@@ -0,0 +1,257 @@
+//! A small template engine for a subset of the
+//! [Jinja](https://jinja.palletsprojects.com/en/stable/templates/) syntax:
+//! `{{ output }}` with filters, `{% if %}`, `{% for %}`, `{% set %}`,
+//! `{% include %}`, `{% extends %}` with `{% block %}`, and `{# comments #}`.
+//! Output is HTML escaped unless marked `safe`.
+
+mod parser;
+mod render;
+
+use std::{collections::HashMap, fmt};
+
+use serde_json::{Map, Value};
+
+pub use render::Loader;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateError {
+  /// The line of the template, 0 when unknown
+  pub line: usize,
+  pub message: String,
+}
+
+impl TemplateError {
+  pub fn new(line: usize, message: impl Into<String>) -> Self {
+    Self {
+      line,
+      message: message.into(),
+    }
+  }
+}
+
+impl fmt::Display for TemplateError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.line {
+      0 => write!(f, "{}", self.message),
+      line => write!(f, "line {line}: {}", self.message),
+    }
+  }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Debug)]
+pub struct Template {
+  parsed: parser::Parsed,
+}
+
+impl Template {
+  pub fn parse(source: &str) -> Result<Self, TemplateError> {
+    parser::parse(source).map(|parsed| Self { parsed })
+  }
+
+  /// Render with the variables of `context`, loading the templates named by
+  /// `include` and `extends` with `loader`
+  pub fn render(&self, context: Value, loader: &Loader) -> Result<String, TemplateError> {
+    let context = match context {
+      Value::Object(context) => context,
+      _ => Map::new(),
+    };
+    let mut renderer = render::Renderer::new(context, loader);
+    renderer.render_template(self, HashMap::new())?;
+    Ok(renderer.finish())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use serde_json::json;
+
+  use super::*;
+
+  fn render_with(
+    source: &str,
+    context: Value,
+    templates: &[(&str, &str)],
+  ) -> Result<String, TemplateError> {
+    let templates: HashMap<_, _> = templates.iter().copied().collect();
+    let loader = |name: &str| match templates.get(name) {
+      Some(source) => Template::parse(source).map(Arc::new),
+      None => Err(TemplateError::new(
+        0,
+        format!("Template \"{name}\" not found"),
+      )),
+    };
+    Template::parse(source)?.render(context, &loader)
+  }
+
+  fn render(source: &str, context: Value) -> String {
+    render_with(source, context, &[]).unwrap()
+  }
+
+  #[test]
+  fn test_output() {
+    let context = json!({
+      "name": "<b>World</b>",
+      "user": { "tags": ["a", "b"] },
+      "count": 3,
+    });
+    assert_eq!(
+      render("Hello {{ name }}!", context.clone()),
+      "Hello &#60;b&#62;World&#60;/b&#62;!"
+    );
+    assert_eq!(render("{{ name | safe }}", context.clone()), "<b>World</b>");
+    assert_eq!(
+      render("{{ user.tags[1] }}{{ user.tags.0 }}", context.clone()),
+      "ba"
+    );
+    assert_eq!(
+      render("{{ missing }}|{{ missing.deep }}", context.clone()),
+      "|"
+    );
+    assert_eq!(
+      render(
+        "{{ count * 2 + 1 }} {{ 7 / 2 }} {{ 7 % 4 }}",
+        context.clone()
+      ),
+      "7 3.5 3"
+    );
+    assert_eq!(render("{{ 'a' ~ count ~ 'b' }}", context.clone()), "a3b");
+    assert_eq!(
+      render("{{ user.tags }}", context.clone()),
+      "[&#34;a&#34;,&#34;b&#34;]"
+    );
+    assert!(render_with("{{ 1 / 0 }}", context, &[]).is_err());
+  }
+
+  #[test]
+  fn test_filters() {
+    let context = json!({ "items": [3, 1, 2], "title": "  hello WORLD " });
+    assert_eq!(
+      render("{{ title | trim | title }}", context.clone()),
+      "Hello World"
+    );
+    assert_eq!(
+      render("{{ title | trim | capitalize }}", context.clone()),
+      "Hello world"
+    );
+    assert_eq!(
+      render("{{ items | sort | join(', ') }}", context.clone()),
+      "1, 2, 3"
+    );
+    assert_eq!(
+      render(
+        "{{ items | length }} {{ items | first }} {{ items | last }}",
+        context.clone()
+      ),
+      "3 3 2"
+    );
+    assert_eq!(
+      render(
+        "{{ missing | default('none') }} {{ title | d('x') | trim }} {{ '' | d('empty', true) }}",
+        context.clone()
+      ),
+      "none hello WORLD empty"
+    );
+    assert_eq!(
+      render("{{ 'a-b' | replace('-', '+') | upper }}", context.clone()),
+      "A+B"
+    );
+    assert_eq!(
+      render(
+        "{{ 2.567 | round(1) }} {{ '42' | int + 1 }}",
+        context.clone()
+      ),
+      "2.6 43"
+    );
+    assert_eq!(render("{{ items | tojson }}", context.clone()), "[3,1,2]");
+    assert_eq!(render("{{ '<' | e }}", context.clone()), "&#60;");
+  }
+
+  #[test]
+  fn test_statements() {
+    let context = json!({
+      "users": [{ "name": "Ann", "admin": true }, { "name": "Bob" }],
+      "scores": { "ann": 1, "bob": 2 },
+    });
+    assert_eq!(
+      render(
+        "{% for user in users %}{{ loop.index }}.{{ user.name }}{% if user.admin %}*{% endif %}{% if not loop.last %},{% endif %}{% endfor %}",
+        context.clone()
+      ),
+      "1.Ann*,2.Bob"
+    );
+    assert_eq!(
+      render(
+        "{% for name, score in scores %}{{ name }}={{ score }};{% endfor %}",
+        context.clone()
+      ),
+      "ann=1;bob=2;"
+    );
+    assert_eq!(
+      render(
+        "{% for x in [] %}{{ x }}{% else %}empty{% endfor %}",
+        context.clone()
+      ),
+      "empty"
+    );
+    assert_eq!(
+      render(
+        "{% if users | length > 2 %}many{% elif users %}some{% else %}none{% endif %}",
+        context.clone()
+      ),
+      "some"
+    );
+    assert_eq!(
+      render(
+        "{% set total = scores.ann + scores.bob %}{{ total }}",
+        context.clone()
+      ),
+      "3"
+    );
+    assert_eq!(
+      render(
+        "{{ 'ann' in scores }} {{ missing is defined }} {{ 3 is odd }}",
+        context.clone()
+      ),
+      "true false true"
+    );
+    assert_eq!(
+      render("a {#- comment -#} b\n{%- if true %} c{% endif %}", context),
+      "ab c"
+    );
+  }
+
+  #[test]
+  fn test_include_and_extends() {
+    let templates = [
+      (
+        "base",
+        "<title>{% block title %}Site{% endblock %}</title>{% block body %}{% endblock %}",
+      ),
+      (
+        "page",
+        "{% extends 'base' %}{% block title %}Page - {{ name }}{% endblock %}{% block body %}{% include 'footer' %}{% endblock %}",
+      ),
+      ("footer", "<footer>{{ name }}</footer>"),
+      ("loop", "{% include 'loop' %}"),
+    ];
+    assert_eq!(
+      render_with(
+        "{% extends 'page' %}{% block title %}Child{% endblock %}",
+        json!({ "name": "x" }),
+        &templates
+      )
+      .unwrap(),
+      "<title>Child</title><footer>x</footer>"
+    );
+    assert_eq!(
+      render_with("{% include 'page' %}", json!({ "name": "x" }), &templates).unwrap(),
+      "<title>Page - x</title><footer>x</footer>"
+    );
+    assert!(render_with("{% include 'loop' %}", json!({}), &templates).is_err());
+    assert!(render_with("{% include 'missing' %}", json!({}), &templates).is_err());
+  }
+}
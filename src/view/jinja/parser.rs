@@ -0,0 +1,774 @@
+use serde_json::Value;
+
+use super::TemplateError;
+
+/// A node of a parsed template
+#[derive(Debug, Clone)]
+pub enum Node {
+  Text(String),
+  /// `{{ expr }}`
+  Output(Expr),
+  /// `{% if %}…{% elif %}…{% else %}…{% endif %}`
+  If {
+    branches: Vec<(Expr, Vec<Node>)>,
+    otherwise: Vec<Node>,
+  },
+  /// `{% for key, value in iterable %}…{% else %}…{% endfor %}`
+  For {
+    key: Option<String>,
+    value: String,
+    iterable: Expr,
+    body: Vec<Node>,
+    otherwise: Vec<Node>,
+  },
+  /// `{% set name = expr %}`
+  Set {
+    name: String,
+    value: Expr,
+  },
+  /// `{% include "name" %}`
+  Include(Expr),
+  /// `{% block name %}…{% endblock %}`
+  Block {
+    name: String,
+    body: Vec<Node>,
+  },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+  Not,
+  Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+  Or,
+  And,
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  In,
+  NotIn,
+  Concat,
+  Add,
+  Sub,
+  Mul,
+  Div,
+  Rem,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+  Literal(Value),
+  List(Vec<Expr>),
+  Variable(String),
+  Attribute(Box<Expr>, String),
+  Index(Box<Expr>, Box<Expr>),
+  Unary(UnaryOp, Box<Expr>),
+  Binary(BinaryOp, Box<Expr>, Box<Expr>),
+  /// `expr | name(args)`
+  Filter {
+    expr: Box<Expr>,
+    name: String,
+    args: Vec<Expr>,
+  },
+  /// `expr is [not] name`
+  Test {
+    expr: Box<Expr>,
+    name: String,
+    negated: bool,
+  },
+}
+
+/// A parsed template: its nodes, and the template it extends, if any
+#[derive(Debug, Clone)]
+pub struct Parsed {
+  pub parent: Option<Expr>,
+  pub nodes: Vec<Node>,
+}
+
+pub fn parse(source: &str) -> Result<Parsed, TemplateError> {
+  let segments = segment(source)?;
+  let mut parser = Parser {
+    segments,
+    position: 0,
+    parent: None,
+  };
+  let (nodes, _) = parser.parse_nodes(&[])?;
+  Ok(Parsed {
+    parent: parser.parent,
+    nodes,
+  })
+}
+
+/// A piece of template source: text, an expression or a statement
+#[derive(Debug)]
+enum Segment {
+  Text(String),
+  Expression { source: String, line: usize },
+  Statement { source: String, line: usize },
+}
+
+/// Split `source` into text, `{{ expressions }}` and `{% statements %}`,
+/// dropping `{# comments #}` and applying `-` whitespace control.
+fn segment(source: &str) -> Result<Vec<Segment>, TemplateError> {
+  let mut segments = Vec::new();
+  let mut rest = source;
+  let mut line = 1;
+  let mut trim_next = false;
+
+  loop {
+    let start = ["{{", "{%", "{#"]
+      .iter()
+      .filter_map(|delimiter| rest.find(delimiter))
+      .min();
+    let Some(start) = start else {
+      push_text(&mut segments, rest, trim_next, false);
+      return Ok(segments);
+    };
+
+    let opening = &rest[start..start + 2];
+    let closing = match opening {
+      "{{" => "}}",
+      "{%" => "%}",
+      _ => "#}",
+    };
+    let inner_start = start + 2;
+    let trim_before = rest[inner_start..].starts_with('-');
+    push_text(&mut segments, &rest[..start], trim_next, trim_before);
+    line += rest[..start].matches('\n').count();
+
+    let Some(length) = rest[inner_start..].find(closing) else {
+      return Err(TemplateError::new(line, format!("Unclosed \"{opening}\"")));
+    };
+    let mut inner = &rest[inner_start..inner_start + length];
+    if trim_before {
+      inner = &inner[1..];
+    }
+    trim_next = inner.ends_with('-');
+    if trim_next {
+      inner = &inner[..inner.len() - 1];
+    }
+
+    let source = inner.trim().to_owned();
+    match opening {
+      "{{" => segments.push(Segment::Expression { source, line }),
+      "{%" => segments.push(Segment::Statement { source, line }),
+      _ => {}
+    }
+    line += rest[inner_start..inner_start + length]
+      .matches('\n')
+      .count();
+    rest = &rest[inner_start + length + 2..];
+  }
+}
+
+fn push_text(segments: &mut Vec<Segment>, text: &str, trim_start: bool, trim_end: bool) {
+  let text = if trim_start { text.trim_start() } else { text };
+  let text = if trim_end { text.trim_end() } else { text };
+  if !text.is_empty() {
+    segments.push(Segment::Text(text.to_owned()));
+  }
+}
+
+/// A statement ending a list of nodes, e.g. `endif` or `else`
+struct End {
+  keyword: String,
+  rest: String,
+  line: usize,
+}
+
+struct Parser {
+  segments: Vec<Segment>,
+  position: usize,
+  parent: Option<Expr>,
+}
+
+impl Parser {
+  /// Parse nodes up to one of the `ends` statements, which is returned
+  fn parse_nodes(&mut self, ends: &[&str]) -> Result<(Vec<Node>, Option<End>), TemplateError> {
+    let mut nodes = Vec::new();
+    while self.position < self.segments.len() {
+      let segment = std::mem::replace(
+        &mut self.segments[self.position],
+        Segment::Text(String::new()),
+      );
+      self.position += 1;
+      match segment {
+        Segment::Text(text) => nodes.push(Node::Text(text)),
+        Segment::Expression { source, line } => {
+          nodes.push(Node::Output(parse_expression(&source, line)?))
+        }
+        Segment::Statement { source, line } => {
+          let (keyword, rest) = source
+            .split_once(char::is_whitespace)
+            .map(|(keyword, rest)| (keyword, rest.trim()))
+            .unwrap_or((source.as_str(), ""));
+          if ends.contains(&keyword) {
+            return Ok((
+              nodes,
+              Some(End {
+                keyword: keyword.to_owned(),
+                rest: rest.to_owned(),
+                line,
+              }),
+            ));
+          }
+          nodes.push(self.parse_statement(keyword, rest, line)?);
+        }
+      }
+    }
+    match ends.last() {
+      Some(end) => Err(TemplateError::new(
+        self.last_line(),
+        format!("Missing \"{end}\""),
+      )),
+      None => Ok((nodes, None)),
+    }
+  }
+
+  fn parse_statement(
+    &mut self,
+    keyword: &str,
+    rest: &str,
+    line: usize,
+  ) -> Result<Node, TemplateError> {
+    match keyword {
+      "if" => {
+        let mut branches = Vec::new();
+        let mut condition = parse_expression(rest, line)?;
+        loop {
+          let (body, end) = self.parse_nodes(&["elif", "else", "endif"])?;
+          branches.push((condition, body));
+          let end = end.expect("parse_nodes returns an end or an error");
+          match end.keyword.as_str() {
+            "elif" => condition = parse_expression(&end.rest, end.line)?,
+            "else" => {
+              let (otherwise, _) = self.parse_nodes(&["endif"])?;
+              return Ok(Node::If {
+                branches,
+                otherwise,
+              });
+            }
+            _ => {
+              return Ok(Node::If {
+                branches,
+                otherwise: Vec::new(),
+              });
+            }
+          }
+        }
+      }
+      "for" => {
+        let Some((targets, iterable)) = rest.split_once(" in ") else {
+          return Err(TemplateError::new(line, "Expected \"for … in …\""));
+        };
+        let mut targets = targets.split(',').map(str::trim);
+        let (key, value) = match (targets.next(), targets.next(), targets.next()) {
+          (Some(value), None, _) => (None, value),
+          (Some(key), Some(value), None) => (Some(key.to_owned()), value),
+          _ => {
+            return Err(TemplateError::new(
+              line,
+              "Expected one or two loop variables",
+            ));
+          }
+        };
+        for target in key.iter().map(String::as_str).chain([value]) {
+          if !is_identifier(target) {
+            return Err(TemplateError::new(
+              line,
+              format!("Invalid loop variable \"{target}\""),
+            ));
+          }
+        }
+        let iterable = parse_expression(iterable, line)?;
+        let (body, end) = self.parse_nodes(&["else", "endfor"])?;
+        let otherwise = match end.map(|end| end.keyword).as_deref() {
+          Some("else") => self.parse_nodes(&["endfor"])?.0,
+          _ => Vec::new(),
+        };
+        Ok(Node::For {
+          key,
+          value: value.to_owned(),
+          iterable,
+          body,
+          otherwise,
+        })
+      }
+      "set" => {
+        let Some((name, value)) = rest.split_once('=') else {
+          return Err(TemplateError::new(line, "Expected \"set name = value\""));
+        };
+        let name = name.trim();
+        if !is_identifier(name) {
+          return Err(TemplateError::new(
+            line,
+            format!("Invalid variable name \"{name}\""),
+          ));
+        }
+        Ok(Node::Set {
+          name: name.to_owned(),
+          value: parse_expression(value, line)?,
+        })
+      }
+      "include" => Ok(Node::Include(parse_expression(rest, line)?)),
+      "block" => {
+        if !is_identifier(rest) {
+          return Err(TemplateError::new(
+            line,
+            format!("Invalid block name \"{rest}\""),
+          ));
+        }
+        let (body, _) = self.parse_nodes(&["endblock"])?;
+        Ok(Node::Block {
+          name: rest.to_owned(),
+          body,
+        })
+      }
+      "elif" | "else" | "endif" | "endfor" | "endblock" => Err(TemplateError::new(
+        line,
+        format!("Unexpected \"{keyword}\""),
+      )),
+      "extends" => {
+        if self.parent.is_some() {
+          return Err(TemplateError::new(
+            line,
+            "A template extends only one template",
+          ));
+        }
+        self.parent = Some(parse_expression(rest, line)?);
+        Ok(Node::Text(String::new()))
+      }
+      _ => Err(TemplateError::new(
+        line,
+        format!("Unknown statement \"{keyword}\""),
+      )),
+    }
+  }
+
+  fn last_line(&self) -> usize {
+    self
+      .segments
+      .iter()
+      .rev()
+      .find_map(|segment| match segment {
+        Segment::Expression { line, .. } | Segment::Statement { line, .. } => Some(*line),
+        Segment::Text(_) => None,
+      })
+      .unwrap_or(1)
+  }
+}
+
+fn is_identifier(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+    && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Identifier(String),
+  Number(f64),
+  String(String),
+  Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 20] = [
+  "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "~", "(", ")", "[", "]", ".", ",",
+  "|", "=",
+];
+
+fn tokenize(source: &str, line: usize) -> Result<Vec<Token>, TemplateError> {
+  let mut tokens = Vec::new();
+  let mut chars = source.char_indices().peekable();
+  while let Some(&(start, c)) = chars.peek() {
+    if c.is_whitespace() {
+      chars.next();
+    } else if c.is_ascii_digit() {
+      let mut end = start;
+      while let Some(&(index, c)) = chars.peek() {
+        // `1.5` is a number, `items.0` an attribute
+        let is_fraction = c == '.'
+          && source[index + 1..].starts_with(|c: char| c.is_ascii_digit())
+          && !source[start..index].contains('.');
+        if !(c.is_ascii_digit() || is_fraction) {
+          break;
+        }
+        end = index + c.len_utf8();
+        chars.next();
+      }
+      let number = source[start..end].parse().map_err(|_| {
+        TemplateError::new(line, format!("Invalid number \"{}\"", &source[start..end]))
+      })?;
+      tokens.push(Token::Number(number));
+    } else if c.is_alphabetic() || c == '_' {
+      let mut end = start;
+      while let Some(&(index, c)) = chars.peek() {
+        if !(c.is_alphanumeric() || c == '_') {
+          break;
+        }
+        end = index + c.len_utf8();
+        chars.next();
+      }
+      tokens.push(Token::Identifier(source[start..end].to_owned()));
+    } else if c == '"' || c == '\'' {
+      chars.next();
+      let mut string = String::new();
+      loop {
+        match chars.next() {
+          Some((_, '\\')) => match chars.next() {
+            Some((_, 'n')) => string.push('\n'),
+            Some((_, 't')) => string.push('\t'),
+            Some((_, escaped)) => string.push(escaped),
+            None => break,
+          },
+          Some((_, quote)) if quote == c => {
+            tokens.push(Token::String(string));
+            break;
+          }
+          Some((_, c)) => string.push(c),
+          None => return Err(TemplateError::new(line, "Unterminated string")),
+        }
+      }
+    } else {
+      let symbol = SYMBOLS
+        .iter()
+        .find(|symbol| source[start..].starts_with(**symbol))
+        .ok_or_else(|| TemplateError::new(line, format!("Unexpected character \"{c}\"")))?;
+      for _ in 0..symbol.len() {
+        chars.next();
+      }
+      tokens.push(Token::Symbol(symbol));
+    }
+  }
+  Ok(tokens)
+}
+
+pub fn parse_expression(source: &str, line: usize) -> Result<Expr, TemplateError> {
+  let tokens = tokenize(source, line)?;
+  if tokens.is_empty() {
+    return Err(TemplateError::new(line, "Expected an expression"));
+  }
+  let mut parser = ExpressionParser {
+    tokens,
+    position: 0,
+    line,
+  };
+  let expr = parser.or()?;
+  match parser.peek() {
+    Some(token) => Err(parser.unexpected(&token.clone())),
+    None => Ok(expr),
+  }
+}
+
+/// A precedence climbing parser of Jinja expressions, from the loosest
+/// binding operator (`or`) to the tightest (filters, attributes)
+struct ExpressionParser {
+  tokens: Vec<Token>,
+  position: usize,
+  line: usize,
+}
+
+impl ExpressionParser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position)
+  }
+
+  fn next(&mut self) -> Option<Token> {
+    let token = self.tokens.get(self.position).cloned();
+    self.position += 1;
+    token
+  }
+
+  fn eat_symbol(&mut self, symbol: &str) -> bool {
+    if matches!(self.peek(), Some(Token::Symbol(candidate)) if *candidate == symbol) {
+      self.position += 1;
+      return true;
+    }
+    false
+  }
+
+  fn eat_keyword(&mut self, keyword: &str) -> bool {
+    if matches!(self.peek(), Some(Token::Identifier(name)) if name == keyword) {
+      self.position += 1;
+      return true;
+    }
+    false
+  }
+
+  fn expect_symbol(&mut self, symbol: &str) -> Result<(), TemplateError> {
+    if self.eat_symbol(symbol) {
+      return Ok(());
+    }
+    Err(TemplateError::new(
+      self.line,
+      format!("Expected \"{symbol}\""),
+    ))
+  }
+
+  fn identifier(&mut self) -> Result<String, TemplateError> {
+    match self.next() {
+      Some(Token::Identifier(name)) => Ok(name),
+      Some(token) => Err(self.unexpected(&token)),
+      None => Err(TemplateError::new(
+        self.line,
+        "Unexpected end of expression",
+      )),
+    }
+  }
+
+  fn unexpected(&self, token: &Token) -> TemplateError {
+    let token = match token {
+      Token::Identifier(name) => name.to_owned(),
+      Token::Number(number) => number.to_string(),
+      Token::String(string) => format!("\"{string}\""),
+      Token::Symbol(symbol) => symbol.to_string(),
+    };
+    TemplateError::new(self.line, format!("Unexpected \"{token}\""))
+  }
+
+  fn or(&mut self) -> Result<Expr, TemplateError> {
+    let mut left = self.and()?;
+    while self.eat_keyword("or") {
+      left = Expr::Binary(BinaryOp::Or, Box::new(left), Box::new(self.and()?));
+    }
+    Ok(left)
+  }
+
+  fn and(&mut self) -> Result<Expr, TemplateError> {
+    let mut left = self.not()?;
+    while self.eat_keyword("and") {
+      left = Expr::Binary(BinaryOp::And, Box::new(left), Box::new(self.not()?));
+    }
+    Ok(left)
+  }
+
+  fn not(&mut self) -> Result<Expr, TemplateError> {
+    if self.eat_keyword("not") {
+      return Ok(Expr::Unary(UnaryOp::Not, Box::new(self.not()?)));
+    }
+    self.comparison()
+  }
+
+  fn comparison(&mut self) -> Result<Expr, TemplateError> {
+    let mut left = self.concat()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol("==")) => BinaryOp::Eq,
+        Some(Token::Symbol("!=")) => BinaryOp::Ne,
+        Some(Token::Symbol("<")) => BinaryOp::Lt,
+        Some(Token::Symbol("<=")) => BinaryOp::Le,
+        Some(Token::Symbol(">")) => BinaryOp::Gt,
+        Some(Token::Symbol(">=")) => BinaryOp::Ge,
+        Some(Token::Identifier(name)) if name == "in" => BinaryOp::In,
+        Some(Token::Identifier(name)) if name == "not" => {
+          // `a not in b`
+          if !matches!(self.tokens.get(self.position + 1), Some(Token::Identifier(name)) if name == "in")
+          {
+            return Ok(left);
+          }
+          self.position += 1;
+          BinaryOp::NotIn
+        }
+        Some(Token::Identifier(name)) if name == "is" => {
+          self.position += 1;
+          let negated = self.eat_keyword("not");
+          let name = self.identifier()?;
+          left = Expr::Test {
+            expr: Box::new(left),
+            name,
+            negated,
+          };
+          continue;
+        }
+        _ => return Ok(left),
+      };
+      self.position += 1;
+      left = Expr::Binary(op, Box::new(left), Box::new(self.concat()?));
+    }
+  }
+
+  fn concat(&mut self) -> Result<Expr, TemplateError> {
+    let mut left = self.sum()?;
+    while self.eat_symbol("~") {
+      left = Expr::Binary(BinaryOp::Concat, Box::new(left), Box::new(self.sum()?));
+    }
+    Ok(left)
+  }
+
+  fn sum(&mut self) -> Result<Expr, TemplateError> {
+    let mut left = self.product()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol("+")) => BinaryOp::Add,
+        Some(Token::Symbol("-")) => BinaryOp::Sub,
+        _ => return Ok(left),
+      };
+      self.position += 1;
+      left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+    }
+  }
+
+  fn product(&mut self) -> Result<Expr, TemplateError> {
+    let mut left = self.unary()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol("*")) => BinaryOp::Mul,
+        Some(Token::Symbol("/")) => BinaryOp::Div,
+        Some(Token::Symbol("%")) => BinaryOp::Rem,
+        _ => return Ok(left),
+      };
+      self.position += 1;
+      left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+    }
+  }
+
+  fn unary(&mut self) -> Result<Expr, TemplateError> {
+    if self.eat_symbol("-") {
+      return Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?)));
+    }
+    self.filtered()
+  }
+
+  fn filtered(&mut self) -> Result<Expr, TemplateError> {
+    let mut expr = self.postfix()?;
+    while self.eat_symbol("|") {
+      let name = self.identifier()?;
+      let args = match self.eat_symbol("(") {
+        true => self.list(")")?,
+        false => Vec::new(),
+      };
+      expr = Expr::Filter {
+        expr: Box::new(expr),
+        name,
+        args,
+      };
+    }
+    Ok(expr)
+  }
+
+  fn postfix(&mut self) -> Result<Expr, TemplateError> {
+    let mut expr = self.primary()?;
+    loop {
+      if self.eat_symbol(".") {
+        let attribute = match self.next() {
+          Some(Token::Identifier(name)) => name,
+          Some(Token::Number(index)) if index.fract() == 0.0 => index.to_string(),
+          Some(token) => return Err(self.unexpected(&token)),
+          None => {
+            return Err(TemplateError::new(
+              self.line,
+              "Unexpected end of expression",
+            ));
+          }
+        };
+        expr = Expr::Attribute(Box::new(expr), attribute);
+      } else if self.eat_symbol("[") {
+        let index = self.or()?;
+        self.expect_symbol("]")?;
+        expr = Expr::Index(Box::new(expr), Box::new(index));
+      } else {
+        return Ok(expr);
+      }
+    }
+  }
+
+  fn primary(&mut self) -> Result<Expr, TemplateError> {
+    match self.next() {
+      Some(Token::Number(number)) => Ok(Expr::Literal(number_value(number))),
+      Some(Token::String(string)) => Ok(Expr::Literal(Value::String(string))),
+      Some(Token::Identifier(name)) => Ok(match name.as_str() {
+        "true" | "True" => Expr::Literal(Value::Bool(true)),
+        "false" | "False" => Expr::Literal(Value::Bool(false)),
+        "none" | "None" | "null" => Expr::Literal(Value::Null),
+        _ => Expr::Variable(name),
+      }),
+      Some(Token::Symbol("(")) => {
+        let expr = self.or()?;
+        self.expect_symbol(")")?;
+        Ok(expr)
+      }
+      Some(Token::Symbol("[")) => Ok(Expr::List(self.list("]")?)),
+      Some(token) => Err(self.unexpected(&token)),
+      None => Err(TemplateError::new(
+        self.line,
+        "Unexpected end of expression",
+      )),
+    }
+  }
+
+  /// Comma separated expressions, up to `close`
+  fn list(&mut self, close: &str) -> Result<Vec<Expr>, TemplateError> {
+    let mut items = Vec::new();
+    while !self.eat_symbol(close) {
+      items.push(self.or()?);
+      if !self.eat_symbol(",") {
+        self.expect_symbol(close)?;
+        break;
+      }
+    }
+    Ok(items)
+  }
+}
+
+/// Integers are kept as integers, so that they render without a fraction
+pub fn number_value(number: f64) -> Value {
+  if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+    Value::from(number as i64)
+  } else {
+    serde_json::Number::from_f64(number).map_or(Value::Null, Value::Number)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_segment() {
+    let segments = segment("a {{ b }}\n  {%- if c -%}  d{# e #}\n{% endif %}").unwrap();
+    let debug = format!("{segments:?}");
+    assert_eq!(
+      debug,
+      "[Text(\"a \"), Expression { source: \"b\", line: 1 }, \
+       Statement { source: \"if c\", line: 2 }, Text(\"d\"), Text(\"\\n\"), \
+       Statement { source: \"endif\", line: 3 }]"
+    );
+    assert!(segment("{{ a").is_err());
+  }
+
+  #[test]
+  fn test_parse_expression() {
+    let expr = parse_expression("a.b[0] + 1 | abs", 1).unwrap();
+    assert_eq!(
+      format!("{expr:?}"),
+      "Binary(Add, Index(Attribute(Variable(\"a\"), \"b\"), Literal(Number(0))), \
+       Filter { expr: Literal(Number(1)), name: \"abs\", args: [] })"
+    );
+    let expr = parse_expression("x is not defined or y not in [1, 2]", 1).unwrap();
+    assert!(matches!(expr, Expr::Binary(BinaryOp::Or, _, _)));
+    assert!(parse_expression("a +", 1).is_err());
+    assert!(parse_expression("a b", 1).is_err());
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert_eq!(
+      parse("{% if a %}").unwrap_err().to_string(),
+      "line 1: Missing \"endif\""
+    );
+    assert_eq!(
+      parse("\n{% endfor %}").unwrap_err().to_string(),
+      "line 2: Unexpected \"endfor\""
+    );
+    assert_eq!(
+      parse("{% frobnicate %}").unwrap_err().to_string(),
+      "line 1: Unknown statement \"frobnicate\""
+    );
+  }
+}
@@ -0,0 +1,542 @@
+use std::{collections::HashMap, fmt::Write, sync::Arc};
+
+use askama_escape::escape_html;
+use serde_json::{Map, Value};
+
+use super::{
+  Template, TemplateError,
+  parser::{BinaryOp, Expr, Node, UnaryOp, number_value},
+};
+
+/// Loads the templates named by `include` and `extends`
+pub type Loader<'a> = dyn Fn(&str) -> Result<Arc<Template>, TemplateError> + 'a;
+
+/// How deep `include` and `extends` may nest, to stop recursive templates
+const MAX_DEPTH: usize = 64;
+
+/// Filters whose output is not escaped again
+const SAFE_FILTERS: [&str; 4] = ["safe", "escape", "e", "tojson"];
+
+pub struct Renderer<'a> {
+  scopes: Vec<Map<String, Value>>,
+  loader: &'a Loader<'a>,
+  depth: usize,
+  output: String,
+}
+
+impl<'a> Renderer<'a> {
+  pub fn new(context: Map<String, Value>, loader: &'a Loader<'a>) -> Self {
+    Self {
+      scopes: vec![context],
+      loader,
+      depth: 0,
+      output: String::new(),
+    }
+  }
+
+  pub fn finish(self) -> String {
+    self.output
+  }
+
+  /// Render `template`, whose blocks are replaced by those of `overrides`
+  pub fn render_template(
+    &mut self,
+    template: &Template,
+    mut overrides: HashMap<String, Vec<Node>>,
+  ) -> Result<(), TemplateError> {
+    self.depth += 1;
+    if self.depth > MAX_DEPTH {
+      return Err(TemplateError::new(
+        0,
+        "Templates are included or extended too deeply",
+      ));
+    }
+
+    match &template.parsed.parent {
+      Some(parent) => {
+        // the blocks of the most derived template win
+        collect_blocks(&template.parsed.nodes, &mut overrides);
+        let parent = self.load(parent)?;
+        self.render_template(&parent, overrides)?;
+      }
+      None => self.render_nodes(&template.parsed.nodes, &overrides)?,
+    }
+
+    self.depth -= 1;
+    Ok(())
+  }
+
+  fn load(&self, name: &Expr) -> Result<Arc<Template>, TemplateError> {
+    match self.eval(name)? {
+      Value::String(name) => (self.loader)(&name),
+      name => Err(TemplateError::new(
+        0,
+        format!("Expected a template name, got {name}"),
+      )),
+    }
+  }
+
+  fn render_nodes(
+    &mut self,
+    nodes: &[Node],
+    overrides: &HashMap<String, Vec<Node>>,
+  ) -> Result<(), TemplateError> {
+    for node in nodes {
+      match node {
+        Node::Text(text) => self.output.push_str(text),
+        Node::Output(expr) => self.output_expr(expr)?,
+        Node::If {
+          branches,
+          otherwise,
+        } => {
+          let mut body = otherwise;
+          for (condition, branch) in branches {
+            if is_truthy(&self.eval(condition)?) {
+              body = branch;
+              break;
+            }
+          }
+          self.render_nodes(body, overrides)?;
+        }
+        Node::For {
+          key,
+          value,
+          iterable,
+          body,
+          otherwise,
+        } => {
+          let items = iterate(self.eval(iterable)?, key.is_some());
+          if items.is_empty() {
+            self.render_nodes(otherwise, overrides)?;
+            continue;
+          }
+          let length = items.len();
+          for (index, item) in items.into_iter().enumerate() {
+            let mut scope = Map::new();
+            match key {
+              Some(key) => {
+                let (item_key, item_value) = unpack(item);
+                scope.insert(key.to_owned(), item_key);
+                scope.insert(value.to_owned(), item_value);
+              }
+              None => {
+                scope.insert(value.to_owned(), item);
+              }
+            }
+            scope.insert("loop".to_owned(), loop_value(index, length));
+            self.scopes.push(scope);
+            let result = self.render_nodes(body, overrides);
+            self.scopes.pop();
+            result?;
+          }
+        }
+        Node::Set { name, value } => {
+          let value = self.eval(value)?;
+          if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_owned(), value);
+          }
+        }
+        Node::Include(name) => {
+          let template = self.load(name)?;
+          self.render_template(&template, HashMap::new())?;
+        }
+        Node::Block { name, body } => {
+          let body = overrides.get(name).unwrap_or(body);
+          self.render_nodes(body, overrides)?;
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Write the value of `expr`, escaped unless it went through `safe`,
+  /// `escape` or `tojson` last
+  fn output_expr(&mut self, expr: &Expr) -> Result<(), TemplateError> {
+    let value = self.eval(expr)?;
+    let text = to_text(&value);
+    match expr {
+      Expr::Filter { name, .. } if SAFE_FILTERS.contains(&name.as_str()) => {
+        self.output.push_str(&text)
+      }
+      _ => {
+        let _ = escape_html(&mut self.output, &text);
+      }
+    }
+    Ok(())
+  }
+
+  fn lookup(&self, name: &str) -> Option<&Value> {
+    self.scopes.iter().rev().find_map(|scope| scope.get(name))
+  }
+
+  /// The value of `expr`, `None` when it is undefined
+  fn resolve(&self, expr: &Expr) -> Result<Option<Value>, TemplateError> {
+    Ok(match expr {
+      Expr::Variable(name) => self.lookup(name).cloned(),
+      Expr::Attribute(object, attribute) => self
+        .resolve(object)?
+        .and_then(|object| get_item(&object, &Value::String(attribute.to_owned()))),
+      Expr::Index(object, index) => {
+        let index = self.eval(index)?;
+        self
+          .resolve(object)?
+          .and_then(|object| get_item(&object, &index))
+      }
+      expr => Some(self.eval(expr)?),
+    })
+  }
+
+  fn eval(&self, expr: &Expr) -> Result<Value, TemplateError> {
+    Ok(match expr {
+      Expr::Literal(value) => value.clone(),
+      Expr::List(items) => Value::Array(
+        items
+          .iter()
+          .map(|item| self.eval(item))
+          .collect::<Result<_, _>>()?,
+      ),
+      Expr::Variable(_) | Expr::Attribute(..) | Expr::Index(..) => {
+        self.resolve(expr)?.unwrap_or(Value::Null)
+      }
+      Expr::Unary(UnaryOp::Not, expr) => Value::Bool(!is_truthy(&self.eval(expr)?)),
+      Expr::Unary(UnaryOp::Neg, expr) => number_value(-to_number(&self.eval(expr)?)),
+      Expr::Binary(BinaryOp::And, left, right) => {
+        let left = self.eval(left)?;
+        match is_truthy(&left) {
+          true => self.eval(right)?,
+          false => left,
+        }
+      }
+      Expr::Binary(BinaryOp::Or, left, right) => {
+        let left = self.eval(left)?;
+        match is_truthy(&left) {
+          true => left,
+          false => self.eval(right)?,
+        }
+      }
+      Expr::Binary(op, left, right) => binary(*op, self.eval(left)?, self.eval(right)?)?,
+      Expr::Filter { expr, name, args } => {
+        let value = match name.as_str() {
+          // `default` replaces undefined values, and falsy ones when its
+          // second argument is true
+          "default" | "d" => {
+            let replace_falsy = match args.get(1) {
+              Some(boolean) => is_truthy(&self.eval(boolean)?),
+              None => false,
+            };
+            match self.resolve(expr)? {
+              Some(value) if !replace_falsy || is_truthy(&value) => return Ok(value),
+              _ => return self.eval_default(args),
+            }
+          }
+          _ => self.eval(expr)?,
+        };
+        let args = args
+          .iter()
+          .map(|arg| self.eval(arg))
+          .collect::<Result<Vec<_>, _>>()?;
+        filter(name, value, &args)?
+      }
+      Expr::Test {
+        expr,
+        name,
+        negated,
+      } => {
+        let value = self.resolve(expr)?;
+        let result = match name.as_str() {
+          "defined" => value.is_some(),
+          "undefined" => value.is_none(),
+          "none" => value.is_none_or(|value| value.is_null()),
+          "string" => value.is_some_and(|value| value.is_string()),
+          "number" => value.is_some_and(|value| value.is_number()),
+          "even" => value.is_some_and(|value| to_number(&value) % 2.0 == 0.0),
+          "odd" => value.is_some_and(|value| to_number(&value).abs() % 2.0 == 1.0),
+          _ => {
+            return Err(TemplateError::new(0, format!("Unknown test \"{name}\"")));
+          }
+        };
+        Value::Bool(result != *negated)
+      }
+    })
+  }
+
+  fn eval_default(&self, args: &[Expr]) -> Result<Value, TemplateError> {
+    match args.first() {
+      Some(default) => self.eval(default),
+      None => Ok(Value::String(String::new())),
+    }
+  }
+}
+
+/// Add the blocks of `nodes`, including nested ones, not yet in `blocks`
+fn collect_blocks(nodes: &[Node], blocks: &mut HashMap<String, Vec<Node>>) {
+  for node in nodes {
+    if let Node::Block { name, body } = node {
+      blocks
+        .entry(name.to_owned())
+        .or_insert_with(|| body.clone());
+      collect_blocks(body, blocks);
+    }
+  }
+}
+
+fn loop_value(index: usize, length: usize) -> Value {
+  serde_json::json!({
+    "index": index + 1,
+    "index0": index,
+    "revindex": length - index,
+    "revindex0": length - index - 1,
+    "first": index == 0,
+    "last": index + 1 == length,
+    "length": length,
+  })
+}
+
+/// The items of a loop: the elements of arrays, and the keys of objects, or
+/// their `[key, value]` pairs when `pairs` is set
+fn iterate(value: Value, pairs: bool) -> Vec<Value> {
+  match value {
+    Value::Array(items) => items,
+    Value::Object(object) => object
+      .into_iter()
+      .map(|(key, value)| match pairs {
+        true => Value::Array(vec![Value::String(key), value]),
+        false => Value::String(key),
+      })
+      .collect(),
+    Value::String(string) => string
+      .chars()
+      .map(|c| Value::String(c.to_string()))
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn unpack(item: Value) -> (Value, Value) {
+  match item {
+    Value::Array(mut pair) if pair.len() == 2 => {
+      let value = pair.pop().unwrap_or_default();
+      let key = pair.pop().unwrap_or_default();
+      (key, value)
+    }
+    item => (Value::Null, item),
+  }
+}
+
+fn get_item(object: &Value, key: &Value) -> Option<Value> {
+  match (object, key) {
+    (Value::String(string), Value::String(key)) if key == "length" => {
+      Some(Value::from(string.chars().count()))
+    }
+    (Value::Array(items), Value::String(key)) if key == "length" => Some(Value::from(items.len())),
+    (Value::Object(object), Value::String(key)) => object.get(key).cloned(),
+    (Value::Array(items), key) => {
+      let index = match key {
+        Value::String(key) => key.parse::<i64>().ok()?,
+        key => key.as_i64()?,
+      };
+      let index = match index < 0 {
+        true => items.len().checked_sub(index.unsigned_abs() as usize)?,
+        false => index as usize,
+      };
+      items.get(index).cloned()
+    }
+    _ => None,
+  }
+}
+
+pub fn is_truthy(value: &Value) -> bool {
+  match value {
+    Value::Null => false,
+    Value::Bool(value) => *value,
+    Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+    Value::String(string) => !string.is_empty(),
+    Value::Array(items) => !items.is_empty(),
+    Value::Object(object) => !object.is_empty(),
+  }
+}
+
+/// The text of a value: nothing for `null`, JSON for arrays and objects
+fn to_text(value: &Value) -> String {
+  match value {
+    Value::Null => String::new(),
+    Value::String(string) => string.to_owned(),
+    value => value.to_string(),
+  }
+}
+
+fn to_number(value: &Value) -> f64 {
+  match value {
+    Value::Number(number) => number.as_f64().unwrap_or_default(),
+    Value::Bool(value) => *value as u8 as f64,
+    Value::String(string) => string.trim().parse().unwrap_or_default(),
+    _ => 0.0,
+  }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<std::cmp::Ordering> {
+  match (left, right) {
+    (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+    (left, right) => to_number(left).partial_cmp(&to_number(right)),
+  }
+}
+
+fn equals(left: &Value, right: &Value) -> bool {
+  match (left, right) {
+    (Value::Number(left), Value::Number(right)) => left.as_f64() == right.as_f64(),
+    (left, right) => left == right,
+  }
+}
+
+fn contains(container: &Value, item: &Value) -> Result<bool, TemplateError> {
+  Ok(match (container, item) {
+    (Value::String(container), item) => container.contains(&to_text(item)),
+    (Value::Array(items), item) => items.iter().any(|candidate| equals(candidate, item)),
+    (Value::Object(object), Value::String(key)) => object.contains_key(key),
+    (Value::Object(_), _) => false,
+    (container, _) => {
+      return Err(TemplateError::new(
+        0,
+        format!("Cannot test membership in {container}"),
+      ));
+    }
+  })
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, TemplateError> {
+  use std::cmp::Ordering::*;
+
+  Ok(match op {
+    BinaryOp::Eq => Value::Bool(equals(&left, &right)),
+    BinaryOp::Ne => Value::Bool(!equals(&left, &right)),
+    BinaryOp::Lt => Value::Bool(compare(&left, &right) == Some(Less)),
+    BinaryOp::Le => Value::Bool(matches!(compare(&left, &right), Some(Less | Equal))),
+    BinaryOp::Gt => Value::Bool(compare(&left, &right) == Some(Greater)),
+    BinaryOp::Ge => Value::Bool(matches!(compare(&left, &right), Some(Greater | Equal))),
+    BinaryOp::In => Value::Bool(contains(&right, &left)?),
+    BinaryOp::NotIn => Value::Bool(!contains(&right, &left)?),
+    BinaryOp::Concat => Value::String(to_text(&left) + &to_text(&right)),
+    BinaryOp::Add => match (left, right) {
+      (Value::String(left), right) => Value::String(left + &to_text(&right)),
+      (Value::Array(mut left), Value::Array(right)) => {
+        left.extend(right);
+        Value::Array(left)
+      }
+      (left, right) => number_value(to_number(&left) + to_number(&right)),
+    },
+    BinaryOp::Sub => number_value(to_number(&left) - to_number(&right)),
+    BinaryOp::Mul => number_value(to_number(&left) * to_number(&right)),
+    BinaryOp::Div | BinaryOp::Rem => {
+      let right = to_number(&right);
+      if right == 0.0 {
+        return Err(TemplateError::new(0, "Division by zero"));
+      }
+      match op {
+        BinaryOp::Div => number_value(to_number(&left) / right),
+        _ => number_value(to_number(&left) % right),
+      }
+    }
+    BinaryOp::And | BinaryOp::Or => unreachable!("evaluated lazily"),
+  })
+}
+
+fn filter(name: &str, value: Value, args: &[Value]) -> Result<Value, TemplateError> {
+  let string_arg = |index: usize, default: &str| {
+    args
+      .get(index)
+      .map(to_text)
+      .unwrap_or_else(|| default.to_owned())
+  };
+
+  Ok(match name {
+    "safe" => value,
+    "escape" | "e" => {
+      let mut escaped = String::new();
+      let _ = escape_html(&mut escaped, &to_text(&value));
+      Value::String(escaped)
+    }
+    "upper" => Value::String(to_text(&value).to_uppercase()),
+    "lower" => Value::String(to_text(&value).to_lowercase()),
+    "capitalize" => {
+      let text = to_text(&value).to_lowercase();
+      let mut chars = text.chars();
+      Value::String(
+        chars
+          .next()
+          .map(|first| first.to_uppercase().chain(chars).collect())
+          .unwrap_or_default(),
+      )
+    }
+    "title" => {
+      let mut title = String::new();
+      let mut word_start = true;
+      for c in to_text(&value).chars() {
+        match word_start {
+          true => title.extend(c.to_uppercase()),
+          false => title.extend(c.to_lowercase()),
+        }
+        word_start = !c.is_alphanumeric();
+      }
+      Value::String(title)
+    }
+    "trim" => Value::String(to_text(&value).trim().to_owned()),
+    "replace" => Value::String(to_text(&value).replace(&string_arg(0, ""), &string_arg(1, ""))),
+    "length" | "count" => Value::from(match &value {
+      Value::String(string) => string.chars().count(),
+      Value::Array(items) => items.len(),
+      Value::Object(object) => object.len(),
+      _ => 0,
+    }),
+    "first" => iterate(value, false).into_iter().next().unwrap_or_default(),
+    "last" => iterate(value, false).pop().unwrap_or_default(),
+    "reverse" => match value {
+      Value::String(string) => Value::String(string.chars().rev().collect()),
+      value => Value::Array(iterate(value, false).into_iter().rev().collect()),
+    },
+    "sort" => {
+      let mut items = iterate(value, false);
+      items.sort_by(|left, right| compare(left, right).unwrap_or(std::cmp::Ordering::Equal));
+      Value::Array(items)
+    }
+    "join" => {
+      let separator = string_arg(0, "");
+      let mut joined = String::new();
+      for (index, item) in iterate(value, false).iter().enumerate() {
+        if index > 0 {
+          joined.push_str(&separator);
+        }
+        let _ = write!(joined, "{}", to_text(item));
+      }
+      Value::String(joined)
+    }
+    "items" => Value::Array(iterate(value, true)),
+    "keys" => match value {
+      Value::Object(object) => Value::Array(object.keys().cloned().map(Value::String).collect()),
+      _ => Value::Array(Vec::new()),
+    },
+    "values" => match value {
+      Value::Object(object) => Value::Array(object.into_iter().map(|(_, value)| value).collect()),
+      _ => Value::Array(Vec::new()),
+    },
+    "abs" => number_value(to_number(&value).abs()),
+    "int" => number_value(to_number(&value).trunc()),
+    "float" => serde_json::Number::from_f64(to_number(&value)).map_or(Value::Null, Value::Number),
+    "round" => {
+      let precision = args.first().map(to_number).unwrap_or_default() as i32;
+      let factor = 10f64.powi(precision);
+      number_value((to_number(&value) * factor).round() / factor)
+    }
+    "string" => Value::String(to_text(&value)),
+    "tojson" => {
+      // safe to embed in HTML, e.g. in a `<script>` element
+      let json = value
+        .to_string()
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+        .replace('\'', "\\u0027");
+      Value::String(json)
+    }
+    _ => return Err(TemplateError::new(0, format!("Unknown filter \"{name}\""))),
+  })
+}
@@ -0,0 +1,313 @@
+pub mod jinja;
+
+use std::{
+  collections::HashMap,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex},
+};
+
+use napi::bindgen_prelude::*;
+use serde_json::Value;
+
+use jinja::{Template, TemplateError};
+
+pub type JsEngineFn<'a> =
+  Function<'a, FnArgs<(String, Object<'static>, Unknown<'static>)>, Unknown<'static>>;
+
+pub type EngineFnRef =
+  FunctionRef<FnArgs<(String, Object<'static>, Unknown<'static>)>, Unknown<'static>>;
+
+/// A template engine, registered with `server.engine(ext, engine)`
+#[derive(Clone)]
+pub enum ViewEngine {
+  /// The built-in Jinja-style engine, rendering without calling into JS
+  Jinja,
+  /// Called as `engine(path, locals, callback)`, renders the file at `path`
+  /// and calls `callback(err, html)` or returns the HTML or a promise of it
+  Function(Arc<EngineFnRef>),
+}
+
+/// The views found by name, and the parsed templates of the `Jinja` engine
+#[derive(Default)]
+pub struct ViewCache {
+  paths: Mutex<HashMap<String, PathBuf>>,
+  templates: Mutex<HashMap<PathBuf, Arc<Template>>>,
+}
+
+/// A view found by `ViewSettings::lookup`
+pub struct View {
+  pub path: PathBuf,
+  pub engine: ViewEngine,
+}
+
+/// The `views`, `view engine` and `view cache` settings, and the engines
+/// registered with `server.engine()`
+#[derive(Clone)]
+pub struct ViewSettings {
+  /// `views`: the directories searched for views, in order
+  pub dirs: Vec<PathBuf>,
+  /// `view engine`: the extension of view names without one
+  pub default_engine: Option<String>,
+  /// `view cache`: cache the views found and the templates parsed
+  pub cache: bool,
+  /// The engines by extension, without the leading dot
+  pub engines: HashMap<String, ViewEngine>,
+  cached: Arc<ViewCache>,
+}
+
+impl Default for ViewSettings {
+  fn default() -> Self {
+    Self {
+      dirs: vec![PathBuf::from("views")],
+      default_engine: None,
+      cache: std::env::var("NODE_ENV").is_ok_and(|env| env == "production"),
+      engines: HashMap::from([("jinja".to_owned(), ViewEngine::Jinja)]),
+      cached: Arc::default(),
+    }
+  }
+}
+
+impl ViewSettings {
+  /// Forget the cached views, after the settings they were found with changed
+  pub fn clear_cache(&mut self) {
+    self.cached = Arc::default();
+  }
+
+  /// Find view `name` in the view directories, adding the extension of the
+  /// `view engine` when `name` has none
+  pub fn lookup(&self, name: &str) -> Result<View> {
+    let ext = match Path::new(name).extension() {
+      Some(ext) => ext.to_string_lossy().into_owned(),
+      None => match &self.default_engine {
+        Some(ext) => ext.to_owned(),
+        None => {
+          return Err(Error::new(
+            Status::InvalidArg,
+            "No default engine was specified and no extension was provided.",
+          ));
+        }
+      },
+    };
+
+    let Some(engine) = self.engines.get(&ext).cloned() else {
+      return Err(Error::new(
+        Status::InvalidArg,
+        format!("No engine is registered for the \".{ext}\" extension"),
+      ));
+    };
+
+    let Some(path) = self.find(name, &ext) else {
+      let dirs = self
+        .dirs
+        .iter()
+        .map(|dir| format!("\"{}\"", dir.display()))
+        .collect::<Vec<_>>();
+      let dirs = match dirs.split_last() {
+        Some((last, [])) => format!("directory {last}"),
+        Some((last, others)) => format!("directories {} or {last}", others.join(", ")),
+        None => "directories".to_owned(),
+      };
+      return Err(Error::new(
+        Status::GenericFailure,
+        format!("Failed to lookup view \"{name}\" in views {dirs}"),
+      ));
+    };
+
+    Ok(View { path, engine })
+  }
+
+  /// The path of view `name`, `root/name.ext` or `root/name/index.ext` in the
+  /// first view directory holding one
+  fn find(&self, name: &str, ext: &str) -> Option<PathBuf> {
+    let key = format!("{name}\0{ext}");
+    if self.cache
+      && let Some(path) = self.cached.paths.lock().ok()?.get(&key)
+    {
+      return Some(path.to_owned());
+    }
+
+    let file_name = match Path::new(name).extension() {
+      Some(_) => PathBuf::from(name),
+      None => PathBuf::from(format!("{name}.{ext}")),
+    };
+    let path = self.dirs.iter().find_map(|dir| {
+      let path = dir.join(&file_name);
+      if path.is_file() {
+        return Some(path);
+      }
+      let path = dir
+        .join(file_name.with_extension(""))
+        .join(format!("index.{ext}"));
+      path.is_file().then_some(path)
+    })?;
+
+    if self.cache
+      && let Ok(mut paths) = self.cached.paths.lock()
+    {
+      paths.insert(key, path.clone());
+    }
+    Some(path)
+  }
+
+  fn load_template(&self, path: &Path) -> std::result::Result<Arc<Template>, TemplateError> {
+    if self.cache
+      && let Some(template) = self
+        .cached
+        .templates
+        .lock()
+        .ok()
+        .and_then(|templates| templates.get(path).cloned())
+    {
+      return Ok(template);
+    }
+
+    let source = std::fs::read_to_string(path)
+      .map_err(|e| TemplateError::new(0, format!("Error reading {}: {e}", path.display())))?;
+    let template = Arc::new(Template::parse(&source)?);
+
+    if self.cache
+      && let Ok(mut templates) = self.cached.templates.lock()
+    {
+      templates.insert(path.to_owned(), template.clone());
+    }
+    Ok(template)
+  }
+
+  /// Render the template at `path` with the `Jinja` engine. The templates
+  /// it includes or extends are looked up like views, with the extension of
+  /// `path` by default.
+  pub fn render_jinja(&self, path: &Path, context: Value) -> Result<String> {
+    let ext = path
+      .extension()
+      .map(|ext| ext.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    let loader = |name: &str| {
+      let path = self
+        .find(name, &ext)
+        .ok_or_else(|| TemplateError::new(0, format!("Template \"{name}\" not found")))?;
+      self
+        .load_template(&path)
+        .map_err(|e| TemplateError::new(0, format!("{}: {e}", path.display())))
+    };
+
+    self
+      .load_template(path)
+      .and_then(|template| template.render(context, &loader))
+      .map_err(|e| {
+        Error::new(
+          Status::GenericFailure,
+          format!("Error rendering {}: {e}", path.display()),
+        )
+      })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use serde_json::json;
+
+  use super::*;
+
+  fn views() -> (tempfile::TempDir, ViewSettings) {
+    let dir = tempfile::tempdir().unwrap();
+    let pages = dir.path().join("pages");
+    let partials = dir.path().join("partials");
+    fs::create_dir_all(pages.join("users")).unwrap();
+    fs::create_dir_all(&partials).unwrap();
+    fs::write(
+      partials.join("layout.jinja"),
+      "<h1>{% block title %}{% endblock %}</h1>{% include 'footer' %}",
+    )
+    .unwrap();
+    fs::write(partials.join("footer.jinja"), "<footer>{{ site }}</footer>").unwrap();
+    fs::write(
+      pages.join("home.jinja"),
+      "{% extends 'layout' %}{% block title %}Hello {{ name }}{% endblock %}",
+    )
+    .unwrap();
+    fs::write(pages.join("users/index.jinja"), "users").unwrap();
+    fs::write(pages.join("broken.jinja"), "{% if %}").unwrap();
+
+    let settings = ViewSettings {
+      dirs: vec![pages, partials],
+      default_engine: Some("jinja".to_owned()),
+      ..Default::default()
+    };
+    (dir, settings)
+  }
+
+  #[test]
+  fn test_lookup() {
+    let (dir, mut settings) = views();
+    let pages = dir.path().join("pages");
+
+    assert_eq!(
+      settings.lookup("home").unwrap().path,
+      pages.join("home.jinja")
+    );
+    assert_eq!(
+      settings.lookup("home.jinja").unwrap().path,
+      pages.join("home.jinja")
+    );
+    assert_eq!(
+      settings.lookup("users").unwrap().path,
+      pages.join("users/index.jinja")
+    );
+    assert_eq!(
+      settings.lookup("footer").unwrap().path,
+      dir.path().join("partials/footer.jinja")
+    );
+
+    let error = settings.lookup("missing").err().unwrap();
+    assert!(
+      error
+        .reason
+        .starts_with("Failed to lookup view \"missing\" in views directories")
+    );
+    assert_eq!(
+      settings.lookup("home.pug").err().unwrap().reason,
+      "No engine is registered for the \".pug\" extension"
+    );
+
+    settings.default_engine = None;
+    assert_eq!(
+      settings.lookup("home").err().unwrap().reason,
+      "No default engine was specified and no extension was provided."
+    );
+  }
+
+  #[test]
+  fn test_render_jinja() {
+    let (dir, mut settings) = views();
+    let home = dir.path().join("pages/home.jinja");
+    let context = json!({ "name": "<Ann>", "site": "example" });
+
+    assert_eq!(
+      settings.render_jinja(&home, context.clone()).unwrap(),
+      "<h1>Hello &#60;Ann&#62;</h1><footer>example</footer>"
+    );
+
+    let broken = dir.path().join("pages/broken.jinja");
+    assert!(
+      settings
+        .render_jinja(&broken, json!({}))
+        .err()
+        .unwrap()
+        .reason
+        .contains("broken.jinja: line 1:")
+    );
+
+    // cached templates are rendered after their file changed
+    settings.cache = true;
+    settings.render_jinja(&home, context.clone()).unwrap();
+    fs::write(&home, "changed").unwrap();
+    assert_eq!(
+      settings.render_jinja(&home, context.clone()).unwrap(),
+      "<h1>Hello &#60;Ann&#62;</h1><footer>example</footer>"
+    );
+    settings.clear_cache();
+    assert_eq!(settings.render_jinja(&home, context).unwrap(), "changed");
+  }
+}
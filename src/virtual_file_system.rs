@@ -0,0 +1,81 @@
+use std::{collections::HashMap, path::Path, time::SystemTime};
+
+use bytes::Bytes;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::utilities::StaticFs;
+
+/// Files served by `StaticMiddleware` and `res.sendFile` in place of a root
+/// directory, e.g. the assets of an application bundled into its binary.
+/// `ETag`, `Last-Modified`, ranges, precompressed `.br` and `.gz` variants and
+/// dotfiles are handled as for files on disk.
+///
+/// ```javascript
+/// const assets = VirtualFileSystem.overlay([
+///   'public',
+///   VirtualFileSystem.fromArchive('ui.tar'),
+/// ])
+///
+/// app.use(new StaticMiddleware(assets))
+/// ```
+#[napi]
+pub struct VirtualFileSystem {
+  inner: StaticFs,
+}
+
+impl From<StaticFs> for VirtualFileSystem {
+  fn from(value: StaticFs) -> Self {
+    Self { inner: value }
+  }
+}
+
+impl VirtualFileSystem {
+  pub fn owned_inner(&self) -> StaticFs {
+    self.inner.to_owned()
+  }
+}
+
+#[napi]
+impl VirtualFileSystem {
+  /// Files held in memory, given as an object of paths to contents, e.g.
+  /// `{ 'index.html': '<!DOCTYPE html>…', 'logo.png': buffer }`. The contents
+  /// are copied, and last modified now.
+  #[napi(factory)]
+  pub fn from_files(files: HashMap<String, Either<Buffer, String>>) -> Self {
+    let files = files.into_iter().map(|(path, content)| {
+      let content = match content {
+        Either::A(buffer) => Bytes::copy_from_slice(&buffer),
+        Either::B(text) => Bytes::from(text),
+      };
+      (path, content)
+    });
+    Self::from(StaticFs::memory(files, SystemTime::now()))
+  }
+
+  /// The files of a `.tar` or `.zip` archive, read into memory once, when the
+  /// file system is created.
+  #[napi(factory)]
+  pub fn from_archive(path: String) -> Result<Self> {
+    StaticFs::archive(Path::new(&path))
+      .map(Self::from)
+      .map_err(|e| {
+        Error::new(
+          Status::GenericFailure,
+          format!("Error reading archive {path}: {e}"),
+        )
+      })
+  }
+
+  /// Layers of root directories and file systems. A path is served by the
+  /// first layer holding it.
+  #[napi(factory)]
+  pub fn overlay(layers: Vec<Either<String, &VirtualFileSystem>>) -> Self {
+    Self::from(StaticFs::overlay(layers.into_iter().map(
+      |layer| match layer {
+        Either::A(root) => StaticFs::disk(root),
+        Either::B(fs) => fs.owned_inner(),
+      },
+    )))
+  }
+}